@@ -0,0 +1,831 @@
+use std::{collections::HashSet, path::PathBuf};
+
+use clap::Parser;
+use miette::{IntoDiagnostic, Result, bail};
+use tauri_bindgen_core::flags_repr;
+use wit_parser::{Function, FunctionResult, Interface, Resource, Type, TypeDef, TypeDefKind};
+
+#[derive(Debug, Parser)]
+pub struct Diff {
+	/// The previous version of the WIT document.
+	old:PathBuf,
+	/// The new version of the WIT document.
+	new:PathBuf,
+	/// Names of functions to skip in both versions. Resource methods are
+	/// addressed as `resource.method`.
+	#[clap(long)]
+	skip:Vec<String>,
+	/// Print the changes as JSON, for use in CI.
+	#[clap(long)]
+	json:bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+	/// Bindings generated from the old version can't talk to bindings
+	/// generated from the new one.
+	Breaking,
+	/// Bindings generated from both versions keep working together.
+	Compatible,
+}
+
+impl Severity {
+	fn as_str(self) -> &'static str {
+		match self {
+			Self::Breaking => "breaking",
+			Self::Compatible => "compatible",
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+	pub severity:Severity,
+	/// The changed item, such as `func greet` or `type person`.
+	pub item:String,
+	pub message:String,
+}
+
+/// Prints the changes between the two documents, returns whether any of them
+/// is breaking.
+pub fn run(opts:&Diff) -> Result<bool> {
+	let skip:HashSet<String> = opts.skip.iter().cloned().collect();
+
+	for path in [&opts.old, &opts.new] {
+		if !path.is_file() {
+			bail!("wit file `{}` does not exist", path.display());
+		}
+	}
+
+	let old = wit_parser::parse_and_resolve_file_skipping(&opts.old, &skip)?;
+
+	let new = wit_parser::parse_and_resolve_file_skipping(&opts.new, &skip)?;
+
+	let changes = diff(&old, &new);
+
+	let breaking = changes.iter().any(|change| change.severity == Severity::Breaking);
+
+	if opts.json {
+		let changes:Vec<_> = changes
+			.iter()
+			.map(|change| {
+				serde_json::json!({
+					"severity": change.severity.as_str(),
+					"item": change.item,
+					"message": change.message,
+				})
+			})
+			.collect();
+
+		let report = serde_json::json!({ "breaking": breaking, "changes": changes });
+
+		serde_json::to_writer_pretty(std::io::stdout().lock(), &report).into_diagnostic()?;
+
+		println!();
+	} else {
+		for change in &changes {
+			println!("{:<10}  {}: {}", change.severity.as_str(), change.item, change.message);
+		}
+
+		if changes.is_empty() {
+			log::info!("no changes");
+		}
+	}
+
+	Ok(breaking)
+}
+
+/// Compares two versions of an interface.
+///
+/// Changes are judged by whether bindings generated from the old version can
+/// still talk to bindings generated from the new one. Values are encoded by
+/// position, so removing or reordering fields and cases is breaking just like
+/// changing a type. Adding functions and resource methods is compatible,
+/// while adding functions the webview exports is breaking since the host
+/// would call functions that older pages don't implement. Constants are
+/// compiled into each side and never sent, so changing them is compatible.
+#[must_use]
+pub fn diff(old:&Interface, new:&Interface) -> Vec<Change> {
+	let mut differ = Differ { old, new, changes:Vec::new() };
+
+	if old.ident != new.ident {
+		differ.push(
+			Severity::Breaking,
+			format!("interface {}", old.ident),
+			format!("renamed to `{}`, calls are sent to another module", new.ident),
+		);
+	}
+
+	differ.diff_functions("func", &old.functions, &new.functions, false);
+
+	differ.diff_functions("export func", &old.exports, &new.exports, true);
+
+	for (_, old_typedef) in &old.typedefs {
+		match new.typedefs.iter().find(|(_, typedef)| typedef.ident == old_typedef.ident) {
+			Some((_, new_typedef)) => differ.diff_typedef(old_typedef, new_typedef),
+			None => differ.typedef_removed(old_typedef),
+		}
+	}
+
+	for (_, new_typedef) in &new.typedefs {
+		if !old.typedefs.iter().any(|(_, typedef)| typedef.ident == new_typedef.ident) {
+			differ.typedef_added(new_typedef);
+		}
+	}
+
+	differ.diff_constants();
+
+	differ.changes
+}
+
+struct Differ<'a> {
+	old:&'a Interface,
+	new:&'a Interface,
+	changes:Vec<Change>,
+}
+
+impl Differ<'_> {
+	fn push(&mut self, severity:Severity, item:String, message:String) {
+		self.changes.push(Change { severity, item, message });
+	}
+
+	/// Compares functions by name, `exported` functions are called by the
+	/// host so adding them is breaking and removing them is not.
+	fn diff_functions(&mut self, kind:&str, old:&[Function], new:&[Function], exported:bool) {
+		let (added, removed) = if exported {
+			(Severity::Breaking, Severity::Compatible)
+		} else {
+			(Severity::Compatible, Severity::Breaking)
+		};
+
+		for old_func in old {
+			let item = format!("{kind} {}", old_func.id);
+
+			match new.iter().find(|func| func.id == old_func.id) {
+				Some(new_func) => self.diff_function(&item, old_func, new_func),
+				None => self.push(removed, item, "removed".to_string()),
+			}
+		}
+
+		for new_func in new {
+			if !old.iter().any(|func| func.id == new_func.id) {
+				self.push(added, format!("{kind} {}", new_func.id), "added".to_string());
+			}
+		}
+	}
+
+	fn diff_constants(&mut self) {
+		for old_const in &self.old.constants {
+			let item = format!("const {}", old_const.id);
+
+			let Some(new_const) = self.new.constants.iter().find(|constant| constant.id == old_const.id)
+			else {
+				self.push(Severity::Compatible, item, "removed".to_string());
+
+				continue;
+			};
+
+			if !self.same_ty(&old_const.ty, &new_const.ty) {
+				self.push(
+					Severity::Compatible,
+					item,
+					format!(
+						"type changed from `{}` to `{}`",
+						print_ty(self.old, &old_const.ty),
+						print_ty(self.new, &new_const.ty)
+					),
+				);
+			} else if old_const.value != new_const.value {
+				self.push(
+					Severity::Compatible,
+					item,
+					format!("value changed from `{}` to `{}`", old_const.value, new_const.value),
+				);
+			}
+		}
+
+		for new_const in &self.new.constants {
+			if !self.old.constants.iter().any(|constant| constant.id == new_const.id) {
+				self.push(Severity::Compatible, format!("const {}", new_const.id), "added".to_string());
+			}
+		}
+	}
+
+	fn diff_function(&mut self, item:&str, old:&Function, new:&Function) {
+		if old.params.len() != new.params.len() {
+			self.push(
+				Severity::Breaking,
+				item.to_string(),
+				format!("takes {} parameters instead of {}", new.params.len(), old.params.len()),
+			);
+		} else {
+			for ((name, old_ty), (_, new_ty)) in old.params.iter().zip(&new.params) {
+				if !self.same_ty(old_ty, new_ty) {
+					self.push(
+						Severity::Breaking,
+						item.to_string(),
+						format!(
+							"parameter `{name}` changed from `{}` to `{}`",
+							print_ty(self.old, old_ty),
+							print_ty(self.new, new_ty)
+						),
+					);
+				}
+			}
+		}
+
+		let old_results:Vec<_> = old.result.iter().flat_map(FunctionResult::types).collect();
+
+		let new_results:Vec<_> = new.result.iter().flat_map(FunctionResult::types).collect();
+
+		let same_results = old_results.len() == new_results.len()
+			&& old_results.iter().zip(&new_results).all(|(old, new)| self.same_ty(old, new));
+
+		if !same_results {
+			self.push(
+				Severity::Breaking,
+				item.to_string(),
+				format!(
+					"result changed from `{}` to `{}`",
+					print_result(self.old, &old.result),
+					print_result(self.new, &new.result)
+				),
+			);
+		}
+	}
+
+	fn diff_typedef(&mut self, old:&TypeDef, new:&TypeDef) {
+		let item = format!("type {}", old.ident);
+
+		if old.params != new.params {
+			self.push(
+				Severity::Breaking,
+				item,
+				format!(
+					"type parameters changed from `<{}>` to `<{}>`",
+					old.params.join(", "),
+					new.params.join(", ")
+				),
+			);
+
+			return;
+		}
+
+		match (&old.kind, &new.kind) {
+			(TypeDefKind::Alias(old_ty), TypeDefKind::Alias(new_ty)) => {
+				if !self.same_ty(old_ty, new_ty) {
+					self.push(
+						Severity::Breaking,
+						item,
+						format!(
+							"changed from `{}` to `{}`",
+							print_ty(self.old, old_ty),
+							print_ty(self.new, new_ty)
+						),
+					);
+				}
+			},
+			(TypeDefKind::Record(old_fields), TypeDefKind::Record(new_fields)) => {
+				// records that end before appended fields with a default take
+				// the default
+				let appended = new_fields
+					.iter()
+					.rev()
+					.take_while(|new| !old_fields.iter().any(|old| old.id == new.id));
+
+				let added = if appended.clone().all(|field| field.default.is_some()) {
+					Severity::Compatible
+				} else {
+					Severity::Breaking
+				};
+
+				let old_fields:Vec<_> =
+					old_fields.iter().map(|field| (field.id.as_str(), Some(&field.ty))).collect();
+
+				let new_fields:Vec<_> =
+					new_fields.iter().map(|field| (field.id.as_str(), Some(&field.ty))).collect();
+
+				self.diff_members(&item, "field", &old_fields, &new_fields, added);
+			},
+			(TypeDefKind::Flags(old_fields), TypeDefKind::Flags(new_fields)) => {
+				// unknown bits are kept by the bindings, unless the flags no
+				// longer fit the integer they are encoded as
+				let added = if flags_width(old_fields) == flags_width(new_fields) {
+					Severity::Compatible
+				} else {
+					Severity::Breaking
+				};
+
+				let old_names:Vec<_> = old_fields.iter().map(|field| (field.id.as_str(), None)).collect();
+
+				let new_names:Vec<_> = new_fields.iter().map(|field| (field.id.as_str(), None)).collect();
+
+				self.diff_members(&item, "flag", &old_names, &new_names, added);
+			},
+			(TypeDefKind::Variant(old_cases), TypeDefKind::Variant(new_cases)) => {
+				let old_cases:Vec<_> =
+					old_cases.iter().map(|case| (case.id.as_str(), case.ty.as_ref())).collect();
+
+				let new_cases:Vec<_> =
+					new_cases.iter().map(|case| (case.id.as_str(), case.ty.as_ref())).collect();
+
+				self.diff_members(&item, "case", &old_cases, &new_cases, Severity::Breaking);
+			},
+			(TypeDefKind::Enum(old_cases), TypeDefKind::Enum(new_cases)) => {
+				let old_cases:Vec<_> = old_cases.iter().map(|case| (case.id.as_str(), None)).collect();
+
+				let new_cases:Vec<_> = new_cases.iter().map(|case| (case.id.as_str(), None)).collect();
+
+				self.diff_members(&item, "case", &old_cases, &new_cases, Severity::Breaking);
+			},
+			(TypeDefKind::Union(old_cases), TypeDefKind::Union(new_cases)) => {
+				let same = old_cases.len() == new_cases.len()
+					&& old_cases.iter().zip(new_cases).all(|(old, new)| self.same_ty(&old.ty, &new.ty));
+
+				if !same {
+					let print = |iface:&Interface, cases:&[wit_parser::UnionCase]| {
+						cases.iter().map(|case| print_ty(iface, &case.ty)).collect::<Vec<_>>().join(", ")
+					};
+
+					self.push(
+						Severity::Breaking,
+						item,
+						format!(
+							"cases changed from `{}` to `{}`",
+							print(self.old, old_cases),
+							print(self.new, new_cases)
+						),
+					);
+				}
+			},
+			(TypeDefKind::Resource(old_resource), TypeDefKind::Resource(new_resource)) => {
+				self.diff_resource(&old.ident, old_resource, new_resource);
+			},
+			(old_kind, new_kind) => {
+				self.push(
+					Severity::Breaking,
+					item,
+					format!("changed from a {} to a {}", kind_name(old_kind), kind_name(new_kind)),
+				);
+			},
+		}
+	}
+
+	/// Compares the fields or cases of a type, which are encoded by their
+	/// position and decoded by their name.
+	fn diff_members(
+		&mut self,
+		item:&str,
+		member:&str,
+		old:&[(&str, Option<&Type>)],
+		new:&[(&str, Option<&Type>)],
+		added:Severity,
+	) {
+		for (name, old_ty) in old {
+			match new.iter().find(|(new_name, _)| new_name == name) {
+				Some((_, new_ty)) => {
+					let same = match (old_ty, new_ty) {
+						(Some(old_ty), Some(new_ty)) => self.same_ty(old_ty, new_ty),
+						(None, None) => true,
+						_ => false,
+					};
+
+					if !same {
+						self.push(
+							Severity::Breaking,
+							item.to_string(),
+							format!(
+								"{member} `{name}` changed from `{}` to `{}`",
+								old_ty.map_or("_".to_string(), |ty| print_ty(self.old, ty)),
+								new_ty.map_or("_".to_string(), |ty| print_ty(self.new, ty))
+							),
+						);
+					}
+				},
+				None => {
+					self.push(Severity::Breaking, item.to_string(), format!("{member} `{name}` removed"));
+				},
+			}
+		}
+
+		let kept_in_old:Vec<_> =
+			old.iter().map(|(name, _)| *name).filter(|name| new.iter().any(|(n, _)| n == name)).collect();
+
+		let kept_in_new:Vec<_> =
+			new.iter().map(|(name, _)| *name).filter(|name| old.iter().any(|(n, _)| n == name)).collect();
+
+		if kept_in_old != kept_in_new {
+			self.push(Severity::Breaking, item.to_string(), format!("{member}s reordered"));
+		}
+
+		let last_kept = new.iter().rposition(|(name, _)| old.iter().any(|(n, _)| n == name));
+
+		for (i, (name, _)) in new.iter().enumerate() {
+			if !old.iter().any(|(old_name, _)| old_name == name) {
+				// `None` is less than every index, all members are appended when
+				// none were kept
+				let severity = if last_kept < Some(i) {
+					added
+				} else {
+					// members inserted before others move them
+					Severity::Breaking
+				};
+
+				self.push(severity, item.to_string(), format!("{member} `{name}` added"));
+			}
+		}
+	}
+
+	fn diff_resource(&mut self, ident:&str, old:&Resource, new:&Resource) {
+		match (&old.constructor, &new.constructor) {
+			(Some(old_func), Some(new_func)) => {
+				self.diff_function(&format!("resource {ident} constructor"), old_func, new_func);
+			},
+			(Some(_), None) => {
+				self.push(
+					Severity::Breaking,
+					format!("resource {ident} constructor"),
+					"removed".to_string(),
+				);
+			},
+			(None, Some(_)) => {
+				self.push(
+					Severity::Compatible,
+					format!("resource {ident} constructor"),
+					"added".to_string(),
+				);
+			},
+			(None, None) => {},
+		}
+
+		self.diff_functions(&format!("resource {ident} method"), &old.methods, &new.methods, false);
+
+		self.diff_functions(&format!("resource {ident} static"), &old.statics, &new.statics, false);
+	}
+
+	fn typedef_removed(&mut self, typedef:&TypeDef) {
+		// functions using the type report their own changes, types renamed
+		// without changing their shape keep working
+		let severity = if matches!(typedef.kind, TypeDefKind::Resource(_)) {
+			Severity::Breaking
+		} else {
+			Severity::Compatible
+		};
+
+		self.push(severity, format!("type {}", typedef.ident), "removed".to_string());
+	}
+
+	fn typedef_added(&mut self, typedef:&TypeDef) {
+		self.push(Severity::Compatible, format!("type {}", typedef.ident), "added".to_string());
+	}
+
+	/// Whether values of the old type can be decoded as the new type and the
+	/// other way around.
+	///
+	/// Type definitions with the same name are treated as the same, their
+	/// changes are reported on their own.
+	fn same_ty(&self, old:&Type, new:&Type) -> bool {
+		match (old, new) {
+			(Type::Generic { id:old_id, args:old_args }, Type::Generic { id:new_id, args:new_args }) => {
+				let old_typedef = &self.old.typedefs[*old_id];
+
+				let new_typedef = &self.new.typedefs[*new_id];
+
+				(old_typedef.ident == new_typedef.ident
+					|| self.same_kind(&old_typedef.kind, &new_typedef.kind))
+					&& old_args.len() == new_args.len()
+					&& old_args.iter().zip(new_args).all(|(old, new)| self.same_ty(old, new))
+			},
+			(Type::Id(old_id), Type::Id(new_id)) => {
+				let old_typedef = &self.old.typedefs[*old_id];
+
+				let new_typedef = &self.new.typedefs[*new_id];
+
+				old_typedef.ident == new_typedef.ident
+					|| self.same_kind(&old_typedef.kind, &new_typedef.kind)
+			},
+			(Type::Id(old_id), new) => {
+				matches!(&self.old.typedefs[*old_id].kind, TypeDefKind::Alias(old) if self.same_ty(old, new))
+			},
+			(old, Type::Id(new_id)) => {
+				matches!(&self.new.typedefs[*new_id].kind, TypeDefKind::Alias(new) if self.same_ty(old, new))
+			},
+			(Type::List(old), Type::List(new))
+			| (Type::Stream(old), Type::Stream(new))
+			| (Type::Option(old), Type::Option(new)) => self.same_ty(old, new),
+			(Type::Tuple(old), Type::Tuple(new)) => {
+				old.len() == new.len() && old.iter().zip(new).all(|(old, new)| self.same_ty(old, new))
+			},
+			(Type::Result { ok:old_ok, err:old_err }, Type::Result { ok:new_ok, err:new_err }) => {
+				self.same_optional_ty(old_ok.as_deref(), new_ok.as_deref())
+					&& self.same_optional_ty(old_err.as_deref(), new_err.as_deref())
+			},
+			(old, new) => old == new,
+		}
+	}
+
+	fn same_optional_ty(&self, old:Option<&Type>, new:Option<&Type>) -> bool {
+		match (old, new) {
+			(Some(old), Some(new)) => self.same_ty(old, new),
+			(None, None) => true,
+			_ => false,
+		}
+	}
+
+	/// Compares the shapes of renamed type definitions.
+	fn same_kind(&self, old:&TypeDefKind, new:&TypeDefKind) -> bool {
+		match (old, new) {
+			(TypeDefKind::Alias(old), new) => {
+				match new {
+					TypeDefKind::Alias(new) => self.same_ty(old, new),
+					_ => {
+						matches!(old, Type::Id(id) if self.same_kind(&self.old.typedefs[*id].kind, new))
+					},
+				}
+			},
+			(old, TypeDefKind::Alias(Type::Id(id))) => self.same_kind(old, &self.new.typedefs[*id].kind),
+			(TypeDefKind::Record(old), TypeDefKind::Record(new)) => {
+				old.len() == new.len()
+					&& old
+						.iter()
+						.zip(new)
+						.all(|(old, new)| old.id == new.id && self.same_ty(&old.ty, &new.ty))
+			},
+			(TypeDefKind::Flags(old), TypeDefKind::Flags(new)) => {
+				old.len() == new.len() && old.iter().zip(new).all(|(old, new)| old.id == new.id)
+			},
+			(TypeDefKind::Variant(old), TypeDefKind::Variant(new)) => {
+				old.len() == new.len()
+					&& old.iter().zip(new).all(|(old, new)| {
+						old.id == new.id && self.same_optional_ty(old.ty.as_ref(), new.ty.as_ref())
+					})
+			},
+			(TypeDefKind::Enum(old), TypeDefKind::Enum(new)) => {
+				old.len() == new.len() && old.iter().zip(new).all(|(old, new)| old.id == new.id)
+			},
+			(TypeDefKind::Union(old), TypeDefKind::Union(new)) => {
+				old.len() == new.len() && old.iter().zip(new).all(|(old, new)| self.same_ty(&old.ty, &new.ty))
+			},
+			// resources are only the same when they have the same name
+			_ => false,
+		}
+	}
+}
+
+/// The number of bytes flags are encoded as.
+fn flags_width(fields:&[wit_parser::FlagsField]) -> usize {
+	match flags_repr(fields) {
+		wit_parser::Int::U8 => 1,
+		wit_parser::Int::U16 => 2,
+		wit_parser::Int::U32 => 4,
+		wit_parser::Int::U64 => 8,
+		wit_parser::Int::U128 => 16,
+	}
+}
+
+fn kind_name(kind:&TypeDefKind) -> &'static str {
+	match kind {
+		TypeDefKind::Alias(_) => "type alias",
+		TypeDefKind::Record(_) => "record",
+		TypeDefKind::Flags(_) => "flags",
+		TypeDefKind::Variant(_) => "variant",
+		TypeDefKind::Enum(_) => "enum",
+		TypeDefKind::Union(_) => "union",
+		TypeDefKind::Resource(_) => "resource",
+	}
+}
+
+/// Prints the type the way it is written in WIT.
+fn print_ty(iface:&Interface, ty:&Type) -> String {
+	match ty {
+		Type::Bool => "bool".to_string(),
+		Type::U8 => "u8".to_string(),
+		Type::U16 => "u16".to_string(),
+		Type::U32 => "u32".to_string(),
+		Type::U64 => "u64".to_string(),
+		Type::U128 => "u128".to_string(),
+		Type::S8 => "s8".to_string(),
+		Type::S16 => "s16".to_string(),
+		Type::S32 => "s32".to_string(),
+		Type::S64 => "s64".to_string(),
+		Type::S128 => "s128".to_string(),
+		Type::Float32 => "float32".to_string(),
+		Type::Float64 => "float64".to_string(),
+		Type::Char => "char".to_string(),
+		Type::String => "string".to_string(),
+		Type::List(ty) => format!("list<{}>", print_ty(iface, ty)),
+		Type::Stream(ty) => format!("stream<{}>", print_ty(iface, ty)),
+		Type::Tuple(types) => {
+			let types = types.iter().map(|ty| print_ty(iface, ty)).collect::<Vec<_>>().join(", ");
+
+			format!("tuple<{types}>")
+		},
+		Type::Option(ty) => format!("option<{}>", print_ty(iface, ty)),
+		Type::Result { ok, err } => {
+			let ok = ok.as_ref().map_or("_".to_string(), |ty| print_ty(iface, ty));
+
+			let err = err.as_ref().map_or("_".to_string(), |ty| print_ty(iface, ty));
+
+			format!("result<{ok}, {err}>")
+		},
+		Type::Id(id) => iface.typedefs[*id].ident.clone(),
+		Type::Generic { id, args } => {
+			let args = args.iter().map(|ty| print_ty(iface, ty)).collect::<Vec<_>>().join(", ");
+
+			format!("{}<{args}>", iface.typedefs[*id].ident)
+		},
+		Type::Param(name) => name.clone(),
+	}
+}
+
+fn print_result(iface:&Interface, result:&Option<FunctionResult>) -> String {
+	match result {
+		Some(FunctionResult::Anon(ty)) => print_ty(iface, ty),
+		Some(FunctionResult::Named(types)) => {
+			let types = types
+				.iter()
+				.map(|(name, ty)| format!("{name}: {}", print_ty(iface, ty)))
+				.collect::<Vec<_>>()
+				.join(", ");
+
+			format!("({types})")
+		},
+		None => "()".to_string(),
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn changes(old:&str, new:&str) -> Vec<(Severity, String, String)> {
+		let old = wit_parser::parse_and_resolve_str(old, |_| false).unwrap();
+
+		let new = wit_parser::parse_and_resolve_str(new, |_| false).unwrap();
+
+		diff(&old, &new)
+			.into_iter()
+			.map(|change| (change.severity, change.item, change.message))
+			.collect()
+	}
+
+	#[test]
+	fn added_functions_are_compatible() {
+		let changes = changes(
+			"interface a {
+				func greet(name: string) -> string
+			}",
+			"interface a {
+				/// docs don't matter
+				func greet(who: string) -> string
+				func farewell()
+			}",
+		);
+
+		assert_eq!(
+			changes,
+			[(Severity::Compatible, "func farewell".to_string(), "added".to_string())]
+		);
+	}
+
+	#[test]
+	fn record_fields() {
+		let changes = changes(
+			"interface a {
+				record point { x: u32, y: u32, z: u32 }
+				func get() -> point
+			}",
+			"interface a {
+				record point { y: u32, x: u64 }
+				func get() -> point
+			}",
+		);
+
+		assert_eq!(
+			changes,
+			[
+				(Severity::Breaking, "type point".to_string(), "field `x` changed from `u32` to `u64`".to_string()),
+				(Severity::Breaking, "type point".to_string(), "field `z` removed".to_string()),
+				(Severity::Breaking, "type point".to_string(), "fields reordered".to_string()),
+			]
+		);
+	}
+
+	#[test]
+	fn appended_fields_with_defaults() {
+		let old = "interface a {
+			record options { size: u32 }
+			func set(options: options)
+		}";
+
+		let changes_to =
+			|new:&str| changes(old, &format!("interface a {{ {new} func set(options: options) }}"));
+
+		assert_eq!(
+			changes_to("record options { size: u32, color: u32 = 1, limit: u32 = 2 }"),
+			[
+				(Severity::Compatible, "type options".to_string(), "field `color` added".to_string()),
+				(Severity::Compatible, "type options".to_string(), "field `limit` added".to_string()),
+			]
+		);
+
+		// fields without a default can't be left out
+		assert_eq!(
+			changes_to("record options { size: u32, color: u32 = 1, limit: u32 }"),
+			[
+				(Severity::Breaking, "type options".to_string(), "field `color` added".to_string()),
+				(Severity::Breaking, "type options".to_string(), "field `limit` added".to_string()),
+			]
+		);
+
+		assert_eq!(
+			changes_to("record options { color: u32 = 1, size: u32 }"),
+			[(Severity::Breaking, "type options".to_string(), "field `color` added".to_string())]
+		);
+	}
+
+	#[test]
+	fn enum_cases() {
+		let changes = changes(
+			"interface a {
+				enum color { red, green }
+				func get() -> color
+			}",
+			"interface a {
+				enum color { green, red, blue }
+				func get() -> color
+			}",
+		);
+
+		assert_eq!(
+			changes,
+			[
+				(Severity::Breaking, "type color".to_string(), "cases reordered".to_string()),
+				(Severity::Breaking, "type color".to_string(), "case `blue` added".to_string()),
+			]
+		);
+	}
+
+	#[test]
+	fn exports() {
+		let changes = changes(
+			"interface a {
+				export {
+					func confirm(message: string) -> bool
+				}
+			}",
+			"interface a {
+				export {
+					func notify(message: string)
+				}
+			}",
+		);
+
+		assert_eq!(
+			changes,
+			[
+				(Severity::Compatible, "export func confirm".to_string(), "removed".to_string()),
+				(Severity::Breaking, "export func notify".to_string(), "added".to_string()),
+			]
+		);
+	}
+
+	#[test]
+	fn constants() {
+		let changes = changes(
+			"interface a {
+				const max_size: u32 = 1024
+				const version: string = \"1.0\"
+			}",
+			"interface a {
+				const max_size: u32 = 2048
+				const timeout: u32 = 30
+			}",
+		);
+
+		assert_eq!(
+			changes,
+			[
+				(Severity::Compatible, "const max_size".to_string(), "value changed from `1024` to `2048`".to_string()),
+				(Severity::Compatible, "const version".to_string(), "removed".to_string()),
+				(Severity::Compatible, "const timeout".to_string(), "added".to_string()),
+			]
+		);
+	}
+
+	#[test]
+	fn renamed_types_are_compatible() {
+		let changes = changes(
+			"interface a {
+				record point { x: u32 }
+				func get() -> point
+			}",
+			"interface a {
+				record position { x: u32 }
+				func get() -> position
+			}",
+		);
+
+		assert!(changes.iter().all(|(severity, ..)| *severity == Severity::Compatible));
+	}
+}
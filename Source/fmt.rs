@@ -0,0 +1,47 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use miette::{IntoDiagnostic, NamedSource, Result, WrapErr};
+
+#[derive(Debug, Parser)]
+pub struct Fmt {
+	/// The WIT documents to format.
+	#[clap(required = true)]
+	files:Vec<PathBuf>,
+	/// Don't write the files, only report the ones that aren't formatted.
+	#[clap(long)]
+	check:bool,
+}
+
+/// Formats the files in place, returns whether any of them wasn't formatted.
+pub fn run(opts:&Fmt) -> Result<bool> {
+	let mut unformatted = false;
+
+	for path in &opts.files {
+		let input = std::fs::read_to_string(path)
+			.into_diagnostic()
+			.wrap_err(format!("failed to read {path:?}"))?;
+
+		let formatted = wit_parser::format(&input).map_err(|error| {
+			error.with_source_code(NamedSource::new(path.to_string_lossy(), input.clone()))
+		})?;
+
+		if formatted == input {
+			continue;
+		}
+
+		unformatted = true;
+
+		if opts.check {
+			log::warn!("{} is not formatted", path.display());
+		} else {
+			log::info!(action = "Formatting"; "{}", path.display());
+
+			std::fs::write(path, formatted)
+				.into_diagnostic()
+				.wrap_err(format!("failed to write {path:?}"))?;
+		}
+	}
+
+	Ok(unformatted && opts.check)
+}
@@ -0,0 +1,448 @@
+use std::{
+	collections::{HashMap, HashSet},
+	path::PathBuf,
+};
+
+use clap::{Parser, ValueEnum};
+use heck::{ToLowerCamelCase, ToSnakeCase};
+use miette::{Result, bail};
+use wit_parser::{Function, Interface, Type, TypeDefId, TypeDefKind};
+
+#[derive(Debug, Parser)]
+pub struct Lint {
+	/// The WIT document to lint.
+	wit:PathBuf,
+	/// Names of functions to skip. Resource methods are addressed as
+	/// `resource.method`.
+	#[clap(long)]
+	skip:Vec<String>,
+	/// Rules to turn off.
+	#[clap(long, value_name = "RULE")]
+	allow:Vec<Rule>,
+	/// Rules to report as warnings.
+	#[clap(long, value_name = "RULE")]
+	warn:Vec<Rule>,
+	/// Rules to report as errors, which make the command exit with status 1.
+	#[clap(long, value_name = "RULE")]
+	deny:Vec<Rule>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+	Allow,
+	Warn,
+	Deny,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ValueEnum)]
+pub enum Rule {
+	/// Identifiers that aren't lower case words separated by underscores.
+	Naming,
+	/// Functions without doc comments.
+	MissingDocs,
+	/// `u128` and `s128`, which JavaScript only has as `bigint`.
+	WideIntegers,
+	/// Flags with more fields than fit into a `u64`.
+	TooManyFlags,
+	/// Functions whose generated name is a keyword in JavaScript or Rust.
+	ReservedWords,
+	/// Resources that have no constructor and that no function returns.
+	UnusedResources,
+}
+
+impl Rule {
+	fn as_str(self) -> &'static str {
+		match self {
+			Self::Naming => "naming",
+			Self::MissingDocs => "missing-docs",
+			Self::WideIntegers => "wide-integers",
+			Self::TooManyFlags => "too-many-flags",
+			Self::ReservedWords => "reserved-words",
+			Self::UnusedResources => "unused-resources",
+		}
+	}
+
+	fn default_level(self) -> Level {
+		match self {
+			Self::MissingDocs => Level::Allow,
+			Self::Naming | Self::WideIntegers | Self::ReservedWords | Self::UnusedResources => {
+				Level::Warn
+			},
+			// generating bindings for these panics
+			Self::TooManyFlags => Level::Deny,
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+	pub rule:Rule,
+	/// The offending item, such as `func greet` or `type person`.
+	pub item:String,
+	pub message:String,
+}
+
+/// Prints the findings of all rules that aren't allowed, returns whether any
+/// of them is denied.
+pub fn run(opts:&Lint) -> Result<bool> {
+	if !opts.wit.is_file() {
+		bail!("wit file `{}` does not exist", opts.wit.display());
+	}
+
+	let skip:HashSet<String> = opts.skip.iter().cloned().collect();
+
+	let iface = wit_parser::parse_and_resolve_file_skipping(&opts.wit, &skip)?;
+
+	// the most severe level given for a rule wins
+	let mut levels = HashMap::new();
+
+	for (rules, level) in [(&opts.allow, Level::Allow), (&opts.warn, Level::Warn), (&opts.deny, Level::Deny)]
+	{
+		for rule in rules {
+			levels.insert(*rule, level);
+		}
+	}
+
+	let mut reported = 0;
+
+	let mut denied = false;
+
+	for finding in lint(&iface) {
+		let level = levels.get(&finding.rule).copied().unwrap_or(finding.rule.default_level());
+
+		match level {
+			Level::Allow => continue,
+			Level::Warn => {
+				log::warn!("{}: {} [{}]", finding.item, finding.message, finding.rule.as_str());
+			},
+			Level::Deny => {
+				log::error!("{}: {} [{}]", finding.item, finding.message, finding.rule.as_str());
+
+				denied = true;
+			},
+		}
+
+		reported += 1;
+	}
+
+	if reported == 0 {
+		log::info!("no problems found");
+	}
+
+	Ok(denied)
+}
+
+/// Checks an interface against all rules, regardless of their level.
+#[must_use]
+pub fn lint(iface:&Interface) -> Vec<Finding> {
+	let mut linter = Linter { findings:Vec::new() };
+
+	linter.naming(&format!("interface {}", iface.ident), &iface.ident);
+
+	for func in &iface.functions {
+		linter.function(&format!("func {}", func.id), func, true);
+	}
+
+	for func in &iface.exports {
+		linter.function(&format!("export func {}", func.id), func, false);
+	}
+
+	for constant in &iface.constants {
+		let item = format!("const {}", constant.id);
+
+		linter.naming(&item, &constant.id);
+
+		linter.wide_integers(&item, [&constant.ty]);
+	}
+
+	let produced = produced_resources(iface);
+
+	for (id, typedef) in &iface.typedefs {
+		let item = format!("type {}", typedef.ident);
+
+		linter.naming(&item, &typedef.ident);
+
+		match &typedef.kind {
+			TypeDefKind::Alias(ty) => linter.wide_integers(&item, [ty]),
+			TypeDefKind::Record(fields) => {
+				for field in fields {
+					linter.naming(&format!("{item} field {}", field.id), &field.id);
+				}
+
+				linter.wide_integers(&item, fields.iter().map(|field| &field.ty));
+			},
+			TypeDefKind::Flags(fields) => {
+				for field in fields {
+					linter.naming(&format!("{item} flag {}", field.id), &field.id);
+				}
+
+				if fields.len() > 64 {
+					linter.push(
+						Rule::TooManyFlags,
+						item.clone(),
+						format!("has {} flags, but at most 64 can be represented", fields.len()),
+					);
+				}
+			},
+			TypeDefKind::Variant(cases) => {
+				for case in cases {
+					linter.naming(&format!("{item} case {}", case.id), &case.id);
+				}
+
+				linter.wide_integers(&item, cases.iter().filter_map(|case| case.ty.as_ref()));
+			},
+			TypeDefKind::Enum(cases) => {
+				for case in cases {
+					linter.naming(&format!("{item} case {}", case.id), &case.id);
+				}
+			},
+			TypeDefKind::Union(cases) => linter.wide_integers(&item, cases.iter().map(|case| &case.ty)),
+			TypeDefKind::Resource(resource) => {
+				let ident = &typedef.ident;
+
+				if let Some(constructor) = &resource.constructor {
+					linter.function(&format!("resource {ident} constructor"), constructor, false);
+				}
+
+				for method in &resource.methods {
+					linter.function(&format!("resource {ident} method {}", method.id), method, false);
+				}
+
+				for func in &resource.statics {
+					linter.function(&format!("resource {ident} static {}", func.id), func, false);
+				}
+
+				if resource.constructor.is_none() && !produced.contains(&id) {
+					linter.push(
+						Rule::UnusedResources,
+						format!("resource {ident}"),
+						"can't be obtained, it has no constructor and no function returns it"
+							.to_string(),
+					);
+				}
+			},
+		}
+	}
+
+	linter.findings
+}
+
+struct Linter {
+	findings:Vec<Finding>,
+}
+
+impl Linter {
+	fn push(&mut self, rule:Rule, item:String, message:String) {
+		self.findings.push(Finding { rule, item, message });
+	}
+
+	fn naming(&mut self, item:&str, ident:&str) {
+		if ident != ident.to_snake_case() {
+			self.push(
+				Rule::Naming,
+				item.to_string(),
+				format!("should be `{}`, lower case words separated by underscores", ident.to_snake_case()),
+			);
+		}
+	}
+
+	/// Checks a function, `free` functions are generated as module level
+	/// functions in JavaScript, where keywords can't be used as names.
+	fn function(&mut self, item:&str, func:&Function, free:bool) {
+		self.naming(item, &func.id);
+
+		for (ident, _) in &func.params {
+			self.naming(&format!("{item} parameter {ident}"), ident);
+		}
+
+		if func.docs.is_empty() {
+			self.push(Rule::MissingDocs, item.to_string(), "has no doc comment".to_string());
+		}
+
+		let results = func.result.iter().flat_map(|result| result.types());
+
+		self.wide_integers(item, func.params.iter().map(|(_, ty)| ty).chain(results));
+
+		let rust = func.id.to_snake_case();
+
+		let js = func.id.to_lower_camel_case();
+
+		if RUST_KEYWORDS.contains(&rust.as_str()) {
+			self.push(
+				Rule::ReservedWords,
+				item.to_string(),
+				format!("is generated as `{rust}`, a keyword in Rust"),
+			);
+		} else if free && JS_KEYWORDS.contains(&js.as_str()) {
+			self.push(
+				Rule::ReservedWords,
+				item.to_string(),
+				format!("is generated as `{js}`, a reserved word in JavaScript"),
+			);
+		}
+	}
+
+	/// Reports `item` once if any of `types` is a 128 bit integer. Named types
+	/// are reported where they're defined.
+	fn wide_integers<'t>(&mut self, item:&str, types:impl IntoIterator<Item = &'t Type>) {
+		if types.into_iter().any(is_wide) {
+			self.push(
+				Rule::WideIntegers,
+				item.to_string(),
+				"uses `u128` or `s128`, which can only be passed as `bigint` in JavaScript"
+					.to_string(),
+			);
+		}
+	}
+}
+
+fn is_wide(ty:&Type) -> bool {
+	match ty {
+		Type::U128 | Type::S128 => true,
+		Type::List(ty) | Type::Option(ty) | Type::Stream(ty) => is_wide(ty),
+		Type::Tuple(types) | Type::Generic { args: types, .. } => types.iter().any(is_wide),
+		Type::Result { ok, err } => ok.iter().chain(err).any(|ty| is_wide(ty)),
+		_ => false,
+	}
+}
+
+/// Collects the resources a caller can get a handle to: those returned by
+/// functions, or passed to the functions the webview exports.
+fn produced_resources(iface:&Interface) -> HashSet<TypeDefId> {
+	let mut stack = Vec::new();
+
+	let results = |func:&Function| {
+		func.result.iter().flat_map(|result| result.types()).cloned().collect::<Vec<_>>()
+	};
+
+	for func in &iface.functions {
+		stack.extend(results(func));
+	}
+
+	for func in &iface.exports {
+		stack.extend(func.params.iter().map(|(_, ty)| ty.clone()));
+	}
+
+	for (_, typedef) in &iface.typedefs {
+		if let TypeDefKind::Resource(resource) = &typedef.kind {
+			for func in resource.methods.iter().chain(&resource.statics) {
+				stack.extend(results(func));
+			}
+		}
+	}
+
+	let mut visited = HashSet::new();
+
+	while let Some(ty) = stack.pop() {
+		match ty {
+			Type::List(ty) | Type::Option(ty) | Type::Stream(ty) => stack.push(*ty),
+			Type::Tuple(types) => stack.extend(types),
+			Type::Result { ok, err } => stack.extend(ok.into_iter().chain(err).map(|ty| *ty)),
+			Type::Generic { id, args } => {
+				stack.extend(args);
+
+				stack.push(Type::Id(id));
+			},
+			Type::Id(id) if visited.insert(id) => {
+				match &iface.typedefs[id].kind {
+					TypeDefKind::Alias(ty) => stack.push(ty.clone()),
+					TypeDefKind::Record(fields) => stack.extend(fields.iter().map(|field| field.ty.clone())),
+					TypeDefKind::Variant(cases) => {
+						stack.extend(cases.iter().filter_map(|case| case.ty.clone()));
+					},
+					TypeDefKind::Union(cases) => stack.extend(cases.iter().map(|case| case.ty.clone())),
+					TypeDefKind::Flags(_) | TypeDefKind::Enum(_) | TypeDefKind::Resource(_) => {},
+				}
+			},
+			_ => {},
+		}
+	}
+
+	visited
+}
+
+const RUST_KEYWORDS:&[&str] = &[
+	"abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate", "do",
+	"dyn", "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in", "let", "loop",
+	"macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "self",
+	"static", "struct", "super", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+	"virtual", "where", "while", "yield",
+];
+
+const JS_KEYWORDS:&[&str] = &[
+	"arguments", "await", "break", "case", "catch", "class", "const", "continue", "debugger",
+	"default", "delete", "do", "else", "enum", "eval", "export", "extends", "false", "finally", "for",
+	"function", "if", "implements", "import", "in", "instanceof", "interface", "let", "new", "null",
+	"package", "private", "protected", "public", "return", "static", "super", "switch", "this",
+	"throw", "true", "try", "typeof", "var", "void", "while", "with", "yield",
+];
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn findings(source:&str) -> Vec<(Rule, String)> {
+		let iface = wit_parser::parse_and_resolve_str(source, |_| false).unwrap();
+
+		lint(&iface)
+			.into_iter()
+			.filter(|finding| finding.rule != Rule::MissingDocs)
+			.map(|finding| (finding.rule, finding.item))
+			.collect()
+	}
+
+	#[test]
+	fn clean() {
+		let findings = findings(
+			"interface demo {
+				resource counter {
+					constructor(start: u32)
+					func get() -> u32
+				}
+				func greet(name: string) -> string
+				func reset(c: counter)
+			}",
+		);
+
+		assert_eq!(findings, []);
+	}
+
+	#[test]
+	fn rules() {
+		let findings = findings(
+			"interface demo {
+				record Point { x: u128 }
+				resource handle {
+					func close()
+				}
+				func delete(at: Point, h: handle)
+				func move()
+			}",
+		);
+
+		assert_eq!(
+			findings,
+			[
+				(Rule::ReservedWords, "func delete".to_string()),
+				(Rule::ReservedWords, "func move".to_string()),
+				(Rule::Naming, "type Point".to_string()),
+				(Rule::WideIntegers, "type Point".to_string()),
+				(Rule::UnusedResources, "resource handle".to_string()),
+			]
+		);
+	}
+
+	#[test]
+	fn too_many_flags() {
+		let flags = (0..65).map(|i| format!("f{i}")).collect::<Vec<_>>().join(", ");
+
+		let findings = findings(&format!(
+			"interface demo {{
+				flags many {{ {flags} }}
+				func get() -> many
+			}}"
+		));
+
+		assert_eq!(findings, [(Rule::TooManyFlags, "type many".to_string())]);
+	}
+}
@@ -0,0 +1,726 @@
+use std::{
+	collections::HashMap,
+	path::{Path, PathBuf},
+};
+
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::{
+	CompletionItem,
+	CompletionItemKind,
+	CompletionOptions,
+	CompletionParams,
+	Diagnostic,
+	DiagnosticRelatedInformation,
+	DiagnosticSeverity,
+	DidChangeTextDocumentParams,
+	DidCloseTextDocumentParams,
+	DidOpenTextDocumentParams,
+	Documentation,
+	GotoDefinitionParams,
+	GotoDefinitionResponse,
+	Hover,
+	HoverContents,
+	HoverParams,
+	HoverProviderCapability,
+	Location,
+	MarkupContent,
+	MarkupKind,
+	NumberOrString,
+	OneOf,
+	Position,
+	PublishDiagnosticsParams,
+	Range,
+	RenameParams,
+	ServerCapabilities,
+	TextDocumentSyncCapability,
+	TextDocumentSyncKind,
+	TextEdit,
+	Url,
+	WorkspaceEdit,
+	notification::{
+		DidChangeTextDocument,
+		DidCloseTextDocument,
+		DidOpenTextDocument,
+		Notification as _,
+		PublishDiagnostics,
+	},
+	request::{Completion, GotoDefinition, HoverRequest, Rename, Request as _},
+};
+use miette::{IntoDiagnostic, Result};
+use serde::{Serialize, de::DeserializeOwned};
+use wit_parser::symbols::{self, DefinitionKind, Symbols};
+
+/// Runs a language server for WIT documents, talking to the editor through
+/// stdin and stdout.
+pub fn run() -> Result<()> {
+	let (connection, io_threads) = Connection::stdio();
+
+	let capabilities = ServerCapabilities {
+		text_document_sync:Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+		hover_provider:Some(HoverProviderCapability::Simple(true)),
+		definition_provider:Some(OneOf::Left(true)),
+		completion_provider:Some(CompletionOptions::default()),
+		rename_provider:Some(OneOf::Left(true)),
+		..ServerCapabilities::default()
+	};
+
+	connection
+		.initialize(serde_json::to_value(capabilities).into_diagnostic()?)
+		.into_diagnostic()?;
+
+	log::info!("language server initialized");
+
+	let mut server = Server { connection, documents:HashMap::new() };
+
+	server.main_loop()?;
+
+	drop(server);
+
+	io_threads.join().into_diagnostic()?;
+
+	Ok(())
+}
+
+struct Document {
+	text:String,
+	/// The symbols of the last version of the document that could be parsed,
+	/// so hovering and completion keep working while typing.
+	symbols:Symbols,
+	/// Whether the document has syntax errors, so some symbols are missing or
+	/// from an earlier version of it.
+	stale:bool,
+}
+
+struct Server {
+	connection:Connection,
+	documents:HashMap<Url, Document>,
+}
+
+impl Server {
+	fn main_loop(&mut self) -> Result<()> {
+		while let Ok(message) = self.connection.receiver.recv() {
+			match message {
+				Message::Request(request) => {
+					if self.connection.handle_shutdown(&request).into_diagnostic()? {
+						return Ok(());
+					}
+
+					let response = self.handle_request(request);
+
+					self.connection.sender.send(Message::Response(response)).into_diagnostic()?;
+				},
+				Message::Notification(notification) => self.handle_notification(notification)?,
+				Message::Response(_) => {},
+			}
+		}
+
+		Ok(())
+	}
+
+	fn handle_request(&self, request:Request) -> Response {
+		match request.method.as_str() {
+			HoverRequest::METHOD => self.respond(request, Self::hover),
+			GotoDefinition::METHOD => self.respond(request, Self::definition),
+			Completion::METHOD => self.respond(request, Self::completion),
+			Rename::METHOD => self.respond(request, Self::rename),
+			_ => {
+				Response::new_err(
+					request.id,
+					ErrorCode::MethodNotFound as i32,
+					format!("unsupported request {}", request.method),
+				)
+			},
+		}
+	}
+
+	fn respond<P, R>(&self, request:Request, handler:fn(&Self, P) -> Result<R, String>) -> Response
+	where
+		P: DeserializeOwned,
+		R: Serialize, {
+		let result = serde_json::from_value(request.params)
+			.map_err(|err| err.to_string())
+			.and_then(|params| handler(self, params));
+
+		match result {
+			Ok(result) => Response::new_ok(request.id, result),
+			Err(message) => Response::new_err(request.id, ErrorCode::InvalidParams as i32, message),
+		}
+	}
+
+	fn handle_notification(&mut self, notification:Notification) -> Result<()> {
+		match notification.method.as_str() {
+			DidOpenTextDocument::METHOD => {
+				let params:DidOpenTextDocumentParams =
+					serde_json::from_value(notification.params).into_diagnostic()?;
+
+				self.update(params.text_document.uri, params.text_document.text)?;
+			},
+			DidChangeTextDocument::METHOD => {
+				let mut params:DidChangeTextDocumentParams =
+					serde_json::from_value(notification.params).into_diagnostic()?;
+
+				// documents are synced in full, the last change holds the whole text
+				if let Some(change) = params.content_changes.pop() {
+					self.update(params.text_document.uri, change.text)?;
+				}
+			},
+			DidCloseTextDocument::METHOD => {
+				let params:DidCloseTextDocumentParams =
+					serde_json::from_value(notification.params).into_diagnostic()?;
+
+				self.documents.remove(&params.text_document.uri);
+
+				self.publish(PublishDiagnosticsParams::new(params.text_document.uri, Vec::new(), None))?;
+			},
+			_ => {},
+		}
+
+		Ok(())
+	}
+
+	/// Stores the new text of a document and publishes its diagnostics.
+	fn update(&mut self, uri:Url, text:String) -> Result<()> {
+		let (symbols, errors) = Symbols::parse(&text);
+
+		let stale = !errors.is_empty();
+
+		let symbols = symbols.unwrap_or_else(|| {
+			self.documents.remove(&uri).map(|document| document.symbols).unwrap_or_default()
+		});
+
+		let mut diagnostics = Vec::new();
+
+		for error in &errors {
+			collect_diagnostics(&uri, &text, error, &mut diagnostics);
+		}
+
+		let first_import =
+			symbols.definitions.iter().find(|definition| definition.kind == DefinitionKind::Import);
+
+		// names imported from other files are resolved with the files next to
+		// the document, documents that aren't files only report syntax errors
+		let resolved = match (first_import, uri.to_file_path()) {
+			_ if !errors.is_empty() => None,
+			(None, _) => Some(wit_parser::parse_and_resolve_str(&text, |_| false)),
+			(Some(_), Ok(path)) => {
+				Some(wit_parser::parse_and_resolve_str_at(&text, path, |_| false))
+			},
+			(Some(_), Err(())) => None,
+		};
+
+		if let Some(Err(error)) = resolved {
+			match (first_import, imported_file(&uri, error.as_ref())) {
+				(Some(import), Some(file)) => {
+					let diagnostic = imported_diagnostic(&text, &import.span, &file, error.as_ref());
+
+					diagnostics.push(diagnostic);
+				},
+				_ => collect_diagnostics(&uri, &text, error.as_ref(), &mut diagnostics),
+			}
+		}
+
+		self.documents.insert(uri.clone(), Document { text, symbols, stale });
+
+		self.publish(PublishDiagnosticsParams::new(uri, diagnostics, None))
+	}
+
+	fn publish(&self, params:PublishDiagnosticsParams) -> Result<()> {
+		let notification = Notification::new(PublishDiagnostics::METHOD.to_string(), params);
+
+		self.connection.sender.send(Message::Notification(notification)).into_diagnostic()
+	}
+
+	/// Returns the document and the index of the definition at the position.
+	fn definition_at(&self, uri:&Url, position:Position) -> Result<Option<(&Document, usize)>, String> {
+		let document = self.documents.get(uri).ok_or_else(|| format!("unknown document {uri}"))?;
+
+		let offset = offset(&document.text, position);
+
+		Ok(document.symbols.find(offset).map(|definition| (document, definition)))
+	}
+
+	fn hover(&self, params:HoverParams) -> Result<Option<Hover>, String> {
+		let position = params.text_document_position_params;
+
+		let Some((document, index)) = self.definition_at(&position.text_document.uri, position.position)?
+		else {
+			return Ok(None);
+		};
+
+		let definition = &document.symbols.definitions[index];
+
+		let mut value = format!("```wit\n{}\n```", definition.signature);
+
+		if !definition.docs.is_empty() {
+			value.push_str("\n\n");
+
+			value.push_str(&definition.docs);
+		}
+
+		Ok(Some(Hover {
+			contents:HoverContents::Markup(MarkupContent { kind:MarkupKind::Markdown, value }),
+			range:None,
+		}))
+	}
+
+	fn definition(&self, params:GotoDefinitionParams) -> Result<Option<GotoDefinitionResponse>, String> {
+		let position = params.text_document_position_params;
+
+		let uri = position.text_document.uri;
+
+		let Some((document, index)) = self.definition_at(&uri, position.position)? else {
+			return Ok(None);
+		};
+
+		let range = range(&document.text, &document.symbols.definitions[index].span);
+
+		Ok(Some(GotoDefinitionResponse::Scalar(Location::new(uri, range))))
+	}
+
+	fn completion(&self, params:CompletionParams) -> Result<Option<Vec<CompletionItem>>, String> {
+		let uri = &params.text_document_position.text_document.uri;
+
+		let document = self.documents.get(uri).ok_or_else(|| format!("unknown document {uri}"))?;
+
+		let keywords = symbols::keywords().map(|keyword| {
+			CompletionItem {
+				label:keyword.to_string(),
+				kind:Some(CompletionItemKind::KEYWORD),
+				..CompletionItem::default()
+			}
+		});
+
+		let types = document
+			.symbols
+			.definitions
+			.iter()
+			.filter_map(|definition| {
+				let kind = match definition.kind {
+					DefinitionKind::Type | DefinitionKind::Import => CompletionItemKind::STRUCT,
+					DefinitionKind::Resource => CompletionItemKind::CLASS,
+					_ => return None,
+				};
+
+				Some(CompletionItem {
+					label:definition.name.clone(),
+					kind:Some(kind),
+					detail:Some(definition.signature.clone()),
+					documentation:(!definition.docs.is_empty())
+						.then(|| Documentation::String(definition.docs.clone())),
+					..CompletionItem::default()
+				})
+			});
+
+		Ok(Some(keywords.chain(types).collect()))
+	}
+
+	fn rename(&self, params:RenameParams) -> Result<Option<WorkspaceEdit>, String> {
+		let position = params.text_document_position;
+
+		let uri = position.text_document.uri;
+
+		let Some((document, index)) = self.definition_at(&uri, position.position)? else {
+			return Ok(None);
+		};
+
+		if document.stale {
+			return Err("the document has syntax errors, fix them before renaming".to_string());
+		}
+
+		if !symbols::is_ident(&params.new_name) {
+			return Err(format!("`{}` is not a valid identifier", params.new_name));
+		}
+
+		match document.symbols.definitions[index].kind {
+			DefinitionKind::Import => {
+				return Err("imported names can only be renamed in the interface defining them".to_string());
+			},
+			DefinitionKind::Interface => {
+				return Err("renaming interfaces would break the `use` items importing them".to_string());
+			},
+			_ => {},
+		}
+
+		let edits = document
+			.symbols
+			.occurrences(index)
+			.map(|span| TextEdit::new(range(&document.text, span), params.new_name.clone()))
+			.collect();
+
+		Ok(Some(WorkspaceEdit::new(HashMap::from([(uri, edits)]))))
+	}
+}
+
+/// Turns an error into diagnostics, the errors of [`wit_parser::Error::Multi`]
+/// become diagnostics of their own.
+fn collect_diagnostics(
+	uri:&Url,
+	text:&str,
+	error:&dyn miette::Diagnostic,
+	diagnostics:&mut Vec<Diagnostic>,
+) {
+	if error.labels().is_none() && error.related().is_some() {
+		for error in errors(error) {
+			collect_diagnostics(uri, text, error, diagnostics);
+		}
+
+		return;
+	}
+
+	let mut labels = error.labels().into_iter().flatten();
+
+	let span = |label:&miette::LabeledSpan| label.offset()..label.offset() + label.len();
+
+	let primary = labels.next().map_or_else(Range::default, |label| range(text, &span(&label)));
+
+	// further labels point at related locations, such as earlier definitions
+	let related_information = labels
+		.map(|label| {
+			DiagnosticRelatedInformation {
+				location:Location::new(uri.clone(), range(text, &span(&label))),
+				message:label.label().unwrap_or_default().to_string(),
+			}
+		})
+		.chain(other_locations(error))
+		.collect::<Vec<_>>();
+
+	let mut message = error.to_string();
+
+	if let Some(help) = error.help() {
+		message.push('\n');
+
+		message.push_str(&help.to_string());
+	}
+
+	diagnostics.push(Diagnostic {
+		range:primary,
+		severity:Some(DiagnosticSeverity::ERROR),
+		code:error.code().map(|code| NumberOrString::String(code.to_string())),
+		source:Some("tauri-bindgen".to_string()),
+		message,
+		related_information:(!related_information.is_empty()).then_some(related_information),
+		..Diagnostic::default()
+	});
+}
+
+/// The errors of [`wit_parser::Error::Multi`], or the error itself. Other
+/// errors use related diagnostics to point at locations in other files.
+fn errors(error:&dyn miette::Diagnostic) -> Vec<&dyn miette::Diagnostic> {
+	match error.related() {
+		Some(related) if error.labels().is_none() => related.collect(),
+		_ => vec![error],
+	}
+}
+
+/// The locations in other files the related diagnostics of an error point
+/// at, such as the earlier definition of an interface.
+fn other_locations(error:&dyn miette::Diagnostic) -> Vec<DiagnosticRelatedInformation> {
+	error
+		.related()
+		.into_iter()
+		.flatten()
+		.filter_map(|related| {
+			// reading on to the end of the file yields all of its contents, which
+			// may be the unsaved text of a document
+			let contents = related.source_code()?.read_span(&(0..0).into(), 0, usize::MAX).ok()?;
+
+			let source = std::str::from_utf8(contents.data()).ok()?;
+
+			let uri = Url::from_file_path(contents.name()?).ok()?;
+
+			let label = related.labels()?.next()?;
+
+			let span = label.offset()..label.offset() + label.len();
+
+			Some(DiagnosticRelatedInformation {
+				location:Location::new(uri, range(source, &span)),
+				message:related.to_string(),
+			})
+		})
+		.collect()
+}
+
+/// The path of the file an error was found in, if it isn't the document at
+/// `uri` but one it imports from.
+fn imported_file(uri:&Url, error:&dyn miette::Diagnostic) -> Option<PathBuf> {
+	let contents = error.source_code()?.read_span(&(0..0).into(), 0, 0).ok()?;
+
+	let file = PathBuf::from(contents.name()?);
+
+	(uri.to_file_path().ok()? != file).then_some(file)
+}
+
+/// Reports an error in a file the document imports from on the first `use`
+/// item of the document, pointing at the errors in the other file.
+fn imported_diagnostic(
+	text:&str,
+	import:&std::ops::Range<usize>,
+	file:&Path,
+	error:&dyn miette::Diagnostic,
+) -> Diagnostic {
+	let errors = errors(error);
+
+	let mut message = format!("error in imported file `{}`:", file.display());
+
+	for error in &errors {
+		message.push('\n');
+
+		message.push_str(&error.to_string());
+	}
+
+	let source = std::fs::read_to_string(file).unwrap_or_default();
+
+	let related_information = Url::from_file_path(file).ok().map(|uri| {
+		errors
+			.iter()
+			.flat_map(|error| {
+				let span = error
+					.labels()
+					.and_then(|mut labels| labels.next())
+					.map_or(0..0, |label| label.offset()..label.offset() + label.len());
+
+				let information = DiagnosticRelatedInformation {
+					location:Location::new(uri.clone(), range(&source, &span)),
+					message:error.to_string(),
+				};
+
+				std::iter::once(information).chain(other_locations(*error))
+			})
+			.collect()
+	});
+
+	Diagnostic {
+		range:range(text, import),
+		severity:Some(DiagnosticSeverity::ERROR),
+		code:error.code().map(|code| NumberOrString::String(code.to_string())),
+		source:Some("tauri-bindgen".to_string()),
+		message,
+		related_information,
+		..Diagnostic::default()
+	}
+}
+
+/// Converts a byte offset into a position, counting characters in UTF-16
+/// code units like editors do.
+fn position(text:&str, offset:usize) -> Position {
+	let mut offset = offset.min(text.len());
+
+	while !text.is_char_boundary(offset) {
+		offset -= 1;
+	}
+
+	let before = &text[..offset];
+
+	let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+
+	let line = before.matches('\n').count();
+
+	let character = before[line_start..].encode_utf16().count();
+
+	Position::new(line as u32, character as u32)
+}
+
+fn offset(text:&str, position:Position) -> usize {
+	let mut line_start = 0;
+
+	for _ in 0..position.line {
+		match text[line_start..].find('\n') {
+			Some(i) => line_start += i + 1,
+			None => return text.len(),
+		}
+	}
+
+	let mut character = 0;
+
+	for (i, ch) in text[line_start..].char_indices() {
+		if character >= position.character || ch == '\n' {
+			return line_start + i;
+		}
+
+		character += ch.len_utf16() as u32;
+	}
+
+	text.len()
+}
+
+fn range(text:&str, span:&std::ops::Range<usize>) -> Range {
+	Range::new(position(text, span.start), position(text, span.end))
+}
+
+#[cfg(test)]
+mod test {
+	use lsp_types::{TextDocumentIdentifier, TextDocumentPositionParams};
+
+	use super::*;
+
+	/// A server together with the client end of its connection.
+	fn server() -> (Server, Connection) {
+		let (connection, client) = Connection::memory();
+
+		(Server { connection, documents:HashMap::new() }, client)
+	}
+
+	/// The diagnostics the server published last.
+	fn diagnostics(client:&Connection) -> Vec<Diagnostic> {
+		let mut diagnostics = None;
+
+		while let Ok(Message::Notification(notification)) = client.receiver.try_recv() {
+			let params:PublishDiagnosticsParams =
+				serde_json::from_value(notification.params).unwrap();
+
+			diagnostics = Some(params.diagnostics);
+		}
+
+		diagnostics.unwrap()
+	}
+
+	#[test]
+	fn utf16_positions() {
+		let text = "interface a {\n\t/// é😀 and ü\n\trecord b { x: u32 }\n}";
+
+		let after_emoji = text.find(" and").unwrap();
+
+		// `é` is a single code unit, `😀` two
+		assert_eq!(position(text, after_emoji), Position::new(1, 8));
+		assert_eq!(offset(text, Position::new(1, 8)), after_emoji);
+
+		// offsets inside a character move to its start
+		assert_eq!(position(text, after_emoji - 1), Position::new(1, 6));
+
+		for (i, _) in text.char_indices() {
+			assert_eq!(offset(text, position(text, i)), i);
+		}
+
+		// positions past the end of a line or the text are clamped
+		assert_eq!(offset(text, Position::new(1, 100)), text.find("\n\trecord").unwrap());
+		assert_eq!(offset(text, Position::new(9, 0)), text.len());
+	}
+
+	#[test]
+	fn rename() {
+		let (mut server, _client) = server();
+
+		let uri = Url::parse("untitled:a.wit").unwrap();
+
+		let text =
+			"interface a {\n\trecord b { x: u32 }\n\t@deprecated(\"é😀\") func f(x: b) -> b\n}";
+
+		server.update(uri.clone(), text.to_string()).unwrap();
+
+		let rename = |line, character| {
+			let params = RenameParams {
+				text_document_position:TextDocumentPositionParams {
+					text_document:TextDocumentIdentifier::new(uri.clone()),
+					position:Position::new(line, character),
+				},
+				new_name:"point".to_string(),
+				work_done_progress_params:Default::default(),
+			};
+
+			server.rename(params)
+		};
+
+		let edit = |line, start| {
+			let range = Range::new(Position::new(line, start), Position::new(line, start + 1));
+
+			TextEdit::new(range, "point".to_string())
+		};
+
+		// `é😀` takes six bytes but three UTF-16 code units
+		let expected = vec![edit(1, 8), edit(2, 30), edit(2, 36)];
+
+		for (line, character) in [(1, 8), (2, 30), (2, 36)] {
+			let changes = rename(line, character).unwrap().unwrap().changes.unwrap();
+
+			assert_eq!(changes[&uri], expected);
+		}
+
+		assert!(rename(1, 10).unwrap().is_none());
+
+		assert!(rename(2, 19).unwrap().is_none());
+	}
+
+	#[test]
+	fn imported_names() {
+		let dir = std::env::temp_dir().join(format!("lsp-imports-{}", std::process::id()));
+
+		std::fs::create_dir_all(&dir).unwrap();
+
+		let b = "interface b { record foo { x: u32 } func g(x: foo) }";
+
+		std::fs::write(dir.join("b.wit"), b).unwrap();
+
+		let uri = Url::from_file_path(dir.join("a.wit")).unwrap();
+
+		let (mut server, client) = server();
+
+		let mut update = |text:&str| {
+			server.update(uri.clone(), text.to_string()).unwrap();
+
+			diagnostics(&client)
+		};
+
+		let valid = update("interface a { use b.{foo} func f(x: foo) }");
+
+		let unknown = update("interface a { use b.{foo} func f(x: bar) }");
+
+		// errors in the imported file are reported on the `use` item
+		std::fs::write(dir.join("b.wit"), b.replace("x: foo", "x: baz")).unwrap();
+
+		let imported = update("interface a { use b.{foo} func f(x: foo) }");
+
+		std::fs::remove_dir_all(&dir).unwrap();
+
+		assert_eq!(valid, []);
+
+		assert_eq!(unknown.len(), 1);
+		assert_eq!(unknown[0].range, Range::new(Position::new(0, 36), Position::new(0, 39)));
+
+		assert_eq!(imported.len(), 1);
+		assert_eq!(imported[0].range, Range::new(Position::new(0, 21), Position::new(0, 24)));
+		assert!(imported[0].message.contains("b.wit"), "{}", imported[0].message);
+
+		let related = imported[0].related_information.as_ref().unwrap();
+
+		assert_eq!(related[0].location.uri, Url::from_file_path(dir.join("b.wit")).unwrap());
+		assert_eq!(related[0].location.range.start, Position::new(0, 46));
+	}
+
+	#[test]
+	fn cyclic_imports() {
+		let dir = std::env::temp_dir().join(format!("lsp-cycle-{}", std::process::id()));
+
+		std::fs::create_dir_all(&dir).unwrap();
+
+		let b = "interface b { use a.{bar} record foo { x: bar } func g(x: foo) }";
+
+		std::fs::write(dir.join("b.wit"), b).unwrap();
+
+		let uri = Url::from_file_path(dir.join("a.wit")).unwrap();
+
+		let (mut server, client) = server();
+
+		// the document isn't saved, the import of it is located in its text
+		let text = "interface a {\n\tuse b.{foo}\n\trecord bar { x: u32 }\n\tfunc f(x: foo)\n}";
+
+		server.update(uri.clone(), text.to_string()).unwrap();
+
+		let diagnostics = diagnostics(&client);
+
+		std::fs::remove_dir_all(&dir).unwrap();
+
+		assert_eq!(diagnostics.len(), 1);
+
+		let related = diagnostics[0].related_information.as_ref().unwrap();
+
+		let locations = related
+			.iter()
+			.map(|related| (related.location.uri.clone(), related.location.range.start))
+			.collect::<Vec<_>>();
+
+		assert_eq!(locations, [
+			(Url::from_file_path(dir.join("b.wit")).unwrap(), Position::new(0, 18)),
+			(uri, Position::new(1, 5)),
+		]);
+	}
+}
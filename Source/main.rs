@@ -1,11 +1,16 @@
 #![allow(dead_code, unused_variables)]
 
 mod completions;
+mod diff;
+mod fmt;
+mod lint;
 mod logger;
+mod lsp;
 
 use std::{
 	collections::HashSet,
 	path::{Path, PathBuf},
+	process::ExitCode,
 	time::Instant,
 };
 
@@ -42,6 +47,18 @@ enum Command {
 	Guest(GuestGenerator),
 	/// Print shell completions to stdout
 	Completions(completions::Completions),
+	/// Compare two versions of a definition file and report breaking
+	/// changes, exits with status 1 if there are any.
+	Diff(diff::Diff),
+	/// Format definition files, `--check` exits with status 1 if any of them
+	/// isn't formatted.
+	Fmt(fmt::Fmt),
+	/// Check a definition file for likely mistakes, exits with status 1 if
+	/// any denied rule is broken.
+	Lint(lint::Lint),
+	/// Run a language server for definition files, talking to the editor
+	/// through stdin and stdout.
+	Lsp,
 	/// This generator outputs a Markdown file describing an interface.
 	#[cfg(feature = "unstable")]
 	Markdown {
@@ -98,7 +115,8 @@ struct WorldOpt {
 	// #[clap(value_name = "DOCUMENT", value_parser = parse_interface)]
 	/// Generate bindings for the WIT document.
 	wit:PathBuf,
-	/// Names of functions to skip generating bindings for.
+	/// Names of functions to skip generating bindings for. Resource methods
+	/// are addressed as `resource.method`.
 	#[clap(long)]
 	skip:Vec<String>,
 }
@@ -114,10 +132,14 @@ struct Common {
 }
 
 // A thin wrapper around `run` so we can pretty-print the error
-fn main() {
+fn main() -> ExitCode {
 	if let Err(err) = run() {
 		log::error!("{:?}", err);
+
+		return ExitCode::FAILURE;
 	}
+
+	ExitCode::SUCCESS
 }
 
 fn run() -> Result<()> {
@@ -154,6 +176,22 @@ fn run() -> Result<()> {
 		Command::Completions(opts) => {
 			completions::run(&opts)?;
 		},
+		Command::Diff(opts) => {
+			if diff::run(&opts)? {
+				std::process::exit(1);
+			}
+		},
+		Command::Fmt(opts) => {
+			if fmt::run(&opts)? {
+				std::process::exit(1);
+			}
+		},
+		Command::Lint(opts) => {
+			if lint::run(&opts)? {
+				std::process::exit(1);
+			}
+		},
+		Command::Lsp => lsp::run()?,
 		#[cfg(feature = "unstable")]
 		Command::Markdown { builder, world } => {
 			let (path, contents) = gen_interface(builder, world)?;
@@ -169,7 +207,7 @@ fn run() -> Result<()> {
 			let skipset:HashSet<String, std::collections::hash_map::RandomState> =
 				world.skip.into_iter().collect();
 
-			let iface = wit_parser::parse_and_resolve_file(&world.wit, |t| skipset.contains(t))?;
+			let iface = wit_parser::parse_and_resolve_file_skipping(&world.wit, &skipset)?;
 
 			let stdout = std::io::stdout().lock();
 
@@ -216,7 +254,11 @@ where
 	let skipset:HashSet<String, std::collections::hash_map::RandomState> =
 		opts.skip.into_iter().collect();
 
-	let iface = wit_parser::parse_and_resolve_file(&opts.wit, |t| skipset.contains(t))?;
+	let iface = wit_parser::parse_and_resolve_file_skipping(&opts.wit, &skipset)?;
+
+	if let Err(message) = builder.check(&iface) {
+		bail!("{message}");
+	}
 
 	let mut gen = builder.build(iface);
 
@@ -233,7 +275,7 @@ fn check_interface(opts:WorldOpt) -> Result<()> {
 	let skipset:HashSet<String, std::collections::hash_map::RandomState> =
 		opts.skip.into_iter().collect();
 
-	wit_parser::parse_and_resolve_file(&opts.wit, |t| skipset.contains(t))?;
+	wit_parser::parse_and_resolve_file_skipping(&opts.wit, &skipset)?;
 
 	Ok(())
 }
@@ -15,6 +15,16 @@ use wit_parser::{
 };
 
 pub trait GeneratorBuilder {
+	/// Checks that the options fit the interface, before [`build`] is called
+	/// with it.
+	///
+	/// [`build`]: GeneratorBuilder::build
+	///
+	/// # Errors
+	///
+	/// Returns a message describing the first option that doesn't fit.
+	fn check(&self, _interface:&Interface) -> Result<(), String> { Ok(()) }
+
 	fn build(self, interface:Interface) -> Box<dyn Generate>;
 }
 
@@ -79,6 +89,12 @@ bitflags::bitflags! {
 		const RESULT = 0b0000_0010;
 		/// Whether or not this type (transitively) has a list.
 		const HAS_LIST = 0b0000_1000;
+		/// Whether or not this type is ever used (transitively) within a
+		/// function exported by the webview.
+		const EXPORT = 0b0001_0000;
+		/// Whether or not this type (transitively) has a resource handle. Like
+		/// lists, handles are borrowed when passed as parameters.
+		const HAS_RESOURCE = 0b0010_0000;
 	}
 }
 
@@ -96,30 +112,57 @@ impl TypeInfos {
 		let mut this = Self::default();
 
 		for func in functions {
-			for (_, ty) in &func.params {
-				this.collect_type_info(typedefs, ty, TypeInfo::PARAM);
-			}
-
-			match &func.result {
-				Some(FunctionResult::Anon(ty)) => {
-					this.collect_type_info(typedefs, ty, TypeInfo::RESULT);
-				},
-				Some(FunctionResult::Named(results)) => {
-					for (_, ty) in results {
-						this.collect_type_info(typedefs, ty, TypeInfo::RESULT);
-					}
-				},
-				None => {},
-			}
+			this.collect_function_info(typedefs, func, TypeInfo::PARAM, TypeInfo::RESULT);
 		}
 
 		for (id, typedef) in typedefs {
-			log::debug!("type info: {} {:#?}", typedef.ident, this[id]);
+			log::debug!("type info: {} {:#?}", typedef.ident, this.infos.get(&id));
 		}
 
 		this
 	}
 
+	/// Adds the types used by functions exported by the webview.
+	///
+	/// Values of these functions travel in the opposite direction, so
+	/// generators choose the info assigned to the types of their parameters
+	/// and results. [`TypeInfo::EXPORT`] is added to both.
+	#[must_use]
+	pub fn with_exports<'a>(
+		mut self,
+		typedefs:&TypeDefArena,
+		functions:impl Iterator<Item = &'a Function>,
+		params:TypeInfo,
+		results:TypeInfo,
+	) -> Self {
+		for func in functions {
+			self.collect_function_info(
+				typedefs,
+				func,
+				params | TypeInfo::EXPORT,
+				results | TypeInfo::EXPORT,
+			);
+		}
+
+		self
+	}
+
+	fn collect_function_info(
+		&mut self,
+		typedefs:&TypeDefArena,
+		func:&Function,
+		params:TypeInfo,
+		results:TypeInfo,
+	) {
+		for (_, ty) in &func.params {
+			self.collect_type_info(typedefs, ty, params);
+		}
+
+		for ty in func.result.iter().flat_map(FunctionResult::types) {
+			self.collect_type_info(typedefs, ty, results);
+		}
+	}
+
 	fn collect_typedef_info(
 		&mut self,
 		typedefs:&TypeDefArena,
@@ -168,7 +211,7 @@ impl TypeInfos {
 		match ty {
 			Type::String => base_info | TypeInfo::HAS_LIST,
 			Type::List(ty) => self.collect_type_info(typedefs, ty, base_info) | TypeInfo::HAS_LIST,
-			Type::Option(ty) => self.collect_type_info(typedefs, ty, base_info),
+			Type::Option(ty) | Type::Stream(ty) => self.collect_type_info(typedefs, ty, base_info),
 			Type::Tuple(types) => {
 				let mut info = base_info;
 
@@ -191,7 +234,24 @@ impl TypeInfos {
 
 				info
 			},
-			Type::Id(id) => base_info | self.collect_typedef_info(typedefs, *id, base_info),
+			Type::Id(id) => {
+				let info = base_info | self.collect_typedef_info(typedefs, *id, base_info);
+
+				if let TypeDefKind::Resource(_) = typedefs[*id].kind {
+					info | TypeInfo::HAS_RESOURCE
+				} else {
+					info
+				}
+			},
+			Type::Generic { id, args } => {
+				let mut info = base_info | self.collect_typedef_info(typedefs, *id, base_info);
+
+				for ty in args {
+					info |= self.collect_type_info(typedefs, ty, base_info);
+				}
+
+				info
+			},
 			_ => base_info,
 		}
 	}
@@ -235,10 +295,12 @@ fn type_ident(typedefs:&TypeDefArena, ty:&Type) -> String {
 		Type::Char => "Char".to_string(),
 		Type::String => "String".to_string(),
 		Type::List(ty) => format!("{}List", type_ident(typedefs, ty)),
+		Type::Stream(ty) => format!("{}Stream", type_ident(typedefs, ty)),
 		Type::Tuple(_) => "Tuple".to_string(),
 		Type::Option(ty) => format!("Optional{}", type_ident(typedefs, ty)),
 		Type::Result { .. } => "Result".to_string(),
-		Type::Id(id) => {
+		Type::Param(name) => name.to_string(),
+		Type::Id(id) | Type::Generic { id, .. } => {
 			match &typedefs[*id].kind {
 				TypeDefKind::Alias(ty) => type_ident(typedefs, ty),
 				TypeDefKind::Record(_) => "Record".to_string(),
@@ -306,3 +368,210 @@ pub fn union_case_names(typedefs:&TypeDefArena, cases:&[UnionCase]) -> Vec<Strin
 
 	case_names
 }
+
+/// A stable hash of everything that determines how the parameters and results
+/// of a function are encoded, as 16 hexadecimal digits.
+///
+/// Host and guest bindings embed the hash of every function so the host can
+/// reject calls of pages built from an incompatible version of the function.
+/// The hash covers the function's name and the types it reaches, so adding
+/// functions or changing others leaves it as is. Documentation, the names of
+/// parameters and types, flags that fit into the same integer and fields with
+/// a default appended to records don't affect the hash, so it only changes
+/// when `tauri-bindgen diff` reports a breaking change.
+#[must_use]
+pub fn function_hash(typedefs:&TypeDefArena, func:&Function) -> String {
+	let schema = describe_function(typedefs, func);
+
+	// 64 bit FNV-1a, unlike the hashers of the standard library it is
+	// guaranteed to stay the same across Rust versions
+	let hash = schema.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+		(hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+	});
+
+	format!("{hash:016x}")
+}
+
+fn describe_function(typedefs:&TypeDefArena, func:&Function) -> String {
+	let params = func
+		.params
+		.iter()
+		.map(|(_, ty)| describe_type(typedefs, ty))
+		.collect::<Vec<_>>()
+		.join(", ");
+
+	let result = match &func.result {
+		Some(FunctionResult::Anon(ty)) => format!(" -> {}", describe_type(typedefs, ty)),
+		Some(FunctionResult::Named(types)) => {
+			let types = types
+				.iter()
+				.map(|(_, ty)| describe_type(typedefs, ty))
+				.collect::<Vec<_>>()
+				.join(", ");
+
+			format!(" -> ({types})")
+		},
+		None => String::new(),
+	};
+
+	format!("{}({params}){result}", func.id)
+}
+
+/// Describes the type with its definition inlined, so renaming a type
+/// definition doesn't change the description.
+fn describe_type(typedefs:&TypeDefArena, ty:&Type) -> String {
+	let describe_all = |types:&mut dyn Iterator<Item = &Type>| {
+		types.map(|ty| describe_type(typedefs, ty)).collect::<Vec<_>>().join(", ")
+	};
+
+	match ty {
+		Type::Bool => "bool".to_string(),
+		Type::U8 => "u8".to_string(),
+		Type::U16 => "u16".to_string(),
+		Type::U32 => "u32".to_string(),
+		Type::U64 => "u64".to_string(),
+		Type::U128 => "u128".to_string(),
+		Type::S8 => "s8".to_string(),
+		Type::S16 => "s16".to_string(),
+		Type::S32 => "s32".to_string(),
+		Type::S64 => "s64".to_string(),
+		Type::S128 => "s128".to_string(),
+		Type::Float32 => "float32".to_string(),
+		Type::Float64 => "float64".to_string(),
+		Type::Char => "char".to_string(),
+		Type::String => "string".to_string(),
+		Type::List(ty) => format!("list<{}>", describe_type(typedefs, ty)),
+		Type::Stream(ty) => format!("stream<{}>", describe_type(typedefs, ty)),
+		Type::Tuple(types) => format!("tuple<{}>", describe_all(&mut types.iter())),
+		Type::Option(ty) => format!("option<{}>", describe_type(typedefs, ty)),
+		Type::Result { ok, err } => {
+			let ok = ok.as_ref().map_or("_".to_string(), |ty| describe_type(typedefs, ty));
+
+			let err = err.as_ref().map_or("_".to_string(), |ty| describe_type(typedefs, ty));
+
+			format!("result<{ok}, {err}>")
+		},
+		Type::Param(name) => name.to_string(),
+		Type::Generic { id, args } => {
+			format!("{}<{}>", describe_typedef(typedefs, *id), describe_all(&mut args.iter()))
+		},
+		Type::Id(id) => describe_typedef(typedefs, *id),
+	}
+}
+
+fn describe_typedef(typedefs:&TypeDefArena, id:TypeDefId) -> String {
+	let describe_all = |types:&mut dyn Iterator<Item = &Type>| {
+		types.map(|ty| describe_type(typedefs, ty)).collect::<Vec<_>>().join(", ")
+	};
+
+	let typedef = &typedefs[id];
+
+	match &typedef.kind {
+		TypeDefKind::Alias(ty) => describe_type(typedefs, ty),
+		TypeDefKind::Record(fields) => {
+			// fields with a default can be appended to records without
+			// breaking callers, so trailing ones don't count
+			let len = fields.iter().rposition(|field| field.default.is_none()).map_or(0, |i| i + 1);
+
+			let fields = fields[..len]
+				.iter()
+				.map(|field| format!("{}: {}", field.id, describe_type(typedefs, &field.ty)))
+				.collect::<Vec<_>>()
+				.join(", ");
+
+			format!("record {{ {fields} }}")
+		},
+		// flags are sent as a bit set, appending a flag that fits into the same
+		// integer keeps the encoding
+		TypeDefKind::Flags(fields) => {
+			let repr = match flags_repr(fields) {
+				Int::U8 => "u8",
+				Int::U16 => "u16",
+				Int::U32 => "u32",
+				Int::U64 => "u64",
+				Int::U128 => "u128",
+			};
+
+			format!("flags<{repr}>")
+		},
+		TypeDefKind::Variant(cases) => {
+			let cases = cases
+				.iter()
+				.map(|case| {
+					match &case.ty {
+						Some(ty) => format!("{}({})", case.id, describe_type(typedefs, ty)),
+						None => case.id.clone(),
+					}
+				})
+				.collect::<Vec<_>>()
+				.join(", ");
+
+			format!("variant {{ {cases} }}")
+		},
+		TypeDefKind::Enum(cases) => {
+			let cases = cases.iter().map(|case| case.id.as_str()).collect::<Vec<_>>().join(", ");
+
+			format!("enum {{ {cases} }}")
+		},
+		TypeDefKind::Union(cases) => {
+			format!("union {{ {} }}", describe_all(&mut cases.iter().map(|case| &case.ty)))
+		},
+		// handles are sent as ids, the functions of resources have hashes of
+		// their own
+		TypeDefKind::Resource(_) => format!("resource {}", typedef.ident),
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn hash_of(source:&str, name:&str) -> String {
+		let iface = wit_parser::parse_and_resolve_str(source, |_| false).unwrap();
+
+		let func = iface.functions.iter().find(|func| func.id == name).unwrap();
+
+		function_hash(&iface.typedefs, func)
+	}
+
+	#[test]
+	fn hash_ignores_compatible_changes() {
+		let base = hash_of(
+			"interface files { record meta { size: u64 } flags mode { read } func stat(path: \
+			 string) -> (meta: meta, mode: mode) }",
+			"stat",
+		);
+
+		// compatible changes
+		let added = hash_of(
+			"interface files { record meta { size: u64 } flags mode { read, write } func \
+			 stat(p: string) -> (meta: meta, mode: mode) func remove(path: string) }",
+			"stat",
+		);
+
+		let defaulted = hash_of(
+			"interface files { record meta { size: u64, mtime: u64 = 0 } flags mode { read } func \
+			 stat(path: string) -> (meta: meta, mode: mode) }",
+			"stat",
+		);
+
+		let renamed = hash_of(
+			"interface files { record info { size: u64 } flags access { read } func stat(path: \
+			 string) -> (meta: info, mode: access) }",
+			"stat",
+		);
+
+		assert_eq!(base, added);
+		assert_eq!(base, defaulted);
+		assert_eq!(base, renamed);
+
+		// breaking changes
+		let changed = hash_of(
+			"interface files { record meta { size: u32 } flags mode { read } func stat(path: \
+			 string) -> (meta: meta, mode: mode) }",
+			"stat",
+		);
+
+		assert_ne!(base, changed);
+	}
+}
@@ -0,0 +1,22 @@
+/**
+ * Sends all calls to the host made by `calls` in a single request.
+ *
+ * Only calls made synchronously are part of the batch, so `calls` must not
+ * await anything before making them. The calls run concurrently on the host.
+ *
+ * ```js
+ * const [user, settings] = await batch(() => [getUser(id), getSettings()])
+ * ```
+ * @template T
+ * @param {() => T} calls
+ * @returns {Promise<{ -readonly [K in keyof T]: Awaited<T[K]> }>}
+ */
+export async function batch(calls) {
+	const [results, pending] = collectBatch(calls);
+
+	if (pending.length > 0) {
+		await sendBatch(pending);
+	}
+
+	return Promise.all(results);
+}
@@ -0,0 +1,6 @@
+/**
+ * Options accepted by every function calling the host.
+ * @typedef {Object} CallOptions
+ * @property {AbortSignal} [signal] Cancels the call, the host stops working on
+ * it and the call fails with an `IpcError` of kind `cancelled`.
+ */
@@ -0,0 +1,26 @@
+/**
+ * The error thrown when a call to the host fails.
+ */
+export class IpcError extends Error {
+	/**
+	 * `transport` when the host couldn't be reached, `not-found` when it doesn't
+	 * define the function, `permission-denied` when the webview lacks a required
+	 * capability, `schema-mismatch` when the bindings were generated from
+	 * another version of the interface than the host, `deserialize` when it
+	 * couldn't decode the parameters, `host` when the function failed, `panic`
+	 * when it panicked and `cancelled` when the call was cancelled through its
+	 * `AbortSignal`.
+	 * @type {'transport' | 'not-found' | 'permission-denied' | 'schema-mismatch' | 'deserialize' | 'host' | 'panic' | 'cancelled'}
+	 */
+	kind;
+
+	/**
+	 * @param {'transport' | 'not-found' | 'permission-denied' | 'schema-mismatch' | 'deserialize' | 'host' | 'panic' | 'cancelled'} kind
+	 * @param {string} message
+	 */
+	constructor(kind, message) {
+		super(message);
+		this.name = "IpcError";
+		this.kind = kind;
+	}
+}
@@ -3,9 +3,16 @@
 use std::{fmt::Write, path::PathBuf};
 
 use heck::{ToKebabCase, ToLowerCamelCase, ToSnakeCase, ToUpperCamelCase};
-use tauri_bindgen_core::{Generate, GeneratorBuilder, TypeInfo, TypeInfos, postprocess};
-use tauri_bindgen_gen_js::{JavaScriptGenerator, SerdeUtils};
-use wit_parser::{Function, FunctionResult, Interface, Type, TypeDefKind};
+use tauri_bindgen_core::{
+	Generate,
+	GeneratorBuilder,
+	TypeInfo,
+	TypeInfos,
+	function_hash,
+	postprocess,
+};
+use tauri_bindgen_gen_js::{JavaScriptGenerator, SerdeUtils, print_deprecated_tag};
+use wit_parser::{Function, FunctionResult, Interface, Resource, Type, TypeDefKind};
 
 #[derive(Debug, Clone, Default)]
 #[cfg_attr(feature = "clap", derive(clap::Args))]
@@ -22,6 +29,10 @@ pub struct Builder {
 	/// faster than `prettier`. Requires a global installation of `rome`.
 	#[cfg_attr(feature = "clap", clap(long))]
 	pub romefmt:bool,
+	/// Encode calls as JSON instead of postcard, which makes them readable in
+	/// the network tab of the devtools at the cost of larger messages.
+	#[cfg_attr(feature = "clap", clap(long))]
+	pub json:bool,
 }
 
 impl GeneratorBuilder for Builder {
@@ -30,21 +41,34 @@ impl GeneratorBuilder for Builder {
 			.typedefs
 			.iter()
 			.filter_map(|(_, typedef)| {
-				if let TypeDefKind::Resource(methods) = &typedef.kind {
-					Some(methods.iter())
+				if let TypeDefKind::Resource(resource) = &typedef.kind {
+					Some(resource.functions())
 				} else {
 					None
 				}
 			})
-			.flatten();
+			.flatten()
+			.collect::<Vec<_>>();
 
+		// exported functions deserialize their parameters and serialize their
+		// results, the opposite of imported ones
 		let infos = TypeInfos::collect_from_functions(
 			&interface.typedefs,
-			interface.functions.iter().chain(methods),
+			interface.functions.iter().chain(methods.iter().copied()),
+		)
+		.with_exports(
+			&interface.typedefs,
+			interface.exports.iter(),
+			TypeInfo::RESULT,
+			TypeInfo::PARAM,
 		);
 
-		let serde_utils =
-			SerdeUtils::collect_from_functions(&interface.typedefs, &interface.functions);
+		let serde_utils = SerdeUtils::collect_from_functions(
+			&interface.typedefs,
+			interface.functions.iter().chain(methods),
+		) | SerdeUtils::collect_from_exports(&interface.typedefs, &interface.exports);
+
+		let serde_utils = if self.json { serde_utils.for_json() } else { serde_utils };
 
 		Box::new(JavaScript { opts:self, interface, infos, serde_utils })
 	}
@@ -68,27 +92,23 @@ impl JavaScript {
 
 		let params = print_function_params(&func.params);
 
-		let deserialize_result = func
+		let decode_result = func
 			.result
 			.as_ref()
-			.map(|res| self.print_deserialize_function_result(res))
+			.map(|res| self.print_decode_function_result(res))
 			.unwrap_or_default();
 
-		let serialize_params = func
-			.params
-			.iter()
-			.map(|(ident, ty)| self.print_serialize_ty(&ident.to_lower_camel_case(), ty))
-			.collect::<Vec<_>>()
-			.join(";\n");
+		let encode_params = self.print_encode_params(&func.params, None);
+
+		let hash = function_hash(&self.interface.typedefs, func);
 
 		format!(
 			r#"
 {docs}
 export async function {ident} ({params}) {{
-    const out = []
-    {serialize_params}
+    {encode_params}
 
-    return fetch('ipc://localhost/{intf_name}/{name}', {{ method: "POST", body: Uint8Array.from(out), headers: {{ 'Content-Type': 'application/octet-stream' }} }}){deserialize_result}
+    return fetchIpc('ipc://localhost/{intf_name}/{name}', out, callOptions?.signal, '{hash}'){decode_result}
 }}
 "#
 		)
@@ -99,75 +119,208 @@ export async function {ident} ({params}) {{
 		mod_ident:&str,
 		docs:&str,
 		ident:&str,
-		functions:&[Function],
+		resource:&Resource,
 		info:TypeInfo,
 	) -> String {
-		let ident = ident.to_upper_camel_case();
+		let mod_ident = mod_ident.to_snake_case();
 
-		let functions: String = functions
-            .iter()
-            .fold(String::new(), |mut str, func| {
-                let docs = self.print_docs(func);
+		let resource_ident = ident.to_snake_case();
 
-                let mod_ident = mod_ident.to_snake_case();
+		let ident = ident.to_upper_camel_case();
 
-                let resource_ident = ident.to_snake_case();
+		let url = format!("ipc://localhost/{mod_ident}::resource::{resource_ident}");
 
-                let ident = func.id.to_lower_camel_case();
+		let constructor = resource
+			.constructor
+			.as_ref()
+			.map(|func| self.print_resource_function(&url, "static async create", "constructor", func))
+			.unwrap_or_default();
 
-                let params = print_function_params(&func.params);
+		let statics:String = resource
+			.statics
+			.iter()
+			.map(|func| {
+				let prefix = format!("static async {}", func.id.to_lower_camel_case());
 
-                let deserialize_result = func
-                    .result
-                    .as_ref()
-                    .map(|res| self.print_deserialize_function_result(res))
-                    .unwrap_or_default();
+				self.print_resource_function(&url, &prefix, &func.id.to_snake_case(), func)
+			})
+			.collect();
 
-                let serialize_params = func
-                    .params
-                    .iter()
-                    .map(|(ident, ty)| self.print_serialize_ty(&ident.to_lower_camel_case(), ty))
-                    .collect::<Vec<_>>()
-                    .join(";\n");
+		let methods:String = resource
+			.methods
+			.iter()
+			.map(|func| {
+				let prefix = format!("async {}", func.id.to_lower_camel_case());
 
-                let _ = write!(str,
-                    r#"{docs}
-async {ident} ({params}) {{
-    const out = []
+				self.print_resource_function(&url, &prefix, &func.id.to_snake_case(), func)
+			})
+			.collect();
+
+		let serialize = match (info.contains(TypeInfo::PARAM), self.opts.json) {
+			(false, _) => String::new(),
+			(true, false) => {
+				"serialize(out) {
     serializeU32(out, this.#id);
-    {serialize_params}
+}"
+				.to_string()
+			},
+			(true, true) => {
+				"toJson() {
+    return this.#id
+}"
+				.to_string()
+			},
+		};
 
-    await fetch('ipc://localhost/{mod_ident}::resource::{resource_ident}/{ident}', {{ method: "POST", body: Uint8Array.from(out), headers: {{ 'Content-Type': 'application/octet-stream' }} }}){deserialize_result}
-}}
-"#
-                );
+		let deserialize = match (info.contains(TypeInfo::RESULT), self.opts.json) {
+			(false, _) => String::new(),
+			(true, false) => {
+				format!(
+					"static deserialize(de) {{
+    const self = new {ident}();
 
-                str
-            });
+    self.#id = deserializeU32(de);
 
-		let deserialize = if info.contains(TypeInfo::RESULT) {
-			format!(
-				"static deserialize(de) {{
+    return self
+}}"
+				)
+			},
+			(true, true) => {
+				format!(
+					"static fromJson(id) {{
     const self = new {ident}();
 
-    self.#id = deserializeU32(de);
+    self.#id = id;
 
     return self
 }}"
-			)
+				)
+			},
+		};
+
+		let encode_id = if self.opts.json {
+			"const out = stringifyJson(this.#id)"
 		} else {
-			String::new()
+			"const out = []
+                serializeU32(out, this.#id);"
 		};
 
 		format!(
 			"{docs}\nexport class {ident} {{
             #id;
-            {functions}
+            {constructor}
+            {statics}
+            {methods}
+            {serialize}
             {deserialize}
+            /**
+             * Releases the resource on the host, it must not be used afterwards.
+             * @returns {{Promise<void>}}
+             */
+            async dispose() {{
+                {encode_id}
+
+                await fetchIpc('{url}/drop', out)
+            }}
+
+            [Symbol.dispose ?? Symbol.for('Symbol.dispose')]() {{
+                this.dispose()
+            }}
         }}"
 		)
 	}
 
+	/// Prints a function of a resource, `prefix` is everything preceding the
+	/// parameter list. Functions that aren't static pass the handle of the
+	/// resource as their first parameter.
+	fn print_resource_function(
+		&self,
+		url:&str,
+		prefix:&str,
+		endpoint:&str,
+		func:&Function,
+	) -> String {
+		let docs = self.print_docs(func);
+
+		let params = print_function_params(&func.params);
+
+		let handle = if prefix.starts_with("static") { None } else { Some("this.#id") };
+
+		let decode_result = func
+			.result
+			.as_ref()
+			.map(|res| self.print_decode_function_result(res))
+			.unwrap_or_default();
+
+		let encode_params = self.print_encode_params(&func.params, handle);
+
+		let hash = function_hash(&self.interface.typedefs, func);
+
+		format!(
+			r#"{docs}
+{prefix} ({params}) {{
+    {encode_params}
+
+    return fetchIpc('{url}/{endpoint}', out, callOptions?.signal, '{hash}'){decode_result}
+}}
+"#
+		)
+	}
+
+	fn print_register_exports(&self, intf_name:&str) -> String {
+		if self.interface.exports.is_empty() {
+			return String::new();
+		}
+
+		let members = self
+			.interface
+			.exports
+			.iter()
+			.map(|func| {
+				let params = func
+					.params
+					.iter()
+					.map(|(ident, ty)| format!("{}: {}", ident.to_lower_camel_case(), self.print_ty(ty)))
+					.collect::<Vec<_>>()
+					.join(", ");
+
+				let result = match &func.result {
+					Some(FunctionResult::Anon(ty)) => self.print_ty(ty),
+					Some(FunctionResult::Named(types)) if !types.is_empty() => {
+						let types = types
+							.iter()
+							.map(|(_, ty)| self.print_ty(ty))
+							.collect::<Vec<_>>()
+							.join(", ");
+
+						format!("[{types}]")
+					},
+					_ => "void".to_string(),
+				};
+
+				format!(
+					"{ident}: ({params}) => Promise<{result}> | {result}",
+					ident = func.id.to_lower_camel_case()
+				)
+			})
+			.collect::<Vec<_>>()
+			.join(", ");
+
+		let handler = self.print_register_export_handler(intf_name, &self.interface.exports);
+
+		format!(
+			r#"
+/**
+ * Registers the implementation of the functions the host can call in this webview.
+ * @param {{{{ {members} }}}} impl
+ */
+export function registerExports (impl) {{
+    {handler}
+}}
+"#
+		)
+	}
+
 	fn print_docs(&self, func:&Function) -> String {
 		let docs = func.docs.lines().fold(String::new(), |mut str, line| {
 			let _ = writeln!(str, " * {line} \n");
@@ -175,6 +328,8 @@ async {ident} ({params}) {{
 			str
 		});
 
+		let deprecated = print_deprecated_tag(&func.annotations);
+
 		let param_docs = func.params.iter().fold(String::new(), |mut str, (name, ty)| {
 			let ident = &name.to_lower_camel_case();
 
@@ -185,6 +340,8 @@ async {ident} ({params}) {{
 			str
 		});
 
+		let param_docs = format!("{param_docs}* @param {{CallOptions}} [callOptions]\n");
+
 		let result_docs = func
 			.result
 			.as_ref()
@@ -208,7 +365,7 @@ async {ident} ({params}) {{
 			})
 			.unwrap_or_default();
 
-		format!("/**\n{docs}{param_docs}{result_docs}*/")
+		format!("/**\n{docs}{deprecated}{param_docs}{result_docs}*/")
 	}
 
 	fn print_ty(&self, ty:&Type) -> String {
@@ -239,6 +396,11 @@ async {ident} ({params}) {{
 
 				format!("{ty} | null")
 			},
+			Type::Stream(ty) => {
+				let ty = self.print_ty(ty);
+
+				format!("AsyncIterableIterator<{ty}>")
+			},
 			Type::Result { ok, err } => {
 				let ok = ok.as_ref().map_or("_".to_string(), |ty| self.print_ty(ty));
 
@@ -247,6 +409,12 @@ async {ident} ({params}) {{
 				format!("Result<{ok}, {err}>")
 			},
 			Type::Id(id) => self.interface.typedefs[*id].ident.to_upper_camel_case(),
+			Type::Generic { id, args } => {
+				let args = args.iter().map(|ty| self.print_ty(ty)).collect::<Vec<_>>().join(", ");
+
+				format!("{}<{args}>", self.interface.typedefs[*id].ident.to_upper_camel_case())
+			},
+			Type::Param(name) => name.to_upper_camel_case(),
 		}
 	}
 
@@ -271,8 +439,11 @@ async {ident} ({params}) {{
 			Type::U128
 			| Type::S128
 			| Type::Bool
+			| Type::Generic { .. }
+			| Type::Param(_)
 			| Type::Tuple(_)
 			| Type::List(_)
+			| Type::Stream(_)
 			| Type::Option(_)
 			| Type::Result { .. }
 			| Type::Char
@@ -285,6 +456,8 @@ impl JavaScriptGenerator for JavaScript {
 	fn interface(&self) -> &Interface { &self.interface }
 
 	fn infos(&self) -> &TypeInfos { &self.infos }
+
+	fn json(&self) -> bool { self.opts.json }
 }
 
 impl Generate for JavaScript {
@@ -297,7 +470,7 @@ impl Generate for JavaScript {
 				let info = self.infos[id];
 
 				if info.contains(TypeInfo::RESULT) {
-					Some(self.print_deserialize_typedef(id))
+					Some(if self.opts.json { self.print_from_json_typedef(id) } else { self.print_deserialize_typedef(id) })
 				} else {
 					None
 				}
@@ -312,7 +485,7 @@ impl Generate for JavaScript {
 				let info = self.infos[id];
 
 				if info.contains(TypeInfo::PARAM) {
-					Some(self.print_serialize_typedef(id))
+					Some(if self.opts.json { self.print_to_json_typedef(id) } else { self.print_serialize_typedef(id) })
 				} else {
 					None
 				}
@@ -333,12 +506,12 @@ impl Generate for JavaScript {
 			.filter_map(|(id, typedef)| {
 				let info = self.infos[id];
 
-				if let TypeDefKind::Resource(functions) = &typedef.kind {
+				if let TypeDefKind::Resource(resource) = &typedef.kind {
 					Some(self.print_resource(
 						&self.interface.ident,
 						&typedef.docs,
 						&typedef.ident,
-						functions,
+						resource,
 						info,
 					))
 				} else {
@@ -347,11 +520,21 @@ impl Generate for JavaScript {
 			})
 			.collect();
 
+		let exports = self.print_register_exports(&self.interface.ident.to_snake_case());
+
+		let ipc_error = include_str!("./ipc_error.js");
+
+		let batch = include_str!("./batch.js");
+
+		let call_options = include_str!("./call_options.js");
+
 		let serde_utils = self.serde_utils.to_string();
 
+		let constants = self.print_constants();
+
 		let mut contents = format!(
-			"{serde_utils}{deserializers}{serializers}\n{functions}\\
-			 n{resources}"
+			"{constants}{ipc_error}{call_options}{batch}{serde_utils}{deserializers}{serializers}\n{functions}\\
+			 n{resources}{exports}"
 		);
 
 		if self.opts.prettier {
@@ -370,10 +553,13 @@ impl Generate for JavaScript {
 	}
 }
 
+/// Prints the parameters of a function calling the host, which are followed by
+/// the options of the call.
 fn print_function_params(params:&[(String, Type)]) -> String {
 	params
 		.iter()
 		.map(|(name, _)| name.to_lower_camel_case())
+		.chain(std::iter::once("callOptions".to_string()))
 		.collect::<Vec<_>>()
 		.join(", ")
 }
@@ -3,12 +3,12 @@
 use std::path::PathBuf;
 
 use heck::{ToKebabCase, ToSnakeCase};
-use proc_macro2::TokenStream;
+use proc_macro2::{Literal, TokenStream};
 use quote::{format_ident, quote};
 use syn::parse_quote;
-use tauri_bindgen_core::{Generate, GeneratorBuilder, TypeInfo, TypeInfos};
+use tauri_bindgen_core::{Generate, GeneratorBuilder, TypeInfo, TypeInfos, function_hash};
 use tauri_bindgen_gen_rust::{BorrowMode, FnSig, RustGenerator};
-use wit_parser::{Function, Interface, TypeDefKind};
+use wit_parser::{Annotation, Function, FunctionResult, Interface, Resource, TypeDefKind};
 
 #[derive(Default, Debug, Clone)]
 #[cfg_attr(feature = "clap", derive(clap::Args))]
@@ -34,17 +34,25 @@ impl GeneratorBuilder for Builder {
 			.typedefs
 			.iter()
 			.filter_map(|(_, typedef)| {
-				if let TypeDefKind::Resource(methods) = &typedef.kind {
-					Some(methods.iter())
+				if let TypeDefKind::Resource(resource) = &typedef.kind {
+					Some(resource.functions())
 				} else {
 					None
 				}
 			})
 			.flatten();
 
+		// values of exported functions are received and returned by the
+		// implementation, so they use the owned variant of their types
 		let infos = TypeInfos::collect_from_functions(
 			&interface.typedefs,
 			interface.functions.iter().chain(methods),
+		)
+		.with_exports(
+			&interface.typedefs,
+			interface.exports.iter(),
+			TypeInfo::RESULT,
+			TypeInfo::RESULT,
 		);
 
 		Box::new(RustWasm { opts:self, interface, infos })
@@ -59,24 +67,135 @@ pub struct RustWasm {
 
 impl RustWasm {
 	pub fn print_function(&self, mod_ident:&str, func:&Function) -> TokenStream {
-		let sig = FnSig { async_:true, unsafe_:false, private:false, self_arg:None, func };
+		let hash = function_hash(&self.interface.typedefs, func);
 
-		let sig = self.print_function_signature(
-			&sig,
-			&BorrowMode::AllBorrowed(parse_quote!('_)),
-			&BorrowMode::Owned,
-		);
+		self.print_invoke_function(mod_ident, &func.id.to_snake_case(), func, &hash)
+	}
 
-		let ident = func.id.to_snake_case();
+	/// Prints a function without receiver that invokes `endpoint` of the given
+	/// module, sending the schema `hash` of the function.
+	fn print_invoke_function(
+		&self,
+		mod_ident:&str,
+		endpoint:&str,
+		func:&Function,
+		hash:&str,
+	) -> TokenStream {
+		let sig = self.print_invoke_signature(func, None);
 
 		let param_idents = func.params.iter().map(|(ident, _)| format_ident!("{}", ident));
 
 		quote! {
 			#sig {
-				::tauri_bindgen_guest_rust::invoke(#mod_ident, #ident, &(#(#param_idents),*)).await.unwrap()
+				::tauri_bindgen_guest_rust::invoke_with_schema(#hash, #mod_ident, #endpoint, &(#(#param_idents),*)).await
 			}
 		}
 	}
+
+	/// Prints the signature of a function calling the host, the result is
+	/// wrapped in a `Result` since the call itself can fail.
+	fn print_invoke_signature(&self, func:&Function, self_arg:Option<TokenStream>) -> TokenStream {
+		let docs = self.print_docs(&func.docs);
+
+		let deprecated = self.print_deprecated(&func.annotations);
+
+		let ident = format_ident!("{}", func.id.to_snake_case());
+
+		let self_arg = self_arg.map(|arg| quote! { #arg, });
+
+		let params =
+			self.print_function_params(&func.params, &BorrowMode::AllBorrowed(parse_quote!('_)));
+
+		let result = match &func.result {
+			Some(FunctionResult::Anon(ty)) => self.print_ty(ty, &BorrowMode::Owned),
+			Some(FunctionResult::Named(types)) if types.len() == 1 => {
+				self.print_ty(&types[0].1, &BorrowMode::Owned)
+			},
+			Some(FunctionResult::Named(types)) => {
+				let types = types.iter().map(|(_, ty)| self.print_ty(ty, &BorrowMode::Owned));
+
+				quote! { (#(#types),*) }
+			},
+			None => quote! { () },
+		};
+
+		quote! {
+			#docs
+			#deprecated
+			pub async fn #ident (#self_arg #params) -> ::core::result::Result<#result, ::tauri_bindgen_guest_rust::Error>
+		}
+	}
+
+	/// Prints the trait of the functions the interface exports to the host,
+	/// and the function registering its implementation.
+	fn print_exports(&self, mod_ident:&str) -> Option<TokenStream> {
+		if self.interface.exports.is_empty() {
+			return None;
+		}
+
+		let functions = self.interface.exports.iter().map(|func| {
+			let sig = FnSig { async_:true, unsafe_:false, private:true, self_arg:Some(quote!(&self)), func };
+
+			let sig = self.print_function_signature(&sig, &BorrowMode::Owned, &BorrowMode::Owned);
+
+			quote! { #sig; }
+		});
+
+		let cases = self.interface.exports.iter().map(|func| {
+			let name = func.id.to_snake_case();
+
+			let ident = format_ident!("{}", func.id.to_snake_case());
+
+			let tys = func.params.iter().map(|(_, ty)| self.print_ty(ty, &BorrowMode::Owned));
+
+			let (param_decl, param_acc) = match func.params.len() {
+				1 => (quote! { #(#tys)* }, quote! { p }),
+				_ => {
+					let ids = (0..func.params.len()).map(|i| {
+						let i = Literal::usize_unsuffixed(i);
+
+						quote! { p.#i }
+					});
+
+					(quote! { (#(#tys),*) }, quote! { #(#ids),* })
+				},
+			};
+
+			quote! {
+				#name => {
+					::tauri_bindgen_guest_rust::call_export(format, &params, move |p: #param_decl| async move {
+						exports.#ident(#param_acc).await
+					})
+					.await
+				}
+			}
+		});
+
+		Some(quote! {
+			/// The functions the host can call in this webview.
+			#[::tauri_bindgen_guest_rust::async_trait(?Send)]
+			pub trait Exports {
+				#(#functions)*
+			}
+
+			/// Registers the implementation of the functions the host can call in
+			/// this webview.
+			pub fn register_exports(exports: impl Exports + 'static) -> Result<(), ::tauri_bindgen_guest_rust::Error> {
+				let exports = ::std::rc::Rc::new(exports);
+
+				::tauri_bindgen_guest_rust::register_exports(#mod_ident, move |format, name, params| {
+					let exports = ::std::rc::Rc::clone(&exports);
+
+					async move {
+						match name.as_str() {
+							#(#cases)*
+							_ => Err(format!("unknown function {name}")),
+						}
+					}
+				})
+			}
+		})
+	}
 }
 
 impl RustGenerator for RustWasm {
@@ -92,9 +211,14 @@ impl RustGenerator for RustWasm {
 				attrs.push(quote! { serde::Serialize });
 			} else if ident.ends_with("Result") {
 				attrs.push(quote! { serde::Deserialize });
+
+				// results of exported functions are sent back to the host
+				if info.contains(TypeInfo::EXPORT) {
+					attrs.push(quote! { serde::Serialize });
+				}
 			}
 		} else {
-			if info.contains(TypeInfo::PARAM) {
+			if info.intersects(TypeInfo::PARAM | TypeInfo::EXPORT) {
 				attrs.push(quote! { serde::Serialize });
 			}
 
@@ -112,33 +236,44 @@ impl RustGenerator for RustWasm {
 		&self,
 		mod_ident:&str,
 		docs:&str,
+		annotations:&[Annotation],
 		ident:&proc_macro2::Ident,
-		functions:&[Function],
+		resource:&Resource,
 		info:TypeInfo,
 	) -> TokenStream {
 		let docs = self.print_docs(docs);
 
+		let deprecated = self.print_deprecated(annotations);
+
 		let additional_attrs = self.additional_attrs(&ident.to_string(), info);
 
-		let functions = functions.iter().map(|func| {
-            let sig = FnSig {
-                async_: true,
-                unsafe_: false,
-                private: false,
-                self_arg: Some(quote!(&self)),
-                func,
-            };
-
-            let sig = self.print_function_signature(
-                &sig,
-                &BorrowMode::AllBorrowed(parse_quote!('_)),
-                &BorrowMode::Owned,
-            );
+		let resource_mod_ident = format!("{mod_ident}::resource::{}", ident.to_string().to_snake_case());
+
+		// the constructor is exposed as `new`, static functions as associated
+		// functions without a receiver
+		let constructor = resource.constructor.as_ref().map(|func| {
+			let hash = function_hash(&self.interface.typedefs, func);
+
+			let func = Function { id:"new".to_string(), ..func.clone() };
+
+			self.print_invoke_function(&resource_mod_ident, "constructor", &func, &hash)
+		});
+
+		let statics = resource.statics.iter().map(|func| {
+			let hash = function_hash(&self.interface.typedefs, func);
+
+			self.print_invoke_function(&resource_mod_ident, &func.id.to_snake_case(), func, &hash)
+		});
+
+		let functions = resource.methods.iter().map(|func| {
+            let sig = self.print_invoke_signature(func, Some(quote!(&self)));
 
             let mod_ident = format!("{mod_ident}::resource::{}", ident.to_string().to_snake_case());
 
             let ident = func.id.to_snake_case();
 
+            let hash = function_hash(&self.interface.typedefs, func);
+
             let param_idents = func
                 .params
                 .iter()
@@ -146,19 +281,28 @@ impl RustGenerator for RustWasm {
 
             quote! {
                 #sig {
-                    ::tauri_bindgen_guest_rust::invoke(#mod_ident, #ident, &(self.0, #(#param_idents),*)).await.unwrap()
+                    ::tauri_bindgen_guest_rust::invoke_with_schema(#hash, #mod_ident, #ident, &(self.0, #(#param_idents),*)).await
                 }
             }
         });
 
 		quote! {
 			#docs
+			#deprecated
 			#additional_attrs
 			pub struct #ident(u32);
 
 			impl #ident {
+				#constructor
+				#(#statics)*
 				#(#functions)*
 			}
+
+			impl Drop for #ident {
+				fn drop(&mut self) {
+					::tauri_bindgen_guest_rust::drop_resource(#resource_mod_ident, self.0);
+				}
+			}
 		}
 	}
 }
@@ -169,6 +313,8 @@ impl tauri_bindgen_core::Generate for RustWasm {
 
 		let ident = format_ident!("{}", self.interface.ident.to_snake_case());
 
+		let constants = self.print_constants();
+
 		let typedefs = self.print_typedefs(
 			self.interface.typedefs.iter().map(|(id, _)| id),
 			&BorrowMode::AllBorrowed(parse_quote!('a)),
@@ -180,18 +326,27 @@ impl tauri_bindgen_core::Generate for RustWasm {
 			.iter()
 			.map(|func| self.print_function(&self.interface.ident.to_snake_case(), func));
 
+		let exports = self.print_exports(&self.interface.ident.to_snake_case());
+
 		quote! {
 			#docs
-			#[allow(unused_imports, unused_variables, dead_code)]
+			#[allow(unused_imports, unused_variables, dead_code, deprecated)]
 			#[rustfmt::skip]
 			#[allow(clippy::all)]
 			pub mod #ident {
 				use ::tauri_bindgen_guest_rust::serde;
 
 				use ::tauri_bindgen_guest_rust::bitflags;
+
+				use ::tauri_bindgen_guest_rust::field_or_default;
+
+				#constants
+
 				#typedefs
 
 				#(#functions)*
+
+				#exports
 			}
 		}
 	}
@@ -0,0 +1,70 @@
+use defaults::{Color, Options, Range};
+
+tauri_bindgen_guest_rust::generate!({
+	path: "../../wit/defaults.wit",
+});
+
+fn options() -> Options {
+	Options { size:12, ratio:2.0, color:Color::Green, limit:Some(3), visible:false }
+}
+
+#[test]
+fn default_values() {
+	assert_eq!(
+		Options::default(),
+		Options { size:5, ratio:0.5, color:Color::Red, limit:None, visible:true }
+	);
+}
+
+#[test]
+fn postcard_round_trip() {
+	for options in [options(), Options::default()] {
+		let bytes = postcard::to_allocvec(&options).unwrap();
+
+		assert_eq!(postcard::from_bytes::<Options>(&bytes).unwrap(), options);
+	}
+}
+
+/// Records encoded before fields with defaults were appended end early.
+#[test]
+fn postcard_missing_fields() {
+	let bytes = postcard::to_allocvec(&options()).unwrap();
+
+	assert_eq!(
+		postcard::from_bytes::<Options>(&bytes[..bytes.len() - 1]).unwrap(),
+		Options { visible:true, ..options() }
+	);
+
+	assert_eq!(postcard::from_bytes::<Options>(&[]).unwrap(), Options::default());
+
+	// fields followed by fields without a default can't be left out
+	let bytes = postcard::to_allocvec(&(2_u32, 8_u32)).unwrap();
+
+	assert_eq!(postcard::from_bytes::<Range>(&bytes).unwrap(), Range { start:2, end:8, step:1 });
+
+	assert!(postcard::from_bytes::<Range>(&bytes[..1]).is_err());
+}
+
+/// Fields that are present but invalid aren't replaced with their default.
+#[test]
+fn postcard_invalid_fields() {
+	let mut bytes = postcard::to_allocvec(&options()).unwrap();
+
+	*bytes.last_mut().unwrap() = 5;
+
+	assert!(postcard::from_bytes::<Options>(&bytes).is_err());
+}
+
+#[test]
+fn json_missing_fields() {
+	let json = serde_json::to_string(&options()).unwrap();
+
+	assert_eq!(serde_json::from_str::<Options>(&json).unwrap(), options());
+
+	assert_eq!(serde_json::from_str::<Options>("{}").unwrap(), Options::default());
+
+	assert_eq!(
+		serde_json::from_str::<Options>(r#"{ "size": 12, "color": "Green" }"#).unwrap(),
+		Options { size:12, color:Color::Green, ..Options::default() }
+	);
+}
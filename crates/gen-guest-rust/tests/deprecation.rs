@@ -0,0 +1,31 @@
+use tauri_bindgen_core::GeneratorBuilder;
+use tauri_bindgen_gen_guest_rust::Builder;
+
+const SOURCE:&str = include_str!("../../../wit/deprecation.wit");
+
+/// Whether `file` contains `code`, ignoring whitespace.
+fn contains(file:&str, code:&str) -> bool {
+	let strip = |s:&str| s.chars().filter(|c| !c.is_whitespace()).collect::<String>();
+
+	strip(file).contains(&strip(code))
+}
+
+#[test]
+fn attributes() {
+	let iface = wit_parser::parse_and_resolve_str(SOURCE, |_| false).unwrap();
+
+	let file = Builder::default().build(iface).to_file().1;
+
+	for code in [
+		r#"#[doc = "Reads a file."] #[deprecated = "use `open` instead"] pub async fn read("#,
+		"#[deprecated] pub async fn new(",
+		r#"#[deprecated = "use `stat`"] pub async fn size("#,
+		"#[deprecated] #[derive(serde::Deserialize)]
+		 #[derive(Debug, Clone, PartialEq)] pub struct Stat",
+		r#"#[deprecated = "use `len`"] pub size: u64"#,
+	] {
+		assert!(contains(&file, code), "missing `{code}` in\n{file}");
+	}
+
+	assert_eq!(file.matches("# [deprecated").count(), 5, "{file}");
+}
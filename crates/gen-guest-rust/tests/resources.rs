@@ -0,0 +1,29 @@
+use resources::{A, B};
+use tauri_bindgen_guest_rust::Error;
+
+tauri_bindgen_guest_rust::generate!({
+	path: "../../wit/resources.wit",
+});
+
+/// Handles are borrowed by the functions they're passed to, so they can
+/// still be used afterwards and aren't released by the call.
+async fn use_after_passing(a:&A, b:&B) -> Result<(), Error> {
+	let _ = b.f2(a).await?;
+
+	let _ = b.f3(Some(&[a, a])).await?;
+
+	let b = B::new(a).await?;
+
+	let _ = b.f2(a).await?;
+
+	a.f1().await?;
+
+	Ok(())
+}
+
+#[test]
+fn handles_are_borrowed() {
+	// calls can't be made without a webview, this checks that the bindings
+	// compile when used like that
+	let _ = use_after_passing;
+}
@@ -0,0 +1,10 @@
+/**
+ * Options accepted by every function calling the host.
+ */
+export interface CallOptions {
+	/**
+	 * Cancels the call, the host stops working on it and the call fails with
+	 * an `IpcError` of kind `cancelled`.
+	 */
+	signal?: AbortSignal;
+}
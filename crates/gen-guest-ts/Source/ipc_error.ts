@@ -0,0 +1,29 @@
+/**
+ * The error thrown when a call to the host fails.
+ */
+export class IpcError extends Error {
+	/**
+	 * `transport` when the host couldn't be reached, `not-found` when it doesn't
+	 * define the function, `permission-denied` when the webview lacks a required
+	 * capability, `schema-mismatch` when the bindings were generated from
+	 * another version of the interface than the host, `deserialize` when it
+	 * couldn't decode the parameters, `host` when the function failed, `panic`
+	 * when it panicked and `cancelled` when the call was cancelled through its
+	 * `AbortSignal`.
+	 */
+	kind:
+		| "transport"
+		| "not-found"
+		| "permission-denied"
+		| "schema-mismatch"
+		| "deserialize"
+		| "host"
+		| "panic"
+		| "cancelled";
+
+	constructor(kind: IpcError["kind"], message: string) {
+		super(message);
+		this.name = "IpcError";
+		this.kind = kind;
+	}
+}
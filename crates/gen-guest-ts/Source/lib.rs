@@ -8,15 +8,24 @@
 use std::{fmt::Write, path::PathBuf};
 
 use heck::{ToKebabCase, ToLowerCamelCase, ToSnakeCase, ToUpperCamelCase};
-use tauri_bindgen_core::{Generate, GeneratorBuilder, TypeInfo, TypeInfos, postprocess};
-use tauri_bindgen_gen_js::{JavaScriptGenerator, SerdeUtils};
+use tauri_bindgen_core::{
+	Generate,
+	GeneratorBuilder,
+	TypeInfo,
+	TypeInfos,
+	function_hash,
+	postprocess,
+};
+use tauri_bindgen_gen_js::{JavaScriptGenerator, SerdeUtils, print_deprecated_tag};
 use wit_parser::{
+	Annotation,
 	EnumCase,
 	FlagsField,
 	Function,
 	FunctionResult,
 	Interface,
 	RecordField,
+	Resource,
 	Type,
 	TypeDefId,
 	TypeDefKind,
@@ -39,6 +48,10 @@ pub struct Builder {
 	/// faster than `prettier`. Requires a global installation of `rome`.
 	#[cfg_attr(feature = "clap", clap(long))]
 	pub romefmt:bool,
+	/// Encode calls as JSON instead of postcard, which makes them readable in
+	/// the network tab of the devtools at the cost of larger messages.
+	#[cfg_attr(feature = "clap", clap(long))]
+	pub json:bool,
 }
 
 impl GeneratorBuilder for Builder {
@@ -47,21 +60,34 @@ impl GeneratorBuilder for Builder {
 			.typedefs
 			.iter()
 			.filter_map(|(_, typedef)| {
-				if let TypeDefKind::Resource(methods) = &typedef.kind {
-					Some(methods.iter())
+				if let TypeDefKind::Resource(resource) = &typedef.kind {
+					Some(resource.functions())
 				} else {
 					None
 				}
 			})
-			.flatten();
+			.flatten()
+			.collect::<Vec<_>>();
 
+		// exported functions deserialize their parameters and serialize their
+		// results, the opposite of imported ones
 		let infos = TypeInfos::collect_from_functions(
 			&interface.typedefs,
-			interface.functions.iter().chain(methods),
+			interface.functions.iter().chain(methods.iter().copied()),
+		)
+		.with_exports(
+			&interface.typedefs,
+			interface.exports.iter(),
+			TypeInfo::RESULT,
+			TypeInfo::PARAM,
 		);
 
-		let serde_utils =
-			SerdeUtils::collect_from_functions(&interface.typedefs, &interface.functions);
+		let serde_utils = SerdeUtils::collect_from_functions(
+			&interface.typedefs,
+			interface.functions.iter().chain(methods),
+		) | SerdeUtils::collect_from_exports(&interface.typedefs, &interface.exports);
+
+		let serde_utils = if self.json { serde_utils.for_json() } else { serde_utils };
 
 		Box::new(TypeScript { opts:self, interface, infos, serde_utils })
 	}
@@ -77,31 +103,28 @@ pub struct TypeScript {
 
 impl TypeScript {
 	pub fn print_function(&self, intf_name:&str, func:&Function) -> String {
-		let docs = print_docs(&func.docs);
+		let docs = print_docs(&func.docs, &func.annotations);
 
 		let ident = func.id.to_lower_camel_case();
 
 		let name = func.id.to_snake_case();
 
-		let params = self.print_function_params(&func.params);
+		let params = self.print_call_params(&func.params);
 
 		let result = func
 			.result
 			.as_ref()
 			.map_or("Promise<void>".to_string(), |result| self.print_function_result(result));
 
-		let deserialize_result = func
+		let decode_result = func
 			.result
 			.as_ref()
-			.map(|res| self.print_deserialize_function_result(res))
+			.map(|res| self.print_decode_function_result(res))
 			.unwrap_or_default();
 
-		let serialize_params = func
-			.params
-			.iter()
-			.map(|(ident, ty)| self.print_serialize_ty(&ident.to_lower_camel_case(), ty))
-			.collect::<Vec<_>>()
-			.join(";\n");
+		let encode_params = self.print_encode_params(&func.params, None);
+
+		let hash = function_hash(&self.interface.typedefs, func);
 
 		let (ret, as_ret) = if func.result.is_some() {
 			("return".to_string(), format!("as {result}"))
@@ -113,10 +136,9 @@ impl TypeScript {
 			r#"
 {docs}
 export async function {ident} ({params}) : {result} {{
-    const out = []
-    {serialize_params}
+    {encode_params}
 
-    {ret} fetch('ipc://localhost/{intf_name}/{name}', {{ method: "POST", body: Uint8Array.from(out) }}){deserialize_result} {as_ret}
+    {ret} fetchIpc('ipc://localhost/{intf_name}/{name}', out, callOptions?.signal, '{hash}'){decode_result} {as_ret}
 }}
         "#
 		)
@@ -136,6 +158,18 @@ export async function {ident} ({params}) : {result} {{
 			.join(", ")
 	}
 
+	/// Prints the parameters of a function calling the host, which are
+	/// followed by the options of the call.
+	fn print_call_params(&self, params:&[(String, Type)]) -> String {
+		let params = self.print_function_params(params);
+
+		if params.is_empty() {
+			"callOptions?: CallOptions".to_string()
+		} else {
+			format!("{params}, callOptions?: CallOptions")
+		}
+	}
+
 	fn print_function_result(&self, result:&FunctionResult) -> String {
 		match result.len() {
 			0 => "Promise<void>".to_string(),
@@ -153,6 +187,57 @@ export async function {ident} ({params}) : {result} {{
 		}
 	}
 
+	fn print_register_exports(&self, intf_name:&str) -> String {
+		if self.interface.exports.is_empty() {
+			return String::new();
+		}
+
+		let members:String = self
+			.interface
+			.exports
+			.iter()
+			.map(|func| {
+				let docs = print_docs(&func.docs, &func.annotations);
+
+				let ident = func.id.to_lower_camel_case();
+
+				let params = self.print_function_params(&func.params);
+
+				let result = match func.result.as_ref().map_or(0, FunctionResult::len) {
+					0 => "void".to_string(),
+					1 => self.print_type(func.result.as_ref().unwrap().types().next().unwrap()),
+					_ => {
+						let tys = func
+							.result
+							.iter()
+							.flat_map(FunctionResult::types)
+							.map(|ty| self.print_type(ty))
+							.collect::<Vec<_>>()
+							.join(", ");
+
+						format!("[{tys}]")
+					},
+				};
+
+				format!("{docs}\n{ident}({params}): Promise<{result}> | {result};\n")
+			})
+			.collect();
+
+		let handler = self.print_register_export_handler(intf_name, &self.interface.exports);
+
+		format!(
+			r#"
+/**
+ * Registers the implementation of the functions the host can call in this webview.
+ */
+export function registerExports(impl: {{
+{members}}}) {{
+    {handler}
+}}
+"#
+		)
+	}
+
 	fn print_type(&self, ty:&Type) -> String {
 		match ty {
 			Type::Bool => "boolean".to_string(),
@@ -182,6 +267,11 @@ export async function {ident} ({params}) : {result} {{
 
 				format!("{ty} | null")
 			},
+			Type::Stream(ty) => {
+				let ty = self.print_type(ty);
+
+				format!("AsyncIterableIterator<{ty}>")
+			},
 			Type::Result { ok, err } => {
 				let ok = ok.as_ref().map_or("null".to_string(), |ty| self.print_type(ty));
 
@@ -190,6 +280,12 @@ export async function {ident} ({params}) : {result} {{
 				format!("Result<{ok}, {err}>")
 			},
 			Type::Id(id) => self.interface.typedefs[*id].ident.to_upper_camel_case(),
+			Type::Generic { id, args } => {
+				let args = args.iter().map(|ty| self.print_type(ty)).collect::<Vec<_>>().join(", ");
+
+				format!("{}<{args}>", self.interface.typedefs[*id].ident.to_upper_camel_case())
+			},
+			Type::Param(name) => name.to_upper_camel_case(),
 		}
 	}
 
@@ -198,17 +294,23 @@ export async function {ident} ({params}) : {result} {{
 
 		let ident = &typedef.ident.to_upper_camel_case();
 
-		let docs = print_docs(&typedef.docs);
+		let docs = print_docs(&typedef.docs, &typedef.annotations);
+
+		let generics = &print_type_params(&typedef.params);
 
 		match &typedef.kind {
-			TypeDefKind::Alias(ty) => self.print_alias(&docs, ident, ty),
-			TypeDefKind::Record(fields) => self.print_record(&docs, ident, fields),
+			TypeDefKind::Alias(ty) => self.print_alias(&docs, &format!("{ident}{generics}"), ty),
+			TypeDefKind::Record(fields) => {
+				self.print_record(&docs, &format!("{ident}{generics}"), fields)
+			},
 			TypeDefKind::Flags(fields) => self.print_flags(&docs, ident, fields),
-			TypeDefKind::Variant(cases) => self.print_variant(&docs, ident, cases),
+			TypeDefKind::Variant(cases) => self.print_variant(&docs, ident, generics, cases),
 			TypeDefKind::Enum(cases) => self.print_enum(&docs, ident, cases),
-			TypeDefKind::Union(cases) => self.print_union(&docs, ident, cases),
-			TypeDefKind::Resource(functions) => {
-				self.print_resource(&self.interface.ident, &docs, ident, functions)
+			TypeDefKind::Union(cases) => {
+				self.print_union(&docs, &format!("{ident}{generics}"), cases)
+			},
+			TypeDefKind::Resource(resource) => {
+				self.print_resource(&self.interface.ident, &docs, ident, resource, self.infos[id])
 			},
 		}
 	}
@@ -221,13 +323,16 @@ export async function {ident} ({params}) : {result} {{
 
 	fn print_record(&self, docs:&str, ident:&str, fields:&[RecordField]) -> String {
 		let fields = fields.iter().fold(String::new(), |mut str, field| {
-			let docs = print_docs(&field.docs);
+			let docs = print_docs(&field.docs, &field.annotations);
 
 			let ident = field.id.to_lower_camel_case();
 
+			// fields with a default are filled in when serializing
+			let optional = if field.default.is_some() { "?" } else { "" };
+
 			let ty = self.print_type(&field.ty);
 
-			let _ = write!(str, "{docs}\n{ident}: {ty},\n");
+			let _ = write!(str, "{docs}\n{ident}{optional}: {ty},\n");
 
 			str
 		});
@@ -237,7 +342,7 @@ export async function {ident} ({params}) : {result} {{
 
 	fn print_flags(&self, docs:&str, ident:&str, fields:&[FlagsField]) -> String {
 		let fields = fields.iter().enumerate().fold(String::new(), |mut str, (i, field)| {
-			let docs = print_docs(&field.docs);
+			let docs = print_docs(&field.docs, &[]);
 
 			let ident = field.id.to_upper_camel_case();
 
@@ -251,10 +356,16 @@ export async function {ident} ({params}) : {result} {{
 		format!("{docs}\nexport enum {ident} {{ {fields} }}\n")
 	}
 
-	fn print_variant(&self, docs:&str, ident:&str, cases:&[VariantCase]) -> String {
+	fn print_variant(
+		&self,
+		docs:&str,
+		ident:&str,
+		generics:&str,
+		cases:&[VariantCase],
+	) -> String {
 		let interfaces:String =
 			cases.iter().enumerate().fold(String::new(), |mut str, (i, case)| {
-				let docs = print_docs(&case.docs);
+				let docs = print_docs(&case.docs, &[]);
 
 				let case_ident = case.id.to_upper_camel_case();
 
@@ -270,7 +381,7 @@ export async function {ident} ({params}) : {result} {{
 
 				let _ = write!(
 					str,
-					"{docs}\nexport interface {ident}{case_ident} {{ tag: {i}{value} }}\n"
+					"{docs}\nexport interface {ident}{case_ident}{generics} {{ tag: {i}{value} }}\n"
 				);
 
 				str
@@ -279,21 +390,21 @@ export async function {ident} ({params}) : {result} {{
 		let cases:String = cases
 			.iter()
 			.map(|case| {
-				let docs = print_docs(&case.docs);
+				let docs = print_docs(&case.docs, &[]);
 
 				let case_ident = case.id.to_upper_camel_case();
 
-				format!("{docs}\n{ident}{case_ident}")
+				format!("{docs}\n{ident}{case_ident}{generics}")
 			})
 			.collect::<Vec<_>>()
 			.join(" | ");
 
-		format!("{interfaces}\n{docs}\nexport type {ident} = {cases}\n")
+		format!("{interfaces}\n{docs}\nexport type {ident}{generics} = {cases}\n")
 	}
 
 	fn print_enum(&self, docs:&str, ident:&str, cases:&[EnumCase]) -> String {
 		let cases = cases.iter().fold(String::new(), |mut str, case| {
-			let docs = print_docs(&case.docs);
+			let docs = print_docs(&case.docs, &[]);
 
 			let ident = case.id.to_upper_camel_case();
 
@@ -309,7 +420,7 @@ export async function {ident} ({params}) : {result} {{
 		let cases:String = cases
 			.iter()
 			.map(|case| {
-				let docs = print_docs(&case.docs);
+				let docs = print_docs(&case.docs, &[]);
 
 				let ty = self.print_type(&case.ty);
 
@@ -326,63 +437,157 @@ export async function {ident} ({params}) : {result} {{
 		mod_ident:&str,
 		docs:&str,
 		ident:&str,
-		functions:&[Function],
+		resource:&Resource,
+		info:TypeInfo,
 	) -> String {
-		let functions: String = functions
-            .iter()
-            .fold(String::new(),|mut str, func| {
-                let docs = print_docs(&func.docs);
+		let mod_ident = mod_ident.to_snake_case();
 
-                let mod_ident = mod_ident.to_snake_case();
+		let resource_ident = ident.to_snake_case();
 
-                let resource_ident = ident.to_snake_case();
+		let url = format!("ipc://localhost/{mod_ident}::resource::{resource_ident}");
 
-                let ident = func.id.to_lower_camel_case();
+		let constructor = resource
+			.constructor
+			.as_ref()
+			.map(|func| self.print_resource_function(&url, "static async create", "constructor", func))
+			.unwrap_or_default();
 
-                let params = self.print_function_params(&func.params);
+		let statics:String = resource
+			.statics
+			.iter()
+			.map(|func| {
+				let prefix = format!("static async {}", func.id.to_lower_camel_case());
 
-                let result = func
-                    .result
-                    .as_ref()
-                    .map_or("void".to_string(), |result| self.print_function_result(result));
+				self.print_resource_function(&url, &prefix, &func.id.to_snake_case(), func)
+			})
+			.collect();
 
-                let deserialize_result = func
-                    .result
-                    .as_ref()
-                    .map(|res| self.print_deserialize_function_result(res))
-                    .unwrap_or_default();
+		let methods:String = resource
+			.methods
+			.iter()
+			.map(|func| {
+				let prefix = format!("async {}", func.id.to_lower_camel_case());
 
-                let serialize_params = func
-                    .params
-                    .iter()
-                    .map(|(ident, ty)| self.print_serialize_ty(&ident.to_lower_camel_case(), ty))
-                    .collect::<Vec<_>>()
-                    .join(";\n");
+				self.print_resource_function(&url, &prefix, &func.id.to_snake_case(), func)
+			})
+			.collect();
 
-                let _ = write!(str,
-                    r#"{docs}
-async {ident} ({params}) : {result} {{
-    const out = []
+		let serialize = match (info.contains(TypeInfo::PARAM), self.opts.json) {
+			(false, _) => String::new(),
+			(true, false) => {
+				"serialize(out: number[]) {
     serializeU32(out, this.#id);
-    {serialize_params}
+}"
+				.to_string()
+			},
+			(true, true) => {
+				"toJson(): number {
+    return this.#id
+}"
+				.to_string()
+			},
+		};
 
-    await fetch('ipc://localhost/{mod_ident}::resource::{resource_ident}/{ident}', {{ method: "POST", body: Uint8Array.from(out), headers: {{ 'Content-Type': 'application/octet-stream' }} }}){deserialize_result}
-}}
-"#
-                );
+		let deserialize = match (info.contains(TypeInfo::RESULT), self.opts.json) {
+			(false, _) => String::new(),
+			(true, false) => {
+				format!(
+					"static deserialize(de: Deserializer): {ident} {{
+    const self = new {ident}();
+
+    self.#id = deserializeU32(de);
+
+    return self
+}}"
+				)
+			},
+			(true, true) => {
+				format!(
+					"static fromJson(id: number): {ident} {{
+    const self = new {ident}();
+
+    self.#id = id;
+
+    return self
+}}"
+				)
+			},
+		};
 
-                str
-            });
+		let encode_id = if self.opts.json {
+			"const out = stringifyJson(this.#id)"
+		} else {
+			"const out = []
+        serializeU32(out, this.#id);"
+		};
 
 		format!(
 			"{docs}\nexport class {ident} {{
     #id: number;
 
-    {functions}
+    {constructor}
+    {statics}
+    {methods}
+    {serialize}
+    {deserialize}
+    /**
+     * Releases the resource on the host, it must not be used afterwards.
+     */
+    async dispose(): Promise<void> {{
+        {encode_id}
+
+        await fetchIpc('{url}/drop', out)
+    }}
+
+    [Symbol.dispose ?? Symbol.for('Symbol.dispose')](): void {{
+        this.dispose()
+    }}
 }}"
 		)
 	}
 
+	/// Prints a function of a resource, `prefix` is everything preceding the
+	/// parameter list. Functions that aren't static pass the handle of the
+	/// resource as their first parameter.
+	fn print_resource_function(
+		&self,
+		url:&str,
+		prefix:&str,
+		endpoint:&str,
+		func:&Function,
+	) -> String {
+		let docs = print_docs(&func.docs, &func.annotations);
+
+		let params = self.print_call_params(&func.params);
+
+		let handle = if prefix.starts_with("static") { None } else { Some("this.#id") };
+
+		let result = func
+			.result
+			.as_ref()
+			.map_or("Promise<void>".to_string(), |result| self.print_function_result(result));
+
+		let decode_result = func
+			.result
+			.as_ref()
+			.map(|res| self.print_decode_function_result(res))
+			.unwrap_or_default();
+
+		let encode_params = self.print_encode_params(&func.params, handle);
+
+		let hash = function_hash(&self.interface.typedefs, func);
+
+		format!(
+			r#"{docs}
+{prefix} ({params}) : {result} {{
+    {encode_params}
+
+    return fetchIpc('{url}/{endpoint}', out, callOptions?.signal, '{hash}'){decode_result}
+}}
+"#
+		)
+	}
+
 	fn array_ty(&self, ty:&Type) -> Option<String> {
 		match ty {
 			Type::U8 => Some("Uint8Array".to_string()),
@@ -404,8 +609,11 @@ async {ident} ({params}) : {result} {{
 			Type::U128
 			| Type::S128
 			| Type::Bool
+			| Type::Generic { .. }
+			| Type::Param(_)
 			| Type::Tuple(_)
 			| Type::List(_)
+			| Type::Stream(_)
 			| Type::Option(_)
 			| Type::Result { .. }
 			| Type::Char
@@ -414,8 +622,23 @@ async {ident} ({params}) : {result} {{
 	}
 }
 
-fn print_docs(docs:&str) -> String {
-	if docs.is_empty() {
+/// Prints the type parameters of a generic type definition, like `<T, U>`.
+fn print_type_params(params:&[String]) -> String {
+	if params.is_empty() {
+		return String::new();
+	}
+
+	let params = params.iter().map(|param| param.to_upper_camel_case()).collect::<Vec<_>>();
+
+	format!("<{}>", params.join(", "))
+}
+
+/// Prints the JSDoc comment of an item, including the `@deprecated` tag of
+/// items annotated with `@deprecated`.
+fn print_docs(docs:&str, annotations:&[Annotation]) -> String {
+	let deprecated = print_deprecated_tag(annotations);
+
+	if docs.is_empty() && deprecated.is_empty() {
 		return String::new();
 	}
 
@@ -425,13 +648,15 @@ fn print_docs(docs:&str) -> String {
 		str
 	});
 
-	format!("/**\n{docs}*/")
+	format!("/**\n{docs}{deprecated}*/")
 }
 
 impl JavaScriptGenerator for TypeScript {
 	fn interface(&self) -> &Interface { &self.interface }
 
 	fn infos(&self) -> &TypeInfos { &self.infos }
+
+	fn json(&self) -> bool { self.opts.json }
 }
 
 impl Generate for TypeScript {
@@ -442,14 +667,23 @@ impl Generate for TypeScript {
 			.interface
 			.functions
 			.iter()
+			.chain(&self.interface.exports)
 			.any(Function::throws)
 			.then_some(
 				"export type Result<T, E> = { tag: 'ok', val: T } | { tag: 'err', val: E };\n",
 			)
 			.unwrap_or_default();
 
+		let ipc_error = include_str!("./ipc_error.ts");
+
+		let batch = include_str!("./batch.ts");
+
+		let call_options = include_str!("./call_options.ts");
+
 		let serde_utils = self.serde_utils.to_string();
 
+		let constants = self.print_constants();
+
 		let deserializers:String = self
 			.interface
 			.typedefs
@@ -458,7 +692,7 @@ impl Generate for TypeScript {
 				let info = self.infos[id];
 
 				if info.contains(TypeInfo::RESULT) {
-					Some(self.print_deserialize_typedef(id))
+					Some(if self.opts.json { self.print_from_json_typedef(id) } else { self.print_deserialize_typedef(id) })
 				} else {
 					None
 				}
@@ -473,7 +707,7 @@ impl Generate for TypeScript {
 				let info = self.infos[id];
 
 				if info.contains(TypeInfo::PARAM) {
-					Some(self.print_serialize_typedef(id))
+					Some(if self.opts.json { self.print_to_json_typedef(id) } else { self.print_serialize_typedef(id) })
 				} else {
 					None
 				}
@@ -490,9 +724,11 @@ impl Generate for TypeScript {
 			.map(|func| self.print_function(&self.interface.ident.to_snake_case(), func))
 			.collect();
 
+		let exports = self.print_register_exports(&self.interface.ident.to_snake_case());
+
 		let mut contents = format!(
-			"{ts_nocheck}{result_ty}{serde_utils}{deserializers}{serializers}\\
-			 n{typedefs}\n{functions}"
+			"{ts_nocheck}{constants}{result_ty}{ipc_error}{call_options}{batch}{serde_utils}{deserializers}{serializers}\\
+			 n{typedefs}\n{functions}{exports}"
 		);
 
 		if self.opts.prettier {
@@ -0,0 +1,43 @@
+use tauri_bindgen_core::GeneratorBuilder;
+use tauri_bindgen_gen_guest_ts::Builder;
+
+const SOURCE:&str = include_str!("../../../wit/deprecation.wit");
+
+/// The doc comment preceding `decl` in `file`.
+fn jsdoc<'a>(file:&'a str, decl:&str) -> &'a str {
+	let end = file.find(decl).unwrap_or_else(|| panic!("missing `{decl}` in\n{file}"));
+
+	let start = file[..end].rfind("/**").unwrap_or_else(|| panic!("`{decl}` has no doc comment"));
+
+	&file[start..end]
+}
+
+#[test]
+fn jsdoc_tags() {
+	let iface = wit_parser::parse_and_resolve_str(SOURCE, |_| false).unwrap();
+
+	let file = Builder::default().build(iface).to_file().1;
+
+	let read = jsdoc(&file, "export async function read (");
+
+	assert!(read.contains("Reads a file."), "{read}");
+	assert!(read.contains(" * @deprecated use `open` instead\n"), "{read}");
+
+	let constructor = jsdoc(&file, "static async create (");
+
+	assert!(constructor.contains(" * @deprecated\n"), "{constructor}");
+
+	let size = jsdoc(&file, "async size (");
+
+	assert!(size.contains(" * @deprecated use `stat`\n"), "{size}");
+
+	let stat = jsdoc(&file, "export interface Stat {");
+
+	assert!(stat.contains(" * @deprecated\n"), "{stat}");
+
+	let field = jsdoc(&file, "size: bigint,");
+
+	assert!(field.contains(" * @deprecated use `len`\n"), "{field}");
+
+	assert_eq!(file.matches("@deprecated").count(), 5, "{file}");
+}
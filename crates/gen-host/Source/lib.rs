@@ -8,11 +8,11 @@
 use std::{collections::HashSet, path::PathBuf};
 
 use heck::{ToKebabCase, ToSnakeCase, ToUpperCamelCase};
-use proc_macro2::{Literal, TokenStream};
+use proc_macro2::{Ident, Literal, TokenStream};
 use quote::{format_ident, quote};
-use tauri_bindgen_core::{Generate, GeneratorBuilder, TypeInfo, TypeInfos};
-use tauri_bindgen_gen_rust::{BorrowMode, FnSig, RustGenerator, print_generics};
-use wit_parser::{Function, Interface, Type, TypeDefKind};
+use tauri_bindgen_core::{Generate, GeneratorBuilder, TypeInfo, TypeInfos, function_hash};
+use tauri_bindgen_gen_rust::{BorrowMode, FnSig, RustGenerator, print_type_param};
+use wit_parser::{Annotation, Function, FunctionResult, Interface, Resource, Type, TypeDefKind};
 
 #[derive(Default, Debug, Clone)]
 #[cfg_attr(feature = "clap", derive(clap::Args))]
@@ -28,31 +28,106 @@ pub struct Builder {
 	/// Whether or not to use async rust functions and traits.
 	#[cfg_attr(feature = "clap", clap(long = "async"))]
 	pub async_:bool,
+
+	/// The capability webviews must be granted to call the functions and
+	/// resources of the interface.
+	#[cfg_attr(feature = "clap", clap(long))]
+	pub capability:Option<String>,
+
+	/// Capabilities webviews must be granted to call single functions or
+	/// resources, in addition to the one of the interface, given as
+	/// `name=capability`. Resource functions are named like
+	/// `resource::function`, constructors like `resource::constructor`.
+	#[cfg_attr(
+		feature = "clap",
+		clap(
+			long = "item-capability",
+			value_name = "NAME=CAPABILITY",
+			value_parser = parse_item_capability
+		)
+	)]
+	pub item_capabilities:Vec<(String, String)>,
+}
+
+#[cfg(feature = "clap")]
+fn parse_item_capability(arg:&str) -> Result<(String, String), String> {
+	let (name, capability) =
+		arg.split_once('=').ok_or_else(|| format!("expected `name=capability`, found `{arg}`"))?;
+
+	Ok((name.to_string(), capability.to_string()))
 }
 
 impl GeneratorBuilder for Builder {
+	fn check(&self, interface:&Interface) -> Result<(), String> {
+		let names = item_names(interface);
+
+		// a misspelled name would leave the function callable by every webview
+		match self.item_capabilities.iter().find(|(name, _)| !names.contains(name)) {
+			Some((name, _)) => {
+				Err(format!(
+					"capability given for `{name}`, which is no function or resource of interface `{}`",
+					interface.ident
+				))
+			},
+			None => Ok(()),
+		}
+	}
+
 	fn build(self, interface:Interface) -> Box<dyn Generate> {
 		let methods = interface
 			.typedefs
 			.iter()
 			.filter_map(|(_, typedef)| {
-				if let TypeDefKind::Resource(methods) = &typedef.kind {
-					Some(methods.iter())
+				if let TypeDefKind::Resource(resource) = &typedef.kind {
+					Some(resource.functions())
 				} else {
 					None
 				}
 			})
 			.flatten();
 
+		// the host sends the parameters of exported functions and receives their
+		// results, the opposite of imported ones
 		let infos = TypeInfos::collect_from_functions(
 			&interface.typedefs,
 			interface.functions.iter().chain(methods),
+		)
+		.with_exports(
+			&interface.typedefs,
+			interface.exports.iter(),
+			TypeInfo::RESULT,
+			TypeInfo::PARAM,
 		);
 
 		Box::new(Host { opts:self, interface, infos })
 	}
 }
 
+/// The names `item_capabilities` can be given for, the functions and
+/// resources of the interface and the functions of its resources.
+fn item_names(interface:&Interface) -> HashSet<String> {
+	let mut names = interface.functions.iter().map(|func| func.id.clone()).collect::<HashSet<_>>();
+
+	for (_, typedef) in interface.typedefs.iter() {
+		if let TypeDefKind::Resource(resource) = &typedef.kind {
+			names.insert(typedef.ident.clone());
+
+			let functions = resource.methods.iter().chain(&resource.statics);
+
+			names.extend(
+				resource
+					.constructor
+					.iter()
+					.map(|_| "constructor")
+					.chain(functions.map(|func| func.id.as_str()))
+					.map(|name| format!("{}::{name}", typedef.ident)),
+			);
+		}
+	}
+
+	names
+}
+
 pub struct Host {
 	opts:Builder,
 	interface:Interface,
@@ -92,15 +167,18 @@ impl RustGenerator for Host {
 		&self,
 		_mod_ident:&str,
 		docs:&str,
+		annotations:&[Annotation],
 		ident:&proc_macro2::Ident,
-		functions:&[Function],
+		resource:&Resource,
 		_info:TypeInfo,
 	) -> TokenStream {
 		let docs = self.print_docs(docs);
 
+		let deprecated = self.print_deprecated(annotations);
+
 		let mut resources = HashSet::new();
 
-		for func in functions {
+		for func in &resource.methods {
 			for (_, ty) in &func.params {
 				self.extract_resources(ty, &mut resources);
 			}
@@ -118,10 +196,12 @@ impl RustGenerator for Host {
 			quote! { type #ident: #ident; }
 		});
 
-		let trait_ = self.print_trait(&ident.to_string(), functions.iter(), resources, false);
+		let trait_ =
+			self.print_trait(&ident.to_string(), resource.methods.iter(), resources, false);
 
 		quote! {
 			#docs
+			#deprecated
 			#trait_
 		}
 	}
@@ -191,6 +271,11 @@ impl RustGenerator for Host {
 
 				quote! { Option<#ty> }
 			},
+			Type::Stream(ty) => {
+				let ty = self.print_ty(ty, &BorrowMode::Owned);
+
+				quote! { ::tauri_bindgen_host::BoxStream<#ty> }
+			},
 			Type::Result { ok, err } => {
 				let ok = ok.as_ref().map(|ty| self.print_ty(ty, mode)).unwrap_or(quote! { () });
 
@@ -201,29 +286,14 @@ impl RustGenerator for Host {
 			Type::Id(id) => {
 				let typedef = &self.interface().typedefs[*id];
 
-				let info = self.infos()[*id];
-
 				if let TypeDefKind::Resource(_) = &typedef.kind {
 					return quote! { ::tauri_bindgen_host::ResourceId };
 				}
 
-				let ident = if self.uses_two_names(info) {
-					match mode {
-						BorrowMode::Owned => {
-							format_ident!("{}Result", typedef.ident.to_upper_camel_case())
-						},
-						BorrowMode::AllBorrowed(_) | BorrowMode::LeafBorrowed(_) => {
-							format_ident!("{}Param", typedef.ident.to_upper_camel_case())
-						},
-					}
-				} else {
-					format_ident!("{}", typedef.ident.to_upper_camel_case())
-				};
-
-				let generics = print_generics(info, mode);
-
-				quote! { #ident #generics }
+				self.print_typedef_ref(*id, &[], mode)
 			},
+			Type::Generic { id, args } => self.print_typedef_ref(*id, args, mode),
+			Type::Param(name) => print_type_param(name),
 		}
 	}
 }
@@ -239,11 +309,17 @@ impl Host {
 		let ident = format_ident!("{}", ident.to_upper_camel_case());
 
 		let functions = functions.map(|func| {
+			let self_arg = if self.uses_resources(func) {
+				quote!(&self, window: &str)
+			} else {
+				quote!(&self)
+			};
+
 			let sig = FnSig {
 				async_:self.opts.async_,
 				unsafe_:false,
 				private:true,
-				self_arg:Some(quote!(&self)),
+				self_arg:Some(self_arg),
 				func,
 			};
 
@@ -266,12 +342,74 @@ impl Host {
 		}
 	}
 
+	fn print_resource_trait_items(&self, resource_name:&str, resource:&Resource) -> TokenStream {
+		let ident = format_ident!("{}", resource_name.to_upper_camel_case());
+
+		let get_ident = format_ident!("get_{}", resource_name.to_snake_case());
+
+		let drop_ident = format_ident!("drop_{}", resource_name.to_snake_case());
+
+		let constructor = resource.constructor.as_ref().map(|constructor| {
+			let docs = self.print_docs(&constructor.docs);
+
+			let deprecated = self.print_deprecated(&constructor.annotations);
+
+			let async_ = self.opts.async_.then_some(quote! { async });
+
+			let new_ident = format_ident!("new_{}", resource_name.to_snake_case());
+
+			let params = self.print_function_params(&constructor.params, &BorrowMode::Owned);
+
+			quote! {
+				#docs
+				#deprecated
+				#async_ fn #new_ident(&self, window: &str, #params) -> ::tauri_bindgen_host::Result<::tauri_bindgen_host::ResourceId>;
+			}
+		});
+
+		quote! {
+			type #ident: #ident + Send + Sync + 'static;
+
+			fn #get_ident(&self, window: &str, id: ::tauri_bindgen_host::ResourceId) -> ::tauri_bindgen_host::Result<::std::sync::Arc<Self::#ident>> {
+				Ok(self.resource_table().get::<Self::#ident>(window, id)?)
+			}
+
+			/// Releases the resource, called when the guest drops its handle.
+			fn #drop_ident(&self, window: &str, id: ::tauri_bindgen_host::ResourceId) -> ::tauri_bindgen_host::Result<()> {
+				self.resource_table().take::<Self::#ident>(window, id)?;
+
+				Ok(())
+			}
+
+			#constructor
+		}
+	}
+
+	/// Whether any of the parameters or results of `func` is a resource
+	/// handle, such functions receive the label of the calling window so they
+	/// can look up handles in its resource table.
+	fn uses_resources(&self, func:&Function) -> bool {
+		let mut resources = HashSet::new();
+
+		for (_, ty) in &func.params {
+			self.extract_resources(ty, &mut resources);
+		}
+
+		if let Some(result) = &func.result {
+			for ty in result.types() {
+				self.extract_resources(ty, &mut resources);
+			}
+		}
+
+		!resources.is_empty()
+	}
+
 	fn extract_resources<'a>(&'a self, ty:&Type, resources:&mut HashSet<&'a str>) {
 		match ty {
-			Type::List(ty) | Type::Option(ty) => {
+			Type::List(ty) | Type::Option(ty) | Type::Stream(ty) => {
 				self.extract_resources(ty, resources);
 			},
-			Type::Tuple(types) => {
+			Type::Tuple(types) | Type::Generic { args: types, .. } => {
 				for ty in types {
 					self.extract_resources(ty, resources);
 				}
@@ -297,31 +435,53 @@ impl Host {
 	}
 
 	fn print_router_fn_definition(&self, mod_name:&str, func:&Function) -> TokenStream {
-		let func_name = func.id.to_snake_case();
+		let func_ident = format_ident!("{}", func.id.to_snake_case());
 
-		let func_ident = format_ident!("{}", func_name);
+		let await_ = self.opts.async_.then_some(quote! { .await });
+
+		let window = self.uses_resources(func).then_some(quote! { window, });
+
+		self.print_router_definition(
+			mod_name,
+			&func.id.to_snake_case(),
+			&func.params,
+			returns_stream(func),
+			|params| quote! { Ok(ctx.#func_ident(#window #params) #await_) },
+		)
+	}
 
-		let param_decl = match func.params.len() {
+	/// Defines a router endpoint that deserializes `params` and evaluates the
+	/// expression returned by `call`, which receives the parameter accessors.
+	/// Endpoints of functions returning a `stream` evaluate to that stream.
+	fn print_router_definition(
+		&self,
+		mod_name:&str,
+		func_name:&str,
+		params:&[(String, Type)],
+		stream:bool,
+		call:impl FnOnce(TokenStream) -> TokenStream,
+	) -> TokenStream {
+		let param_decl = match params.len() {
 			0 => quote! { () },
 			1 => {
-				let ty = &func.params.first().unwrap().1;
+				let ty = &params.first().unwrap().1;
 
 				let ty = self.print_ty(ty, &BorrowMode::Owned);
 
 				quote! { #ty }
 			},
 			_ => {
-				let tys = func.params.iter().map(|(_, ty)| self.print_ty(ty, &BorrowMode::Owned));
+				let tys = params.iter().map(|(_, ty)| self.print_ty(ty, &BorrowMode::Owned));
 
 				quote! { (#(#tys),*) }
 			},
 		};
 
-		let param_acc = match func.params.len() {
+		let param_acc = match params.len() {
 			0 => quote! {},
 			1 => quote! { p },
 			_ => {
-				let ids = func.params.iter().enumerate().map(|(i, _)| {
+				let ids = params.iter().enumerate().map(|(i, _)| {
 					let i = Literal::usize_unsuffixed(i);
 
 					quote! { p.#i }
@@ -331,20 +491,26 @@ impl Host {
 			},
 		};
 
+		let call = call(param_acc);
+
+		let (define, define_async) = print_define_idents(stream);
+
 		if self.opts.async_ {
 			quote! {
 				let get_cx = ::std::sync::Arc::clone(&wrapped_get_cx);
 
-				router.define_async(
+				router.#define_async(
 					#mod_name,
 					#func_name,
-					move |ctx: ::tauri_bindgen_host::ipc_router_wip::Caller<T>, p: #param_decl| {
+					move |caller: ::tauri_bindgen_host::ipc_router_wip::Caller<T>, p: #param_decl| {
 						let get_cx = get_cx.clone();
 
 						Box::pin(async move {
-							let ctx = get_cx(ctx.data());
+							let ctx = get_cx(caller.data());
+
+							let window = caller.window_label();
 
-							Ok(ctx.#func_ident(#param_acc).await)
+							#call
 						})
 					})?;
 			}
@@ -352,19 +518,78 @@ impl Host {
 			quote! {
 				let get_cx = ::std::sync::Arc::clone(&wrapped_get_cx);
 
-				router.define(
+				router.#define(
 					#mod_name,
 					#func_name,
-					move |ctx: ::tauri_bindgen_host::ipc_router_wip::Caller<T>, p: #param_decl| {
-						let ctx = get_cx(ctx.data());
+					move |caller: ::tauri_bindgen_host::ipc_router_wip::Caller<T>, p: #param_decl| {
+						let ctx = get_cx(caller.data());
+
+						let window = caller.window_label();
 
-						Ok(ctx.#func_ident(#param_acc))
+						#call
 					},
 				)?;
 			}
 		}
 	}
 
+	/// Defines the constructor, `drop` and static function endpoints of a
+	/// resource. Methods are defined by
+	/// [`Self::print_router_method_definition`].
+	fn print_router_resource_definition(
+		&self,
+		mod_name:&str,
+		resource_name:&str,
+		resource:&Resource,
+	) -> TokenStream {
+		let resource_mod_name = format!("{mod_name}::resource::{resource_name}");
+
+		let await_ = self.opts.async_.then_some(quote! { .await });
+
+		let constructor = resource.constructor.as_ref().map(|constructor| {
+			let new_ident = format_ident!("new_{}", resource_name.to_snake_case());
+
+			self.print_router_definition(
+				&resource_mod_name,
+				"constructor",
+				&constructor.params,
+				false,
+				|params| quote! { ctx.#new_ident(window, #params) #await_ },
+			)
+		});
+
+		let drop_ident = format_ident!("drop_{}", resource_name.to_snake_case());
+
+		let drop = self.print_router_definition(
+			&resource_mod_name,
+			"drop",
+			&[("id".to_string(), Type::U32)],
+			false,
+			|params| quote! { ctx.#drop_ident(window, #params) },
+		);
+
+		let statics = resource.statics.iter().map(|func| {
+			let func_ident =
+				format_ident!("{}_{}", resource_name.to_snake_case(), func.id.to_snake_case());
+
+			let window = self.uses_resources(func).then_some(quote! { window, });
+
+			self.print_router_definition(
+				&resource_mod_name,
+				&func.id.to_snake_case(),
+				&func.params,
+				returns_stream(func),
+				|params| quote! { Ok(ctx.#func_ident(#window #params) #await_) },
+			)
+		});
+
+		quote! {
+			#constructor
+			#drop
+			#(#statics)*
+		}
+	}
+
 	fn print_router_method_definition(
 		&self,
 		mod_name:&str,
@@ -395,22 +620,28 @@ impl Host {
 
 		let get_r_ident = format_ident!("get_{}", resource_name.to_snake_case());
 
+		let window = self.uses_resources(method).then_some(quote! { window, });
+
+		let (define, define_async) = print_define_idents(returns_stream(method));
+
 		if self.opts.async_ {
 			quote! {
 				let get_cx = ::std::sync::Arc::clone(&wrapped_get_cx);
 
-				router.define_async(
+				router.#define_async(
 					#mod_name,
 					#func_name,
-					move |ctx: ::tauri_bindgen_host::ipc_router_wip::Caller<T>, p: (::tauri_bindgen_host::ResourceId, #(#param_decl),*)| {
+					move |caller: ::tauri_bindgen_host::ipc_router_wip::Caller<T>, p: (::tauri_bindgen_host::ResourceId, #(#param_decl),*)| {
 						let get_cx = get_cx.clone();
 
 						Box::pin(async move {
-							let ctx = get_cx(ctx.data());
+							let ctx = get_cx(caller.data());
+
+							let window = caller.window_label();
 
-							let r = ctx.#get_r_ident(p.0)?;
+							let r = ctx.#get_r_ident(window, p.0)?;
 
-							Ok(r.#func_ident(#param_acc).await)
+							Ok(r.#func_ident(#window #param_acc).await)
 						})
 					})?;
 			}
@@ -418,29 +649,260 @@ impl Host {
 			quote! {
 				let get_cx = ::std::sync::Arc::clone(&wrapped_get_cx);
 
-				router.define(
+				router.#define(
 					#mod_name,
 					#func_name,
 					move |
-						ctx: ::tauri_bindgen_host::ipc_router_wip::Caller<T>,
+						caller: ::tauri_bindgen_host::ipc_router_wip::Caller<T>,
 						p: (::tauri_bindgen_host::ResourceId, #(#param_decl),*)
 					| {
-						let ctx = get_cx(ctx.data());
+						let ctx = get_cx(caller.data());
 
-						let r = ctx.#get_r_ident(p.0)?;
+						let window = caller.window_label();
 
-						Ok(r.#func_ident(#param_acc))
+						let r = ctx.#get_r_ident(window, p.0)?;
+
+						Ok(r.#func_ident(#window #param_acc))
 					},
 				)?;
 			}
 		}
 	}
 
+	/// Prints the client calling the functions exported by a webview.
+	fn print_exports_client(&self, mod_name:&str) -> Option<TokenStream> {
+		if self.interface.exports.is_empty() {
+			return None;
+		}
+
+		let functions = self.interface.exports.iter().map(|func| {
+			let docs = self.print_docs(&func.docs);
+
+			let deprecated = self.print_deprecated(&func.annotations);
+
+			let ident = format_ident!("{}", func.id.to_snake_case());
+
+			let name = func.id.to_snake_case();
+
+			let params = self.print_function_params(&func.params, &BorrowMode::Owned);
+
+			let param_idents =
+				func.params.iter().map(|(ident, _)| format_ident!("{}", ident.to_snake_case()));
+
+			let result = match func.result.as_ref() {
+				Some(FunctionResult::Anon(ty)) => self.print_ty(ty, &BorrowMode::Owned),
+				Some(FunctionResult::Named(types)) if types.len() == 1 => {
+					self.print_ty(&types[0].1, &BorrowMode::Owned)
+				},
+				Some(FunctionResult::Named(types)) => {
+					let types = types.iter().map(|(_, ty)| self.print_ty(ty, &BorrowMode::Owned));
+
+					quote! { (#(#types),*) }
+				},
+				None => quote! { () },
+			};
+
+			quote! {
+				#docs
+				#deprecated
+				pub async fn #ident(&self, #params) -> ::std::result::Result<#result, ::tauri_bindgen_host::ExportError> {
+					::tauri_bindgen_host::ipc_router_wip::call_export(
+						&self.webview,
+						#mod_name,
+						#name,
+						&(#(#param_idents),*),
+						self.timeout,
+					)
+					.await
+				}
+			}
+		});
+
+		Some(quote! {
+			/// Calls the functions exported by a webview.
+			#[derive(Clone)]
+			pub struct Exports<R: ::tauri_bindgen_host::tauri::Runtime> {
+				webview: ::tauri_bindgen_host::tauri::Webview<R>,
+				timeout: ::std::time::Duration,
+			}
+
+			impl<R: ::tauri_bindgen_host::tauri::Runtime> Exports<R> {
+				/// Creates a client calling the given webview, calls fail if
+				/// the webview doesn't reply within 30 seconds.
+				pub fn new(webview: ::tauri_bindgen_host::tauri::Webview<R>) -> Self {
+					Self { webview, timeout: ::std::time::Duration::from_secs(30) }
+				}
+
+				/// Sets how long calls wait for the reply of the webview.
+				#[must_use]
+				pub fn with_timeout(mut self, timeout: ::std::time::Duration) -> Self {
+					self.timeout = timeout;
+
+					self
+				}
+
+				#( #functions )*
+			}
+		})
+	}
+
+	/// Requires the capabilities of single functions and resources, given to
+	/// the builder or by `@capability` annotations.
+	fn print_item_capabilities(&self, mod_name:&str) -> TokenStream {
+		let capabilities = |name:&str, annotations:&'_ [Annotation]| {
+			self.opts
+				.item_capabilities
+				.iter()
+				.filter(|(item, _)| item == name)
+				.map(|(_, capability)| capability.clone())
+				.chain(wit_parser::capabilities(annotations).map(ToString::to_string))
+				.collect::<Vec<_>>()
+		};
+
+		let require = |module:&str, func_name:Option<&str>, capabilities:Vec<String>| {
+			let func_name = match func_name {
+				Some(func_name) => quote! { Some(#func_name) },
+				None => quote! { None },
+			};
+
+			quote! {
+				#( router.require_capability(#module, #func_name, #capabilities); )*
+			}
+		};
+
+		let functions = self.interface.functions.iter().map(|func| {
+			let func_name = func.id.to_snake_case();
+
+			require(mod_name, Some(&func_name), capabilities(&func.id, &func.annotations))
+		});
+
+		let resources = self.interface.typedefs.iter().filter_map(|(_, typedef)| {
+			let TypeDefKind::Resource(resource) = &typedef.kind else { return None };
+
+			let module = format!("{mod_name}::resource::{}", typedef.ident);
+
+			let constructor = resource.constructor.iter().map(|constructor| {
+				let name = format!("{}::constructor", typedef.ident);
+
+				require(&module, Some("constructor"), capabilities(&name, &constructor.annotations))
+			});
+
+			let functions = resource.methods.iter().chain(&resource.statics).map(|func| {
+				let name = format!("{}::{}", typedef.ident, func.id);
+
+				let func_name = func.id.to_snake_case();
+
+				require(&module, Some(&func_name), capabilities(&name, &func.annotations))
+			});
+
+			let resource = require(&module, None, capabilities(&typedef.ident, &typedef.annotations));
+
+			Some(quote! {
+				#resource
+				#( #constructor )*
+				#( #functions )*
+			})
+		});
+
+		quote! {
+			#( #functions )*
+			#( #resources )*
+		}
+	}
+
+	/// Prints the statements requiring calls of every function to be made by
+	/// bindings generated from the same version of the function.
+	fn print_schemas(&self, mod_name:&str) -> TokenStream {
+		let typedefs = &self.interface.typedefs;
+
+		let require = |module:&str, func_name:&str, func:&Function| {
+			let hash = function_hash(typedefs, func);
+
+			quote! { router.require_schema(#module, #func_name, #hash); }
+		};
+
+		let functions = self
+			.interface
+			.functions
+			.iter()
+			.map(|func| require(mod_name, &func.id.to_snake_case(), func));
+
+		let resources = typedefs.iter().filter_map(|(_, typedef)| {
+			let TypeDefKind::Resource(resource) = &typedef.kind else { return None };
+
+			let module = format!("{mod_name}::resource::{}", typedef.ident);
+
+			let constructor = resource
+				.constructor
+				.iter()
+				.map(|constructor| require(&module, "constructor", constructor));
+
+			let functions = resource
+				.methods
+				.iter()
+				.chain(&resource.statics)
+				.map(|func| require(&module, &func.id.to_snake_case(), func));
+
+			Some(quote! {
+				#( #constructor )*
+				#( #functions )*
+			})
+		});
+
+		quote! {
+			#( #functions )*
+			#( #resources )*
+		}
+	}
+
+	/// Prints the statements marking the endpoints of functions annotated with
+	/// `@deprecated` as deprecated, so the router warns when they're called.
+	fn print_deprecations(&self, mod_name:&str) -> TokenStream {
+		let deprecate = |module:&str, func_name:&str, func:&Function| {
+			let note = wit_parser::deprecation(&func.annotations)?;
+
+			Some(quote! { router.deprecate(#module, #func_name, #note); })
+		};
+
+		let functions = self
+			.interface
+			.functions
+			.iter()
+			.filter_map(|func| deprecate(mod_name, &func.id.to_snake_case(), func));
+
+		let resources = self.interface.typedefs.iter().filter_map(|(_, typedef)| {
+			let TypeDefKind::Resource(resource) = &typedef.kind else { return None };
+
+			let module = format!("{mod_name}::resource::{}", typedef.ident);
+
+			let constructor = resource
+				.constructor
+				.iter()
+				.filter_map(|constructor| deprecate(&module, "constructor", constructor));
+
+			let functions = resource
+				.methods
+				.iter()
+				.chain(&resource.statics)
+				.filter_map(|func| deprecate(&module, &func.id.to_snake_case(), func));
+
+			Some(quote! {
+				#( #constructor )*
+				#( #functions )*
+			})
+		});
+
+		quote! {
+			#( #functions )*
+			#( #resources )*
+		}
+	}
+
 	fn print_add_to_router<'a>(
 		&self,
 		mod_ident:&str,
 		functions:impl Iterator<Item = &'a Function>,
-		methods:impl Iterator<Item = (&'a str, &'a Function)>,
+		resources:impl Iterator<Item = (&'a str, &'a Resource)> + Clone,
 	) -> TokenStream {
 		let trait_ident = format_ident!("{}", mod_ident.to_upper_camel_case());
 
@@ -448,8 +910,43 @@ impl Host {
 
 		let functions = functions.map(|func| self.print_router_fn_definition(&mod_name, func));
 
-		let methods = methods.map(|(resource_name, method)| {
-			self.print_router_method_definition(&mod_name, resource_name, method)
+		let methods = resources
+			.clone()
+			.flat_map(|(resource_name, resource)| {
+				std::iter::repeat(resource_name).zip(&resource.methods)
+			})
+			.map(|(resource_name, method)| {
+				self.print_router_method_definition(&mod_name, resource_name, method)
+			});
+
+		let cleanup = resources.clone().next().is_some().then(|| {
+			quote! {
+				let get_cx = ::std::sync::Arc::clone(&wrapped_get_cx);
+
+				router.on_window_cleanup(move |data, window| get_cx(data).drop_window(window));
+			}
+		});
+
+		let modules = std::iter::once(mod_name.clone()).chain(
+			resources
+				.clone()
+				.map(|(resource_name, _)| format!("{mod_name}::resource::{resource_name}")),
+		);
+
+		let schemas = self.print_schemas(&mod_name);
+
+		let capabilities = self.opts.capability.as_ref().map(|capability| {
+			quote! {
+				#( router.require_capability(#modules, None, #capability); )*
+			}
+		});
+
+		let item_capabilities = self.print_item_capabilities(&mod_name);
+
+		let deprecations = self.print_deprecations(&mod_name);
+
+		let resources = resources.map(|(resource_name, resource)| {
+			self.print_router_resource_definition(&mod_name, resource_name, resource)
 		});
 
 		quote! {
@@ -463,8 +960,14 @@ impl Host {
 			{
 				let wrapped_get_cx = ::std::sync::Arc::new(get_cx);
 
+				#schemas
+				#capabilities
+				#item_capabilities
+				#deprecations
 				#( #functions )*
+				#( #resources )*
 				#( #methods )*
+				#cleanup
 
 				Ok(())
 			}
@@ -480,65 +983,90 @@ impl Generate for Host {
 
 		let ident = format_ident!("{}", iface_name);
 
+		let constants = self.print_constants();
+
 		let typedefs = self
 			.print_typedefs(self.interface.typedefs.iter().map(|(id, _)| id), &BorrowMode::Owned);
 
-		let methods = self
-			.interface()
+		let resources = self
+			.interface
 			.typedefs
 			.iter()
 			.filter_map(|(_, typedef)| {
-				if let TypeDefKind::Resource(methods) = &typedef.kind {
-					Some(std::iter::repeat(typedef.ident.as_str()).zip(methods.iter()))
+				if let TypeDefKind::Resource(resource) = &typedef.kind {
+					Some((typedef.ident.as_str(), resource))
 				} else {
 					None
 				}
 			})
-			.flatten();
+			.collect::<Vec<_>>();
 
-		let resources = self.interface.typedefs.iter().filter_map(|(_, typedef)| {
-            if let TypeDefKind::Resource(_) = &typedef.kind {
-                let ident = format_ident!("{}", typedef.ident.to_upper_camel_case());
+		// static functions are implemented by the interface, prefixed with the
+		// name of their resource
+		let statics = resources
+			.iter()
+			.flat_map(|(resource_name, resource)| {
+				resource.statics.iter().map(move |func| {
+					Function { id:format!("{resource_name}-{}", func.id), ..func.clone() }
+				})
+			})
+			.collect::<Vec<_>>();
 
-                let func_ident = format_ident!("get_{}", typedef.ident.to_snake_case());
+		// handles are looked up in and released from the table by default, so
+		// implementations only have to push the resources they create
+		let drop_window = (!resources.is_empty()).then(|| {
+			quote! {
+				/// The table holding the resources handed out to webviews.
+				fn resource_table(&self) -> &::tauri_bindgen_host::ResourceTable;
 
-                Some(quote! {
-                    type #ident: #ident + Send + Sync;
+				/// Releases all resources owned by the window, called when it is
+				/// destroyed or reloaded.
+				fn drop_window(&self, window: &str) {
+					self.resource_table().drop_window(window);
+				}
+			}
+		});
 
-                    fn #func_ident(&self, id: ::tauri_bindgen_host::ResourceId) -> ::tauri_bindgen_host::Result<::std::sync::Arc<Self::#ident>>;
-                })
-            } else {
-                None
-            }
-        });
+		let resource_items = resources
+			.iter()
+			.map(|(resource_name, resource)| self.print_resource_trait_items(resource_name, resource))
+			.chain(drop_window);
 
 		let trait_ = self.print_trait(
 			&self.interface.ident,
-			self.interface.functions.iter(),
-			resources,
+			self.interface.functions.iter().chain(&statics),
+			resource_items,
 			true,
 		);
 
 		let add_to_router = self.print_add_to_router(
 			&self.interface.ident,
 			self.interface.functions.iter(),
-			methods,
+			resources.iter().copied(),
 		);
 
+		let exports = self.print_exports_client(&iface_name);
+
 		quote! {
 			#docs
-			#[allow(unused_imports, unused_variables, dead_code)]
+			#[allow(unused_imports, unused_variables, dead_code, deprecated)]
 			#[rustfmt::skip]
 			pub mod #ident {
 				use ::tauri_bindgen_host::serde;
 
 				use ::tauri_bindgen_host::bitflags;
 
+				use ::tauri_bindgen_host::field_or_default;
+
+				#constants
+
 				#typedefs
 
 				#trait_
 
 				#add_to_router
+
+				#exports
 			}
 		}
 	}
@@ -558,3 +1086,17 @@ impl Generate for Host {
 		}
 	}
 }
+
+fn returns_stream(func:&Function) -> bool {
+	matches!(func.result, Some(FunctionResult::Anon(Type::Stream(_))))
+}
+
+/// The names of the sync and async router functions used to define an
+/// endpoint.
+fn print_define_idents(stream:bool) -> (Ident, Ident) {
+	if stream {
+		(format_ident!("define_stream"), format_ident!("define_stream_async"))
+	} else {
+		(format_ident!("define"), format_ident!("define_async"))
+	}
+}
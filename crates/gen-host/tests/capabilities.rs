@@ -0,0 +1,102 @@
+use tauri_bindgen_core::GeneratorBuilder;
+use tauri_bindgen_gen_host::Builder;
+
+const SOURCE:&str = r#"interface files {
+	@capability("fs:read")
+	func read(path: string) -> string
+
+	func write(path: string, contents: string)
+
+	func ping()
+
+	@capability("fs:watch")
+	resource watcher {
+		constructor(path: string)
+
+		@capability("fs:read")
+		func changes() -> list<string>
+	}
+
+	func watch(path: string) -> watcher
+}"#;
+
+fn generate(builder:Builder) -> String {
+	let iface = wit_parser::parse_and_resolve_str(SOURCE, |_| false).unwrap();
+
+	builder.build(iface).to_file().1
+}
+
+/// Whether `file` contains `code`, ignoring the line breaks and trailing
+/// commas of the formatter.
+fn contains(file:&str, code:&str) -> bool {
+	let strip = |s:&str| {
+		s.chars().filter(|c| !c.is_whitespace()).collect::<String>().replace(",)", ")")
+	};
+
+	strip(file).contains(&strip(code))
+}
+
+#[test]
+fn annotations() {
+	let file = generate(Builder { fmt:true, ..Default::default() });
+
+	for line in [
+		r#"router.require_capability("files", Some("read"), "fs:read");"#,
+		r#"router.require_capability("files::resource::watcher", None, "fs:watch");"#,
+		r#"router.require_capability("files::resource::watcher", Some("changes"), "fs:read");"#,
+	] {
+		assert!(contains(&file, line), "missing `{line}` in\n{file}");
+	}
+
+	assert_eq!(file.matches("require_capability").count(), 3, "{file}");
+}
+
+#[test]
+fn builder() {
+	let file = generate(Builder {
+		fmt:true,
+		capability:Some("files".to_string()),
+		item_capabilities:vec![
+			("write".to_string(), "fs:write".to_string()),
+			("watcher::constructor".to_string(), "fs:write".to_string()),
+		],
+		..Default::default()
+	});
+
+	for line in [
+		r#"router.require_capability("files", None, "files");"#,
+		r#"router.require_capability("files::resource::watcher", None, "files");"#,
+		r#"router.require_capability("files", Some("read"), "fs:read");"#,
+		r#"router.require_capability("files", Some("write"), "fs:write");"#,
+		r#"router.require_capability("files::resource::watcher", Some("constructor"), "fs:write");"#,
+	] {
+		assert!(contains(&file, line), "missing `{line}` in\n{file}");
+	}
+
+	assert!(!file.contains(r#"Some("ping")"#), "{file}");
+}
+
+#[test]
+fn unknown_item() {
+	let iface = wit_parser::parse_and_resolve_str(SOURCE, |_| false).unwrap();
+
+	let check = |names:&[&str]| {
+		Builder {
+			item_capabilities:names
+				.iter()
+				.map(|name| (name.to_string(), "fs:write".to_string()))
+				.collect(),
+			..Default::default()
+		}
+		.check(&iface)
+	};
+
+	assert_eq!(check(&["write", "watcher", "watcher::constructor", "watcher::changes"]), Ok(()));
+
+	let err = check(&["write", "wirte"]).unwrap_err();
+
+	assert!(err.contains("capability given for `wirte`"), "{err}");
+
+	// methods need the name of their resource
+	assert!(check(&["changes"]).is_err());
+}
@@ -0,0 +1,52 @@
+use tauri_bindgen_core::GeneratorBuilder;
+use tauri_bindgen_gen_host::Builder;
+
+const SOURCE:&str = include_str!("../../../wit/deprecation.wit");
+
+/// Whether `file` contains `code`, ignoring the line breaks and trailing
+/// commas of the formatter.
+fn contains(file:&str, code:&str) -> bool {
+	let strip = |s:&str| {
+		s.chars().filter(|c| !c.is_whitespace()).collect::<String>().replace(",)", ")")
+	};
+
+	strip(file).contains(&strip(code))
+}
+
+#[test]
+fn attributes() {
+	let iface = wit_parser::parse_and_resolve_str(SOURCE, |_| false).unwrap();
+
+	let file = Builder { fmt:true, ..Default::default() }.build(iface).to_file().1;
+
+	for code in [
+		r#"/// Reads a file.
+		#[deprecated = "use `open` instead"] fn read("#,
+		"#[deprecated] fn new_file(",
+		r#"#[deprecated = "use `stat`"] fn size("#,
+		"#[deprecated] #[derive(serde::Serialize)]
+		 #[derive(Debug, Clone, PartialEq)] pub struct Stat",
+		r#"#[deprecated = "use `len`"] pub size: u64"#,
+	] {
+		assert!(contains(&file, code), "missing `{code}` in\n{file}");
+	}
+
+	assert_eq!(file.matches("#[deprecated").count(), 5, "{file}");
+}
+
+#[test]
+fn router_warnings() {
+	let iface = wit_parser::parse_and_resolve_str(SOURCE, |_| false).unwrap();
+
+	let file = Builder { fmt:true, ..Default::default() }.build(iface).to_file().1;
+
+	for code in [
+		r#"router.deprecate("files", "read", "use `open` instead");"#,
+		r#"router.deprecate("files::resource::file", "constructor", "");"#,
+		r#"router.deprecate("files::resource::file", "size", "use `stat`");"#,
+	] {
+		assert!(contains(&file, code), "missing `{code}` in\n{file}");
+	}
+
+	assert_eq!(file.matches("router.deprecate").count(), 3, "{file}");
+}
@@ -0,0 +1,44 @@
+function registerExportHandler(module, handler, json = false) {
+	const handlers = (window.__TAURI_BINDGEN_EXPORTS__ ??= {});
+
+	if (json) {
+		// the host passes the parameters as text, replies are sent as
+		// `[id, { "Ok": result }]` or `[id, { "Err": message }]`
+		handlers[module] = async (id, name, params) => {
+			let out;
+
+			try {
+				const result = await handler(name, parseJson(params));
+
+				out = `[${id},{"Ok":${stringifyJson(result ?? null)}}]`;
+			} catch (err) {
+				out = stringifyJson([id, { Err: String(err) }]);
+			}
+
+			await fetchIpc("ipc://localhost/tauri_bindgen::export/reply", out);
+		};
+
+		handlers[module].format = "json";
+
+		return;
+	}
+
+	handlers[module] = async (id, name, bytes) => {
+		const out = [];
+		serializeU32(out, id);
+
+		// replies are sent as `Result<Vec<u8>, String>`
+		try {
+			const result = [];
+			await handler(name, new Deserializer(bytes), result);
+
+			serializeU32(out, 0);
+			serializeBytes(out, result);
+		} catch (err) {
+			serializeU32(out, 1);
+			serializeBytes(out, __text_encoder.encode(String(err)));
+		}
+
+		await fetchIpc("ipc://localhost/tauri_bindgen::export/reply", out);
+	};
+}
@@ -0,0 +1,252 @@
+async function fetchIpc(url, out, signal, schema) {
+	if (signal?.aborted) {
+		throw new IpcError("cancelled", "the call was cancelled");
+	}
+
+	// calls made inside of `batch` are sent together once it returns
+	const pending = globalThis.__TAURI_BINDGEN_BATCH__;
+
+	if (pending !== undefined) {
+		return new Promise((resolve, reject) => {
+			// the host still runs cancelled calls of a batch, only their
+			// results are discarded
+			signal?.addEventListener(
+				"abort",
+				() => reject(new IpcError("cancelled", "the call was cancelled")),
+				{ once: true },
+			);
+
+			pending.push({ url, out, schema, resolve, reject });
+		});
+	}
+
+	// calls encoded as JSON pass the text of their parameters
+	const json = typeof out === "string";
+
+	const headers = { "Content-Type": json ? "application/json" : "application/octet-stream" };
+
+	// the host rejects calls of bindings generated from another version of
+	// the function, batches carry the hashes of all their calls
+	if (schema !== undefined) {
+		headers["Tauri-Bindgen-Schema"] = schema;
+	}
+
+	let onAbort;
+
+	// the host cancels the call by the id the page assigned to it
+	if (signal !== undefined) {
+		const id = (globalThis.__TAURI_BINDGEN_CALL_ID__ = ((globalThis.__TAURI_BINDGEN_CALL_ID__ ?? 0) + 1) >>> 0);
+
+		headers["Tauri-Bindgen-Call-Id"] = String(id);
+
+		onAbort = () => {
+			let out = [];
+
+			if (json) {
+				out = stringifyJson(id);
+			} else {
+				pushVarint(out, id);
+			}
+
+			fetchIpc("ipc://localhost/tauri_bindgen::cancel/call", out).catch(() => {});
+		};
+
+		signal.addEventListener("abort", onAbort, { once: true });
+	}
+
+	try {
+		let res;
+
+		try {
+			res = await fetch(url, {
+				method: "POST",
+				body: json ? out : Uint8Array.from(out),
+				headers,
+				signal,
+			});
+		} catch (err) {
+			if (signal?.aborted) {
+				throw new IpcError("cancelled", "the call was cancelled");
+			}
+
+			throw new IpcError("transport", String(err));
+		}
+
+		// failed calls carry their kind in a header and the message in the body
+		if (!res.ok) {
+			const kind = res.headers.get("Tauri-Bindgen-Error");
+
+			if (kind === null) {
+				throw new IpcError("transport", `unexpected response status ${res.status}`);
+			}
+
+			throw new IpcError(kind, await res.text());
+		}
+
+		return await res.arrayBuffer();
+	} catch (err) {
+		if (signal?.aborted && !(err instanceof IpcError)) {
+			throw new IpcError("cancelled", "the call was cancelled");
+		}
+
+		throw err;
+	} finally {
+		signal?.removeEventListener("abort", onAbort);
+	}
+}
+
+// encodes an unsigned integer the way postcard does
+function pushVarint(out, val) {
+	while (val >= 0x80) {
+		out.push((val & 0x7f) | 0x80);
+		val >>>= 7;
+	}
+	out.push(val);
+}
+
+function collectBatch(calls) {
+	if (globalThis.__TAURI_BINDGEN_BATCH__ !== undefined) {
+		throw new Error("batches can't be nested");
+	}
+
+	const pending = [];
+
+	globalThis.__TAURI_BINDGEN_BATCH__ = pending;
+
+	try {
+		return [calls(), pending];
+	} finally {
+		globalThis.__TAURI_BINDGEN_BATCH__ = undefined;
+	}
+}
+
+// the batch is sent as `Vec<(String, String, Vec<u8>)>` and answered with
+// `Vec<Result<Vec<u8>, (String, String)>>`, failed calls carry the kind and
+// message of their error. JSON embeds the parameters and results as is.
+async function sendBatch(pending) {
+	const json = typeof pending[0].out === "string";
+
+	if (pending.some(({ out }) => (typeof out === "string") !== json)) {
+		const err = new IpcError("transport", "batches can't mix JSON and postcard calls");
+
+		for (const { reject } of pending) {
+			reject(err);
+		}
+
+		return;
+	}
+
+	const calls = pending.map(({ url, out }) => {
+		const path = url.slice("ipc://localhost/".length);
+		const idx = path.indexOf("/");
+
+		return { module: path.slice(0, idx), method: path.slice(idx + 1), params: out };
+	});
+
+	let results;
+
+	try {
+		const schemas = new Set(
+			pending.map(({ schema }) => schema).filter((schema) => schema !== undefined),
+		);
+
+		const schema = schemas.size > 0 ? [...schemas].join(",") : undefined;
+
+		results = json
+			? await sendJsonBatch(calls, schema)
+			: await sendPostcardBatch(calls, schema);
+	} catch (err) {
+		for (const { reject } of pending) {
+			reject(err);
+		}
+
+		return;
+	}
+
+	for (let i = 0; i < pending.length; i++) {
+		const { resolve, reject } = pending[i];
+		const result = results[i];
+
+		if (result.ok) {
+			resolve(result.bytes);
+		} else {
+			reject(new IpcError(result.kind, result.message));
+		}
+	}
+}
+
+async function sendPostcardBatch(calls, schema) {
+	const out = [];
+	const encoder = new TextEncoder();
+
+	const pushBytes = (bytes) => {
+		pushVarint(out, bytes.length);
+		out.push(...bytes);
+	};
+
+	pushVarint(out, calls.length);
+
+	for (const { module, method, params } of calls) {
+		pushBytes(encoder.encode(module));
+		pushBytes(encoder.encode(method));
+		pushBytes(params);
+	}
+
+	const bytes = await fetchIpc("ipc://localhost/tauri_bindgen::batch/call", out, undefined, schema);
+
+	const de = new Deserializer(new Uint8Array(bytes));
+	const decoder = new TextDecoder("utf-8");
+
+	const popVarint = () => {
+		let val = 0;
+
+		for (let shift = 0; ; shift += 7) {
+			const byte = de.pop();
+			val |= (byte & 0x7f) << shift;
+
+			if ((byte & 0x80) === 0) {
+				return val >>> 0;
+			}
+		}
+	};
+
+	const popBytes = () => de.try_take_n(popVarint());
+
+	const results = [];
+	const len = popVarint();
+
+	for (let i = 0; i < len; i++) {
+		if (popVarint() === 0) {
+			results.push({ ok: true, bytes: popBytes().buffer });
+		} else {
+			const kind = decoder.decode(popBytes());
+
+			results.push({ ok: false, kind, message: decoder.decode(popBytes()) });
+		}
+	}
+
+	return results;
+}
+
+async function sendJsonBatch(calls, schema) {
+	const entries = calls.map(
+		({ module, method, params }) =>
+			`[${JSON.stringify(module)},${JSON.stringify(method)},${params}]`,
+	);
+
+	const bytes = await fetchIpc(
+		"ipc://localhost/tauri_bindgen::batch/call",
+		`[${entries.join(",")}]`,
+		undefined,
+		schema,
+	);
+
+	const encoder = new TextEncoder();
+
+	// results are encoded again so they are decoded like other results
+	return parseJson(bytes).map((result) =>
+		"Ok" in result
+			? { ok: true, bytes: encoder.encode(stringifyJson(result.Ok)).buffer }
+			: { ok: false, kind: result.Err[0], message: result.Err[1] },
+	);
+}
@@ -0,0 +1,19 @@
+// 64 and 128 bit integers are bigints, which are written as numbers where
+// `JSON.rawJSON` is supported and lose precision otherwise
+function stringifyJson(val) {
+	return JSON.stringify(val, (_, v) =>
+		typeof v === "bigint" ? (JSON.rawJSON?.(v.toString()) ?? Number(v)) : v,
+	);
+}
+
+// integers too large for numbers are read as bigints where `JSON.parse`
+// exposes the source text of values
+function parseJson(src) {
+	const text = typeof src === "string" ? src : new TextDecoder("utf-8").decode(src);
+
+	return JSON.parse(text, (_, v, ctx) =>
+		typeof v === "number" && !Number.isSafeInteger(v) && /^-?\d+$/.test(ctx?.source ?? "")
+			? BigInt(ctx.source)
+			: v,
+	);
+}
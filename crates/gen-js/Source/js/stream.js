@@ -0,0 +1,76 @@
+class Stream {
+	#id;
+	#deserialize;
+	#json;
+	#done = false;
+
+	constructor(id, deserialize, json = false) {
+		this.#id = id;
+		this.#deserialize = deserialize;
+		this.#json = json;
+	}
+
+	async next() {
+		if (this.#done) {
+			return { done: true, value: undefined };
+		}
+
+		if (this.#json) {
+			const items = await fetchIpc(
+				"ipc://localhost/tauri_bindgen::stream/next",
+				stringifyJson(this.#id),
+			).then(parseJson);
+
+			// items are sent as arrays with at most one element, an empty array
+			// marks the end of the stream
+			if (items.length === 0) {
+				this.#done = true;
+
+				return { done: true, value: undefined };
+			}
+
+			return { done: false, value: this.#deserialize(items[0]) };
+		}
+
+		const out = [];
+		serializeU32(out, this.#id);
+
+		const de = await fetchIpc("ipc://localhost/tauri_bindgen::stream/next", out).then(
+			(bytes) => new Deserializer(new Uint8Array(bytes)),
+		);
+
+		// items are sent as options, `None` marks the end of the stream
+		if (de.pop() === 0) {
+			this.#done = true;
+
+			return { done: true, value: undefined };
+		}
+
+		return { done: false, value: this.#deserialize(de) };
+	}
+
+	async return() {
+		if (!this.#done) {
+			this.#done = true;
+
+			let out = [];
+
+			if (this.#json) {
+				out = stringifyJson(this.#id);
+			} else {
+				serializeU32(out, this.#id);
+			}
+
+			await fetchIpc("ipc://localhost/tauri_bindgen::stream/cancel", out);
+		}
+
+		return { done: true, value: undefined };
+	}
+
+	[Symbol.asyncIterator]() {
+		return this;
+	}
+}
+function deserializeStream(de, deserialize) {
+	return new Stream(deserializeU32(de), deserialize);
+}
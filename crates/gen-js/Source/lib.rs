@@ -1,9 +1,10 @@
 use std::fmt;
 
 use fmt::Write;
-use heck::{ToLowerCamelCase, ToUpperCamelCase};
+use heck::{ToLowerCamelCase, ToShoutySnekCase, ToSnakeCase, ToUpperCamelCase};
 use tauri_bindgen_core::{TypeInfos, flags_repr, union_case_names};
 use wit_parser::{
+	Annotation,
 	EnumCase,
 	FlagsField,
 	Function,
@@ -15,6 +16,7 @@ use wit_parser::{
 	TypeDefId,
 	TypeDefKind,
 	UnionCase,
+	Value,
 	VariantCase,
 };
 
@@ -23,6 +25,77 @@ pub trait JavaScriptGenerator {
 
 	fn infos(&self) -> &TypeInfos;
 
+	/// Whether calls are encoded as JSON rather than postcard.
+	fn json(&self) -> bool { false }
+
+	/// Prints the statements encoding the parameters of a call into `out`,
+	/// `handle` is the handle of the resource a method is called on.
+	fn print_encode_params(&self, params:&[(String, Type)], handle:Option<&str>) -> String {
+		if self.json() {
+			let mut values:Vec<_> = params
+				.iter()
+				.map(|(ident, ty)| self.print_to_json_ty(&ident.to_lower_camel_case(), ty))
+				.collect();
+
+			// the host decodes a single parameter as is and several as a tuple,
+			// methods of resources always pass a tuple starting with the handle
+			let value = match handle {
+				Some(handle) => {
+					values.insert(0, handle.to_string());
+
+					format!("[{}]", values.join(", "))
+				},
+				None if values.is_empty() => "null".to_string(),
+				None if values.len() == 1 => values.remove(0),
+				None => format!("[{}]", values.join(", ")),
+			};
+
+			return format!("const out = stringifyJson({value})");
+		}
+
+		let serialize_handle =
+			handle.map(|handle| format!("serializeU32(out, {handle});")).unwrap_or_default();
+
+		let serialize_params = params
+			.iter()
+			.map(|(ident, ty)| self.print_serialize_ty(&ident.to_lower_camel_case(), ty))
+			.collect::<Vec<_>>()
+			.join(";\n");
+
+		format!("const out = []\n    {serialize_handle}\n    {serialize_params}")
+	}
+
+	/// Prints the callback decoding the result of a call, if there is one.
+	fn print_decode_function_result(&self, result:&FunctionResult) -> String {
+		if !self.json() {
+			return self.print_deserialize_function_result(result);
+		}
+
+		let value = match result.len() {
+			0 => return String::new(),
+			1 => self.print_from_json_ty("val", result.types().next().unwrap()),
+			_ => {
+				let tys = result
+					.types()
+					.enumerate()
+					.map(|(idx, ty)| self.print_from_json_ty(&format!("val[{idx}]"), ty))
+					.collect::<Vec<_>>()
+					.join(", ");
+
+				format!("[{tys}]")
+			},
+		};
+
+		format!(
+			"
+        .then(bytes => {{
+            const val = parseJson(bytes)
+
+            return {value}
+        }})"
+		)
+	}
+
 	fn print_deserialize_function_result(&self, result:&FunctionResult) -> String {
 		match result.len() {
 			0 => String::new(),
@@ -31,7 +104,6 @@ pub trait JavaScriptGenerator {
 
 				format!(
 					"
-        .then(r => r.arrayBuffer())
         .then(bytes => {{
             const de = new Deserializer(new Uint8Array(bytes))
 
@@ -48,9 +120,8 @@ pub trait JavaScriptGenerator {
 
 				format!(
 					"
-        .then(r => r.arrayBuffer())
         .then(bytes => {{
-            const de = new Deserializer(Uint8Array.from(bytes))
+            const de = new Deserializer(new Uint8Array(bytes))
 
             return [{tys}]
         }})"
@@ -59,6 +130,137 @@ pub trait JavaScriptGenerator {
 		}
 	}
 
+	/// Prints the call registering `impl` as the implementation of the
+	/// functions the interface exports to the host.
+	fn print_register_export_handler(&self, intf_name:&str, functions:&[Function]) -> String {
+		if self.json() {
+			return self.print_register_json_export_handler(intf_name, functions);
+		}
+
+		let cases:String = functions
+			.iter()
+			.map(|func| {
+				let name = func.id.to_snake_case();
+
+				let ident = func.id.to_lower_camel_case();
+
+				let params = func
+					.params
+					.iter()
+					.map(|(ident, _)| ident.to_lower_camel_case())
+					.collect::<Vec<_>>()
+					.join(", ");
+
+				let deserialize_params = func
+					.params
+					.iter()
+					.map(|(ident, ty)| {
+						format!(
+							"const {} = {}",
+							ident.to_lower_camel_case(),
+							self.print_deserialize_ty(ty)
+						)
+					})
+					.collect::<Vec<_>>()
+					.join(";\n");
+
+				let serialize_result = match &func.result {
+					Some(result) if result.len() == 1 => {
+						self.print_serialize_ty("result", result.types().next().unwrap())
+					},
+					Some(result) => {
+						result
+							.types()
+							.enumerate()
+							.map(|(idx, ty)| self.print_serialize_ty(&format!("result[{idx}]"), ty))
+							.collect::<Vec<_>>()
+							.join(";\n")
+					},
+					None => String::new(),
+				};
+
+				format!(
+					"
+            case '{name}': {{
+                {deserialize_params}
+                const result = await impl.{ident}({params})
+                {serialize_result}
+                break
+            }}"
+				)
+			})
+			.collect();
+
+		format!(
+			"registerExportHandler('{intf_name}', async (name, de, out) => {{
+        switch (name) {{{cases}
+            default:
+                throw new Error(`unknown function ${{name}}`)
+        }}
+    }})"
+		)
+	}
+
+	/// Like [`Self::print_register_export_handler`], but for calls encoded as
+	/// JSON. The handler returns the result, which the host decodes as is for
+	/// a single value and as a tuple for several.
+	fn print_register_json_export_handler(&self, intf_name:&str, functions:&[Function]) -> String {
+		let cases:String = functions
+			.iter()
+			.map(|func| {
+				let name = func.id.to_snake_case();
+
+				let ident = func.id.to_lower_camel_case();
+
+				let params = match func.params.len() {
+					1 => self.print_from_json_ty("params", &func.params[0].1),
+					_ => {
+						func.params
+							.iter()
+							.enumerate()
+							.map(|(idx, (_, ty))| self.print_from_json_ty(&format!("params[{idx}]"), ty))
+							.collect::<Vec<_>>()
+							.join(", ")
+					},
+				};
+
+				let result = match &func.result {
+					Some(result) if result.len() == 1 => {
+						self.print_to_json_ty("result", result.types().next().unwrap())
+					},
+					Some(result) if result.len() > 1 => {
+						let values = result
+							.types()
+							.enumerate()
+							.map(|(idx, ty)| self.print_to_json_ty(&format!("result[{idx}]"), ty))
+							.collect::<Vec<_>>()
+							.join(", ");
+
+						format!("[{values}]")
+					},
+					_ => "null".to_string(),
+				};
+
+				format!(
+					"
+            case '{name}': {{
+                const result = await impl.{ident}({params})
+                return {result}
+            }}"
+				)
+			})
+			.collect();
+
+		format!(
+			"registerExportHandler('{intf_name}', async (name, params) => {{
+        switch (name) {{{cases}
+            default:
+                throw new Error(`unknown function ${{name}}`)
+        }}
+    }}, true)"
+		)
+	}
+
 	fn print_deserialize_ty(&self, ty:&Type) -> String {
 		match ty {
 			Type::Bool => "deserializeBool(de)".to_string(),
@@ -96,6 +298,11 @@ pub trait JavaScriptGenerator {
 
 				format!("deserializeOption(de, (de) => {ty})")
 			},
+			Type::Stream(ty) => {
+				let ty = self.print_deserialize_ty(ty);
+
+				format!("deserializeStream(de, (de) => {ty})")
+			},
 			Type::Result { ok, err } => {
 				let ok = ok.as_ref().map_or("() => {}".to_string(), |ty| {
 					format!("(de) => {}", self.print_deserialize_ty(ty))
@@ -107,6 +314,19 @@ pub trait JavaScriptGenerator {
 
 				format!("deserializeResult(de, {ok}, {err})")
 			},
+			Type::Param(name) => format!("deserialize{}(de)", name.to_upper_camel_case()),
+			Type::Generic { id, args } => {
+				let args = args.iter().fold(String::new(), |mut str, ty| {
+					let _ = write!(str, ", (de) => {}", self.print_deserialize_ty(ty));
+
+					str
+				});
+
+				format!(
+					"deserialize{}(de{args})",
+					self.interface().typedefs[*id].ident.to_upper_camel_case()
+				)
+			},
 			Type::Id(id) => {
 				if let TypeDefKind::Resource(_) = self.interface().typedefs[*id].kind {
 					format!(
@@ -128,40 +348,57 @@ pub trait JavaScriptGenerator {
 
 		let ident = &typedef.ident.to_upper_camel_case();
 
+		let callbacks = &print_callback_params("deserialize", &typedef.params);
+
 		match &typedef.kind {
-			TypeDefKind::Alias(ty) => self.print_deserialize_alias(ident, ty),
-			TypeDefKind::Record(fields) => self.print_deserialize_record(ident, fields),
+			TypeDefKind::Alias(ty) => self.print_deserialize_alias(ident, callbacks, ty),
+			TypeDefKind::Record(fields) => self.print_deserialize_record(ident, callbacks, fields),
 			TypeDefKind::Flags(fields) => self.print_deserialize_flags(ident, fields),
-			TypeDefKind::Variant(cases) => self.print_deserialize_variant(ident, cases),
+			TypeDefKind::Variant(cases) => self.print_deserialize_variant(ident, callbacks, cases),
 			TypeDefKind::Enum(cases) => self.print_deserialize_enum(ident, cases),
-			TypeDefKind::Union(cases) => self.print_deserialize_union(ident, cases),
+			TypeDefKind::Union(cases) => self.print_deserialize_union(ident, callbacks, cases),
 			TypeDefKind::Resource(_) => String::new(),
 		}
 	}
 
-	fn print_deserialize_alias(&self, ident:&str, ty:&Type) -> String {
+	fn print_deserialize_alias(&self, ident:&str, callbacks:&str, ty:&Type) -> String {
 		let inner = self.print_deserialize_ty(ty);
 
 		format!(
-			r#"function deserialize{ident}(de) {{
+			r#"function deserialize{ident}(de{callbacks}) {{
     return {inner}
 }}"#
 		)
 	}
 
-	fn print_deserialize_record(&self, ident:&str, fields:&[RecordField]) -> String {
+	fn print_deserialize_record(&self, ident:&str, callbacks:&str, fields:&[RecordField]) -> String {
+		// the fields with a default after the last field without one take it
+		// when the input ends early, it was encoded before they were added
+		let trailing =
+			fields.iter().rposition(|field| field.default.is_none()).map_or(0, |i| i + 1);
+
 		let fields = fields
 			.iter()
-			.map(|field| {
+			.enumerate()
+			.map(|(i, field)| {
 				let ident = field.id.to_lower_camel_case();
 
-				format!("{ident}: {}", self.print_deserialize_ty(&field.ty))
+				let inner = self.print_deserialize_ty(&field.ty);
+
+				match &field.default {
+					Some(value) if i >= trailing => {
+						let value = self.print_value(value, &field.ty);
+
+						format!("{ident}: de.offset < de.source.length ? {inner} : {value}")
+					},
+					_ => format!("{ident}: {inner}"),
+				}
 			})
 			.collect::<Vec<_>>()
 			.join(",\n");
 
 		format!(
-			r#"function deserialize{ident}(de) {{
+			r#"function deserialize{ident}(de{callbacks}) {{
     return {{
         {fields}
     }}
@@ -185,7 +422,7 @@ pub trait JavaScriptGenerator {
 		)
 	}
 
-	fn print_deserialize_variant(&self, ident:&str, cases:&[VariantCase]) -> String {
+	fn print_deserialize_variant(&self, ident:&str, callbacks:&str, cases:&[VariantCase]) -> String {
 		let cases = cases.iter().enumerate().fold(String::new(), |mut str, (tag, case)| {
 			let inner =
 				case.ty.as_ref().map_or("null".to_string(), |ty| self.print_deserialize_ty(ty));
@@ -203,7 +440,7 @@ pub trait JavaScriptGenerator {
 		});
 
 		format!(
-			r#"function deserialize{ident}(de) {{
+			r#"function deserialize{ident}(de{callbacks}) {{
     const tag = deserializeU32(de)
 
     switch (tag) {{
@@ -244,7 +481,7 @@ pub trait JavaScriptGenerator {
 		)
 	}
 
-	fn print_deserialize_union(&self, ident:&str, cases:&[UnionCase]) -> String {
+	fn print_deserialize_union(&self, ident:&str, callbacks:&str, cases:&[UnionCase]) -> String {
 		let cases = union_case_names(&self.interface().typedefs, cases)
 			.into_iter()
 			.zip(cases)
@@ -263,7 +500,7 @@ pub trait JavaScriptGenerator {
 			});
 
 		format!(
-			r#"function deserialize{ident}(de) {{
+			r#"function deserialize{ident}(de{callbacks}) {{
     const tag = deserializeU32(de)
 
     switch (tag) {{
@@ -317,6 +554,7 @@ pub trait JavaScriptGenerator {
 
 				format!("serializeOption(out, (out, v) => {inner}, {ident})")
 			},
+			Type::Stream(_) => unreachable!("streams can only be returned from functions"),
 			Type::Result { ok, err } => {
 				let ok =
 					ok.as_ref().map_or("{}".to_string(), |ty| self.print_serialize_ty("v", ty));
@@ -326,6 +564,19 @@ pub trait JavaScriptGenerator {
 
 				format!("serializeResult(out, (out, v) => {ok}, (out, v) => {err}, {ident})")
 			},
+			Type::Param(name) => format!("serialize{}(out, {ident})", name.to_upper_camel_case()),
+			Type::Generic { id, args } => {
+				let args = args.iter().fold(String::new(), |mut str, ty| {
+					let _ = write!(str, ", (out, v) => {}", self.print_serialize_ty("v", ty));
+
+					str
+				});
+
+				format!(
+					"serialize{}(out, {ident}{args})",
+					self.interface().typedefs[*id].ident.to_upper_camel_case()
+				)
+			},
 			Type::Id(id) => {
 				if let TypeDefKind::Resource(_) = self.interface().typedefs[*id].kind {
 					format!("{ident}.serialize(out)")
@@ -344,36 +595,42 @@ pub trait JavaScriptGenerator {
 
 		let ident = &typedef.ident.to_upper_camel_case();
 
+		let callbacks = &print_callback_params("serialize", &typedef.params);
+
 		match &typedef.kind {
-			TypeDefKind::Alias(ty) => self.print_serialize_alias(ident, ty),
-			TypeDefKind::Record(fields) => self.print_serialize_record(ident, fields),
+			TypeDefKind::Alias(ty) => self.print_serialize_alias(ident, callbacks, ty),
+			TypeDefKind::Record(fields) => self.print_serialize_record(ident, callbacks, fields),
 			TypeDefKind::Flags(fields) => self.print_serialize_flags(ident, fields),
-			TypeDefKind::Variant(cases) => self.print_serialize_variant(ident, cases),
+			TypeDefKind::Variant(cases) => self.print_serialize_variant(ident, callbacks, cases),
 			TypeDefKind::Enum(cases) => self.print_serialize_enum(ident, cases),
-			TypeDefKind::Union(cases) => self.print_serialize_union(ident, cases),
+			TypeDefKind::Union(cases) => self.print_serialize_union(ident, callbacks, cases),
 			TypeDefKind::Resource(_) => String::new(),
 		}
 	}
 
-	fn print_serialize_alias(&self, ident:&str, ty:&Type) -> String {
+	fn print_serialize_alias(&self, ident:&str, callbacks:&str, ty:&Type) -> String {
 		let inner = self.print_serialize_ty("val", ty);
 
 		format!(
-			"function serialize{ident}(out, val) {{
+			"function serialize{ident}(out, val{callbacks}) {{
     {inner}
 }}"
 		)
 	}
 
-	fn print_serialize_record(&self, ident:&str, fields:&[RecordField]) -> String {
+	fn print_serialize_record(&self, ident:&str, callbacks:&str, fields:&[RecordField]) -> String {
 		let inner = fields
 			.iter()
-			.map(|field| self.print_serialize_ty(&format!("val.{}", field.id), &field.ty))
+			.map(|field| {
+				let expr = self.print_field_or_default(&format!("val.{}", field.id), field);
+
+				self.print_serialize_ty(&expr, &field.ty)
+			})
 			.collect::<Vec<_>>()
 			.join(",\n");
 
 		format!(
-			"function serialize{ident}(out, val) {{
+			"function serialize{ident}(out, val{callbacks}) {{
     {inner}
 }}"
 		)
@@ -395,7 +652,7 @@ pub trait JavaScriptGenerator {
 		)
 	}
 
-	fn print_serialize_variant(&self, ident:&str, cases:&[VariantCase]) -> String {
+	fn print_serialize_variant(&self, ident:&str, callbacks:&str, cases:&[VariantCase]) -> String {
 		let cases = cases.iter().enumerate().fold(String::new(), |mut str, (tag, case)| {
 			let prop_access = format!("val.{}", case.id.to_upper_camel_case());
 
@@ -419,7 +676,7 @@ pub trait JavaScriptGenerator {
 		});
 
 		format!(
-			r#"function serialize{ident}(out, val) {{
+			r#"function serialize{ident}(out, val{callbacks}) {{
     {cases}
 
     throw new Error("unknown variant case")
@@ -454,7 +711,7 @@ pub trait JavaScriptGenerator {
 		)
 	}
 
-	fn print_serialize_union(&self, ident:&str, cases:&[UnionCase]) -> String {
+	fn print_serialize_union(&self, ident:&str, callbacks:&str, cases:&[UnionCase]) -> String {
 		let cases = union_case_names(&self.interface().typedefs, cases)
 			.into_iter()
 			.zip(cases)
@@ -478,13 +735,466 @@ pub trait JavaScriptGenerator {
 			});
 
 		format!(
-			r#"function serialize{ident}(out, val) {{
+			r#"function serialize{ident}(out, val{callbacks}) {{
     {cases}
 
     throw new Error("unknown union case")
 }}"#
 		)
 	}
+
+	/// Prints the constants of the interface as exported bindings.
+	fn print_constants(&self) -> String {
+		self.interface().constants.iter().fold(String::new(), |mut str, constant| {
+			let deprecated = print_deprecated_tag(&constant.annotations);
+
+			if !constant.docs.is_empty() || !deprecated.is_empty() {
+				let docs = constant.docs.lines().fold(String::new(), |mut str, line| {
+					let _ = writeln!(str, " * {line}");
+
+					str
+				});
+
+				let _ = writeln!(str, "/**\n{docs}{deprecated}*/");
+			}
+
+			let _ = writeln!(
+				str,
+				"export const {} = {};",
+				constant.id.TO_SHOUTY_SNEK_CASE(),
+				self.print_value(&constant.value, &constant.ty)
+			);
+
+			str
+		})
+	}
+
+	/// Prints the expression reading a record field, fields with a default are
+	/// optional and replaced with it when missing.
+	fn print_field_or_default(&self, expr:&str, field:&RecordField) -> String {
+		match &field.default {
+			Some(value) => format!("({expr} ?? {})", self.print_value(value, &field.ty)),
+			None => expr.to_string(),
+		}
+	}
+
+	/// Prints `value` as a literal of the JavaScript type used for `ty`.
+	fn print_value(&self, value:&Value, ty:&Type) -> String {
+		match value {
+			Value::Bool(value) => value.to_string(),
+			Value::Int(int) if is_bigint(&self.interface().typedefs, ty) => format!("{int}n"),
+			Value::Int(int) => int.to_string(),
+			Value::Float(float) => float.to_string(),
+			Value::Char(char) => format!("{:?}", char.to_string()),
+			Value::String(string) => format!("{string:?}"),
+			Value::Enum(case) => format!("\"{}\"", case.to_upper_camel_case()),
+			Value::None => "null".to_string(),
+		}
+	}
+
+	/// Prints the expression converting `expr` to the value `serde_json`
+	/// expects for the type.
+	fn print_to_json_ty(&self, expr:&str, ty:&Type) -> String {
+		match ty {
+			Type::Bool
+			| Type::U8
+			| Type::U16
+			| Type::U32
+			| Type::U64
+			| Type::U128
+			| Type::S8
+			| Type::S16
+			| Type::S32
+			| Type::S64
+			| Type::S128
+			| Type::Float32
+			| Type::Float64
+			| Type::Char
+			| Type::String => expr.to_string(),
+			Type::List(ty) if **ty == Type::U8 => format!("Array.from({expr})"),
+			Type::List(ty) => {
+				let inner = self.print_to_json_ty("v", ty);
+
+				if inner == "v" { expr.to_string() } else { format!("{expr}.map((v) => {inner})") }
+			},
+			Type::Tuple(tys) if tys.is_empty() => "null".to_string(),
+			Type::Tuple(tys) => {
+				let tys = tys
+					.iter()
+					.enumerate()
+					.map(|(idx, ty)| self.print_to_json_ty(&format!("{expr}[{idx}]"), ty))
+					.collect::<Vec<_>>()
+					.join(", ");
+
+				format!("[{tys}]")
+			},
+			Type::Option(ty) => {
+				let inner = self.print_to_json_ty(expr, ty);
+
+				format!("({expr} == null ? null : {inner})")
+			},
+			Type::Stream(_) => unreachable!("streams can only be returned from functions"),
+			Type::Result { ok, err } => {
+				let val = format!("{expr}.val");
+
+				let ok =
+					ok.as_ref().map_or("null".to_string(), |ty| self.print_to_json_ty(&val, ty));
+
+				let err =
+					err.as_ref().map_or("null".to_string(), |ty| self.print_to_json_ty(&val, ty));
+
+				format!("({expr}.tag === 'ok' ? {{ Ok: {ok} }} : {{ Err: {err} }})")
+			},
+			Type::Param(name) => format!("toJson{}({expr})", name.to_upper_camel_case()),
+			Type::Generic { id, args } => {
+				let args = args.iter().fold(String::new(), |mut str, ty| {
+					let _ = write!(str, ", (v) => {}", self.print_to_json_ty("v", ty));
+
+					str
+				});
+
+				format!(
+					"toJson{}({expr}{args})",
+					self.interface().typedefs[*id].ident.to_upper_camel_case()
+				)
+			},
+			Type::Id(id) => {
+				if let TypeDefKind::Resource(_) = self.interface().typedefs[*id].kind {
+					format!("{expr}.toJson()")
+				} else {
+					format!(
+						"toJson{}({expr})",
+						self.interface().typedefs[*id].ident.to_upper_camel_case()
+					)
+				}
+			},
+		}
+	}
+
+	/// Prints the expression converting the value `serde_json` produced for
+	/// the type.
+	fn print_from_json_ty(&self, expr:&str, ty:&Type) -> String {
+		match ty {
+			Type::Bool
+			| Type::U8
+			| Type::U16
+			| Type::U32
+			| Type::S8
+			| Type::S16
+			| Type::S32
+			| Type::Float32
+			| Type::Float64
+			| Type::Char
+			| Type::String => expr.to_string(),
+			Type::U64 | Type::U128 | Type::S64 | Type::S128 => format!("BigInt({expr})"),
+			Type::List(ty) if **ty == Type::U8 => format!("Uint8Array.from({expr})"),
+			Type::List(ty) => {
+				let inner = self.print_from_json_ty("v", ty);
+
+				if inner == "v" { expr.to_string() } else { format!("{expr}.map((v) => {inner})") }
+			},
+			Type::Tuple(tys) => {
+				let tys = tys
+					.iter()
+					.enumerate()
+					.map(|(idx, ty)| self.print_from_json_ty(&format!("{expr}[{idx}]"), ty))
+					.collect::<Vec<_>>()
+					.join(", ");
+
+				format!("[{tys}]")
+			},
+			Type::Option(ty) => {
+				let inner = self.print_from_json_ty(expr, ty);
+
+				format!("({expr} === null ? null : {inner})")
+			},
+			Type::Stream(ty) => {
+				let inner = self.print_from_json_ty("v", ty);
+
+				format!("new Stream({expr}, (v) => {inner}, true)")
+			},
+			Type::Result { ok, err } => {
+				let ok = ok.as_ref().map_or("undefined".to_string(), |ty| {
+					self.print_from_json_ty(&format!("{expr}.Ok"), ty)
+				});
+
+				let err = err.as_ref().map_or("undefined".to_string(), |ty| {
+					self.print_from_json_ty(&format!("{expr}.Err"), ty)
+				});
+
+				format!(
+					"('Ok' in {expr} ? {{ tag: 'ok', val: {ok} }} : {{ tag: 'err', val: {err} }})"
+				)
+			},
+			Type::Param(name) => format!("fromJson{}({expr})", name.to_upper_camel_case()),
+			Type::Generic { id, args } => {
+				let args = args.iter().fold(String::new(), |mut str, ty| {
+					let _ = write!(str, ", (v) => {}", self.print_from_json_ty("v", ty));
+
+					str
+				});
+
+				format!(
+					"fromJson{}({expr}{args})",
+					self.interface().typedefs[*id].ident.to_upper_camel_case()
+				)
+			},
+			Type::Id(id) => {
+				if let TypeDefKind::Resource(_) = self.interface().typedefs[*id].kind {
+					format!(
+						"{}.fromJson({expr})",
+						self.interface().typedefs[*id].ident.to_upper_camel_case()
+					)
+				} else {
+					format!(
+						"fromJson{}({expr})",
+						self.interface().typedefs[*id].ident.to_upper_camel_case()
+					)
+				}
+			},
+		}
+	}
+
+	/// Prints the function converting values of the type definition to the
+	/// values `serde_json` expects, which follow the Rust types generated for
+	/// the host.
+	fn print_to_json_typedef(&self, id:TypeDefId) -> String {
+		let typedef = &self.interface().typedefs[id];
+
+		let ident = &typedef.ident.to_upper_camel_case();
+
+		let body = match &typedef.kind {
+			TypeDefKind::Alias(ty) => format!("return {}", self.print_to_json_ty("val", ty)),
+			TypeDefKind::Record(fields) => {
+				let fields = fields
+					.iter()
+					.map(|field| {
+						let expr = self.print_field_or_default(
+							&format!("val.{}", field.id.to_lower_camel_case()),
+							field,
+						);
+
+						let value = self.print_to_json_ty(&expr, &field.ty);
+
+						format!("{}: {value}", field.id.to_snake_case())
+					})
+					.collect::<Vec<_>>()
+					.join(",\n");
+
+				format!("return {{\n{fields}\n}}")
+			},
+			// flags are written as the names of the set flags, like `A | B`
+			TypeDefKind::Flags(fields) => {
+				let big = is_big_flags(fields);
+
+				let checks = fields.iter().enumerate().fold(String::new(), |mut str, (i, field)| {
+					let name = field.id.TO_SHOUTY_SNEK_CASE();
+
+					let check = if big { format!("(val >> {i}n) & 1n") } else { format!("(val >>> {i}) & 1") };
+
+					let _ = writeln!(str, "if ({check}) names.push(\"{name}\")");
+
+					str
+				});
+
+				format!("const names = []\n{checks}\nreturn names.join(\" | \")")
+			},
+			TypeDefKind::Variant(cases) => {
+				let cases = cases.iter().fold(String::new(), |mut str, case| {
+					let name = case.id.to_upper_camel_case();
+
+					let _ = match &case.ty {
+						Some(ty) => {
+							let inner = self.print_to_json_ty(&format!("val.{name}"), ty);
+
+							writeln!(str, "if ('{name}' in val) return {{ {name}: {inner} }}")
+						},
+						None => writeln!(str, "if ('{name}' in val) return \"{name}\""),
+					};
+
+					str
+				});
+
+				format!("{cases}\nthrow new Error(\"unknown variant case\")")
+			},
+			TypeDefKind::Enum(_) => "return val".to_string(),
+			TypeDefKind::Union(cases) => {
+				let cases = union_case_names(&self.interface().typedefs, cases)
+					.into_iter()
+					.zip(cases)
+					.fold(String::new(), |mut str, (name, case)| {
+						let inner = self.print_to_json_ty(&format!("val.{name}"), &case.ty);
+
+						let _ = writeln!(str, "if ('{name}' in val) return {{ {name}: {inner} }}");
+
+						str
+					});
+
+				format!("{cases}\nthrow new Error(\"unknown union case\")")
+			},
+			TypeDefKind::Resource(_) => return String::new(),
+		};
+
+		let callbacks = print_callback_params("toJson", &typedef.params);
+
+		format!("function toJson{ident}(val{callbacks}) {{\n{body}\n}}\n")
+	}
+
+	/// Prints the function converting the values `serde_json` produced for
+	/// the type definition.
+	fn print_from_json_typedef(&self, id:TypeDefId) -> String {
+		let typedef = &self.interface().typedefs[id];
+
+		let ident = &typedef.ident.to_upper_camel_case();
+
+		let body = match &typedef.kind {
+			TypeDefKind::Alias(ty) => format!("return {}", self.print_from_json_ty("val", ty)),
+			TypeDefKind::Record(fields) => {
+				let fields = fields
+					.iter()
+					.map(|field| {
+						let expr = format!("val.{}", field.id.to_snake_case());
+
+						let value = self.print_from_json_ty(&expr, &field.ty);
+
+						// older hosts leave out fields with a default
+						let value = match &field.default {
+							Some(default) => {
+								let default = self.print_value(default, &field.ty);
+
+								format!("{expr} === undefined ? {default} : {value}")
+							},
+							None => value,
+						};
+
+						format!("{}: {value}", field.id.to_lower_camel_case())
+					})
+					.collect::<Vec<_>>()
+					.join(",\n");
+
+				format!("return {{\n{fields}\n}}")
+			},
+			TypeDefKind::Flags(fields) => {
+				let big = is_big_flags(fields);
+
+				let cases = fields.iter().enumerate().fold(String::new(), |mut str, (i, field)| {
+					let name = field.id.TO_SHOUTY_SNEK_CASE();
+
+					let set =
+						if big { format!("bits |= 1n << {i}n") } else { format!("bits = (bits | (1 << {i})) >>> 0") };
+
+					let _ = writeln!(str, "case \"{name}\":\n    {set}\n    break");
+
+					str
+				});
+
+				// unknown bits are written in hex
+				let (zero, unknown) = if big {
+					("0n", "bits |= BigInt(name)")
+				} else {
+					("0", "bits = (bits | Number(name)) >>> 0")
+				};
+
+				format!(
+					"let bits = {zero}
+
+for (const name of val.split(\"|\").map((name) => name.trim())) {{
+    switch (name) {{
+        {cases}
+        case \"\":
+            break
+        default:
+            {unknown}
+    }}
+}}
+
+return bits"
+				)
+			},
+			TypeDefKind::Variant(cases) => {
+				let (unit, data):(Vec<_>, Vec<_>) = cases.iter().partition(|case| case.ty.is_none());
+
+				let unit = unit.iter().fold(String::new(), |mut str, case| {
+					let name = case.id.to_upper_camel_case();
+
+					let _ = writeln!(str, "if (val === \"{name}\") return {{ {name}: null }}");
+
+					str
+				});
+
+				let data = data.iter().fold(String::new(), |mut str, case| {
+					let name = case.id.to_upper_camel_case();
+
+					let inner =
+						self.print_from_json_ty(&format!("val.{name}"), case.ty.as_ref().unwrap());
+
+					let _ = writeln!(
+						str,
+						"if (typeof val === \"object\" && '{name}' in val) return {{ {name}: {inner} }}"
+					);
+
+					str
+				});
+
+				format!("{unit}{data}\nthrow new Error(\"unknown variant case\")")
+			},
+			TypeDefKind::Enum(_) => "return val".to_string(),
+			TypeDefKind::Union(cases) => {
+				let cases = union_case_names(&self.interface().typedefs, cases)
+					.into_iter()
+					.zip(cases)
+					.fold(String::new(), |mut str, (name, case)| {
+						let inner = self.print_from_json_ty(&format!("val.{name}"), &case.ty);
+
+						let _ = writeln!(str, "if ('{name}' in val) return {{ {name}: {inner} }}");
+
+						str
+					});
+
+				format!("{cases}\nthrow new Error(\"unknown union case\")")
+			},
+			TypeDefKind::Resource(_) => return String::new(),
+		};
+
+		let callbacks = print_callback_params("fromJson", &typedef.params);
+
+		format!("function fromJson{ident}(val{callbacks}) {{\n{body}\n}}\n")
+	}
+}
+
+/// Whether integers of the type are represented as bigints rather than numbers.
+fn is_bigint(typedefs:&TypeDefArena, ty:&Type) -> bool {
+	match ty {
+		Type::U64 | Type::U128 | Type::S64 | Type::S128 => true,
+		Type::Id(id) => matches!(&typedefs[*id].kind, TypeDefKind::Alias(ty) if is_bigint(typedefs, ty)),
+		_ => false,
+	}
+}
+
+/// Prints the `@deprecated` JSDoc tag of items annotated with `@deprecated`,
+/// or nothing.
+#[must_use]
+pub fn print_deprecated_tag(annotations:&[Annotation]) -> String {
+	match wit_parser::deprecation(annotations) {
+		Some("") => " * @deprecated\n".to_string(),
+		Some(note) => format!(" * @deprecated {note}\n"),
+		None => String::new(),
+	}
+}
+
+/// Prints the callbacks a generic type definition takes for each of its type
+/// parameters, like `, deserializeT`.
+fn print_callback_params(prefix:&str, params:&[String]) -> String {
+	params.iter().fold(String::new(), |mut str, param| {
+		let _ = write!(str, ", {prefix}{}", param.to_upper_camel_case());
+
+		str
+	})
+}
+
+/// Whether the flags are represented as bigints rather than numbers.
+fn is_big_flags(fields:&[FlagsField]) -> bool {
+	matches!(flags_repr(fields), wit_parser::Int::U64 | wit_parser::Int::U128)
 }
 
 bitflags::bitflags! {
@@ -532,6 +1242,12 @@ bitflags::bitflags! {
 
 		const STR_UTIL          = 1 << 22;
 
+		const _STREAM           = 1 << 23;
+
+		const _EXPORTS          = 1 << 24;
+
+		const JSON              = 1 << 25;
+
 		const VARINT            = Self::_VARINT.bits() | Self::VARINT_MAX.bits();
 
 		const U8               = Self::BITS8.bits() | Self::VARINT.bits() | Self::UNSIGNED.bits();
@@ -560,11 +1276,15 @@ bitflags::bitflags! {
 
 		const BYTES             = Self::_BYTES.bits() | Self::U64.bits();
 
-		const OPTION            = Self::_OPTION.bits() | Self::U32.bits();
+		const OPTION            = Self::_OPTION.bits() | Self::U8.bits() | Self::U32.bits();
 
-		const RESULT            = Self::_RESULT.bits() | Self::U32.bits();
+		const RESULT            = Self::_RESULT.bits() | Self::U8.bits() | Self::U32.bits();
 
 		const LIST              = Self::_LIST.bits() | Self::U64.bits();
+
+		const STREAM            = Self::_STREAM.bits() | Self::U32.bits() | Self::SER.bits();
+
+		const EXPORTS           = Self::_EXPORTS.bits() | Self::U32.bits() | Self::BYTES.bits() | Self::STR_UTIL.bits() | Self::SER.bits();
 	}
 }
 
@@ -573,6 +1293,8 @@ impl std::fmt::Display for SerdeUtils {
 	fn fmt(&self, f:&mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		f.write_str(include_str!("./js/deserializer.js"))?;
 
+		f.write_str(include_str!("./js/ipc.js"))?;
+
 		if self.contains(SerdeUtils::VARINT_MAX) {
 			f.write_str(include_str!("./js/varint_max.js"))?;
 		}
@@ -737,6 +1459,18 @@ impl std::fmt::Display for SerdeUtils {
 			f.write_str(include_str!("./js/ser_list.js"))?;
 		}
 
+		if self.contains(SerdeUtils::JSON) {
+			f.write_str(include_str!("./js/json.js"))?;
+		}
+
+		if self.contains(SerdeUtils::_STREAM) {
+			f.write_str(include_str!("./js/stream.js"))?;
+		}
+
+		if self.contains(SerdeUtils::_EXPORTS) {
+			f.write_str(include_str!("./js/exports.js"))?;
+		}
+
 		if self.contains(SerdeUtils::STR_UTIL | SerdeUtils::DE) {
 			f.write_str("const __text_decoder = new TextDecoder('utf-8');\n")?;
 		}
@@ -750,8 +1484,18 @@ impl std::fmt::Display for SerdeUtils {
 }
 
 impl SerdeUtils {
+	/// The utilities needed when calls are encoded as JSON, which replace the
+	/// postcard serializers and deserializers.
 	#[must_use]
-	pub fn collect_from_functions(typedefs:&TypeDefArena, functions:&[Function]) -> Self {
+	pub fn for_json(self) -> Self {
+		SerdeUtils::JSON | (self & (SerdeUtils::_STREAM | SerdeUtils::_EXPORTS))
+	}
+
+	#[must_use]
+	pub fn collect_from_functions<'a>(
+		typedefs:&TypeDefArena,
+		functions:impl IntoIterator<Item = &'a Function>,
+	) -> Self {
 		let mut info = Self::empty();
 
 		for func in functions {
@@ -781,6 +1525,32 @@ impl SerdeUtils {
 		info
 	}
 
+	/// Like [`Self::collect_from_functions`], but for functions exported by the
+	/// webview, which deserialize their parameters and serialize their results.
+	#[must_use]
+	pub fn collect_from_exports<'a>(
+		typedefs:&TypeDefArena,
+		functions:impl IntoIterator<Item = &'a Function>,
+	) -> Self {
+		let mut info = Self::empty();
+
+		for func in functions {
+			info |= SerdeUtils::EXPORTS;
+
+			for (_, ty) in &func.params {
+				info |= SerdeUtils::DE;
+
+				info |= Self::collect_type_info(typedefs, ty);
+			}
+
+			for ty in func.result.iter().flat_map(FunctionResult::types) {
+				info |= Self::collect_type_info(typedefs, ty);
+			}
+		}
+
+		info
+	}
+
 	fn collect_typedef_info(typedefs:&TypeDefArena, id:TypeDefId) -> SerdeUtils {
 		let mut info = SerdeUtils::empty();
 
@@ -809,9 +1579,13 @@ impl SerdeUtils {
 					info |= Self::collect_type_info(typedefs, &case.ty);
 				}
 			},
-			TypeDefKind::Enum(_) | TypeDefKind::Resource(_) => {
+			TypeDefKind::Enum(_) => {
 				info |= SerdeUtils::U32;
 			},
+			TypeDefKind::Resource(_) => {
+				// resources serialize their handle to call methods or release it
+				info |= SerdeUtils::U32 | SerdeUtils::SER;
+			},
 			TypeDefKind::Flags(fields) => {
 				info |= match flags_repr(fields) {
 					wit_parser::Int::U8 => SerdeUtils::U8,
@@ -851,6 +1625,7 @@ impl SerdeUtils {
 			Type::List(ty) if **ty == Type::U8 => SerdeUtils::BYTES,
 			Type::List(ty) => SerdeUtils::LIST | Self::collect_type_info(typedefs, ty),
 			Type::Option(ty) => SerdeUtils::OPTION | Self::collect_type_info(typedefs, ty),
+			Type::Stream(ty) => SerdeUtils::STREAM | Self::collect_type_info(typedefs, ty),
 			Type::Result { ok, err } => {
 				let ok = ok
 					.as_ref()
@@ -863,6 +1638,11 @@ impl SerdeUtils {
 				SerdeUtils::RESULT | ok | err
 			},
 			Type::Id(id) => Self::collect_typedef_info(typedefs, *id),
+			Type::Generic { id, args } => {
+				Self::collect_typedef_info(typedefs, *id)
+					| args.iter().map(|ty| Self::collect_type_info(typedefs, ty)).collect::<Self>()
+			},
+			Type::Param(_) => SerdeUtils::empty(),
 		}
 	}
 }
@@ -2,7 +2,7 @@ use std::{fmt::Write, path::PathBuf};
 
 use heck::{ToKebabCase, ToSnakeCase};
 use tauri_bindgen_core::{Generate, GeneratorBuilder};
-use wit_parser::{Function, FunctionResult, Interface, Type, TypeDefId};
+use wit_parser::{Annotation, Constant, Function, FunctionResult, Interface, Type, TypeDefId};
 
 #[derive(Default, Debug, Clone)]
 #[cfg_attr(feature = "clap", derive(clap::Args))]
@@ -44,6 +44,11 @@ impl Markdown {
 
 				format!("list<{ty}>")
 			},
+			Type::Stream(ty) => {
+				let ty = self.print_ty(ty);
+
+				format!("stream<{ty}>")
+			},
 			Type::Tuple(types) => {
 				let types = types.iter().map(|ty| self.print_ty(ty)).collect::<Vec<_>>().join(", ");
 
@@ -68,15 +73,33 @@ impl Markdown {
 
 				format!("[{ident}](#{lnk})")
 			},
+			Type::Generic { id, args } => {
+				let ident = &self.interface.typedefs[*id].ident;
+
+				let lnk = ident.to_snake_case();
+
+				let args = args.iter().map(|ty| self.print_ty(ty)).collect::<Vec<_>>().join(", ");
+
+				format!("[{ident}](#{lnk})<{args}>")
+			},
+			Type::Param(name) => name.to_string(),
 		}
 	}
 
 	fn print_typedef(&self, id:TypeDefId) -> String {
 		let typedef = &self.interface.typedefs[id];
 
-		let ident = &typedef.ident;
+		let ident = &if typedef.params.is_empty() {
+			typedef.ident.clone()
+		} else {
+			format!("{}<{}>", typedef.ident, typedef.params.join(", "))
+		};
 
-		let docs = print_docs(&typedef.docs);
+		let docs = format!(
+			"{}{}",
+			print_deprecated(&typedef.annotations),
+			print_docs(&typedef.docs)
+		);
 
 		match &typedef.kind {
 			wit_parser::TypeDefKind::Alias(ty) => {
@@ -86,11 +109,18 @@ impl Markdown {
 			},
 			wit_parser::TypeDefKind::Record(fields) => {
 				let fields = fields.iter().fold(String::new(), |mut str, field| {
+					let default = field
+						.default
+						.as_ref()
+						.map(|value| format!(" = `{value}`"))
+						.unwrap_or_default();
+
 					let _ = write!(
 						str,
-						"#### {ident}: `{ty}`\n{docs}\n",
+						"#### {ident}: `{ty}`{default}\n{deprecated}{docs}\n",
 						ident = field.id,
 						ty = self.print_ty(&field.ty),
+						deprecated = print_deprecated(&field.annotations),
 						docs = field.docs
 					);
 
@@ -152,40 +182,75 @@ impl Markdown {
 
 				format!("## Union {ident}\n\n{docs}\n\n### Cases\n\n{cases}")
 			},
-			wit_parser::TypeDefKind::Resource(functions) => {
-				let functions = functions.iter().fold(String::new(), |mut str, func| {
-					let _ = write!(
-						str,
-						"### Method {ident}\n\n`func {ident} ({params}){result}`\n\n{docs}",
-						ident = func.id,
-						params = self.print_named_types(&func.params),
-						result = func
-							.result
-							.as_ref()
-							.map(|result| self.print_result(result))
-							.unwrap_or_default(),
-						docs = func.docs
-					);
-
-					str
-				});
-
-				format!("## Resource {ident}\n\n{docs}\n\n### Methods\n\n{functions}")
+			wit_parser::TypeDefKind::Resource(resource) => {
+				let print_functions = |kind:&str, functions:&[Function]| {
+					functions.iter().fold(String::new(), |mut str, func| {
+						let _ = write!(
+							str,
+							"### {kind} {ident}\n\n`func {ident} ({params}){result}`\n\n{deprecated}{docs}\n\n",
+							ident = func.id,
+							params = self.print_named_types(&func.params),
+							result = func
+								.result
+								.as_ref()
+								.map(|result| self.print_result(result))
+								.unwrap_or_default(),
+							deprecated = print_deprecated(&func.annotations),
+							docs = func.docs
+						);
+
+						str
+					})
+				};
+
+				let constructor = resource
+					.constructor
+					.as_ref()
+					.map(|func| {
+						format!(
+							"### Constructor\n\n`constructor ({params})`\n\n{deprecated}{docs}\n\n",
+							params = self.print_named_types(&func.params),
+							deprecated = print_deprecated(&func.annotations),
+							docs = func.docs
+						)
+					})
+					.unwrap_or_default();
+
+				let statics = print_functions("Static Function", &resource.statics);
+
+				let methods = print_functions("Method", &resource.methods);
+
+				format!(
+					"## Resource {ident}\n\n{docs}\n\n{constructor}{statics}### \
+					 Methods\n\n{methods}"
+				)
 			},
 		}
 	}
 
 	fn print_function(&self, func:&Function) -> String {
 		format!(
-			"### Function {ident}\n\n` func {ident} ({params}){result}`\n\n{docs}",
+			"### Function {ident}\n\n` func {ident} ({params}){result}`\n\n{deprecated}{docs}",
 			ident = func.id,
 			params = self.print_named_types(&func.params),
 			result =
 				func.result.as_ref().map(|result| self.print_result(result)).unwrap_or_default(),
+			deprecated = print_deprecated(&func.annotations),
 			docs = func.docs
 		)
 	}
 
+	fn print_constant(&self, constant:&Constant) -> String {
+		format!(
+			"### Constant {ident}\n\n`const {ident}: {ty} = {value}`\n\n{deprecated}{docs}",
+			ident = constant.id,
+			ty = self.print_ty(&constant.ty),
+			value = constant.value,
+			deprecated = print_deprecated(&constant.annotations),
+			docs = constant.docs
+		)
+	}
+
 	fn print_named_types(&self, types:&[(String, Type)]) -> String {
 		types
 			.iter()
@@ -214,6 +279,16 @@ impl Markdown {
 
 fn print_docs(docs:&str) -> String { docs.lines().map(str::trim).collect::<Vec<_>>().join("\n") }
 
+/// Prints the badge of items annotated with `@deprecated`, followed by the
+/// message of the annotation.
+fn print_deprecated(annotations:&[Annotation]) -> String {
+	match wit_parser::deprecation(annotations) {
+		Some("") => "**Deprecated**\n\n".to_string(),
+		Some(note) => format!("**Deprecated**: {note}\n\n"),
+		None => String::new(),
+	}
+}
+
 impl Generate for Markdown {
 	fn to_file(&mut self) -> (std::path::PathBuf, String) {
 		let ident = &self.interface.ident;
@@ -228,6 +303,20 @@ impl Generate for Markdown {
 			.collect::<Vec<_>>()
 			.join("\n");
 
+		let constants = self
+			.interface
+			.constants
+			.iter()
+			.map(|constant| self.print_constant(constant))
+			.collect::<Vec<_>>()
+			.join("\n");
+
+		let constants = if constants.is_empty() {
+			String::new()
+		} else {
+			format!("## Constants\n\n{constants}\n\n")
+		};
+
 		let functions = self
 			.interface
 			.functions
@@ -236,9 +325,23 @@ impl Generate for Markdown {
 			.collect::<Vec<_>>()
 			.join("\n");
 
+		let exports = self
+			.interface
+			.exports
+			.iter()
+			.map(|func| self.print_function(func))
+			.collect::<Vec<_>>()
+			.join("\n");
+
+		let exports = if exports.is_empty() {
+			String::new()
+		} else {
+			format!("\n\n## Exports\n\n{exports}")
+		};
+
 		let contents = format!(
-			"# {ident}\n\n{docs}\n\n## Type definitions\n\n{typedefs}\n\n## \
-			 Functions\n\n{functions}",
+			"# {ident}\n\n{docs}\n\n{constants}## Type definitions\n\n{typedefs}\n\n## \
+			 Functions\n\n{functions}{exports}",
 		);
 
 		let mut filename = PathBuf::from(self.interface.ident.to_kebab_case());
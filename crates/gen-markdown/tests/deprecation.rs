@@ -0,0 +1,24 @@
+use tauri_bindgen_core::GeneratorBuilder;
+use tauri_bindgen_gen_markdown::Builder;
+
+const SOURCE:&str = include_str!("../../../wit/deprecation.wit");
+
+#[test]
+fn badges() {
+	let iface = wit_parser::parse_and_resolve_str(SOURCE, |_| false).unwrap();
+
+	let file = Builder::default().build(iface).to_file().1;
+
+	for text in [
+		"` func read (path: string) -> string`\n\n**Deprecated**: use `open` instead\n",
+		"**Deprecated**: use `open` instead\n\nReads a file.",
+		"`constructor (path: string)`\n\n**Deprecated**\n",
+		"`func size () -> u64`\n\n**Deprecated**: use `stat`\n",
+		"## Struct stat\n\n**Deprecated**\n",
+		"#### size: `u64`\n**Deprecated**: use `len`\n",
+	] {
+		assert!(file.contains(text), "missing `{text}` in\n{file}");
+	}
+
+	assert_eq!(file.matches("**Deprecated**").count(), 5, "{file}");
+}
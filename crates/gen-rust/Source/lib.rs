@@ -1,11 +1,16 @@
-#![allow(clippy::missing_panics_doc, clippy::missing_errors_doc)]
+#![allow(
+	clippy::missing_panics_doc,
+	clippy::missing_errors_doc,
+	clippy::too_many_arguments
+)]
 
 use heck::{ToShoutySnekCase, ToSnakeCase, ToUpperCamelCase};
-use proc_macro2::{Ident, Literal, TokenStream};
+use proc_macro2::{Ident, Literal, Span, TokenStream};
 use quote::{format_ident, quote};
 use syn::Lifetime;
 use tauri_bindgen_core::{TypeInfo, TypeInfos, flags_repr, union_case_names};
 use wit_parser::{
+	Annotation,
 	EnumCase,
 	FlagsField,
 	Function,
@@ -13,10 +18,13 @@ use wit_parser::{
 	Int,
 	Interface,
 	RecordField,
+	Resource,
 	Type,
+	TypeDefArena,
 	TypeDefId,
 	TypeDefKind,
 	UnionCase,
+	Value,
 	VariantCase,
 };
 
@@ -33,8 +41,9 @@ pub trait RustGenerator {
 		&self,
 		print_resource:&str,
 		docs:&str,
+		annotations:&[Annotation],
 		ident:&Ident,
-		functions:&[Function],
+		resource:&Resource,
 		info:TypeInfo,
 	) -> TokenStream;
 
@@ -51,25 +60,40 @@ pub trait RustGenerator {
 			for TypeVariant { ident, borrow_mode } in variants {
 				let docs = &typedef.docs;
 
+				let annotations = &typedef.annotations;
+
+				let params = &typedef.params;
+
 				log::debug!("generating {:?} with mode info {:?} and mode {:?}", ident, info, mode);
 
 				let typedef = match &typedef.kind {
 					TypeDefKind::Alias(ty) => {
-						self.print_alias(docs, &ident, ty, info, &borrow_mode)
+						self.print_alias(docs, annotations, &ident, params, ty, info, &borrow_mode)
 					},
 					TypeDefKind::Record(fields) => {
-						self.print_record(docs, &ident, fields, info, &borrow_mode)
+						self.print_record(docs, annotations, &ident, params, fields, info, &borrow_mode)
+					},
+					TypeDefKind::Flags(fields) => {
+						self.print_flags(docs, annotations, &ident, fields, info)
 					},
-					TypeDefKind::Flags(fields) => self.print_flags(docs, &ident, fields, info),
 					TypeDefKind::Variant(cases) => {
-						self.print_variant(docs, &ident, cases, info, &borrow_mode)
+						self.print_variant(docs, annotations, &ident, params, cases, info, &borrow_mode)
+					},
+					TypeDefKind::Enum(cases) => {
+						self.print_enum(docs, annotations, &ident, cases, info)
 					},
-					TypeDefKind::Enum(cases) => self.print_enum(docs, &ident, cases, info),
 					TypeDefKind::Union(cases) => {
-						self.print_union(docs, &ident, cases, info, &borrow_mode)
+						self.print_union(docs, annotations, &ident, params, cases, info, &borrow_mode)
 					},
-					TypeDefKind::Resource(functions) => {
-						self.print_resource(&self.interface().ident, docs, &ident, functions, info)
+					TypeDefKind::Resource(resource) => {
+						self.print_resource(
+							&self.interface().ident,
+							docs,
+							annotations,
+							&ident,
+							resource,
+							info,
+						)
 					},
 				};
 
@@ -145,6 +169,11 @@ pub trait RustGenerator {
 
 				quote! { Option<#ty> }
 			},
+			Type::Stream(ty) => {
+				let ty = self.print_ty(ty, &BorrowMode::Owned);
+
+				quote! { ::tauri_bindgen_guest_rust::Stream<#ty> }
+			},
 			Type::Result { ok, err } => {
 				let ok = ok.as_ref().map(|ty| self.print_ty(ty, mode)).unwrap_or(quote! { () });
 
@@ -152,47 +181,69 @@ pub trait RustGenerator {
 
 				quote! { Result<#ok, #err> }
 			},
-			Type::Id(id) => {
-				let typedef = &self.interface().typedefs[*id];
+			Type::Id(id) => self.print_typedef_ref(*id, &[], mode),
+			Type::Generic { id, args } => self.print_typedef_ref(*id, args, mode),
+			Type::Param(name) => print_type_param(name),
+		}
+	}
 
-				let info = self.infos()[*id];
+	/// Prints a reference to the named type `id`, instantiated with `args` if
+	/// it is generic.
+	fn print_typedef_ref(&self, id:TypeDefId, args:&[Type], mode:&BorrowMode) -> TokenStream {
+		let typedef = &self.interface().typedefs[id];
 
-				let ident = if self.uses_two_names(info) {
-					match mode {
-						BorrowMode::Owned => {
-							format_ident!("{}Result", typedef.ident.to_upper_camel_case())
-						},
-						BorrowMode::AllBorrowed(_) | BorrowMode::LeafBorrowed(_) => {
-							format_ident!("{}Param", typedef.ident.to_upper_camel_case())
-						},
-					}
-				} else {
-					format_ident!("{}", typedef.ident.to_upper_camel_case())
-				};
+		let info = self.infos()[id];
 
-				let generics = print_generics(info, mode);
+		// handles passed as parameters are borrowed, since dropping a handle
+		// releases its resource
+		if let TypeDefKind::Resource(_) = typedef.kind {
+			let ident = format_ident!("{}", typedef.ident.to_upper_camel_case());
 
-				quote! { #ident #generics }
-			},
+			return match mode {
+				BorrowMode::Owned => quote! { #ident },
+				BorrowMode::AllBorrowed(lt) | BorrowMode::LeafBorrowed(lt) => quote! { &#lt #ident },
+			};
 		}
+
+		let ident = if self.uses_two_names(info) {
+			match mode {
+				BorrowMode::Owned => format_ident!("{}Result", typedef.ident.to_upper_camel_case()),
+				BorrowMode::AllBorrowed(_) | BorrowMode::LeafBorrowed(_) => {
+					format_ident!("{}Param", typedef.ident.to_upper_camel_case())
+				},
+			}
+		} else {
+			format_ident!("{}", typedef.ident.to_upper_camel_case())
+		};
+
+		let args = args.iter().map(|ty| self.print_ty(ty, mode)).collect::<Vec<_>>();
+
+		let generics = print_generic_args(info, mode, &args);
+
+		quote! { #ident #generics }
 	}
 
 	fn print_alias(
 		&self,
 		docs:&str,
+		annotations:&[Annotation],
 		ident:&Ident,
+		params:&[String],
 		ty:&Type,
 		info:TypeInfo,
 		mode:&BorrowMode,
 	) -> TokenStream {
 		let docs = self.print_docs(docs);
 
+		let deprecated = self.print_deprecated(annotations);
+
 		let ty = self.print_ty(ty, mode);
 
-		let generics = print_generics(info, mode);
+		let generics = print_generic_params(info, mode, params);
 
 		quote! {
 			#docs
+			#deprecated
 			pub type #ident #generics = #ty;
 		}
 	}
@@ -200,54 +251,237 @@ pub trait RustGenerator {
 	fn print_record(
 		&self,
 		docs:&str,
+		annotations:&[Annotation],
 		ident:&Ident,
+		params:&[String],
 		fields:&[RecordField],
 		info:TypeInfo,
 		mode:&BorrowMode,
 	) -> TokenStream {
 		let docs = self.print_docs(docs);
 
+		let deprecated = self.print_deprecated(annotations);
+
 		let additional_attrs = self.additional_attrs(&ident.to_string(), info);
 
-		let generics = print_generics(info, mode);
+		let generics = print_generic_params(info, mode, params);
+
+		// the fields with a default after the last field without one, postcard
+		// input that ends before them was encoded before they were added
+		let trailing =
+			fields.iter().rposition(|field| field.default.is_none()).map_or(0, |i| i + 1);
+
+		let record_fields = fields.iter().enumerate().map(|(i, field)| {
+			self.print_record_field(ident, field, i >= trailing, mode)
+		});
+
+		let default_fns = fields.iter().enumerate().filter_map(|(i, field)| {
+			let value = field.default.as_ref()?;
+
+			let fn_ident = default_fn_ident(ident, field);
+
+			let lifetime = print_generics(info, mode);
+
+			let ty = self.print_ty(&field.ty, mode);
+
+			let value = self.print_value(value, &field.ty, mode);
+
+			let deserialize_fn = (i >= trailing).then(|| {
+				let deserialize_ident = deserialize_fn_ident(ident, field);
+
+				let lifetime = lifetime_for(info, mode).map(|lt| quote! { #lt, });
+
+				quote! {
+					fn #deserialize_ident<'de, #lifetime D>(
+						deserializer: D,
+					) -> ::core::result::Result<#ty, D::Error>
+					where
+						D: serde::Deserializer<'de>,
+						#ty: serde::Deserialize<'de>,
+					{
+						field_or_default(deserializer, #fn_ident)
+					}
+				}
+			});
+
+			Some(quote! {
+				fn #fn_ident #lifetime () -> #ty {
+					#value
+				}
+
+				#deserialize_fn
+			})
+		});
+
+		// records where every field has a default can be created from defaults
+		let default_impl = fields.iter().all(|field| field.default.is_some()).then(|| {
+			let fields = fields.iter().map(|field| {
+				let field_ident = format_ident!("{}", field.id.to_snake_case());
+
+				let fn_ident = default_fn_ident(ident, field);
+
+				quote! { #field_ident: #fn_ident() }
+			});
 
-		let fields = fields.iter().map(|field| self.print_record_field(field, mode));
+			quote! {
+				impl #generics Default for #ident #generics {
+					fn default() -> Self {
+						Self {
+							#(#fields),*
+						}
+					}
+				}
+			}
+		});
 
 		quote! {
 			#docs
+			#deprecated
 			#additional_attrs
 			#[derive(Debug, Clone, PartialEq)]
 			pub struct #ident #generics {
-				#(#fields),*
+				#(#record_fields),*
 			}
+
+			#(#default_fns)*
+
+			#default_impl
 		}
 	}
 
-	fn print_record_field(&self, field:&RecordField, mode:&BorrowMode) -> TokenStream {
+	/// Prints a field of a record, `trailing` fields are followed only by
+	/// fields with a default and may be missing at the end of the input.
+	fn print_record_field(
+		&self,
+		record_ident:&Ident,
+		field:&RecordField,
+		trailing:bool,
+		mode:&BorrowMode,
+	) -> TokenStream {
 		let docs = self.print_docs(&field.docs);
 
+		let deprecated = self.print_deprecated(&field.annotations);
+
 		let borrow_attr = self.needs_borrow(&field.ty, mode).then_some(quote! { #[serde(borrow)] });
 
+		// missing fields are filled in with their default when deserializing
+		let default_attr = field.default.as_ref().map(|_| {
+			let fn_ident = default_fn_ident(record_ident, field).to_string();
+
+			if trailing {
+				let deserialize_ident = deserialize_fn_ident(record_ident, field).to_string();
+
+				quote! { #[serde(default = #fn_ident, deserialize_with = #deserialize_ident)] }
+			} else {
+				quote! { #[serde(default = #fn_ident)] }
+			}
+		});
+
 		let ident = format_ident!("{}", field.id.to_snake_case());
 
 		let ty = self.print_ty(&field.ty, mode);
 
 		quote! {
 			#docs
+			#deprecated
 			#borrow_attr
+			#default_attr
 			pub #ident: #ty
 		}
 	}
 
+	/// Prints the default `value` of a field of type `ty`.
+	fn print_value(&self, value:&Value, ty:&Type, mode:&BorrowMode) -> TokenStream {
+		match value {
+			Value::Bool(value) => quote! { #value },
+			Value::Int(int) => {
+				let int = Literal::i128_unsuffixed(*int);
+
+				quote! { #int }
+			},
+			Value::Float(float) => {
+				let float = Literal::f64_unsuffixed(*float);
+
+				quote! { #float }
+			},
+			Value::Char(char) => quote! { #char },
+			Value::String(string) => {
+				match mode {
+					BorrowMode::Owned => quote! { #string.to_string() },
+					BorrowMode::AllBorrowed(_) | BorrowMode::LeafBorrowed(_) => quote! { #string },
+				}
+			},
+			Value::Enum(case) => {
+				let ty = self.print_ty(ty, mode);
+
+				let case = format_ident!("{}", case.to_upper_camel_case());
+
+				quote! { #ty::#case }
+			},
+			Value::None => quote! { None },
+		}
+	}
+
+	/// Prints the constants of the interface. Strings are borrowed, as
+	/// `String`s can't be constructed in constants.
+	fn print_constants(&self) -> TokenStream {
+		let mode = BorrowMode::AllBorrowed(Lifetime::new("'static", Span::call_site()));
+
+		let constants = self.interface().constants.iter().map(|constant| {
+			let docs = self.print_docs(&constant.docs);
+
+			let deprecated = self.print_deprecated(&constant.annotations);
+
+			let ident = format_ident!("{}", constant.id.TO_SHOUTY_SNEK_CASE());
+
+			let ty = self.print_const_ty(&constant.ty);
+
+			let value = self.print_value(&constant.value, &constant.ty, &mode);
+
+			quote! {
+				#docs
+				#deprecated
+				pub const #ident: #ty = #value;
+			}
+		});
+
+		quote! { #(#constants)* }
+	}
+
+	/// Prints the type of a constant, aliases of strings are replaced by
+	/// borrowed strings.
+	fn print_const_ty(&self, ty:&Type) -> TokenStream {
+		match ty {
+			Type::String => quote! { &str },
+			Type::Option(ty) => {
+				let ty = self.print_const_ty(ty);
+
+				quote! { Option<#ty> }
+			},
+			Type::Id(id) => {
+				match &self.interface().typedefs[*id].kind {
+					TypeDefKind::Alias(ty) if holds_str(&self.interface().typedefs, ty) => {
+						self.print_const_ty(ty)
+					},
+					_ => self.print_ty(ty, &BorrowMode::Owned),
+				}
+			},
+			_ => self.print_ty(ty, &BorrowMode::Owned),
+		}
+	}
+
 	fn print_flags(
 		&self,
 		docs:&str,
+		annotations:&[Annotation],
 		ident:&Ident,
 		fields:&[FlagsField],
 		info:TypeInfo,
 	) -> TokenStream {
 		let docs = self.print_docs(docs);
 
+		let deprecated = self.print_deprecated(annotations);
+
 		let additional_attrs = self.additional_attrs(&ident.to_string(), info);
 
 		let repr = self.print_int(&flags_repr(fields));
@@ -268,6 +502,7 @@ pub trait RustGenerator {
 		quote! {
 			bitflags::bitflags! {
 				#docs
+				#deprecated
 				#additional_attrs
 				pub struct #ident: #repr {
 					#(#fields)*
@@ -279,21 +514,26 @@ pub trait RustGenerator {
 	fn print_variant(
 		&self,
 		docs:&str,
+		annotations:&[Annotation],
 		ident:&Ident,
+		params:&[String],
 		cases:&[VariantCase],
 		info:TypeInfo,
 		mode:&BorrowMode,
 	) -> TokenStream {
 		let docs = self.print_docs(docs);
 
+		let deprecated = self.print_deprecated(annotations);
+
 		let additional_attrs = self.additional_attrs(&ident.to_string(), info);
 
-		let generics = print_generics(info, mode);
+		let generics = print_generic_params(info, mode, params);
 
 		let cases = cases.iter().map(|case| self.print_variant_case(case, mode));
 
 		quote! {
 			#docs
+			#deprecated
 			#additional_attrs
 			#[derive(Debug, Clone, PartialEq)]
 			pub enum #ident #generics {
@@ -319,15 +559,25 @@ pub trait RustGenerator {
 		}
 	}
 
-	fn print_enum(&self, docs:&str, ident:&Ident, cases:&[EnumCase], info:TypeInfo) -> TokenStream {
+	fn print_enum(
+		&self,
+		docs:&str,
+		annotations:&[Annotation],
+		ident:&Ident,
+		cases:&[EnumCase],
+		info:TypeInfo,
+	) -> TokenStream {
 		let docs = self.print_docs(docs);
 
+		let deprecated = self.print_deprecated(annotations);
+
 		let additional_attrs = self.additional_attrs(&ident.to_string(), info);
 
 		let cases = cases.iter().map(|case| self.print_enum_case(case));
 
 		quote! {
 			#docs
+			#deprecated
 			#additional_attrs
 			#[derive(Debug, Clone, PartialEq)]
 			pub enum #ident {
@@ -350,16 +600,20 @@ pub trait RustGenerator {
 	fn print_union(
 		&self,
 		docs:&str,
+		annotations:&[Annotation],
 		ident:&Ident,
+		params:&[String],
 		cases:&[UnionCase],
 		info:TypeInfo,
 		mode:&BorrowMode,
 	) -> TokenStream {
 		let docs = self.print_docs(docs);
 
+		let deprecated = self.print_deprecated(annotations);
+
 		let additional_attrs = self.additional_attrs(&ident.to_string(), info);
 
-		let generics = print_generics(info, mode);
+		let generics = print_generic_params(info, mode, params);
 
 		let cases = union_case_names(&self.interface().typedefs, cases).into_iter().zip(cases).map(
 			|(name, case)| {
@@ -378,6 +632,7 @@ pub trait RustGenerator {
 
 		quote! {
 			#docs
+			#deprecated
 			#additional_attrs
 			#[derive(Debug, Clone, PartialEq)]
 			pub enum #ident #generics {
@@ -394,6 +649,8 @@ pub trait RustGenerator {
 	) -> TokenStream {
 		let docs = self.print_docs(&sig.func.docs);
 
+		let deprecated = self.print_deprecated(&sig.func.annotations);
+
 		let ident = format_ident!("{}", sig.func.id.to_snake_case());
 
 		let pub_ = (!sig.private).then_some(quote! { pub });
@@ -414,6 +671,7 @@ pub trait RustGenerator {
 
 		quote! {
 			#docs
+			#deprecated
 			#pub_ #unsafe_ #async_ fn #ident (#self_arg #params) #result
 		}
 	}
@@ -461,6 +719,16 @@ pub trait RustGenerator {
 			.unwrap_or_default()
 	}
 
+	/// Prints the `#[deprecated]` attribute of items annotated with
+	/// `@deprecated`.
+	fn print_deprecated(&self, annotations:&[Annotation]) -> TokenStream {
+		match wit_parser::deprecation(annotations) {
+			Some("") => quote! { #[deprecated] },
+			Some(note) => quote! { #[deprecated = #note] },
+			None => quote! {},
+		}
+	}
+
 	fn print_int(&self, int:&Int) -> TokenStream {
 		match int {
 			Int::U8 => quote! { u8 },
@@ -472,7 +740,7 @@ pub trait RustGenerator {
 	}
 
 	fn uses_two_names(&self, info:TypeInfo) -> bool {
-		info.contains(TypeInfo::HAS_LIST)
+		info.intersects(TypeInfo::HAS_LIST | TypeInfo::HAS_RESOURCE)
 			&& info.contains(TypeInfo::PARAM | TypeInfo::RESULT)
 			&& match self.default_param_mode() {
 				BorrowMode::AllBorrowed(_) | BorrowMode::LeafBorrowed(_) => true,
@@ -484,10 +752,14 @@ pub trait RustGenerator {
 		let mut result = Vec::new();
 
 		if !self.uses_two_names(info) {
-			return vec![TypeVariant {
-				ident:format_ident!("{ident}"),
-				borrow_mode:default_mode.clone(),
-			}];
+			// types that are never sent by the generated code are always owned
+			let borrow_mode = if info.contains(TypeInfo::PARAM) {
+				default_mode.clone()
+			} else {
+				BorrowMode::Owned
+			};
+
+			return vec![TypeVariant { ident:format_ident!("{ident}"), borrow_mode }];
 		}
 
 		if info.contains(TypeInfo::PARAM) {
@@ -516,6 +788,11 @@ pub trait RustGenerator {
 
 				lifetime_for(info, mode).is_some()
 			},
+			Type::Generic { id, args } => {
+				let info = self.infos()[*id];
+
+				lifetime_for(info, mode).is_some() || args.iter().any(|ty| self.needs_borrow(ty, mode))
+			},
 			Type::Tuple(types) => types.iter().any(|ty| self.needs_borrow(ty, mode)),
 			Type::List(ty) | Type::Option(ty) => self.needs_borrow(ty, mode),
 			_ => false,
@@ -535,7 +812,7 @@ pub struct FnSig<'a> {
 pub fn lifetime_for(info:TypeInfo, mode:&BorrowMode) -> Option<&Lifetime> {
 	match mode {
 		BorrowMode::AllBorrowed(s) | BorrowMode::LeafBorrowed(s)
-			if info.contains(TypeInfo::HAS_LIST) =>
+			if info.intersects(TypeInfo::HAS_LIST | TypeInfo::HAS_RESOURCE) =>
 		{
 			Some(s)
 		},
@@ -554,6 +831,73 @@ pub fn print_generics(info:TypeInfo, mode:&BorrowMode) -> Option<TokenStream> {
 	})
 }
 
+/// Like [`print_generics`], but followed by the given type arguments.
+#[must_use]
+pub fn print_generic_args(
+	info:TypeInfo,
+	mode:&BorrowMode,
+	args:&[TokenStream],
+) -> Option<TokenStream> {
+	let lt = lifetime_for(info, mode).map(|lt| quote! { #lt });
+
+	let generics = lt.into_iter().chain(args.iter().cloned()).collect::<Vec<_>>();
+
+	(!generics.is_empty()).then(|| {
+		quote! {
+			<#(#generics),*>
+		}
+	})
+}
+
+/// Like [`print_generics`], but followed by the type parameters of a generic
+/// type definition.
+#[must_use]
+pub fn print_generic_params(
+	info:TypeInfo,
+	mode:&BorrowMode,
+	params:&[String],
+) -> Option<TokenStream> {
+	let params = params.iter().map(|param| print_type_param(param)).collect::<Vec<_>>();
+
+	print_generic_args(info, mode, &params)
+}
+
+/// Whether the type is a string, an option of one or an alias of either.
+fn holds_str(typedefs:&TypeDefArena, ty:&Type) -> bool {
+	match ty {
+		Type::String => true,
+		Type::Option(ty) => holds_str(typedefs, ty),
+		Type::Id(id) => matches!(&typedefs[*id].kind, TypeDefKind::Alias(ty) if holds_str(typedefs, ty)),
+		_ => false,
+	}
+}
+
+/// The function returning the default of a record field, named after the
+/// type variant as records with two names get a function for each.
+fn default_fn_ident(record_ident:&Ident, field:&RecordField) -> Ident {
+	format_ident!(
+		"default_{}_{}",
+		record_ident.to_string().to_snake_case(),
+		field.id.to_snake_case()
+	)
+}
+
+/// The function decoding a field with a default at the end of a record.
+fn deserialize_fn_ident(record_ident:&Ident, field:&RecordField) -> Ident {
+	format_ident!(
+		"deserialize_{}_{}",
+		record_ident.to_string().to_snake_case(),
+		field.id.to_snake_case()
+	)
+}
+
+#[must_use]
+pub fn print_type_param(ident:&str) -> TokenStream {
+	let ident = format_ident!("{}", ident.to_upper_camel_case());
+
+	quote! { #ident }
+}
+
 #[derive(Clone, PartialEq, Eq)]
 pub enum BorrowMode {
 	Owned,
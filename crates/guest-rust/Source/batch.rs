@@ -0,0 +1,210 @@
+use std::{
+	cell::RefCell,
+	future::{Future, poll_fn},
+	pin::{Pin, pin},
+	rc::Rc,
+	task::{Context, Poll, Waker},
+};
+
+use serde_json::value::RawValue;
+
+use crate::{
+	Error,
+	format::{self, Format},
+};
+
+/// The module of the host function running several calls sent in a single
+/// request.
+const BATCH_MODULE:&str = "tauri_bindgen::batch";
+
+/// The result of a call in a batch, failed calls carry the kind and message of
+/// their error.
+type EntryResult = Result<Vec<u8>, (String, String)>;
+
+#[derive(Default)]
+struct Reply {
+	result:Option<Result<Vec<u8>, Error>>,
+	waker:Option<Waker>,
+}
+
+struct Entry {
+	/// The schema hash of the bindings that made the call.
+	schema:Option<String>,
+	module:String,
+	method:String,
+	params:Vec<u8>,
+	reply:Rc<RefCell<Reply>>,
+}
+
+thread_local! {
+	/// The calls started while [`batch`] polls its future for the first time.
+	static PENDING: RefCell<Option<Vec<Entry>>> = const { RefCell::new(None) };
+}
+
+/// Resolves to the encoded result of a call sent as part of a batch.
+pub(crate) struct BatchReply(Rc<RefCell<Reply>>);
+
+impl Future for BatchReply {
+	type Output = Result<Vec<u8>, Error>;
+
+	fn poll(self:Pin<&mut Self>, cx:&mut Context<'_>) -> Poll<Self::Output> {
+		let mut reply = self.0.borrow_mut();
+
+		match reply.result.take() {
+			Some(result) => Poll::Ready(result),
+			None => {
+				reply.waker = Some(cx.waker().clone());
+
+				Poll::Pending
+			},
+		}
+	}
+}
+
+/// Adds the call to the batch that is being collected, returns the
+/// parameters back when there is none.
+pub(crate) fn enqueue(
+	schema:Option<&str>,
+	module:&str,
+	method:&str,
+	params:Vec<u8>,
+) -> Result<BatchReply, Vec<u8>> {
+	PENDING.with(|pending| {
+		let mut pending = pending.borrow_mut();
+
+		let Some(pending) = pending.as_mut() else {
+			return Err(params);
+		};
+
+		let reply = Rc::default();
+
+		pending.push(Entry {
+			schema:schema.map(ToString::to_string),
+			module:module.to_string(),
+			method:method.to_string(),
+			params,
+			reply:Rc::clone(&reply),
+		});
+
+		Ok(BatchReply(reply))
+	})
+}
+
+/// Sends all calls to the host that `calls` starts right away in a single
+/// request.
+///
+/// Calls start when their future is first polled, so only the calls polled
+/// before `calls` waits for anything else are part of the batch, such as the
+/// calls of a `join`:
+///
+/// ```ignore
+/// let (user, settings) = batch(async { futures::join!(get_user(id), get_settings()) }).await;
+/// ```
+///
+/// The calls run concurrently on the host.
+pub async fn batch<F:Future>(calls:F) -> F::Output {
+	let mut calls = pin!(calls);
+
+	let (first, pending) = poll_fn(|cx| {
+		let outer = PENDING.with(|pending| pending.replace(Some(Vec::new())));
+
+		let first = calls.as_mut().poll(cx);
+
+		let pending = PENDING.with(|pending| pending.replace(outer)).unwrap_or_default();
+
+		Poll::Ready((first, pending))
+	})
+	.await;
+
+	if let Poll::Ready(output) = first {
+		return output;
+	}
+
+	if !pending.is_empty() {
+		send_batch(pending).await;
+	}
+
+	calls.await
+}
+
+async fn send_batch(pending:Vec<Entry>) {
+	let entries:Vec<_> = pending
+		.iter()
+		.map(|entry| (entry.module.as_str(), entry.method.as_str(), entry.params.as_slice()))
+		.collect();
+
+	let format = format::format();
+
+	// the calls of a batch can be made by bindings of several interfaces
+	let mut schemas:Vec<_> = pending.iter().filter_map(|entry| entry.schema.as_deref()).collect();
+
+	schemas.sort_unstable();
+
+	schemas.dedup();
+
+	let schemas = (!schemas.is_empty()).then(|| schemas.join(","));
+
+	let results = async {
+		// JSON embeds the parameters and results of the calls as is
+		let params = match format {
+			Format::Postcard => format.encode(&entries)?,
+			Format::Json => {
+				let entries = entries
+					.iter()
+					.map(|(module, method, params)| Ok((module, method, format::raw_json(params)?)))
+					.collect::<Result<Vec<_>, Error>>()?;
+
+				format.encode(&entries)?
+			},
+		};
+
+		let body = crate::send(format, schemas.as_deref(), BATCH_MODULE, "call", &params).await?;
+
+		let results:Vec<EntryResult> = match format {
+			Format::Postcard => format.decode(&body)?,
+			Format::Json => {
+				let results:Vec<Result<Box<RawValue>, (String, String)>> = format.decode(&body)?;
+
+				results
+					.into_iter()
+					.map(|res| res.map(|raw| raw.get().as_bytes().to_vec()))
+					.collect()
+			},
+		};
+
+		if results.len() != entries.len() {
+			return Err(Error::Transport(format!(
+				"the host returned {} results for {} calls",
+				results.len(),
+				entries.len()
+			)));
+		}
+
+		Ok(results)
+	}
+	.await;
+
+	let results:Vec<_> = match results {
+		Ok(results) => {
+			results
+				.into_iter()
+				.map(|res| res.map_err(|(kind, message)| Error::from_response(&kind, message)))
+				.collect()
+		},
+		Err(err) => {
+			let message = err.to_string();
+
+			pending.iter().map(|_| Err(Error::Transport(message.clone()))).collect()
+		},
+	};
+
+	for (entry, result) in pending.into_iter().zip(results) {
+		let mut reply = entry.reply.borrow_mut();
+
+		reply.result = Some(result);
+
+		if let Some(waker) = reply.waker.take() {
+			waker.wake();
+		}
+	}
+}
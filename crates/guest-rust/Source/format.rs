@@ -0,0 +1,79 @@
+use std::cell::Cell;
+
+use serde::{Deserialize, Deserializer, Serialize, de::DeserializeOwned};
+use serde_json::value::RawValue;
+
+use crate::Error;
+
+/// The encoding of parameters and results sent to and from the host.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+	/// The compact binary encoding of [postcard](https://docs.rs/postcard).
+	#[default]
+	Postcard,
+	/// JSON, which makes calls readable in the network tab of the devtools.
+	Json,
+}
+
+thread_local! {
+	static FORMAT: Cell<Format> = const { Cell::new(Format::Postcard) };
+}
+
+/// Sets the encoding of all calls made afterwards, postcard is used by
+/// default.
+///
+/// Exported functions keep the encoding that was set when they were
+/// registered.
+pub fn set_format(format:Format) { FORMAT.with(|current| current.set(format)); }
+
+/// The encoding of calls made right now.
+pub(crate) fn format() -> Format { FORMAT.with(Cell::get) }
+
+impl Format {
+	pub(crate) fn content_type(self) -> &'static str {
+		match self {
+			Self::Postcard => "application/octet-stream",
+			Self::Json => "application/json",
+		}
+	}
+
+	pub(crate) fn encode<T:Serialize + ?Sized>(self, val:&T) -> Result<Vec<u8>, Error> {
+		match self {
+			Self::Postcard => Ok(postcard::to_allocvec(val)?),
+			Self::Json => Ok(serde_json::to_vec(val)?),
+		}
+	}
+
+	pub(crate) fn decode<T:DeserializeOwned>(self, bytes:&[u8]) -> Result<T, Error> {
+		match self {
+			Self::Postcard => Ok(postcard::from_bytes(bytes)?),
+			Self::Json => Ok(serde_json::from_slice(bytes)?),
+		}
+	}
+}
+
+/// Embeds a JSON encoded value into another value.
+pub(crate) fn raw_json(bytes:&[u8]) -> Result<Box<RawValue>, Error> {
+	Ok(serde_json::from_slice(bytes)?)
+}
+
+/// Decodes a field with a default at the end of a record, taking the default
+/// when the input ended before the field.
+///
+/// Postcard doesn't mark where a record ends, records encoded before fields
+/// with defaults were appended to it just end early. Used by generated
+/// bindings.
+#[doc(hidden)]
+pub fn field_or_default<'de, D, T>(deserializer:D, default:fn() -> T) -> Result<T, D::Error>
+where
+	D: Deserializer<'de>,
+	T: Deserialize<'de>, {
+	match T::deserialize(deserializer) {
+		// the error type of a deserializer is opaque, but postcard's is the
+		// only one that runs out of input in the middle of a record
+		Err(err) if err.to_string() == postcard::Error::DeserializeUnexpectedEnd.to_string() => {
+			Ok(default())
+		},
+		res => res,
+	}
+}
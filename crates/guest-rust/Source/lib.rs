@@ -1,25 +1,103 @@
+mod batch;
+mod format;
+
+use std::{
+	future::Future,
+	pin::Pin,
+	task::{Context, Poll},
+};
+
 use js_sys::Uint8Array;
-use serde::{Serialize, de::DeserializeOwned};
+use serde::{Deserialize, Deserializer, Serialize, de::DeserializeOwned};
 pub use tauri_bindgen_guest_rust_macro::*;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen_futures::JsFuture;
 use web_sys::{Request, RequestInit, RequestMode, Response};
 #[doc(hidden)]
-pub use {bitflags, serde, tracing};
+pub use {async_trait::async_trait, bitflags, serde, tracing};
+
+pub use crate::{
+	batch::batch,
+	format::{Format, field_or_default, set_format},
+};
+
+/// The response header carrying the kind of error when a call failed on the
+/// host.
+const ERROR_HEADER:&str = "Tauri-Bindgen-Error";
+
+/// The request header carrying the id of a call, which is passed to
+/// [`CANCEL_MODULE`] to cancel it.
+const CALL_ID_HEADER:&str = "Tauri-Bindgen-Call-Id";
+
+/// The request header carrying the schema hashes of the bindings that made a
+/// call, separated by commas.
+const SCHEMA_HEADER:&str = "Tauri-Bindgen-Schema";
+
+/// The module of the host function cancelling calls that are still running.
+const CANCEL_MODULE:&str = "tauri_bindgen::cancel";
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
-	#[error("todo")]
+	/// The request could not be sent or the host sent an unexpected response.
+	#[error("failed to reach the host: {0}")]
+	Transport(String),
+	/// The host doesn't define the called function.
+	#[error("{0}")]
+	NotFound(String),
+	/// The webview wasn't granted a capability required by the function.
+	#[error("{0}")]
+	PermissionDenied(String),
+	/// The bindings were generated from another version of the interface
+	/// than the host.
+	#[error("{0}")]
+	SchemaMismatch(String),
+	/// The host failed to deserialize the parameters of the call.
+	#[error("{0}")]
+	Deserialize(String),
+	/// The host function returned an error.
+	#[error("{0}")]
+	Host(String),
+	/// The host function panicked.
+	#[error("{0}")]
+	Panic(String),
+	/// The call was cancelled before it completed.
+	#[error("{0}")]
+	Cancelled(String),
+	/// Encoding the parameters or decoding the result failed.
+	#[error("failed to encode or decode a message: {0}")]
 	Postcard(#[from] postcard::Error),
-	#[error("todo")]
+	/// Encoding the parameters or decoding the result as JSON failed.
+	#[error("failed to encode or decode a message: {0}")]
+	Json(#[from] serde_json::Error),
+	#[error("javascript error: {0:?}")]
 	JsError(JsValue),
-	#[error("todo")]
+	#[error("not running in a browser window")]
 	NoWindow,
 }
 
+impl Error {
+	/// Creates the error for a failed call from the kind sent by the host.
+	fn from_response(kind:&str, message:String) -> Self {
+		match kind {
+			"not-found" => Self::NotFound(message),
+			"permission-denied" => Self::PermissionDenied(message),
+			"schema-mismatch" => Self::SchemaMismatch(message),
+			"deserialize" => Self::Deserialize(message),
+			"host" => Self::Host(message),
+			"panic" => Self::Panic(message),
+			"cancelled" => Self::Cancelled(message),
+			_ => Self::Transport(message),
+		}
+	}
+}
+
+/// Dropping the returned future before it completes cancels the call on the
+/// host, unless it was sent as part of a [`batch`].
+///
 /// # Errors
 ///
-/// Everything here is fallible (TODO improve this)
+/// Fails when the request can't be sent, the host fails to handle it or the
+/// result can't be decoded.
 ///
 /// # Panics
 ///
@@ -28,15 +106,60 @@ pub async fn invoke<P, R>(module:&str, method:&str, val:&P) -> Result<R, Error>
 where
 	P: Serialize,
 	R: DeserializeOwned, {
+	call(None, module, method, val).await
+}
+
+/// Like [`invoke`], but sends the schema hash of the function the bindings
+/// were generated from, so the host can reject calls of outdated bindings.
+///
+/// # Errors
+///
+/// Fails when the request can't be sent, the host fails to handle it or the
+/// result can't be decoded.
+#[doc(hidden)]
+pub async fn invoke_with_schema<P, R>(
+	schema:&str,
+	module:&str,
+	method:&str,
+	val:&P,
+) -> Result<R, Error>
+where
+	P: Serialize,
+	R: DeserializeOwned, {
+	call(Some(schema), module, method, val).await
+}
+
+async fn call<P, R>(schema:Option<&str>, module:&str, method:&str, val:&P) -> Result<R, Error>
+where
+	P: Serialize,
+	R: DeserializeOwned, {
+	let format = format::format();
+
+	let params = format.encode(val)?;
+
+	let body = match batch::enqueue(schema, module, method, params) {
+		Ok(reply) => reply.await?,
+		Err(params) => send(format, schema, module, method, &params).await?,
+	};
+
+	format.decode(&body)
+}
+
+/// Sends the encoded parameters to the host and returns the encoded result.
+async fn send(
+	format:Format,
+	schema:Option<&str>,
+	module:&str,
+	method:&str,
+	params:&[u8],
+) -> Result<Vec<u8>, Error> {
 	let mut opts = RequestInit::new();
 
 	opts.method("POST");
 
 	opts.mode(RequestMode::Cors);
 
-	let bytes = postcard::to_allocvec(val)?;
-
-	let body = unsafe { Uint8Array::view(&bytes) };
+	let body = unsafe { Uint8Array::view(params) };
 
 	opts.body(Some(&body));
 
@@ -44,27 +167,279 @@ where
 
 	let request = Request::new_with_str_and_init(&url, &opts).map_err(Error::JsError)?;
 
-	request
-		.headers()
-		.set("Accept", "application/octet-stream")
-		.map_err(Error::JsError)?;
+	let headers = request.headers();
+
+	headers.set("Accept", format.content_type()).map_err(Error::JsError)?;
+
+	headers.set("Content-Type", format.content_type()).map_err(Error::JsError)?;
+
+	if let Some(schema) = schema {
+		headers.set(SCHEMA_HEADER, schema).map_err(Error::JsError)?;
+	}
 
 	let window = web_sys::window().ok_or(Error::NoWindow)?;
 
-	let resp_value = JsFuture::from(window.fetch_with_request(&request))
-		.await
-		.map_err(Error::JsError)?;
+	let id = next_call_id(&window);
+
+	request.headers().set(CALL_ID_HEADER, &id.to_string()).map_err(Error::JsError)?;
+
+	let guard = CancelOnDrop { id, done:false };
+
+	let response = async {
+		let resp_value = JsFuture::from(window.fetch_with_request(&request))
+			.await
+			.map_err(|err| Error::Transport(format!("{err:?}")))?;
+
+		// `resp_value` is a `Response` object.
+		assert!(resp_value.is_instance_of::<Response>());
+
+		let resp:Response = resp_value.dyn_into().map_err(Error::JsError)?;
+
+		let body = JsFuture::from(resp.array_buffer().map_err(Error::JsError)?)
+			.await
+			.map_err(Error::JsError)?;
+
+		Ok::<_, Error>((resp, Uint8Array::new(&body).to_vec()))
+	}
+	.await;
+
+	// the call either completed or failed to reach the host
+	guard.complete();
+
+	let (resp, body) = response?;
+
+	if !resp.ok() {
+		let kind = resp.headers().get(ERROR_HEADER).map_err(Error::JsError)?;
+
+		return Err(match kind {
+			Some(kind) => Error::from_response(&kind, String::from_utf8_lossy(&body).into_owned()),
+			None => Error::Transport(format!("unexpected response status {}", resp.status())),
+		});
+	}
+
+	Ok(body)
+}
+
+/// Assigns the next id to a call, the counter is shared with the JavaScript
+/// bindings of the page so ids are unique per page.
+fn next_call_id(window:&web_sys::Window) -> u32 {
+	let key = JsValue::from_str("__TAURI_BINDGEN_CALL_ID__");
+
+	#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+	let id = js_sys::Reflect::get(window, &key)
+		.ok()
+		.and_then(|id| id.as_f64())
+		.map_or(0, |id| id as u32)
+		.wrapping_add(1);
+
+	let _ = js_sys::Reflect::set(window, &key, &JsValue::from(id));
+
+	id
+}
+
+/// Cancels the call on the host when the request is dropped before the
+/// response arrived.
+struct CancelOnDrop {
+	id:u32,
+	done:bool,
+}
+
+impl CancelOnDrop {
+	fn complete(mut self) { self.done = true; }
+}
+
+impl Drop for CancelOnDrop {
+	fn drop(&mut self) {
+		if self.done {
+			return;
+		}
 
-	// `resp_value` is a `Response` object.
-	assert!(resp_value.is_instance_of::<Response>());
+		let id = self.id;
 
-	let resp:Response = resp_value.dyn_into().map_err(Error::JsError)?;
+		wasm_bindgen_futures::spawn_local(async move {
+			let _:Result<(), Error> = invoke(CANCEL_MODULE, "call", &id).await;
+		});
+	}
+}
+
+/// Releases the resource with the given handle on the host.
+///
+/// This is called by the `Drop` implementation of generated resources, since
+/// that can't be async the request is sent in the background and errors are
+/// ignored.
+#[doc(hidden)]
+pub fn drop_resource(module:&'static str, id:u32) {
+	wasm_bindgen_futures::spawn_local(async move {
+		let _:Result<(), Error> = invoke(module, "drop", &id).await;
+	});
+}
+
+/// The module of the host functions used to pull values from a stream.
+const STREAM_MODULE:&str = "tauri_bindgen::stream";
+
+type NextItem<T> = Pin<Box<dyn Future<Output = Result<Option<T>, Error>>>>;
+
+/// Values pushed by the host, returned by functions with a `stream<T>` result.
+///
+/// The next item is requested from the host once the previous one has been
+/// received. Dropping the stream before it ended cancels it on the host, the
+/// stream ends after yielding an error.
+pub struct Stream<T> {
+	id:u32,
+	next:Option<NextItem<T>>,
+	done:bool,
+}
+
+impl<T:DeserializeOwned + 'static> futures_core::Stream for Stream<T> {
+	type Item = Result<T, Error>;
+
+	fn poll_next(mut self:Pin<&mut Self>, cx:&mut Context<'_>) -> Poll<Option<Self::Item>> {
+		if self.done {
+			return Poll::Ready(None);
+		}
+
+		let id = self.id;
+
+		let next = self.next.get_or_insert_with(|| Box::pin(next_item(id)));
+
+		let item = futures_core::ready!(next.as_mut().poll(cx));
+
+		self.next = None;
+
+		self.done = !matches!(item, Ok(Some(_)));
+
+		Poll::Ready(item.transpose())
+	}
+}
+
+/// Requests the next item of the stream, `None` marks its end.
+async fn next_item<T:DeserializeOwned>(id:u32) -> Result<Option<T>, Error> {
+	match format::format() {
+		Format::Postcard => invoke(STREAM_MODULE, "next", &id).await,
+		// items are sent as arrays with at most one element, since `null`
+		// would be ambiguous for streams of optional items
+		Format::Json => {
+			let items:Vec<T> = invoke(STREAM_MODULE, "next", &id).await?;
+
+			Ok(items.into_iter().next())
+		},
+	}
+}
+
+impl<'de, T> Deserialize<'de> for Stream<T> {
+	fn deserialize<D:Deserializer<'de>>(deserializer:D) -> Result<Self, D::Error> {
+		let id = u32::deserialize(deserializer)?;
+
+		Ok(Self { id, next:None, done:false })
+	}
+}
+
+impl<T> Drop for Stream<T> {
+	fn drop(&mut self) {
+		if self.done {
+			return;
+		}
+
+		let id = self.id;
+
+		wasm_bindgen_futures::spawn_local(async move {
+			let _:Result<(), Error> = invoke(STREAM_MODULE, "cancel", &id).await;
+		});
+	}
+}
 
-	let body = JsFuture::from(resp.array_buffer().map_err(Error::JsError)?)
-		.await
+/// The module of the host function receiving the replies of exported
+/// functions.
+const EXPORT_MODULE:&str = "tauri_bindgen::export";
+
+/// Registers `handler` as the implementation of the functions `module`
+/// exports to the host.
+///
+/// The handler receives the encoding of the call, the name of the called
+/// function and its encoded parameters and returns the encoded result, or an
+/// error message.
+///
+/// # Errors
+///
+/// Fails when not running in a browser window.
+#[doc(hidden)]
+pub fn register_exports<F, Fut>(module:&str, handler:F) -> Result<(), Error>
+where
+	F: Fn(Format, String, Vec<u8>) -> Fut + 'static,
+	Fut: Future<Output = Result<Vec<u8>, String>> + 'static, {
+	let window = web_sys::window().ok_or(Error::NoWindow)?;
+
+	let key = JsValue::from_str("__TAURI_BINDGEN_EXPORTS__");
+
+	let mut handlers = js_sys::Reflect::get(&window, &key).map_err(Error::JsError)?;
+
+	if handlers.is_undefined() {
+		handlers = js_sys::Object::new().into();
+
+		js_sys::Reflect::set(&window, &key, &handlers).map_err(Error::JsError)?;
+	}
+
+	let format = format::format();
+
+	// the host passes JSON parameters as text
+	let closure =
+		Closure::<dyn Fn(u32, String, JsValue)>::new(move |id:u32, name:String, params:JsValue| {
+			let params = match format {
+				Format::Postcard => Uint8Array::new(&params).to_vec(),
+				Format::Json => params.as_string().unwrap_or_default().into_bytes(),
+			};
+
+			let reply = handler(format, name, params);
+
+			wasm_bindgen_futures::spawn_local(async move {
+				let reply = reply.await;
+
+				let _:Result<(), Error> = match format {
+					Format::Postcard => invoke(EXPORT_MODULE, "reply", &(id, reply)).await,
+					Format::Json => {
+						let reply = reply.and_then(|bytes| {
+							format::raw_json(&bytes).map_err(|err| err.to_string())
+						});
+
+						invoke(EXPORT_MODULE, "reply", &(id, reply)).await
+					},
+				};
+			});
+		});
+
+	if format == Format::Json {
+		js_sys::Reflect::set(closure.as_ref(), &JsValue::from_str("format"), &JsValue::from_str("json"))
+			.map_err(Error::JsError)?;
+	}
+
+	js_sys::Reflect::set(&handlers, &JsValue::from_str(module), closure.as_ref())
 		.map_err(Error::JsError)?;
 
-	let body = Uint8Array::new(&body);
+	// the handler stays registered for the lifetime of the page
+	closure.forget();
+
+	Ok(())
+}
+
+/// Decodes the parameters of an exported function, calls it and encodes its
+/// result.
+///
+/// # Errors
+///
+/// Returns the error message when decoding or encoding fails.
+#[doc(hidden)]
+pub async fn call_export<P, R, Fut>(
+	format:Format,
+	params:&[u8],
+	func:impl FnOnce(P) -> Fut,
+) -> Result<Vec<u8>, String>
+where
+	P: DeserializeOwned,
+	Fut: Future<Output = R>,
+	R: Serialize, {
+	let params = format.decode(params).map_err(|err| err.to_string())?;
+
+	let result = func(params).await;
 
-	Ok(postcard::from_bytes(&body.to_vec())?)
+	format.encode(&result).map_err(|err| err.to_string())
 }
@@ -2,6 +2,7 @@ use proc_macro::TokenStream;
 use syn::{
 	Token,
 	parse::{Parse, ParseStream, Result},
+	punctuated::Punctuated,
 };
 use tauri_bindgen_gen_host::Builder;
 
@@ -12,11 +13,17 @@ pub fn generate(input:TokenStream) -> TokenStream {
 
 mod kw {
 	syn::custom_keyword!(tracing);
+
+	syn::custom_keyword!(capability);
+
+	syn::custom_keyword!(item_capabilities);
 }
 
 enum Opt {
 	Async(bool),
 	Tracing(bool),
+	Capability(String),
+	ItemCapabilities(Vec<(String, String)>),
 }
 
 impl Parse for Opt {
@@ -35,6 +42,31 @@ impl Parse for Opt {
 			input.parse::<Token![:]>()?;
 
 			Ok(Opt::Tracing(input.parse::<syn::LitBool>()?.value))
+		} else if l.peek(kw::capability) {
+			input.parse::<kw::capability>()?;
+
+			input.parse::<Token![:]>()?;
+
+			Ok(Opt::Capability(input.parse::<syn::LitStr>()?.value()))
+		} else if l.peek(kw::item_capabilities) {
+			input.parse::<kw::item_capabilities>()?;
+
+			input.parse::<Token![:]>()?;
+
+			let content;
+
+			syn::braced!(content in input);
+
+			// `{ "name": "capability", ... }`
+			let entries = Punctuated::<_, Token![,]>::parse_terminated_with(&content, |input| {
+				let name = input.parse::<syn::LitStr>()?.value();
+
+				input.parse::<Token![:]>()?;
+
+				Ok((name, input.parse::<syn::LitStr>()?.value()))
+			})?;
+
+			Ok(Opt::ItemCapabilities(entries.into_iter().collect()))
 		} else {
 			Err(l.error())
 		}
@@ -46,6 +78,8 @@ impl rust_macro_shared::Configure<Builder> for Opt {
 		match self {
 			Opt::Async(val) => builder.async_ = val,
 			Opt::Tracing(val) => builder.tracing = val,
+			Opt::Capability(val) => builder.capability = Some(val),
+			Opt::ItemCapabilities(val) => builder.item_capabilities = val,
 		}
 	}
 }
@@ -4,22 +4,76 @@ use std::{
 	sync::{Arc, RwLock},
 };
 
+pub use ipc_router_wip::{BoxStream, ExportError};
 pub use tauri_bindgen_host_macro::*;
 #[doc(hidden)]
-pub use {anyhow, async_trait::async_trait, bitflags, ipc_router_wip, serde, tauri, tracing};
+pub use {
+	anyhow,
+	async_trait::async_trait,
+	bitflags,
+	ipc_router_wip::{self, field_or_default},
+	serde,
+	tauri,
+	tracing,
+};
 pub type Result<T> = anyhow::Result<T>;
 
 pub type ResourceId = u32;
 
+/// Errors returned when accessing a [`ResourceTable`].
+#[derive(Debug, thiserror::Error)]
+pub enum ResourceError {
+	#[error("table has no free keys")]
+	TableFull,
+	#[error("resource {0} not in table")]
+	NotFound(ResourceId),
+	/// The resource exists but was created by a different window. The owning
+	/// window is intentionally not included, as the error is reported back to
+	/// the calling window.
+	#[error("resource {0} belongs to a different window")]
+	ForeignHandle(ResourceId),
+	#[error("resource {0} is a different type")]
+	WrongType(ResourceId),
+	#[error("cannot mutably borrow shared resource {0}")]
+	Borrowed(ResourceId),
+}
+
+/// A table of resources handed out to webviews.
+///
+/// Every resource is owned by the window that created it, identified by its
+/// webview label (see [`ipc_router_wip::Caller::window_label`]). Resources can
+/// only be accessed by their owner, and all resources of a window are released
+/// at once with [`ResourceTable::drop_window`].
+///
+/// # Migrating
+///
+/// Resources used to be shared between all windows. Every method now takes the
+/// label of the window owning the resource, and errors are reported as
+/// [`ResourceError`] instead of [`anyhow::Error`]:
+///
+/// ```ignore
+/// // before
+/// let id = table.push(Arc::new(resource))?;
+/// let resource = table.get::<Resource>(id)?;
+///
+/// // after
+/// let id = table.push(caller.window_label(), Arc::new(resource))?;
+/// let resource = table.get::<Resource>(caller.window_label(), id)?;
+/// ```
 #[derive(Default)]
 pub struct ResourceTable(RwLock<ResourceTableInner>);
 
 #[derive(Default)]
 pub struct ResourceTableInner {
-	map:HashMap<ResourceId, Arc<dyn Any + Send + Sync>>,
+	map:HashMap<ResourceId, Entry>,
 	next_rid:ResourceId,
 }
 
+struct Entry {
+	window:Arc<str>,
+	resource:Arc<dyn Any + Send + Sync>,
+}
+
 impl ResourceTable {
 	/// Create an empty table.
 	#[must_use]
@@ -27,7 +81,7 @@ impl ResourceTable {
 		Self(RwLock::new(ResourceTableInner { map:HashMap::new(), next_rid:0 }))
 	}
 
-	/// Insert a resource at the next available index.
+	/// Insert a resource owned by `window` at the next available index.
 	///
 	/// # Errors
 	///
@@ -36,47 +90,52 @@ impl ResourceTable {
 	/// # Panics
 	///
 	/// Panics if the resource is already borrowed.
-	pub fn push<T:Any + Send + Sync>(&self, a:Arc<T>) -> Result<ResourceId> {
+	pub fn push<T:Any + Send + Sync>(
+		&self,
+		window:&str,
+		a:Arc<T>,
+	) -> std::result::Result<ResourceId, ResourceError> {
 		let mut inner = self.0.write().unwrap();
 		// NOTE: The performance of this new key calculation could be very bad
 		// once keys wrap around.
 		if inner.map.len() == u32::MAX as usize {
-			return Err(anyhow::anyhow!("table has no free keys"));
+			return Err(ResourceError::TableFull);
 		}
 
 		loop {
 			let key = inner.next_rid;
 
-			inner.next_rid += 1;
+			inner.next_rid = inner.next_rid.wrapping_add(1);
 
 			if inner.map.contains_key(&key) {
 				continue;
 			}
 
-			inner.map.insert(key, a);
+			inner.map.insert(key, Entry { window:window.into(), resource:a });
 
 			return Ok(key);
 		}
 	}
 
-	/// Check if the table has a resource at the given index.
+	/// Check if `window` owns a resource at the given index.
 	///
 	/// # Panics
 	///
 	/// Panics if the resource is already borrowed.
-	pub fn contains_key(&self, key:ResourceId) -> bool {
-		self.0.read().unwrap().map.contains_key(&key)
+	pub fn contains_key(&self, window:&str, key:ResourceId) -> bool {
+		self.0.read().unwrap().map.get(&key).is_some_and(|entry| &*entry.window == window)
 	}
 
-	/// Check if the resource at a given index can be downcast to a given type.
+	/// Check if the resource at a given index is owned by `window` and can be
+	/// downcast to a given type.
 	/// Note: this will always fail if the resource is already borrowed.
 	///
 	/// # Panics
 	///
 	/// Panics if the resource is already borrowed.
-	pub fn is<T:Any + Sized>(&self, key:ResourceId) -> bool {
-		if let Some(r) = self.0.read().unwrap().map.get(&key) {
-			r.is::<T>()
+	pub fn is<T:Any + Sized>(&self, window:&str, key:ResourceId) -> bool {
+		if let Some(entry) = self.0.read().unwrap().map.get(&key) {
+			&*entry.window == window && entry.resource.is::<T>()
 		} else {
 			false
 		}
@@ -87,52 +146,203 @@ impl ResourceTable {
 	///
 	/// # Errors
 	///
-	/// Returns an error if the resource is not of the given type.
+	/// Returns an error if the resource doesn't exist, is owned by a different
+	/// window or is not of the given type.
 	///
 	/// # Panics
 	///
 	/// Panics if the resource is already borrowed.
-	pub fn get<T:Any + Send + Sync + Sized>(&self, key:ResourceId) -> Result<Arc<T>> {
-		if let Some(r) = self.0.read().unwrap().map.get(&key).cloned() {
-			r.downcast::<T>().map_err(|_| anyhow::anyhow!("element is a different type"))
-		} else {
-			Err(anyhow::anyhow!("key not in table"))
+	pub fn get<T:Any + Send + Sync + Sized>(
+		&self,
+		window:&str,
+		key:ResourceId,
+	) -> std::result::Result<Arc<T>, ResourceError> {
+		let inner = self.0.read().unwrap();
+
+		let entry = inner.map.get(&key).ok_or(ResourceError::NotFound(key))?;
+
+		if &*entry.window != window {
+			return Err(ResourceError::ForeignHandle(key));
 		}
+
+		entry.resource.clone().downcast::<T>().map_err(|_| ResourceError::WrongType(key))
 	}
 
 	/// Get a mutable reference to a resource of a given type at a given index.
 	///
 	/// # Errors
 	///
-	/// Returns an error if the resource is not of the given type or if the
-	/// resource is already borrowed.
+	/// Returns an error if the resource doesn't exist, is owned by a different
+	/// window, is not of the given type or if the resource is already
+	/// borrowed.
 	///
 	/// # Panics
 	///
 	/// Panics if the resource is already borrowed.
-	pub fn get_mut<T:Any>(&mut self, key:ResourceId) -> Result<&mut T> {
-		let entry = self
-			.0
-			.get_mut()
-			.unwrap()
-			.map
-			.get_mut(&key)
-			.ok_or(anyhow::anyhow!("key not in table"))?;
-
+	pub fn get_mut<T:Any>(
+		&mut self,
+		window:&str,
+		key:ResourceId,
+	) -> std::result::Result<&mut T, ResourceError> {
 		let entry =
-			Arc::get_mut(entry).ok_or(anyhow::anyhow!("cannot mutably borrow shared file"))?;
+			self.0.get_mut().unwrap().map.get_mut(&key).ok_or(ResourceError::NotFound(key))?;
+
+		if &*entry.window != window {
+			return Err(ResourceError::ForeignHandle(key));
+		}
+
+		let resource = Arc::get_mut(&mut entry.resource).ok_or(ResourceError::Borrowed(key))?;
 
-		entry
-			.downcast_mut::<T>()
-			.ok_or_else(|| anyhow::anyhow!("element is a different type"))
+		resource.downcast_mut::<T>().ok_or(ResourceError::WrongType(key))
 	}
 
 	/// Remove a resource at a given index from the table and returns it.
 	///
+	/// # Errors
+	///
+	/// Returns an error if the resource doesn't exist, is owned by a different
+	/// window or is not of the given type. The resource is only removed when
+	/// this function succeeds.
+	///
+	/// # Panics
+	///
+	/// Panics if the resource is already borrowed.
+	pub fn take<T:Any + Send + Sync>(
+		&self,
+		window:&str,
+		key:ResourceId,
+	) -> std::result::Result<Arc<T>, ResourceError> {
+		let mut inner = self.0.write().unwrap();
+
+		let entry = inner.map.get(&key).ok_or(ResourceError::NotFound(key))?;
+
+		if &*entry.window != window {
+			return Err(ResourceError::ForeignHandle(key));
+		}
+
+		if !entry.resource.is::<T>() {
+			return Err(ResourceError::WrongType(key));
+		}
+
+		let entry = inner.map.remove(&key).unwrap();
+
+		Ok(entry.resource.downcast::<T>().unwrap())
+	}
+
+	/// Remove all resources owned by `window` from the table, this should be
+	/// called when the window is destroyed or reloaded (see
+	/// [`ipc_router_wip::Router::on_window_cleanup`]).
+	///
 	/// # Panics
 	///
 	/// Panics if the resource is already borrowed.
-	pub fn take<T:Any + Send + Sync>(&self, key:ResourceId) -> Option<Arc<T>> {
-		self.0.write().unwrap().map.remove(&key).map(|r| r.downcast::<T>().unwrap())
+	pub fn drop_window(&self, window:&str) {
+		self.0.write().unwrap().map.retain(|_, entry| &*entry.window != window);
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[derive(Debug)]
+	struct Foo(u32);
+
+	#[test]
+	fn owned_by_window() {
+		let table = ResourceTable::new();
+
+		let a = table.push("a", Arc::new(Foo(1))).unwrap();
+		let b = table.push("b", Arc::new(Foo(2))).unwrap();
+
+		assert_ne!(a, b);
+		assert_eq!(table.get::<Foo>("a", a).unwrap().0, 1);
+		assert_eq!(table.get::<Foo>("b", b).unwrap().0, 2);
+		assert!(table.contains_key("a", a));
+		assert!(!table.contains_key("a", b));
+		assert!(table.is::<Foo>("b", b));
+		assert!(!table.is::<Foo>("a", b));
+		assert!(!table.is::<u32>("b", b));
+	}
+
+	#[test]
+	fn foreign_handle() {
+		let table = ResourceTable::new();
+
+		let a = table.push("a", Arc::new(Foo(1))).unwrap();
+
+		let err = table.get::<Foo>("b", a).unwrap_err();
+
+		assert!(matches!(err, ResourceError::ForeignHandle(id) if id == a));
+
+		let err = table.take::<Foo>("b", a).unwrap_err();
+
+		assert!(matches!(err, ResourceError::ForeignHandle(id) if id == a));
+
+		// a failed take leaves the resource with its owner
+		assert_eq!(table.get::<Foo>("a", a).unwrap().0, 1);
+	}
+
+	#[test]
+	fn wrong_type() {
+		let table = ResourceTable::new();
+
+		let a = table.push("a", Arc::new(Foo(1))).unwrap();
+
+		assert!(matches!(table.get::<u32>("a", a), Err(ResourceError::WrongType(_))));
+		assert!(matches!(table.take::<u32>("a", a), Err(ResourceError::WrongType(_))));
+		assert!(table.contains_key("a", a));
+	}
+
+	#[test]
+	fn take() {
+		let table = ResourceTable::new();
+
+		let a = table.push("a", Arc::new(Foo(1))).unwrap();
+
+		assert_eq!(table.take::<Foo>("a", a).unwrap().0, 1);
+		assert!(matches!(table.get::<Foo>("a", a), Err(ResourceError::NotFound(_))));
+		assert!(matches!(table.take::<Foo>("a", a), Err(ResourceError::NotFound(_))));
+	}
+
+	#[test]
+	fn get_mut() {
+		let mut table = ResourceTable::new();
+
+		let a = table.push("a", Arc::new(Foo(1))).unwrap();
+
+		table.get_mut::<Foo>("a", a).unwrap().0 = 2;
+		assert!(matches!(table.get_mut::<Foo>("b", a), Err(ResourceError::ForeignHandle(_))));
+
+		let shared = table.get::<Foo>("a", a).unwrap();
+
+		assert!(matches!(table.get_mut::<Foo>("a", a), Err(ResourceError::Borrowed(_))));
+		assert_eq!(shared.0, 2);
+	}
+
+	#[test]
+	fn drop_window() {
+		let table = ResourceTable::new();
+
+		let a1 = table.push("a", Arc::new(Foo(1))).unwrap();
+		let a2 = table.push("a", Arc::new(Foo(2))).unwrap();
+		let b = table.push("b", Arc::new(Foo(3))).unwrap();
+
+		// resources still borrowed by a call stay alive until it returns
+		let borrowed = table.get::<Foo>("a", a1).unwrap();
+
+		table.drop_window("a");
+
+		assert!(!table.contains_key("a", a1));
+		assert!(!table.contains_key("a", a2));
+		assert_eq!(borrowed.0, 1);
+		assert_eq!(Arc::strong_count(&borrowed), 1);
+		assert_eq!(table.get::<Foo>("b", b).unwrap().0, 3);
+
+		// a reloaded window starts with an empty set of handles
+		let a3 = table.push("a", Arc::new(Foo(4))).unwrap();
+
+		assert!(table.contains_key("a", a3));
+		assert!(!table.contains_key("a", a1));
 	}
 }
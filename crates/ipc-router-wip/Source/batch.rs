@@ -0,0 +1,93 @@
+use futures_util::future::join_all;
+use serde_json::value::RawValue;
+use tauri::http::StatusCode;
+
+use crate::{CallError, Caller, Format, Router};
+
+/// The module of the builtin function running several calls sent in a single
+/// request.
+pub(crate) const BATCH_MODULE:&str = "tauri_bindgen::batch";
+
+/// A call as part of a batch, `(module, method, params)`.
+///
+/// With JSON the parameters are embedded as is rather than as bytes, the same
+/// goes for the results.
+type Entry = (String, String, Vec<u8>);
+
+/// The result of a call in a batch, failed calls carry the kind and message of
+/// their error.
+type EntryResult<T = Vec<u8>> = Result<T, (String, String)>;
+
+impl<T:Send + Sync + 'static> Router<T> {
+	/// Runs all calls of the batch concurrently and returns their results in
+	/// order.
+	///
+	/// Each call passes through the layers and permission checks on its own,
+	/// just like calls sent in separate requests.
+	pub(crate) async fn call_batch(
+		&self,
+		caller:&Caller<T>,
+		name:&str,
+		params:&[u8],
+	) -> Result<Vec<u8>, CallError> {
+		if name != "call" {
+			return Err(CallError::NotFound(format!("{BATCH_MODULE}/{name}")));
+		}
+
+		let format = caller.format;
+
+		let entries:Vec<Entry> = match format {
+			Format::Postcard => format.decode(params)?,
+			Format::Json => {
+				let entries:Vec<(String, String, Box<RawValue>)> = format.decode(params)?;
+
+				entries
+					.into_iter()
+					.map(|(module, method, params)| (module, method, params.get().as_bytes().to_vec()))
+					.collect()
+			},
+		};
+
+		if entries.iter().any(|(module, ..)| module == BATCH_MODULE) {
+			return Err(CallError::Rejected(
+				StatusCode::BAD_REQUEST,
+				"batches can't be nested".to_string(),
+			));
+		}
+
+		// the calls share the schema hashes sent with the batch
+		let calls = entries
+			.iter()
+			.map(|(module, method, params)| self.call(caller.clone(), Some(module), method, params));
+
+		let results:Vec<EntryResult> = join_all(calls)
+			.await
+			.into_iter()
+			.map(|res| res.map_err(|err| (err.kind().to_string(), err.to_string())))
+			.collect();
+
+		match format {
+			Format::Postcard => format.encode(&results),
+			Format::Json => {
+				let results = results
+					.into_iter()
+					.map(|res| {
+						match res {
+							Ok(bytes) => raw_json(bytes).map(Ok),
+							Err(err) => Ok(Err(err)),
+						}
+					})
+					.collect::<Result<Vec<EntryResult<Box<RawValue>>>, CallError>>()?;
+
+				format.encode(&results)
+			},
+		}
+	}
+}
+
+/// Embeds the JSON encoded result of a call.
+fn raw_json(bytes:Vec<u8>) -> Result<Box<RawValue>, CallError> {
+	let json = String::from_utf8(bytes).map_err(anyhow::Error::from)?;
+
+	Ok(RawValue::from_string(json).map_err(anyhow::Error::from)?)
+}
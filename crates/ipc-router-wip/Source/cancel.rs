@@ -0,0 +1,96 @@
+use std::{
+	collections::HashMap,
+	future::Future,
+	sync::{
+		Arc,
+		Mutex,
+		atomic::{AtomicU64, Ordering},
+	},
+};
+
+use futures_util::future::{AbortHandle, Abortable};
+
+use crate::{CallError, Format};
+
+/// The module of the builtin function cancelling calls that are still
+/// running.
+pub(crate) const CANCEL_MODULE:&str = "tauri_bindgen::cancel";
+
+/// The request header carrying the id the webview assigned to a call, which
+/// it passes to [`CANCEL_MODULE`]`/call` to cancel it.
+pub(crate) const CALL_ID_HEADER:&str = "Tauri-Bindgen-Call-Id";
+
+struct RunningCall {
+	/// The window that made the call.
+	window:Arc<str>,
+	/// The id the window assigned to the call, if any.
+	id:Option<u32>,
+	handle:AbortHandle,
+}
+
+/// The calls that are still running.
+#[derive(Default)]
+pub(crate) struct Running {
+	map:Mutex<HashMap<u64, RunningCall>>,
+	next_key:AtomicU64,
+}
+
+impl Running {
+	/// Runs the call until it completes or is cancelled, the future of the
+	/// call is dropped when cancelled.
+	pub async fn run<F>(&self, window:Arc<str>, id:Option<u32>, call:F) -> Result<Vec<u8>, CallError>
+	where
+		F: Future<Output = Result<Vec<u8>, CallError>>, {
+		let (handle, registration) = AbortHandle::new_pair();
+
+		let key = self.next_key.fetch_add(1, Ordering::Relaxed);
+
+		self.map.lock().unwrap().insert(key, RunningCall { window, id, handle });
+
+		// removes the call when it completes, or when the request is dropped
+		let _guard = Guard { running:self, key };
+
+		Abortable::new(call, registration).await.unwrap_or(Err(CallError::Cancelled))
+	}
+
+	pub fn call(
+		&self,
+		window:&str,
+		format:Format,
+		name:&str,
+		params:&[u8],
+	) -> Result<Vec<u8>, CallError> {
+		if name != "call" {
+			return Err(CallError::NotFound(format!("{CANCEL_MODULE}/{name}")));
+		}
+
+		let id:u32 = format.decode(params)?;
+
+		// the call might have completed in the meantime
+		for call in self.map.lock().unwrap().values() {
+			if &*call.window == window && call.id == Some(id) {
+				call.handle.abort();
+			}
+		}
+
+		format.encode(&())
+	}
+
+	/// Cancels all running calls of the window.
+	pub fn drop_window(&self, window:&str) {
+		for call in self.map.lock().unwrap().values() {
+			if &*call.window == window {
+				call.handle.abort();
+			}
+		}
+	}
+}
+
+struct Guard<'a> {
+	running:&'a Running,
+	key:u64,
+}
+
+impl Drop for Guard<'_> {
+	fn drop(&mut self) { self.running.map.lock().unwrap().remove(&self.key); }
+}
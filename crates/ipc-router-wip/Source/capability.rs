@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+
+use crate::{CallError, Caller};
+
+/// The webviews a capability is granted to.
+///
+/// A scope without windows or origins matches every webview, each added
+/// window label or origin restricts it to webviews matching one of them.
+#[derive(Debug, Clone, Default)]
+pub struct Scope {
+	windows:Vec<String>,
+	origins:Vec<String>,
+}
+
+impl Scope {
+	#[must_use]
+	pub fn new() -> Self { Self::default() }
+
+	/// Restricts the scope to webviews with the given label.
+	#[must_use]
+	pub fn window(mut self, label:impl Into<String>) -> Self {
+		self.windows.push(label.into());
+
+		self
+	}
+
+	/// Restricts the scope to pages loaded from the given origin, such as
+	/// `tauri://localhost`.
+	///
+	/// Calls without an `Origin` header never match a scope restricted to
+	/// origins.
+	#[must_use]
+	pub fn origin(mut self, origin:impl Into<String>) -> Self {
+		self.origins.push(origin.into());
+
+		self
+	}
+
+	fn matches(&self, window:&str, origin:Option<&str>) -> bool {
+		let window_matches = self.windows.is_empty() || self.windows.iter().any(|w| w == window);
+
+		let origin_matches = self.origins.is_empty()
+			|| origin.is_some_and(|origin| self.origins.iter().any(|o| o == origin));
+
+		window_matches && origin_matches
+	}
+}
+
+/// The capabilities required by modules and functions and the webviews they
+/// are granted to.
+#[derive(Default)]
+pub(crate) struct Capabilities {
+	required:HashMap<(String, Option<String>), Vec<String>>,
+	granted:HashMap<String, Vec<Scope>>,
+}
+
+impl Capabilities {
+	pub fn require(&mut self, module:&str, name:Option<&str>, capability:&str) {
+		self.required
+			.entry((module.to_string(), name.map(ToString::to_string)))
+			.or_default()
+			.push(capability.to_string());
+	}
+
+	pub fn grant(&mut self, capability:&str, scope:Scope) {
+		self.granted.entry(capability.to_string()).or_default().push(scope);
+	}
+
+	/// Checks that the caller was granted all capabilities required by the
+	/// module and the function.
+	pub fn check<T>(&self, module:&str, name:&str, caller:&Caller<T>) -> Result<(), CallError> {
+		if self.required.is_empty() {
+			return Ok(());
+		}
+
+		let module_reqs = self.required.get(&(module.to_string(), None));
+
+		let func_reqs = self.required.get(&(module.to_string(), Some(name.to_string())));
+
+		for capability in module_reqs.into_iter().chain(func_reqs).flatten() {
+			let granted = self.granted.get(capability).is_some_and(|scopes| {
+				scopes.iter().any(|scope| scope.matches(caller.window_label(), caller.origin()))
+			});
+
+			if !granted {
+				return Err(CallError::PermissionDenied(format!(
+					"`{module}/{name}` requires the `{capability}` capability"
+				)));
+			}
+		}
+
+		Ok(())
+	}
+}
@@ -0,0 +1,44 @@
+use std::{
+	collections::HashMap,
+	sync::atomic::{AtomicBool, Ordering},
+};
+
+/// A deprecated function and whether a call of it was logged yet.
+struct Deprecation {
+	note:String,
+	warned:AtomicBool,
+}
+
+/// The deprecated functions of all modules.
+#[derive(Default)]
+pub(crate) struct Deprecations {
+	functions:HashMap<(String, String), Deprecation>,
+}
+
+impl Deprecations {
+	pub fn deprecate(&mut self, module:&str, name:&str, note:&str) {
+		let deprecation = Deprecation { note:note.to_string(), warned:AtomicBool::new(false) };
+
+		self.functions.insert((module.to_string(), name.to_string()), deprecation);
+	}
+
+	/// Returns the warning to log if `name` is deprecated and wasn't called
+	/// before.
+	pub fn warning(&self, module:&str, name:&str) -> Option<String> {
+		if self.functions.is_empty() {
+			return None;
+		}
+
+		let deprecation = self.functions.get(&(module.to_string(), name.to_string()))?;
+
+		if deprecation.warned.swap(true, Ordering::Relaxed) {
+			return None;
+		}
+
+		if deprecation.note.is_empty() {
+			Some(format!("called deprecated function `{module}/{name}`"))
+		} else {
+			Some(format!("called deprecated function `{module}/{name}`: {}", deprecation.note))
+		}
+	}
+}
@@ -0,0 +1,99 @@
+use std::any::Any;
+
+use tauri::http::{
+	Response,
+	StatusCode,
+	header::{CONTENT_TYPE, HeaderValue},
+};
+
+use crate::FormatError;
+
+/// The response header carrying the kind of a failed call, the body of the
+/// response contains the error message.
+pub(crate) const ERROR_HEADER:&str = "Tauri-Bindgen-Error";
+
+/// The ways a call from a webview can fail.
+///
+/// The webview receives the kind of the error along with its message.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum CallError {
+	/// The request was rejected before reaching a function, for example
+	/// because it isn't a `POST` request or its body is too large.
+	#[error("{1}")]
+	Rejected(StatusCode, String),
+	/// The module or function doesn't exist.
+	#[error("unknown function `{0}`")]
+	NotFound(String),
+	/// The webview wasn't granted a capability required by the function.
+	#[error("permission denied: {0}")]
+	PermissionDenied(String),
+	/// The bindings that made the call were generated from another version
+	/// of the interface than the host.
+	#[error("schema mismatch: {0}")]
+	SchemaMismatch(String),
+	/// The parameters sent by the webview don't match the function.
+	#[error("failed to deserialize the parameters: {0}")]
+	Deserialize(FormatError),
+	/// The host function returned an error.
+	#[error("{0:#}")]
+	Host(#[from] anyhow::Error),
+	/// The host function panicked.
+	#[error("the host panicked: {0}")]
+	Panic(String),
+	/// The webview cancelled the call, or navigated away before it completed.
+	#[error("the call was cancelled")]
+	Cancelled,
+}
+
+impl CallError {
+	pub(crate) fn from_panic(payload:Box<dyn Any + Send>) -> Self {
+		let msg = match payload.downcast::<String>() {
+			Ok(msg) => *msg,
+			Err(payload) => {
+				payload.downcast_ref::<&str>().map_or("unknown panic", |msg| msg).to_string()
+			},
+		};
+
+		Self::Panic(msg)
+	}
+
+	/// The kind of error sent to the webview.
+	#[must_use]
+	pub fn kind(&self) -> &'static str {
+		match self {
+			Self::Rejected(..) => "transport",
+			Self::NotFound(_) => "not-found",
+			Self::PermissionDenied(_) => "permission-denied",
+			Self::SchemaMismatch(_) => "schema-mismatch",
+			Self::Deserialize(_) => "deserialize",
+			Self::Host(_) => "host",
+			Self::Panic(_) => "panic",
+			Self::Cancelled => "cancelled",
+		}
+	}
+
+	#[must_use]
+	pub fn status(&self) -> StatusCode {
+		match self {
+			Self::Rejected(status, _) => *status,
+			Self::NotFound(_) => StatusCode::NOT_FOUND,
+			Self::PermissionDenied(_) => StatusCode::FORBIDDEN,
+			Self::SchemaMismatch(_) => StatusCode::CONFLICT,
+			Self::Deserialize(_) => StatusCode::BAD_REQUEST,
+			Self::Host(_) | Self::Panic(_) => StatusCode::INTERNAL_SERVER_ERROR,
+			// nobody is waiting for the response anymore, this is the status
+			// servers commonly use for requests closed by the client
+			Self::Cancelled => StatusCode::from_u16(499).unwrap(),
+		}
+	}
+
+	pub(crate) fn into_response(self) -> Response<Vec<u8>> {
+		Response::builder()
+			.status(self.status())
+			.header(CONTENT_TYPE, HeaderValue::from_static("text/plain; charset=utf-8"))
+			.header(ERROR_HEADER, HeaderValue::from_static(self.kind()))
+			.body(self.to_string().into_bytes())
+			.unwrap()
+	}
+}
@@ -0,0 +1,178 @@
+use std::{
+	collections::{HashMap, hash_map::Entry},
+	sync::{
+		Arc,
+		Mutex,
+		atomic::{AtomicU32, Ordering},
+	},
+	time::Duration,
+};
+
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::value::RawValue;
+use tauri::{Manager, Runtime, Webview};
+use tokio::sync::oneshot;
+
+use crate::{Format, error::CallError};
+
+/// The module of the builtin function receiving the replies of exported
+/// functions.
+pub(crate) const EXPORT_MODULE:&str = "tauri_bindgen::export";
+
+/// The result of an exported function in the format the webview encoded it
+/// in, or the error it threw.
+type Reply = Result<(Format, Vec<u8>), String>;
+
+type ReplySender = oneshot::Sender<Reply>;
+
+/// Errors returned when calling a function exported by a webview.
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError {
+	#[error("the webview did not reply within {0:?}")]
+	Timeout(Duration),
+	/// The webview was destroyed or reloaded before it replied.
+	#[error("the webview was closed before it replied")]
+	Closed,
+	/// The implementation threw an exception, or the webview doesn't
+	/// implement the function at all.
+	#[error("the webview failed to handle the call: {0}")]
+	Failed(String),
+	#[error("no ipc router has been registered with the app")]
+	NoRouter,
+	#[error(transparent)]
+	Postcard(#[from] postcard::Error),
+	#[error(transparent)]
+	Json(#[from] serde_json::Error),
+	#[error(transparent)]
+	Tauri(#[from] tauri::Error),
+}
+
+/// Calls that have been sent to a webview and wait for its reply.
+///
+/// The webview sends the reply to [`EXPORT_MODULE`]`/reply` as
+/// `(u32, Result<Vec<u8>, String>)`, or with the result embedded as is when
+/// using JSON. Only the window a call was sent to can reply to it.
+#[derive(Default)]
+pub(crate) struct PendingCalls {
+	map:Mutex<HashMap<u32, (Arc<str>, ReplySender)>>,
+	next_id:AtomicU32,
+}
+
+impl PendingCalls {
+	fn insert(&self, window:&str) -> (u32, oneshot::Receiver<Reply>) {
+		let (tx, rx) = oneshot::channel();
+
+		let mut map = self.map.lock().unwrap();
+
+		loop {
+			let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+
+			if let Entry::Vacant(entry) = map.entry(id) {
+				entry.insert((window.into(), tx));
+
+				return (id, rx);
+			}
+		}
+	}
+
+	fn remove(&self, id:u32) { self.map.lock().unwrap().remove(&id); }
+
+	pub fn call(
+		&self,
+		window:&str,
+		format:Format,
+		name:&str,
+		params:&[u8],
+	) -> Result<Vec<u8>, CallError> {
+		if name != "reply" {
+			return Err(CallError::NotFound(format!("{EXPORT_MODULE}/{name}")));
+		}
+
+		let (id, reply):(u32, Reply) = match format {
+			Format::Postcard => {
+				let (id, reply):(u32, Result<Vec<u8>, String>) = format.decode(params)?;
+
+				(id, reply.map(|bytes| (format, bytes)))
+			},
+			Format::Json => {
+				let (id, reply):(u32, Result<Box<RawValue>, String>) = format.decode(params)?;
+
+				(id, reply.map(|raw| (format, raw.get().as_bytes().to_vec())))
+			},
+		};
+
+		let mut map = self.map.lock().unwrap();
+
+		match map.get(&id) {
+			Some((owner, _)) if &**owner == window => {},
+			Some(_) => return Err(anyhow::anyhow!("call {id} was sent to a different window").into()),
+			None => return Err(anyhow::anyhow!("call {id} not found").into()),
+		}
+
+		let (_, sender) = map.remove(&id).unwrap();
+
+		// the caller might have timed out in the meantime
+		let _ = sender.send(reply);
+
+		format.encode(&())
+	}
+
+	/// Drops all calls waiting for a reply of the window, their callers
+	/// receive [`ExportError::Closed`].
+	pub fn drop_window(&self, window:&str) {
+		self.map.lock().unwrap().retain(|_, (owner, _)| &**owner != window);
+	}
+}
+
+/// Calls the function `name` of `module` that the given webview exports.
+///
+/// The call fails with [`ExportError::Timeout`] if the webview doesn't reply
+/// within `timeout`, which includes webviews that never registered an
+/// implementation of `module`.
+pub async fn call_export<R, P, T>(
+	webview:&Webview<R>,
+	module:&str,
+	name:&str,
+	params:&P,
+	timeout:Duration,
+) -> Result<T, ExportError>
+where
+	R: Runtime,
+	P: Serialize,
+	T: DeserializeOwned, {
+	let calls = webview.try_state::<Arc<PendingCalls>>().ok_or(ExportError::NoRouter)?;
+
+	let calls = Arc::clone(&calls);
+
+	// the handler declares which format it expects, JSON is passed as text
+	let bytes = postcard::to_allocvec(params)?;
+
+	let json = serde_json::to_string(&serde_json::to_string(params)?)?;
+
+	let (id, reply) = calls.insert(webview.label());
+
+	let script = format!(
+		"(() => {{ const handler = window.__TAURI_BINDGEN_EXPORTS__?.[{module:?}]; \
+		 handler?.({id}, {name:?}, handler.format === 'json' ? {json} : new \
+		 Uint8Array({bytes:?})) }})()"
+	);
+
+	if let Err(err) = webview.eval(script) {
+		calls.remove(id);
+
+		return Err(err.into());
+	}
+
+	let Ok(reply) = tokio::time::timeout(timeout, reply).await else {
+		calls.remove(id);
+
+		return Err(ExportError::Timeout(timeout));
+	};
+
+	let (format, bytes) = reply.map_err(|_| ExportError::Closed)?.map_err(ExportError::Failed)?;
+
+	match format {
+		Format::Postcard => Ok(postcard::from_bytes(&bytes)?),
+		Format::Json => Ok(serde_json::from_slice(&bytes)?),
+	}
+}
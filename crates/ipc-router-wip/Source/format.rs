@@ -0,0 +1,116 @@
+use serde::{Deserialize, Deserializer, Serialize, de::DeserializeOwned};
+use tauri::http::StatusCode;
+
+use crate::CallError;
+
+/// The encoding of parameters and results, chosen by the `Content-Type` of
+/// the request.
+///
+/// Responses use the same encoding as their request.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Format {
+	/// The compact binary encoding of [postcard](https://docs.rs/postcard),
+	/// used by `application/octet-stream` requests and requests without a
+	/// `Content-Type`.
+	#[default]
+	Postcard,
+	/// JSON as produced by `serde_json`, used by `application/json` requests.
+	///
+	/// This is mostly useful for debugging, since calls can be read in the
+	/// network tab of the devtools, and for callers that don't use generated
+	/// bindings.
+	Json,
+}
+
+/// Failures to decode parameters sent by the webview.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum FormatError {
+	#[error(transparent)]
+	Postcard(#[from] postcard::Error),
+	#[error(transparent)]
+	Json(#[from] serde_json::Error),
+}
+
+impl Format {
+	/// The format of a request with the given `Content-Type`.
+	pub(crate) fn from_content_type(content_type:Option<&str>) -> Result<Self, CallError> {
+		// parameters like `charset` don't change the encoding
+		let mime = content_type.map(|ty| ty.split(';').next().unwrap_or_default().trim());
+
+		match mime {
+			None | Some("application/octet-stream") => Ok(Self::Postcard),
+			Some("application/json") => Ok(Self::Json),
+			Some(other) => {
+				Err(CallError::Rejected(
+					StatusCode::UNSUPPORTED_MEDIA_TYPE,
+					format!("unsupported content type `{other}`"),
+				))
+			},
+		}
+	}
+
+	#[must_use]
+	pub fn content_type(self) -> &'static str {
+		match self {
+			Self::Postcard => "application/octet-stream",
+			Self::Json => "application/json",
+		}
+	}
+
+	/// Decodes the parameters of a call.
+	pub fn decode<P:DeserializeOwned>(self, bytes:&[u8]) -> Result<P, CallError> {
+		let res = match self {
+			Self::Postcard => postcard::from_bytes(bytes).map_err(FormatError::from),
+			Self::Json => serde_json::from_slice(bytes).map_err(FormatError::from),
+		};
+
+		res.map_err(CallError::Deserialize)
+	}
+
+	/// Encodes the result of a call.
+	pub fn encode<R:Serialize>(self, val:&R) -> Result<Vec<u8>, CallError> {
+		let res = match self {
+			Self::Postcard => postcard::to_allocvec(val).map_err(anyhow::Error::from),
+			Self::Json => serde_json::to_vec(val).map_err(anyhow::Error::from),
+		};
+
+		res.map_err(CallError::Host)
+	}
+
+	/// Encodes the next item of a stream, or the end of the stream when
+	/// `item` is `None`.
+	///
+	/// Postcard sends an `Option<T>`, JSON an array with at most one element
+	/// since `null` would be ambiguous for streams of optional items.
+	pub(crate) fn encode_next(self, item:Option<Vec<u8>>) -> Vec<u8> {
+		match (self, item) {
+			(Self::Postcard, Some(item)) => [&[1], &item[..]].concat(),
+			(Self::Postcard, None) => vec![0],
+			(Self::Json, Some(item)) => [b"[", &item[..], b"]"].concat(),
+			(Self::Json, None) => b"[]".to_vec(),
+		}
+	}
+}
+
+/// Decodes a field with a default at the end of a record, taking the default
+/// when the input ended before the field.
+///
+/// Postcard doesn't mark where a record ends, records encoded before fields
+/// with defaults were appended to it just end early. Used by generated
+/// bindings.
+#[doc(hidden)]
+pub fn field_or_default<'de, D, T>(deserializer:D, default:fn() -> T) -> Result<T, D::Error>
+where
+	D: Deserializer<'de>,
+	T: Deserialize<'de>, {
+	match T::deserialize(deserializer) {
+		// the error type of a deserializer is opaque, but postcard's is the
+		// only one that runs out of input in the middle of a record
+		Err(err) if err.to_string() == postcard::Error::DeserializeUnexpectedEnd.to_string() => {
+			Ok(default())
+		},
+		res => res,
+	}
+}
@@ -0,0 +1,72 @@
+use std::{future::Future, pin::Pin};
+
+use crate::{CallError, Caller, Router};
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Middleware wrapping every call dispatched by a [`Router`].
+///
+/// A layer can inspect the call before passing it on through [`Next::run`],
+/// inspect the result afterwards or return an error without calling the
+/// function at all.
+///
+/// ```ignore
+/// struct Logger;
+///
+/// impl<T: Send + Sync + 'static> Layer<T> for Logger {
+///     fn call<'a>(&'a self, call: Call<'a, T>, next: Next<'a, T>) -> BoxFuture<'a, Result<Vec<u8>, CallError>> {
+///         Box::pin(async move {
+///             let method = call.method();
+///
+///             let res = next.run(call).await;
+///
+///             log::info!("{method} succeeded: {}", res.is_ok());
+///
+///             res
+///         })
+///     }
+/// }
+/// ```
+pub trait Layer<T>: Send + Sync + 'static {
+	fn call<'a>(&'a self, call:Call<'a, T>, next:Next<'a, T>) -> BoxFuture<'a, Result<Vec<u8>, CallError>>;
+}
+
+/// A call made by a webview.
+pub struct Call<'a, T> {
+	pub(crate) caller:Caller<T>,
+	pub(crate) module:Option<&'a str>,
+	pub(crate) method:&'a str,
+	pub(crate) params:&'a [u8],
+}
+
+impl<'a, T> Call<'a, T> {
+	#[must_use]
+	pub fn caller(&self) -> &Caller<T> { &self.caller }
+
+	#[must_use]
+	pub fn module(&self) -> Option<&'a str> { self.module }
+
+	#[must_use]
+	pub fn method(&self) -> &'a str { self.method }
+
+	/// The encoded parameters of the call.
+	#[must_use]
+	pub fn params(&self) -> &'a [u8] { self.params }
+}
+
+/// The remaining layers of the stack, followed by the function itself.
+pub struct Next<'a, T> {
+	pub(crate) router:&'a Router<T>,
+	pub(crate) layers:&'a [Box<dyn Layer<T>>],
+}
+
+impl<'a, T:Send + Sync + 'static> Next<'a, T> {
+	/// Passes the call on to the next layer, or calls the function when this
+	/// is the last layer.
+	pub fn run(self, call:Call<'a, T>) -> BoxFuture<'a, Result<Vec<u8>, CallError>> {
+		match self.layers.split_first() {
+			Some((layer, layers)) => layer.call(call, Next { router:self.router, layers }),
+			None => Box::pin(self.router.dispatch(call)),
+		}
+	}
+}
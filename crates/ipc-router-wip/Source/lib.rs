@@ -1,33 +1,112 @@
 #![allow(clippy::missing_panics_doc, clippy::missing_errors_doc)]
 
+mod batch;
+mod cancel;
+mod capability;
+mod deprecation;
+mod error;
+mod export;
+mod format;
+mod layer;
+mod origin;
+mod schema;
+mod stream;
+
 use std::{
 	collections::{HashMap, hash_map::Entry},
 	future::Future,
 	marker::PhantomData,
+	panic::AssertUnwindSafe,
 	pin::Pin,
 	sync::Arc,
 };
 
 pub use anyhow::Error;
-use futures_util::FutureExt;
+use batch::BATCH_MODULE;
+use cancel::{CALL_ID_HEADER, CANCEL_MODULE, Running};
+use capability::Capabilities;
+pub use capability::Scope;
+use deprecation::Deprecations;
+use error::ERROR_HEADER;
+pub use error::CallError;
+use export::{EXPORT_MODULE, PendingCalls};
+pub use export::{ExportError, call_export};
+pub use format::{Format, FormatError, field_or_default};
+use futures_util::{FutureExt, Stream};
+pub use layer::{BoxFuture, Call, Layer, Next};
+use schema::{SCHEMA_HEADER, Schemas};
 use serde::{Serialize, de::DeserializeOwned};
-use tauri::http::{Request, Response, StatusCode, header::CONTENT_TYPE};
+use stream::{STREAM_MODULE, Streams};
+pub use stream::BoxStream;
+use tauri::http::{
+	Method,
+	Request,
+	Response,
+	StatusCode,
+	header::{
+		ACCESS_CONTROL_ALLOW_HEADERS,
+		ACCESS_CONTROL_ALLOW_METHODS,
+		ACCESS_CONTROL_ALLOW_ORIGIN,
+		ACCESS_CONTROL_EXPOSE_HEADERS,
+		ALLOW,
+		CONTENT_TYPE,
+		HeaderValue,
+		VARY,
+	},
+};
+
+/// The default limit for the size of request bodies, 16 MiB.
+pub const DEFAULT_MAX_BODY_SIZE:usize = 16 * 1024 * 1024;
 
 type Definition<T> =
-	Box<dyn Fn(Caller<T>, &[u8]) -> anyhow::Result<CallResult> + Send + Sync + 'static>;
+	Box<dyn Fn(Caller<T>, &[u8]) -> Result<CallResult, CallError> + Send + Sync + 'static>;
+
+type Cleanup<T> = Box<dyn Fn(&T, &str) + Send + Sync + 'static>;
 
 enum CallResult {
 	Value(Vec<u8>),
-	Future(Pin<Box<dyn Future<Output = anyhow::Result<Vec<u8>>> + Send + 'static>>),
+	Future(Pin<Box<dyn Future<Output = Result<Vec<u8>, CallError>> + Send + 'static>>),
 }
 
 pub struct Caller<T> {
 	data:Arc<T>,
+	window:Arc<str>,
+	origin:Option<Arc<str>>,
+	format:Format,
+	/// The schema hashes sent with the call.
+	schema:Option<Arc<str>>,
+}
+
+impl<T> Clone for Caller<T> {
+	fn clone(&self) -> Self {
+		Self {
+			data:self.data.clone(),
+			window:self.window.clone(),
+			origin:self.origin.clone(),
+			format:self.format,
+			schema:self.schema.clone(),
+		}
+	}
 }
 
 impl<T> Caller<T> {
 	#[must_use]
 	pub fn data(&self) -> &T { &self.data }
+
+	/// The label of the webview that made the call.
+	///
+	/// Use [`tauri::Manager::get_webview`] to retrieve the webview itself.
+	#[must_use]
+	pub fn window_label(&self) -> &str { &self.window }
+
+	/// The origin of the page that made the call, taken from the `Origin`
+	/// header of the request.
+	#[must_use]
+	pub fn origin(&self) -> Option<&str> { self.origin.as_deref() }
+
+	/// The encoding of the parameters and the result of the call.
+	#[must_use]
+	pub fn format(&self) -> Format { self.format }
 }
 
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
@@ -42,6 +121,16 @@ pub struct Router<T> {
 	string2idx:HashMap<Arc<str>, usize>,
 	strings:Vec<Arc<str>>,
 	map:HashMap<ImportKey, Definition<T>>,
+	cleanup:Vec<Cleanup<T>>,
+	layers:Vec<Box<dyn Layer<T>>>,
+	capabilities:Capabilities,
+	deprecations:Deprecations,
+	schemas:Schemas,
+	origins:Vec<String>,
+	max_body_size:usize,
+	streams:Arc<Streams>,
+	exports:Arc<PendingCalls>,
+	running:Running,
 }
 
 impl<T> Router<T> {
@@ -52,9 +141,98 @@ impl<T> Router<T> {
 			string2idx:HashMap::new(),
 			strings:Vec::new(),
 			map:HashMap::new(),
+			cleanup:Vec::new(),
+			layers:Vec::new(),
+			capabilities:Capabilities::default(),
+			deprecations:Deprecations::default(),
+			schemas:Schemas::default(),
+			origins:Vec::new(),
+			max_body_size:DEFAULT_MAX_BODY_SIZE,
+			streams:Arc::default(),
+			exports:Arc::default(),
+			running:Running::default(),
 		}
 	}
 
+	/// Registers a callback that is invoked with the label of a webview
+	/// whenever it is destroyed or reloaded, so state owned by it can be
+	/// released.
+	///
+	/// Calls of the webview that are still running are cancelled at that
+	/// point as well.
+	pub fn on_window_cleanup<F>(&mut self, func:F)
+	where
+		F: Fn(&T, &str) + Send + Sync + 'static, {
+		self.cleanup.push(Box::new(func));
+	}
+
+	/// Adds a layer of middleware wrapping every call, including the calls
+	/// of builtin functions like the ones pulling items from streams.
+	///
+	/// Layers run in the order they were added, so the first layer sees a
+	/// call first and its result last.
+	pub fn layer(&mut self, layer:impl Layer<T>) { self.layers.push(Box::new(layer)); }
+
+	/// Requires callers of `module` to be granted `capability`, either for all
+	/// functions of the module or only for the function `name`.
+	///
+	/// Calls from webviews lacking the capability fail with
+	/// [`CallError::PermissionDenied`]. Functions without requirements can be
+	/// called by every webview.
+	pub fn require_capability(&mut self, module:&str, name:Option<&str>, capability:&str) {
+		self.capabilities.require(module, name, capability);
+	}
+
+	/// Grants `capability` to the webviews matching `scope`.
+	pub fn grant_capability(&mut self, capability:&str, scope:Scope) {
+		self.capabilities.grant(capability, scope);
+	}
+
+	/// Marks the function `name` of `module` as deprecated, the first call of
+	/// it logs a warning with `note`. Generated host bindings deprecate the
+	/// functions annotated with `@deprecated`.
+	pub fn deprecate(&mut self, module:&str, name:&str, note:&str) {
+		self.deprecations.deprecate(module, name, note);
+	}
+
+	/// Requires calls of the function `name` of `module` to be made by
+	/// bindings generated from the version of the function with the given
+	/// schema hash.
+	///
+	/// Calls of pages built from an incompatible version of the function fail
+	/// with [`CallError::SchemaMismatch`] rather than decoding garbage.
+	/// Calls that don't send a hash, like hand written `fetch` calls, are
+	/// accepted. Generated host bindings require the hash of each of their
+	/// functions.
+	pub fn require_schema(&mut self, module:&str, name:&str, hash:&str) {
+		self.schemas.require(module, name, hash);
+	}
+
+	/// Accepts calls from pages loaded from `origin`, such as
+	/// `https://example.com`.
+	///
+	/// Calls from the app's own pages and, in development, from the dev server
+	/// are always accepted, calls from all other origins are rejected.
+	pub fn allow_origin(&mut self, origin:impl Into<String>) { self.origins.push(origin.into()); }
+
+	/// Rejects requests with bodies larger than `bytes`, defaults to
+	/// [`DEFAULT_MAX_BODY_SIZE`].
+	pub fn max_body_size(&mut self, bytes:usize) { self.max_body_size = bytes; }
+
+	fn cleanup_window(&self, window:&str) {
+		log::debug!("cleaning up state of window {}", window);
+
+		for func in &self.cleanup {
+			func(&self.data, window);
+		}
+
+		self.streams.drop_window(window);
+
+		self.exports.drop_window(window);
+
+		self.running.drop_window(window);
+	}
+
 	pub fn define<F, P, R>(&mut self, module:&str, name:&str, func:F) -> anyhow::Result<()>
 	where
 		F: Fn(Caller<T>, P) -> anyhow::Result<R> + Send + Sync + 'static,
@@ -65,17 +243,21 @@ impl<T> Router<T> {
 		self.insert(
 			key,
 			Box::new(move |caller, params| {
-				let params = postcard::from_bytes(params)?;
+				let format = caller.format;
+
+				let params = format.decode(params)?;
 
 				let res = func(caller, params)?;
 
-				Ok(CallResult::Value(postcard::to_allocvec(&res).unwrap()))
+				Ok(CallResult::Value(format.encode(&res)?))
 			}),
 		)?;
 
 		Ok(())
 	}
 
+	/// Defines a function returning a future, which is dropped when the
+	/// webview cancels the call or navigates away before it completes.
 	pub fn define_async<F, P, R, RV>(
 		&mut self,
 		module:&str,
@@ -92,10 +274,84 @@ impl<T> Router<T> {
 		self.insert(
 			key,
 			Box::new(move |caller, params| {
-				let params = postcard::from_bytes(params)?;
+				let format = caller.format;
+
+				let params = format.decode(params)?;
+
+				let fut = func(caller, params).map(move |res| format.encode(&res?)).boxed();
+
+				Ok(CallResult::Future(fut))
+			}),
+		)?;
+
+		Ok(())
+	}
+
+	/// Defines a function returning a stream, the caller receives the id of
+	/// the stream and pulls its items one by one.
+	pub fn define_stream<F, P, S, R>(&mut self, module:&str, name:&str, func:F) -> anyhow::Result<()>
+	where
+		F: Fn(Caller<T>, P) -> anyhow::Result<S> + Send + Sync + 'static,
+		P: DeserializeOwned,
+		S: Stream<Item = R> + Send + 'static,
+		R: Serialize + Send + 'static, {
+		let key = self.import_key(Some(module), name);
+
+		let streams = self.streams.clone();
+
+		self.insert(
+			key,
+			Box::new(move |caller, params| {
+				let format = caller.format;
+
+				let params = format.decode(params)?;
+
+				let window = caller.window.clone();
+
+				let stream = func(caller, params)?;
+
+				let id = streams.insert(window, stream);
+
+				Ok(CallResult::Value(format.encode(&id)?))
+			}),
+		)?;
+
+		Ok(())
+	}
+
+	pub fn define_stream_async<F, P, Fut, S, R>(
+		&mut self,
+		module:&str,
+		name:&str,
+		func:F,
+	) -> anyhow::Result<()>
+	where
+		F: Fn(Caller<T>, P) -> Pin<Box<Fut>> + Send + Sync + 'static,
+		P: DeserializeOwned,
+		Fut: Future<Output = anyhow::Result<S>> + Send + 'static,
+		S: Stream<Item = R> + Send + 'static,
+		R: Serialize + Send + 'static, {
+		let key = self.import_key(Some(module), name);
+
+		let streams = self.streams.clone();
+
+		self.insert(
+			key,
+			Box::new(move |caller, params| {
+				let format = caller.format;
+
+				let params = format.decode(params)?;
+
+				let window = caller.window.clone();
+
+				let streams = streams.clone();
 
 				let fut = func(caller, params)
-					.map(|res| postcard::to_allocvec(&res?).map_err(Into::into))
+					.map(move |res| {
+						let id = streams.insert(window, res?);
+
+						format.encode(&id)
+					})
 					.boxed();
 
 				Ok(CallResult::Future(fut))
@@ -105,16 +361,72 @@ impl<T> Router<T> {
 		Ok(())
 	}
 
-	async fn call(&self, module:Option<&str>, name:&str, params:&[u8]) -> anyhow::Result<Vec<u8>> {
-		let key = self.import_key_read_only(module, name)?;
+	/// Calls the function through all layers, panics of the host function
+	/// or a layer are caught and returned as [`CallError::Panic`].
+	async fn call(
+		&self,
+		caller:Caller<T>,
+		module:Option<&str>,
+		method:&str,
+		params:&[u8],
+	) -> Result<Vec<u8>, CallError>
+	where
+		T: Send + Sync + 'static, {
+		let call = Call { caller, module, method, params };
+
+		let next = Next { router:self, layers:&self.layers };
+
+		// layers may panic before returning their future, so the stack only
+		// runs once polled
+		AssertUnwindSafe(async move { next.run(call).await })
+			.catch_unwind()
+			.await
+			.unwrap_or_else(|payload| Err(CallError::from_panic(payload)))
+	}
+
+	/// Calls the function after the call passed through all layers.
+	pub(crate) async fn dispatch(&self, call:Call<'_, T>) -> Result<Vec<u8>, CallError>
+	where
+		T: Send + Sync + 'static, {
+		let Call { caller, module, method:name, params } = call;
+
+		if module == Some(STREAM_MODULE) {
+			return self.streams.call(&caller.window, caller.format, name, params).await;
+		}
+
+		if module == Some(EXPORT_MODULE) {
+			return self.exports.call(&caller.window, caller.format, name, params);
+		}
+
+		if module == Some(BATCH_MODULE) {
+			return self.call_batch(&caller, name, params).await;
+		}
+
+		if module == Some(CANCEL_MODULE) {
+			return self.running.call(&caller.window, caller.format, name, params);
+		}
+
+		let not_found = || {
+			CallError::NotFound(module.map_or(name.to_string(), |module| format!("{module}/{name}")))
+		};
+
+		let key = self.import_key_read_only(module, name).ok_or_else(not_found)?;
 
-		let handler = self.map.get(&key).ok_or(anyhow::anyhow!("method not found"))?;
+		let handler = self.map.get(&key).ok_or_else(not_found)?;
 
-		let caller = Caller { data:self.data.clone() };
+		if let Some(module) = module {
+			self.schemas.check(module, name, &caller)?;
+
+			self.capabilities.check(module, name, &caller)?;
+
+			if let Some(warning) = self.deprecations.warning(module, name) {
+				log::warn!("{}", warning);
+			}
+		}
 
 		match handler(caller, params)? {
 			CallResult::Value(val) => Ok(val),
-			CallResult::Future(fut) => Ok(fut.await?),
+			CallResult::Future(fut) => fut.await,
 		}
 	}
 
@@ -145,16 +457,16 @@ impl<T> Router<T> {
 		}
 	}
 
-	fn import_key_read_only(&self, module:Option<&str>, name:&str) -> anyhow::Result<ImportKey> {
+	fn import_key_read_only(&self, module:Option<&str>, name:&str) -> Option<ImportKey> {
 		let module = if let Some(module) = module {
-			*self.string2idx.get(module).ok_or(anyhow::anyhow!("unknown module"))?
+			*self.string2idx.get(module)?
 		} else {
 			usize::MAX
 		};
 
-		let name = *self.string2idx.get(name).ok_or(anyhow::anyhow!("unknown function"))?;
+		let name = *self.string2idx.get(name)?;
 
-		Ok(ImportKey { module, name })
+		Some(ImportKey { module, name })
 	}
 
 	fn intern_str(&mut self, string:&str) -> usize {
@@ -183,54 +495,1053 @@ impl<R:tauri::Runtime> BuilderExt for tauri::Builder<R> {
 	fn ipc_router<U:Send + Sync + 'static>(self, router:Router<U>) -> Self {
 		let router = Arc::new(router);
 
-		self.register_asynchronous_uri_scheme_protocol("ipc", move |_app, req, responder| {
-			let router = router.clone();
-
-			tauri::async_runtime::spawn(async move {
-				let mut response = match uri_scheme_inner(&router, req).await {
-					Ok(res) => res,
-					Err(err) => {
-						Response::builder()
-							.status(StatusCode::BAD_REQUEST)
-							.body(err.to_string().into_bytes())
-							.unwrap()
-					},
-				};
+		self.plugin(router_plugin(router.clone())).register_asynchronous_uri_scheme_protocol(
+			"ipc",
+			move |ctx, req, responder| {
+				let router = router.clone();
+
+				let window:Arc<str> = ctx.webview_label().into();
+
+				let origin = origin::request_origin(ctx.app_handle(), &window, &req);
+
+				// responses for rejected origins don't carry CORS headers, so the
+				// webview can't read them either
+				let allowed_origin = origin
+					.as_deref()
+					.filter(|origin| origin::is_allowed(ctx.app_handle(), &router.origins, origin))
+					.and_then(|origin| HeaderValue::from_str(origin).ok());
+
+				tauri::async_runtime::spawn(async move {
+					let response = handle_request(&router, window, origin, allowed_origin, req).await;
+
+					responder.respond(response);
+				});
+			},
+		)
+	}
+}
 
-				response.headers_mut().insert(
-					tauri::http::header::ACCESS_CONTROL_ALLOW_ORIGIN,
-					tauri::http::header::HeaderValue::from_static("*"),
-				);
+/// Runs the cleanup callbacks of the router when a window is destroyed or one
+/// of its webviews starts loading a new page.
+///
+/// The calls waiting for replies of webviews are managed as app state, so
+/// [`call_export`] can reach them through the webview it calls.
+fn router_plugin<R:tauri::Runtime, U:Send + Sync + 'static>(
+	router:Arc<Router<U>>,
+) -> tauri::plugin::TauriPlugin<R> {
+	let router2 = router.clone();
 
-				responder.respond(response);
-			});
+	let exports = router.exports.clone();
+
+	tauri::plugin::Builder::new("ipc-router")
+		.setup(move |app, _api| {
+			tauri::Manager::manage(app, exports);
+
+			Ok(())
+		})
+		.on_page_load(move |webview, payload| {
+			if matches!(payload.event(), tauri::webview::PageLoadEvent::Started) {
+				router.cleanup_window(webview.label());
+			}
+		})
+		.on_event(move |_app, event| {
+			if let tauri::RunEvent::WindowEvent { label, event: tauri::WindowEvent::Destroyed, .. } =
+				event
+			{
+				router2.cleanup_window(label);
+			}
 		})
+		.build()
+}
+
+/// Answers a request of `window`, `allowed_origin` is the origin of the page
+/// if calls from it are accepted.
+async fn handle_request<T:Send + Sync + 'static>(
+	router:&Router<T>,
+	window:Arc<str>,
+	origin:Option<String>,
+	allowed_origin:Option<HeaderValue>,
+	request:Request<Vec<u8>>,
+) -> Response<Vec<u8>> {
+	let res = if allowed_origin.is_some() {
+		uri_scheme_inner(router, window, origin.map(Into::into), request).await
+	} else {
+		Err(CallError::PermissionDenied(format!(
+			"calls from origin {} are not allowed",
+			origin.as_deref().unwrap_or("<unknown>")
+		)))
+	};
+
+	let mut response = match res {
+		Ok(res) => res,
+		Err(err) => {
+			log::debug!("ipc call failed: {}", err);
+
+			err.into_response()
+		},
+	};
+
+	let headers = response.headers_mut();
+
+	headers.insert(VARY, HeaderValue::from_static("Origin"));
+
+	if let Some(origin) = allowed_origin {
+		headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+
+		headers.insert(ACCESS_CONTROL_EXPOSE_HEADERS, HeaderValue::from_static(ERROR_HEADER));
 	}
+
+	response
 }
 
 #[inline]
-async fn uri_scheme_inner<T>(
+async fn uri_scheme_inner<T:Send + Sync + 'static>(
 	router:&Router<T>,
+	window:Arc<str>,
+	origin:Option<Arc<str>>,
 	request:Request<Vec<u8>>,
-) -> anyhow::Result<Response<Vec<u8>>> {
-	let path = request.uri().path().strip_prefix('/').unwrap();
+) -> Result<Response<Vec<u8>>, CallError> {
+	// the webview sends a preflight request before the first call
+	if request.method() == Method::OPTIONS {
+		return Ok(Response::builder()
+			.status(StatusCode::NO_CONTENT)
+			.header(ACCESS_CONTROL_ALLOW_METHODS, HeaderValue::from_static("POST"))
+			.header(
+				ACCESS_CONTROL_ALLOW_HEADERS,
+				HeaderValue::from_static("Content-Type, Tauri-Bindgen-Call-Id, Tauri-Bindgen-Schema"),
+			)
+			.body(Vec::new())
+			.unwrap());
+	}
+
+	if request.method() != Method::POST {
+		let mut response = CallError::Rejected(
+			StatusCode::METHOD_NOT_ALLOWED,
+			format!("method {} is not allowed, calls must use POST", request.method()),
+		)
+		.into_response();
+
+		response.headers_mut().insert(ALLOW, HeaderValue::from_static("POST"));
+
+		return Ok(response);
+	}
+
+	if request.body().len() > router.max_body_size {
+		return Err(CallError::Rejected(
+			StatusCode::PAYLOAD_TOO_LARGE,
+			format!("request bodies are limited to {} bytes", router.max_body_size),
+		));
+	}
+
+	let format = Format::from_content_type(
+		request.headers().get(CONTENT_TYPE).and_then(|ty| ty.to_str().ok()),
+	)?;
+
+	let path = request.uri().path();
+
+	let malformed = || {
+		CallError::Rejected(StatusCode::BAD_REQUEST, format!("malformed call path `{path}`"))
+	};
+
+	let path = path.strip_prefix('/').ok_or_else(malformed)?;
 
 	let (module, method) = path
 		.split_once('/')
 		.map_or((None, path), |(module, method)| (Some(module), method));
 
+	if method.is_empty() || module.is_some_and(str::is_empty) {
+		return Err(malformed());
+	}
+
 	log::debug!("ipc request for {:?}::{}", module, method);
 
-	let response = router.call(module, method, request.body()).await?;
+	// calls carrying an id can be cancelled by the webview
+	let id = request
+		.headers()
+		.get(CALL_ID_HEADER)
+		.and_then(|id| id.to_str().ok()?.parse::<u32>().ok());
+
+	let schema = request
+		.headers()
+		.get(SCHEMA_HEADER)
+		.and_then(|schema| schema.to_str().ok())
+		.map(Into::into);
+
+	let caller = Caller { data:router.data.clone(), window:window.clone(), origin, format, schema };
+
+	let call = router.call(caller, module, method, request.body());
+
+	let response = router.running.run(window, id, call).await?;
 
 	log::debug!("call result {:?}", response);
 
 	let mut resp = Response::builder().status(StatusCode::OK);
 
-	resp.headers_mut().unwrap().insert(
-		CONTENT_TYPE,
-		tauri::http::header::HeaderValue::from_static("application/octet-stream"),
-	);
+	resp.headers_mut()
+		.unwrap()
+		.insert(CONTENT_TYPE, HeaderValue::from_static(format.content_type()));
+
+	Ok(resp.body(response).unwrap())
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn request(path:&str) -> tauri::http::request::Builder {
+		Request::builder().method(Method::POST).uri(format!("ipc://localhost/{path}"))
+	}
+
+	/// Sends the request through the protocol handler as the webview `window`
+	/// showing a page of `origin`.
+	async fn send<T:Send + Sync + 'static>(
+		router:&Router<T>,
+		window:&str,
+		origin:Option<&str>,
+		request:Request<Vec<u8>>,
+	) -> Response<Vec<u8>> {
+		uri_scheme_inner(router, window.into(), origin.map(Into::into), request)
+			.await
+			.unwrap_or_else(CallError::into_response)
+	}
+
+	/// Calls `path` without parameters.
+	async fn call<T:Send + Sync + 'static>(
+		router:&Router<T>,
+		window:&str,
+		path:&str,
+	) -> Response<Vec<u8>> {
+		send(router, window, None, request(path).body(Vec::new()).unwrap()).await
+	}
+
+	fn error_kind(response:&Response<Vec<u8>>) -> Option<&str> {
+		response.headers().get(ERROR_HEADER).and_then(|kind| kind.to_str().ok())
+	}
+
+	fn router() -> Router<()> {
+		let mut router = Router::new(());
+
+		router.define("files", "read", |_, ():()| Ok(1u32)).unwrap();
+
+		router.define("files", "write", |_, ():()| Ok(2u32)).unwrap();
+
+		router.define("net", "fetch", |_, ():()| Ok(3u32)).unwrap();
+
+		router
+	}
+
+	#[tokio::test]
+	async fn errors() {
+		let mut router = router();
+
+		router
+			.define("files", "fail", |_, ():()| -> anyhow::Result<()> { anyhow::bail!("disk full") })
+			.unwrap();
+
+		router.define("files", "panic", |_, ():()| -> anyhow::Result<()> { panic!("oops") }).unwrap();
+
+		router.define("files", "open", |_, path:String| Ok(path.len() as u32)).unwrap();
+
+		for (path, status, kind, message) in [
+			("files/delete", StatusCode::NOT_FOUND, "not-found", "unknown function `files/delete`"),
+			("fs/read", StatusCode::NOT_FOUND, "not-found", "unknown function `fs/read`"),
+			("files/fail", StatusCode::INTERNAL_SERVER_ERROR, "host", "disk full"),
+			("files/panic", StatusCode::INTERNAL_SERVER_ERROR, "panic", "the host panicked: oops"),
+			// the string parameter is missing
+			("files/open", StatusCode::BAD_REQUEST, "deserialize", "failed to deserialize"),
+		] {
+			let res = call(&router, "main", path).await;
+
+			assert_eq!(res.status(), status, "{path}");
+			assert_eq!(error_kind(&res), Some(kind), "{path}");
+			assert_eq!(res.headers()[CONTENT_TYPE], "text/plain; charset=utf-8");
+
+			let body = String::from_utf8_lossy(res.body());
+
+			assert!(body.starts_with(message), "{path}: {body}");
+		}
+
+		// the router keeps serving calls after a function panicked
+		let res = call(&router, "main", "files/read").await;
+
+		assert_eq!(res.status(), StatusCode::OK);
+		assert_eq!(error_kind(&res), None);
+		assert_eq!(res.body(), &[1]);
+	}
+
+	/// Logs the calls passing through it.
+	struct Record {
+		name:&'static str,
+		log:Arc<std::sync::Mutex<Vec<String>>>,
+	}
+
+	impl<T:Send + Sync + 'static> Layer<T> for Record {
+		fn call<'a>(
+			&'a self,
+			call:Call<'a, T>,
+			next:Next<'a, T>,
+		) -> BoxFuture<'a, Result<Vec<u8>, CallError>> {
+			Box::pin(async move {
+				let path = format!("{}/{}", call.module().unwrap_or_default(), call.method());
+
+				self.log.lock().unwrap().push(format!("{} before {path}", self.name));
+
+				let res = next.run(call).await;
+
+				self.log.lock().unwrap().push(format!("{} after {path}", self.name));
+
+				res
+			})
+		}
+	}
+
+	/// Rejects calls of functions named `write`.
+	struct ReadOnly;
+
+	impl<T:Send + Sync + 'static> Layer<T> for ReadOnly {
+		fn call<'a>(
+			&'a self,
+			call:Call<'a, T>,
+			next:Next<'a, T>,
+		) -> BoxFuture<'a, Result<Vec<u8>, CallError>> {
+			Box::pin(async move {
+				if call.method() == "write" {
+					return Err(CallError::PermissionDenied("read only".to_string()));
+				}
+
+				next.run(call).await
+			})
+		}
+	}
+
+	#[tokio::test]
+	async fn layers_run_in_order() {
+		let log = Arc::default();
+
+		let mut router = router();
+
+		router.layer(Record { name:"outer", log:Arc::clone(&log) });
+
+		router.layer(Record { name:"inner", log:Arc::clone(&log) });
+
+		assert_eq!(call(&router, "main", "files/read").await.status(), StatusCode::OK);
+
+		// builtin functions pass through the layers as well
+		let cancel = request("tauri_bindgen::cancel/call").body(vec![7]).unwrap();
+
+		assert_eq!(send(&router, "main", None, cancel).await.status(), StatusCode::OK);
+
+		assert_eq!(*log.lock().unwrap(), [
+			"outer before files/read",
+			"inner before files/read",
+			"inner after files/read",
+			"outer after files/read",
+			"outer before tauri_bindgen::cancel/call",
+			"inner before tauri_bindgen::cancel/call",
+			"inner after tauri_bindgen::cancel/call",
+			"outer after tauri_bindgen::cancel/call",
+		]);
+	}
+
+	#[tokio::test]
+	async fn layer_rejects_call() {
+		let mut router = Router::new(std::sync::atomic::AtomicU32::new(0));
+
+		router
+			.define("files", "write", |caller, ():()| {
+				Ok(caller.data().fetch_add(1, std::sync::atomic::Ordering::SeqCst))
+			})
+			.unwrap();
+
+		router.layer(ReadOnly);
+
+		let res = call(&router, "main", "files/write").await;
+
+		assert_eq!(res.status(), StatusCode::FORBIDDEN);
+		assert_eq!(error_kind(&res), Some("permission-denied"));
+		assert_eq!(res.body(), b"permission denied: read only");
+		assert_eq!(router.data.load(std::sync::atomic::Ordering::SeqCst), 0);
+	}
+
+	#[tokio::test]
+	async fn layer_panic() {
+		struct Panic;
+
+		impl<T:Send + Sync + 'static> Layer<T> for Panic {
+			fn call<'a>(
+				&'a self,
+				_call:Call<'a, T>,
+				_next:Next<'a, T>,
+			) -> BoxFuture<'a, Result<Vec<u8>, CallError>> {
+				panic!("layer failed")
+			}
+		}
+
+		let mut router = router();
+
+		router.layer(Panic);
+
+		let res = call(&router, "main", "files/read").await;
+
+		assert_eq!(error_kind(&res), Some("panic"));
+		assert_eq!(res.body(), b"the host panicked: layer failed");
+	}
+
+	#[test]
+	fn accepted_origins() {
+		let mut router = router();
+
+		router.allow_origin("https://example.com");
+
+		let dev_url = tauri::Url::parse("http://localhost:1420/index.html").unwrap();
+
+		for origin in [
+			"tauri://localhost",
+			"http://tauri.localhost",
+			"https://tauri.localhost",
+			"https://example.com",
+		] {
+			assert!(origin::accepts(&router.origins, None, origin), "{origin}");
+		}
+
+		for origin in [
+			"https://example.org",
+			"http://example.com",
+			"https://example.com:8443",
+			"tauri://localhost.evil.com",
+			"null",
+			"",
+		] {
+			assert!(!origin::accepts(&router.origins, None, origin), "{origin}");
+		}
+
+		// the dev server is only accepted in development
+		assert!(origin::accepts(&router.origins, Some(&dev_url), "http://localhost:1420"));
+		assert!(!origin::accepts(&router.origins, Some(&dev_url), "http://localhost:3000"));
+		assert!(!origin::accepts(&router.origins, None, "http://localhost:1420"));
+	}
+
+	/// Calls `path` from a page of `origin`, which passed the origin check if
+	/// `accepted`.
+	async fn call_from(
+		router:&Router<()>,
+		origin:Option<&str>,
+		accepted:bool,
+		path:&str,
+	) -> Response<Vec<u8>> {
+		let allowed_origin =
+			origin.filter(|_| accepted).map(|origin| HeaderValue::from_str(origin).unwrap());
+
+		let req = request(path).body(Vec::new()).unwrap();
+
+		handle_request(router, "main".into(), origin.map(Into::into), allowed_origin, req).await
+	}
 
-	Ok(resp.body(response)?)
+	#[tokio::test]
+	async fn cors_headers() {
+		let router = router();
+
+		let res = call_from(&router, Some("tauri://localhost"), true, "files/read").await;
+
+		assert_eq!(res.status(), StatusCode::OK);
+		assert_eq!(res.headers()[ACCESS_CONTROL_ALLOW_ORIGIN], "tauri://localhost");
+		assert_eq!(res.headers()[ACCESS_CONTROL_EXPOSE_HEADERS], ERROR_HEADER);
+		assert_eq!(res.headers()[VARY], "Origin");
+
+		// the page can read the errors of its calls
+		let res = call_from(&router, Some("tauri://localhost"), true, "files/delete").await;
+
+		assert_eq!(error_kind(&res), Some("not-found"));
+		assert_eq!(res.headers()[ACCESS_CONTROL_ALLOW_ORIGIN], "tauri://localhost");
+
+		// rejected origins never reach the function and get no CORS headers
+		let res = call_from(&router, Some("https://evil.com"), false, "files/read").await;
+
+		assert_eq!(res.status(), StatusCode::FORBIDDEN);
+		assert_eq!(error_kind(&res), Some("permission-denied"));
+		assert_eq!(res.headers()[VARY], "Origin");
+		assert!(!res.headers().contains_key(ACCESS_CONTROL_ALLOW_ORIGIN));
+		assert!(!res.headers().contains_key(ACCESS_CONTROL_EXPOSE_HEADERS));
+
+		let message = String::from_utf8_lossy(res.body());
+
+		assert_eq!(message, "permission denied: calls from origin https://evil.com are not allowed");
+
+		let res = call_from(&router, None, false, "files/read").await;
+
+		assert_eq!(error_kind(&res), Some("permission-denied"));
+		assert!(!res.headers().contains_key(ACCESS_CONTROL_ALLOW_ORIGIN));
+	}
+
+	#[tokio::test]
+	async fn methods() {
+		let router = router();
+
+		let preflight = Request::builder()
+			.method(Method::OPTIONS)
+			.uri("ipc://localhost/files/read")
+			.body(Vec::new())
+			.unwrap();
+
+		let res = send(&router, "main", None, preflight).await;
+
+		assert_eq!(res.status(), StatusCode::NO_CONTENT);
+		assert_eq!(res.headers()[ACCESS_CONTROL_ALLOW_METHODS], "POST");
+		let allowed_headers = res.headers()[ACCESS_CONTROL_ALLOW_HEADERS].to_str().unwrap();
+
+		assert!(allowed_headers.contains(CALL_ID_HEADER), "{allowed_headers}");
+		assert!(allowed_headers.contains(SCHEMA_HEADER), "{allowed_headers}");
+		assert!(res.body().is_empty());
+
+		for method in [Method::GET, Method::PUT, Method::DELETE] {
+			let req = Request::builder()
+				.method(&method)
+				.uri("ipc://localhost/files/read")
+				.body(Vec::new())
+				.unwrap();
+
+			let res = send(&router, "main", None, req).await;
+
+			assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED, "{method}");
+			assert_eq!(res.headers()[ALLOW], "POST");
+			assert_eq!(error_kind(&res), Some("transport"));
+		}
+	}
+
+	#[tokio::test]
+	async fn body_size() {
+		let mut router = router();
+
+		router.define("files", "open", |_, path:String| Ok(path.len() as u32)).unwrap();
+
+		router.max_body_size(4);
+
+		// a string is sent as its length followed by its bytes
+		let open = |body:&[u8]| request("files/open").body(body.to_vec()).unwrap();
+
+		let res = send(&router, "main", None, open(b"\x03abc")).await;
+
+		assert_eq!(res.status(), StatusCode::OK);
+		assert_eq!(res.body(), &[3]);
+
+		let res = send(&router, "main", None, open(b"\x04abcd")).await;
+
+		assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+		assert_eq!(error_kind(&res), Some("transport"));
+	}
+
+	#[tokio::test]
+	async fn malformed_paths() {
+		let router = router();
+
+		for path in ["", "files/", "/read"] {
+			let res = call(&router, "main", path).await;
+
+			assert_eq!(res.status(), StatusCode::BAD_REQUEST, "{path}");
+			assert_eq!(error_kind(&res), Some("transport"), "{path}");
+		}
+
+		// functions are always called through their module
+		assert_eq!(error_kind(&call(&router, "main", "read").await), Some("not-found"));
+	}
+
+	type BatchResults = Vec<Result<Vec<u8>, (String, String)>>;
+
+	#[tokio::test]
+	async fn batch() {
+		let log = Arc::default();
+
+		let mut router = router();
+
+		router
+			.define("files", "fail", |_, ():()| -> anyhow::Result<()> { anyhow::bail!("disk full") })
+			.unwrap();
+
+		router.require_capability("net", None, "net");
+
+		router.layer(Record { name:"layer", log:Arc::clone(&log) });
+
+		let entries:&[(&str, &str, Vec<u8>)] = &[
+			("files", "read", vec![]),
+			("files", "delete", vec![]),
+			("net", "fetch", vec![]),
+			("files", "fail", vec![]),
+			("files", "write", vec![]),
+		];
+
+		let body = postcard::to_allocvec(entries).unwrap();
+
+		let batch = |body:Vec<u8>| request("tauri_bindgen::batch/call").body(body).unwrap();
+
+		let res = send(&router, "main", None, batch(body)).await;
+
+		assert_eq!(res.status(), StatusCode::OK);
+
+		// failed calls don't fail the batch, each result is in the order of its call
+		let results:BatchResults = postcard::from_bytes(res.body()).unwrap();
+
+		assert_eq!(results, [
+			Ok(vec![1]),
+			Err(("not-found".to_string(), "unknown function `files/delete`".to_string())),
+			Err((
+				"permission-denied".to_string(),
+				"permission denied: `net/fetch` requires the `net` capability".to_string()
+			)),
+			Err(("host".to_string(), "disk full".to_string())),
+			Ok(vec![2]),
+		]);
+
+		// every call passes through the layers on its own
+		let log = log.lock().unwrap();
+
+		assert_eq!(log.first().unwrap(), "layer before tauri_bindgen::batch/call");
+		assert_eq!(log.last().unwrap(), "layer after tauri_bindgen::batch/call");
+		assert_eq!(log.len(), 12);
+		assert!(log.contains(&"layer before net/fetch".to_string()));
+	}
+
+	#[tokio::test]
+	async fn batch_json() {
+		let router = router();
+
+		let body = br#"[["files", "read", null], ["files", "delete", null]]"#.to_vec();
+
+		let req = request("tauri_bindgen::batch/call")
+			.header(CONTENT_TYPE, "application/json")
+			.body(body)
+			.unwrap();
+
+		let res = send(&router, "main", None, req).await;
+
+		assert_eq!(res.status(), StatusCode::OK);
+		assert_eq!(res.headers()[CONTENT_TYPE], "application/json");
+
+		// results are embedded as is rather than as bytes
+		let results:serde_json::Value = serde_json::from_slice(res.body()).unwrap();
+
+		assert_eq!(
+			results,
+			serde_json::json!([
+				{ "Ok": 1 },
+				{ "Err": ["not-found", "unknown function `files/delete`"] },
+			])
+		);
+	}
+
+	#[tokio::test]
+	async fn batch_rejected() {
+		let router = router();
+
+		let nested = postcard::to_allocvec(&vec![("tauri_bindgen::batch", "call", vec![0u8])]).unwrap();
+
+		let batch = |body:Vec<u8>| request("tauri_bindgen::batch/call").body(body).unwrap();
+
+		let res = send(&router, "main", None, batch(nested)).await;
+
+		assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+		assert_eq!(error_kind(&res), Some("transport"));
+
+		let res = call(&router, "main", "tauri_bindgen::batch/run").await;
+
+		assert_eq!(error_kind(&res), Some("not-found"));
+
+		// the entries can't be decoded
+		let res = send(&router, "main", None, batch(vec![3])).await;
+
+		assert_eq!(error_kind(&res), Some("deserialize"));
+	}
+
+	/// A router with a function `files/watch` that returns once notified.
+	fn watch_router(notify:Arc<tokio::sync::Notify>) -> Router<()> {
+		let mut router = router();
+
+		router
+			.define_async("files", "watch", move |_, ():()| {
+				let notify = notify.clone();
+
+				Box::pin(async move {
+					notify.notified().await;
+
+					Ok(4u32)
+				})
+			})
+			.unwrap();
+
+		router
+	}
+
+	fn watch(id:u32) -> Request<Vec<u8>> {
+		request("files/watch").header(CALL_ID_HEADER, id.to_string()).body(Vec::new()).unwrap()
+	}
+
+	fn cancel(id:u32) -> Request<Vec<u8>> {
+		request("tauri_bindgen::cancel/call").body(postcard::to_allocvec(&id).unwrap()).unwrap()
+	}
+
+	#[tokio::test]
+	async fn cancel_by_id() {
+		let router = watch_router(Arc::default());
+
+		let (res, ()) = tokio::join!(send(&router, "main", None, watch(7)), async {
+			tokio::task::yield_now().await;
+
+			let res = send(&router, "main", None, cancel(7)).await;
+
+			assert_eq!(res.status(), StatusCode::OK);
+		});
+
+		assert_eq!(res.status().as_u16(), 499);
+		assert_eq!(error_kind(&res), Some("cancelled"));
+	}
+
+	#[tokio::test]
+	async fn cancel_other_call() {
+		let notify = Arc::default();
+
+		let router = watch_router(Arc::clone(&notify));
+
+		let (res, ()) = tokio::join!(send(&router, "main", None, watch(7)), async {
+			tokio::task::yield_now().await;
+
+			// calls can only be cancelled by the window that made them
+			send(&router, "other", None, cancel(7)).await;
+
+			send(&router, "main", None, cancel(8)).await;
+
+			// cancelling a call that completed already is fine
+			send(&router, "main", None, cancel(6)).await;
+
+			notify.notify_one();
+		});
+
+		assert_eq!(res.status(), StatusCode::OK);
+		assert_eq!(res.body(), &[4]);
+	}
+
+	#[tokio::test]
+	async fn cancel_on_cleanup() {
+		let router = watch_router(Arc::default());
+
+		let (res, other, ()) = tokio::join!(
+			send(&router, "main", None, watch(7)),
+			tokio::time::timeout(
+				std::time::Duration::from_millis(50),
+				send(&router, "other", None, watch(7))
+			),
+			async {
+				tokio::task::yield_now().await;
+
+				router.cleanup_window("main");
+			}
+		);
+
+		assert_eq!(error_kind(&res), Some("cancelled"));
+
+		// the call of the other window is still running
+		assert!(other.is_err());
+	}
+
+	#[tokio::test]
+	async fn formats() {
+		let mut router = router();
+
+		router.define("files", "open", |_, params:(String, bool)| Ok(params)).unwrap();
+
+		let open = |content_type:Option<&str>, body:&[u8]| {
+			let req = request("files/open");
+
+			let req = match content_type {
+				Some(content_type) => req.header(CONTENT_TYPE, content_type),
+				None => req,
+			};
+
+			req.body(body.to_vec()).unwrap()
+		};
+
+		let postcard = postcard::to_allocvec(&("a.txt", true)).unwrap();
+
+		let json = br#"["a.txt", true]"#;
+
+		for content_type in [None, Some("application/octet-stream")] {
+			let res = send(&router, "main", None, open(content_type, &postcard)).await;
+
+			assert_eq!(res.status(), StatusCode::OK);
+			assert_eq!(res.headers()[CONTENT_TYPE], "application/octet-stream");
+			assert_eq!(res.body(), &postcard);
+		}
+
+		for content_type in ["application/json", "application/json; charset=utf-8"] {
+			let res = send(&router, "main", None, open(Some(content_type), json)).await;
+
+			assert_eq!(res.status(), StatusCode::OK);
+			assert_eq!(res.headers()[CONTENT_TYPE], "application/json");
+			assert_eq!(res.body(), br#"["a.txt",true]"#);
+		}
+
+		// the parameters are decoded with the format of the request
+		let res = send(&router, "main", None, open(Some("application/json"), &postcard)).await;
+
+		assert_eq!(error_kind(&res), Some("deserialize"));
+
+		let res = send(&router, "main", None, open(None, json)).await;
+
+		assert_eq!(error_kind(&res), Some("deserialize"));
+
+		for content_type in ["text/plain", "application/x-www-form-urlencoded"] {
+			let res = send(&router, "main", None, open(Some(content_type), json)).await;
+
+			assert_eq!(res.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE, "{content_type}");
+			assert_eq!(error_kind(&res), Some("transport"));
+		}
+	}
+
+	#[tokio::test]
+	async fn schema() {
+		let mut router = router();
+
+		router.require_schema("files", "read", "1111");
+
+		let read = |schema:Option<&str>| {
+			let req = request("files/read");
+
+			let req = match schema {
+				Some(schema) => req.header(SCHEMA_HEADER, schema),
+				None => req,
+			};
+
+			req.body(Vec::new()).unwrap()
+		};
+
+		for schema in ["1111", "2222,1111", "2222, 1111"] {
+			let res = send(&router, "main", None, read(Some(schema))).await;
+
+			assert_eq!(res.status(), StatusCode::OK, "{schema}");
+		}
+
+		for sent in ["2222", "11"] {
+			let res = send(&router, "main", None, read(Some(sent))).await;
+
+			assert_eq!(res.status(), StatusCode::CONFLICT);
+			assert_eq!(error_kind(&res), Some("schema-mismatch"));
+
+			let message = String::from_utf8_lossy(res.body());
+
+			let expected = format!("expects bindings of schema 1111 but the page sent {sent}");
+
+			assert!(message.contains(&expected), "{message}");
+		}
+
+		// functions without a schema accept every call, also of other functions
+		// of the module
+		assert_eq!(call(&router, "main", "net/fetch").await.status(), StatusCode::OK);
+
+		let write = request("files/write").header(SCHEMA_HEADER, "2222").body(Vec::new()).unwrap();
+
+		assert_eq!(send(&router, "main", None, write).await.status(), StatusCode::OK);
+	}
+
+	#[tokio::test]
+	async fn schema_without_header() {
+		let mut router = router();
+
+		router.require_schema("files", "read", "1111");
+
+		// calls that weren't made by generated bindings don't send a hash
+		assert_eq!(call(&router, "main", "files/read").await.status(), StatusCode::OK);
+
+		let entries = vec![("files", "read", Vec::<u8>::new())];
+
+		let batch = request("tauri_bindgen::batch/call")
+			.body(postcard::to_allocvec(&entries).unwrap())
+			.unwrap();
+
+		let res = send(&router, "main", None, batch).await;
+
+		let results:BatchResults = postcard::from_bytes(res.body()).unwrap();
+
+		assert_eq!(results, [Ok(vec![1])]);
+	}
+
+	#[tokio::test]
+	async fn schema_of_batch() {
+		let mut router = router();
+
+		router.require_schema("files", "read", "1111");
+
+		router.require_schema("net", "fetch", "2222");
+
+		let entries = vec![("files", "read", Vec::<u8>::new()), ("net", "fetch", Vec::new())];
+
+		let batch = |schema:&str| {
+			request("tauri_bindgen::batch/call")
+				.header(SCHEMA_HEADER, schema)
+				.body(postcard::to_allocvec(&entries).unwrap())
+				.unwrap()
+		};
+
+		// the calls share the hashes sent with the batch
+		let res = send(&router, "main", None, batch("1111,2222")).await;
+
+		let results:BatchResults = postcard::from_bytes(res.body()).unwrap();
+
+		assert_eq!(results, [Ok(vec![1]), Ok(vec![3])]);
+
+		let res = send(&router, "main", None, batch("1111")).await;
+
+		assert_eq!(res.status(), StatusCode::OK);
+
+		let results:BatchResults = postcard::from_bytes(res.body()).unwrap();
+
+		assert_eq!(results[0], Ok(vec![1]));
+		assert!(matches!(&results[1], Err((kind, _)) if kind == "schema-mismatch"));
+	}
+
+	#[tokio::test]
+	async fn capability_of_module() {
+		let mut router = router();
+
+		router.require_capability("files", None, "fs");
+
+		router.grant_capability("fs", Scope::new().window("main"));
+
+		for path in ["files/read", "files/write"] {
+			let res = call(&router, "main", path).await;
+
+			assert_eq!(res.status(), StatusCode::OK, "{path}");
+
+			let res = call(&router, "other", path).await;
+
+			assert_eq!(res.status(), StatusCode::FORBIDDEN, "{path}");
+			assert_eq!(error_kind(&res), Some("permission-denied"));
+		}
+
+		// modules without requirements can be called by every window
+		assert_eq!(call(&router, "other", "net/fetch").await.status(), StatusCode::OK);
+	}
+
+	/// Collects the warnings logged while the tests run.
+	struct Warnings(std::sync::Mutex<Vec<String>>);
+
+	impl log::Log for Warnings {
+		fn enabled(&self, metadata:&log::Metadata) -> bool { metadata.level() <= log::Level::Warn }
+
+		fn log(&self, record:&log::Record) {
+			if self.enabled(record.metadata()) {
+				self.0.lock().unwrap().push(record.args().to_string());
+			}
+		}
+
+		fn flush(&self) {}
+	}
+
+	static WARNINGS:Warnings = Warnings(std::sync::Mutex::new(Vec::new()));
+
+	#[tokio::test]
+	async fn deprecation() {
+		let _ = log::set_logger(&WARNINGS);
+
+		log::set_max_level(log::LevelFilter::Warn);
+
+		let mut router = router();
+
+		router.deprecate("files", "read", "use `open` instead");
+
+		router.deprecate("files", "write", "");
+
+		for _ in 0..3 {
+			let res = call(&router, "main", "files/read").await;
+
+			assert_eq!(res.status(), StatusCode::OK);
+			assert_eq!(res.body(), &[1]);
+		}
+
+		call(&router, "main", "files/write").await;
+
+		call(&router, "other", "files/write").await;
+
+		call(&router, "main", "net/fetch").await;
+
+		let warnings = WARNINGS
+			.0
+			.lock()
+			.unwrap()
+			.iter()
+			.filter(|warning| warning.contains("deprecated"))
+			.cloned()
+			.collect::<Vec<_>>();
+
+		assert_eq!(
+			warnings,
+			[
+				"called deprecated function `files/read`: use `open` instead",
+				"called deprecated function `files/write`",
+			]
+		);
+	}
+
+	#[tokio::test]
+	async fn capability_of_function() {
+		let mut router = router();
+
+		router.require_capability("files", Some("write"), "fs:write");
+
+		router.grant_capability("fs:write", Scope::new().window("main"));
+
+		let res = call(&router, "main", "files/write").await;
+
+		assert_eq!(res.status(), StatusCode::OK);
+		assert_eq!(res.body(), &[2]);
+
+		let res = call(&router, "other", "files/write").await;
+
+		assert_eq!(error_kind(&res), Some("permission-denied"));
+		let message = String::from_utf8_lossy(res.body());
+
+		assert!(message.contains("`files/write` requires the `fs:write` capability"), "{message}");
+
+		// other functions of the module don't require the capability
+		assert_eq!(call(&router, "other", "files/read").await.status(), StatusCode::OK);
+	}
+
+	#[tokio::test]
+	async fn capabilities_add_up() {
+		let mut router = router();
+
+		router.require_capability("files", None, "fs");
+
+		router.require_capability("files", Some("write"), "fs:write");
+
+		router.grant_capability("fs", Scope::new());
+
+		router.grant_capability("fs:write", Scope::new().window("main"));
+
+		assert_eq!(call(&router, "other", "files/read").await.status(), StatusCode::OK);
+		assert_eq!(call(&router, "main", "files/write").await.status(), StatusCode::OK);
+		let res = call(&router, "other", "files/write").await;
+
+		assert_eq!(error_kind(&res), Some("permission-denied"));
+	}
+
+	#[tokio::test]
+	async fn capability_scoped_to_origin() {
+		let mut router = router();
+
+		router.require_capability("files", None, "fs");
+
+		router.grant_capability("fs", Scope::new().window("main").origin("tauri://localhost"));
+
+		let send_from = |window, origin| {
+			send(&router, window, origin, request("files/read").body(Vec::new()).unwrap())
+		};
+
+		assert_eq!(send_from("main", Some("tauri://localhost")).await.status(), StatusCode::OK);
+
+		for (window, origin) in
+			[("main", Some("https://example.com")), ("main", None), ("other", Some("tauri://localhost"))]
+		{
+			let res = send_from(window, origin).await;
+
+			assert_eq!(res.status(), StatusCode::FORBIDDEN, "{window} {origin:?}");
+		}
+	}
 }
@@ -0,0 +1,58 @@
+use tauri::{
+	AppHandle,
+	Manager,
+	Runtime,
+	Url,
+	http::{Request, header::ORIGIN},
+};
+
+/// The origins the app's own pages are served from, depending on the
+/// platform.
+const APP_ORIGINS:[&str; 3] = ["tauri://localhost", "http://tauri.localhost", "https://tauri.localhost"];
+
+/// Returns `scheme://host[:port]` of the url.
+///
+/// [`Url::origin`] can't be used since it treats urls with custom schemes
+/// like `tauri://localhost` as opaque.
+pub(crate) fn origin_of(url:&Url) -> String {
+	let host = url.host_str().unwrap_or_default();
+
+	match url.port() {
+		Some(port) => format!("{}://{host}:{port}", url.scheme()),
+		None => format!("{}://{host}", url.scheme()),
+	}
+}
+
+/// The origin of the page making the request.
+///
+/// This is the `Origin` header when present, otherwise the origin of the url
+/// the webview currently displays.
+pub(crate) fn request_origin<R:Runtime>(
+	app:&AppHandle<R>,
+	window:&str,
+	request:&Request<Vec<u8>>,
+) -> Option<String> {
+	if let Some(origin) = request.headers().get(ORIGIN) {
+		return origin.to_str().ok().map(ToString::to_string);
+	}
+
+	let url = app.get_webview_window(window)?.url().ok()?;
+
+	Some(origin_of(&url))
+}
+
+/// Whether calls from `origin` are accepted, which are the app's own pages,
+/// the dev server in development and the explicitly allowed origins.
+pub(crate) fn is_allowed<R:Runtime>(app:&AppHandle<R>, allowed:&[String], origin:&str) -> bool {
+	let dev_url = tauri::is_dev().then(|| app.config().build.dev_url.as_ref()).flatten();
+
+	accepts(allowed, dev_url, origin)
+}
+
+/// Checks `origin` against the app's origins, the `allowed` origins and the
+/// url of the dev server if running in development.
+pub(crate) fn accepts(allowed:&[String], dev_url:Option<&Url>, origin:&str) -> bool {
+	APP_ORIGINS.contains(&origin)
+		|| allowed.iter().any(|allowed| allowed == origin)
+		|| dev_url.is_some_and(|url| origin_of(url) == origin)
+}
@@ -0,0 +1,49 @@
+use std::collections::HashMap;
+
+use crate::{CallError, Caller};
+
+/// The request header carrying the schema hashes of the bindings that made
+/// the call, separated by commas.
+///
+/// Batches carry the hashes of all their calls.
+pub(crate) const SCHEMA_HEADER:&str = "Tauri-Bindgen-Schema";
+
+/// The schema hashes of the functions the bindings were generated from.
+#[derive(Default)]
+pub(crate) struct Schemas {
+	required:HashMap<(String, String), String>,
+}
+
+impl Schemas {
+	pub fn require(&mut self, module:&str, name:&str, hash:&str) {
+		self.required.insert((module.to_string(), name.to_string()), hash.to_string());
+	}
+
+	/// Checks that the caller was generated from the same version of the
+	/// function.
+	///
+	/// Calls without the header weren't made by generated bindings, e.g. by
+	/// hand written `fetch` calls, and are accepted.
+	pub fn check<T>(&self, module:&str, name:&str, caller:&Caller<T>) -> Result<(), CallError> {
+		if self.required.is_empty() {
+			return Ok(());
+		}
+
+		let Some(sent) = caller.schema.as_deref() else {
+			return Ok(());
+		};
+
+		let Some(expected) = self.required.get(&(module.to_string(), name.to_string())) else {
+			return Ok(());
+		};
+
+		if sent.split(',').any(|hash| hash.trim() == expected) {
+			return Ok(());
+		}
+
+		Err(CallError::SchemaMismatch(format!(
+			"`{module}/{name}` expects bindings of schema {expected} but the page sent {sent}, \
+			 regenerate the bindings and reload the page"
+		)))
+	}
+}
@@ -0,0 +1,112 @@
+use std::{
+	collections::{HashMap, hash_map::Entry},
+	pin::Pin,
+	sync::{
+		Arc,
+		Mutex,
+		atomic::{AtomicU32, Ordering},
+	},
+};
+
+use futures_util::{Stream, StreamExt};
+use serde::Serialize;
+
+use crate::{Format, error::CallError};
+
+/// The stream type returned by host functions with a `stream<T>` result.
+pub type BoxStream<T> = Pin<Box<dyn Stream<Item = T> + Send + 'static>>;
+
+/// The module of the builtin functions used to pull values from a stream.
+/// Module names of generated bindings never contain `::` outside of resources
+/// so this can't clash with user defined functions.
+pub(crate) const STREAM_MODULE:&str = "tauri_bindgen::stream";
+
+/// Encodes an item in the format of the request pulling it.
+type EncodeItem = Box<dyn FnOnce(Format) -> Result<Vec<u8>, CallError> + Send>;
+
+type SerializedStream = Arc<tokio::sync::Mutex<BoxStream<EncodeItem>>>;
+
+/// The open streams of all windows.
+///
+/// A function returning a stream only sends back the id of the stream, the
+/// webview then requests one item at a time through [`STREAM_MODULE`]`/next`
+/// until the stream ends or is cancelled through [`STREAM_MODULE`]`/cancel`.
+#[derive(Default)]
+pub(crate) struct Streams {
+	map:Mutex<HashMap<u32, (Arc<str>, SerializedStream)>>,
+	next_id:AtomicU32,
+}
+
+impl Streams {
+	pub fn insert<S, R>(&self, window:Arc<str>, stream:S) -> u32
+	where
+		S: Stream<Item = R> + Send + 'static,
+		R: Serialize + Send + 'static, {
+		let stream = stream
+			.map(|item| Box::new(move |format:Format| format.encode(&item)) as EncodeItem)
+			.boxed();
+
+		let mut map = self.map.lock().unwrap();
+
+		loop {
+			let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+
+			if let Entry::Vacant(entry) = map.entry(id) {
+				entry.insert((window, Arc::new(tokio::sync::Mutex::new(stream))));
+
+				return id;
+			}
+		}
+	}
+
+	pub async fn call(
+		&self,
+		window:&str,
+		format:Format,
+		name:&str,
+		params:&[u8],
+	) -> Result<Vec<u8>, CallError> {
+		let id:u32 = format.decode(params)?;
+
+		match name {
+			"next" => {
+				let stream = self.get(window, id)?;
+
+				let item = stream.lock().await.next().await;
+
+				match item {
+					Some(encode) => Ok(format.encode_next(Some(encode(format)?))),
+					None => {
+						self.map.lock().unwrap().remove(&id);
+
+						Ok(format.encode_next(None))
+					},
+				}
+			},
+			"cancel" => {
+				self.get(window, id)?;
+
+				self.map.lock().unwrap().remove(&id);
+
+				format.encode(&())
+			},
+			_ => Err(CallError::NotFound(format!("{STREAM_MODULE}/{name}"))),
+		}
+	}
+
+	pub fn drop_window(&self, window:&str) {
+		self.map.lock().unwrap().retain(|_, (owner, _)| &**owner != window);
+	}
+
+	fn get(&self, window:&str, id:u32) -> anyhow::Result<SerializedStream> {
+		let map = self.map.lock().unwrap();
+
+		let (owner, stream) = map.get(&id).ok_or(anyhow::anyhow!("stream {id} not found"))?;
+
+		if &**owner != window {
+			anyhow::bail!("stream {id} belongs to a different window");
+		}
+
+		Ok(stream.clone())
+	}
+}
@@ -22,7 +22,11 @@ where
 	let input = syn::parse_macro_input!(input as Opts<F, B>);
 
 	let iface =
-		wit_parser::parse_and_resolve_file(&input.file, |t| input.skip.contains(t)).unwrap();
+		wit_parser::parse_and_resolve_file_skipping(&input.file, &input.skip).unwrap();
+
+	if let Err(message) = input.builder.check(&iface) {
+		return Error::new(proc_macro2::Span::call_site(), message).to_compile_error().into();
+	}
 
 	let mut gen = input.builder.build(iface);
 
@@ -1,5 +1,5 @@
 use logos::Span;
-use miette::Diagnostic;
+use miette::{Diagnostic, NamedSource};
 
 use crate::{lex::Token, util::print_list};
 
@@ -91,6 +91,92 @@ pub enum Error {
 		#[label("type cannot refer to itself")]
 		location:Span,
 	},
+	/// Values of a stream are delivered one at a time after the function
+	/// returned, so streams can't be nested inside other types.
+	#[error("`stream` is only allowed as the result of a function.")]
+	#[diagnostic(code(wit_parser::misplaced_stream))]
+	MisplacedStream {
+		#[label("streams cannot be used here")]
+		location:Span,
+	},
+	/// Every resource has a `drop` endpoint that releases its handle, so no
+	/// method or static function can use that name.
+	#[error("`drop` is reserved for releasing resource handles.")]
+	#[diagnostic(code(wit_parser::reserved_method))]
+	ReservedMethod {
+		#[label("rename this function")]
+		location:Span,
+	},
+	/// Interfaces can't import from each other in a cycle, as the types of an
+	/// interface need to be resolved before other interfaces can `use` them.
+	#[error("interface imports form a cycle.")]
+	#[diagnostic(code(wit_parser::cyclic_use))]
+	CyclicUse {
+		#[label("this import leads back to the importing interface")]
+		location:Span,
+		#[help]
+		help:Option<String>,
+		/// The other imports of the cycle, which are in other files.
+		#[related]
+		imports:Vec<RelatedLocation>,
+	},
+	/// Each interface of a package must have a unique name, so `use` items can
+	/// refer to them unambiguously.
+	#[error("interface already defined in {previous}.")]
+	#[diagnostic(code(wit_parser::interface_already_defined))]
+	InterfaceAlreadyDefined {
+		#[label("cannot be defined twice")]
+		location:Span,
+		previous:String,
+		/// The earlier definition, which is in another file.
+		#[related]
+		definition:Vec<RelatedLocation>,
+	},
+	/// Every name passed to `skip` has to match a function or resource method,
+	/// so typos don't silently result in bindings being generated.
+	#[error("`{name}` does not match any function.")]
+	#[diagnostic(code(wit_parser::unmatched_skip))]
+	UnmatchedSkip {
+		#[label("no function or resource method of this interface matches")]
+		location:Span,
+		name:String,
+		#[help]
+		help:Option<String>,
+	},
+	/// Generic types have to be given exactly one argument for each of their
+	/// type parameters.
+	#[error("expected {expected} type arguments, but found {found}.")]
+	#[diagnostic(code(wit_parser::type_arguments))]
+	TypeArguments {
+		#[label("wrong number of type arguments")]
+		location:Span,
+		expected:usize,
+		found:usize,
+	},
+	/// Default values and constants have to be literals of their type.
+	#[error("invalid value.")]
+	#[diagnostic(code(wit_parser::invalid_value))]
+	InvalidValue {
+		#[label("expected a value of type `{expected}`")]
+		location:Span,
+		expected:String,
+	},
+	/// `@deprecated` is the one annotation the parser knows, generators turn
+	/// it into the deprecation markers of their language.
+	#[error("invalid `@deprecated` annotation.")]
+	#[diagnostic(code(wit_parser::invalid_deprecation))]
+	InvalidDeprecation {
+		#[label("expected no arguments or a message, like `@deprecated(\"use get_user instead\")`")]
+		location:Span,
+	},
+	/// `@capability` names a capability the host requires to call a function
+	/// or resource.
+	#[error("invalid `@capability` annotation.")]
+	#[diagnostic(code(wit_parser::invalid_capability))]
+	InvalidCapability {
+		#[label("expected the name of a capability, like `@capability(\"fs:read\")`")]
+		location:Span,
+	},
 	#[error("Unused variable")]
 	#[diagnostic(code(wit_parser::unused_type))]
 	UnusedType {
@@ -104,6 +190,30 @@ pub enum Error {
 	},
 }
 
+/// A location in another file of a package that an error refers to, carrying
+/// the source code of that file so both locations can be rendered.
+#[derive(Debug, thiserror::Error, Diagnostic)]
+#[error("{message}")]
+pub struct RelatedLocation {
+	#[source_code]
+	source_code:NamedSource,
+	#[label("{label}")]
+	location:Span,
+	message:String,
+	label:String,
+}
+
+impl RelatedLocation {
+	pub fn new(
+		source_code:NamedSource,
+		loc:impl Into<Span>,
+		message:impl Into<String>,
+		label:impl Into<String>,
+	) -> Self {
+		Self { source_code, location:loc.into(), message:message.into(), label:label.into() }
+	}
+}
+
 impl From<Vec<Error>> for Error {
 	fn from(errors:Vec<Error>) -> Self { Self::Multi { errors } }
 }
@@ -156,6 +266,62 @@ impl Error {
 		Self::RecursiveType { location:loc.into() }
 	}
 
+	pub fn misplaced_stream(loc:impl Into<Span>) -> Self {
+		Self::MisplacedStream { location:loc.into() }
+	}
+
+	pub fn reserved_method(loc:impl Into<Span>) -> Self {
+		Self::ReservedMethod { location:loc.into() }
+	}
+
+	pub fn cyclic_use(
+		loc:impl Into<Span>,
+		help:impl Into<String>,
+		imports:Vec<RelatedLocation>,
+	) -> Self {
+		Self::CyclicUse { location:loc.into(), help:Some(help.into()), imports }
+	}
+
+	pub fn interface_already_defined(
+		loc:impl Into<Span>,
+		previous:impl Into<String>,
+		definition:RelatedLocation,
+	) -> Self {
+		Self::InterfaceAlreadyDefined {
+			location:loc.into(),
+			previous:previous.into(),
+			definition:vec![definition],
+		}
+	}
+
+	pub fn unmatched_skip(loc:impl Into<Span>, name:impl Into<String>) -> Self {
+		Self::UnmatchedSkip { location:loc.into(), name:name.into(), help:None }
+	}
+
+	pub fn unmatched_skip_with_help(
+		loc:impl Into<Span>,
+		name:impl Into<String>,
+		help:impl Into<String>,
+	) -> Self {
+		Self::UnmatchedSkip { location:loc.into(), name:name.into(), help:Some(help.into()) }
+	}
+
+	pub fn type_arguments(loc:impl Into<Span>, expected:usize, found:usize) -> Self {
+		Self::TypeArguments { location:loc.into(), expected, found }
+	}
+
+	pub fn invalid_value(loc:impl Into<Span>, expected:impl Into<String>) -> Self {
+		Self::InvalidValue { location:loc.into(), expected:expected.into() }
+	}
+
+	pub fn invalid_deprecation(loc:impl Into<Span>) -> Self {
+		Self::InvalidDeprecation { location:loc.into() }
+	}
+
+	pub fn invalid_capability(loc:impl Into<Span>) -> Self {
+		Self::InvalidCapability { location:loc.into() }
+	}
+
 	pub fn unused_type(loc:impl Into<Span>) -> Self { Self::UnusedType { location:loc.into() } }
 
 	#[must_use]
@@ -0,0 +1,618 @@
+use logos::{Logos, Span};
+
+use crate::{
+	Result,
+	lex::Token,
+	parse::{
+		Annotation,
+		FromTokens,
+		Func,
+		FuncResult,
+		Interface,
+		InterfaceItem,
+		InterfaceItemInner,
+		MethodKind,
+		NamedTypeList,
+		Tokens,
+		Type,
+	},
+	util::detect_invalid_input,
+};
+
+const INDENT:&str = "  ";
+
+pub fn format(input:&str) -> Result<String> {
+	detect_invalid_input(input)?;
+
+	let mut lexer = Token::lexer(input);
+
+	let mut tokens = Vec::new();
+
+	while let Some(token) = lexer.next() {
+		tokens.push((token?, lexer.span()));
+	}
+
+	let comments = lexer.extras;
+
+	let iface = Interface::parse(&mut Tokens::new(input))?;
+
+	let mut printer = Printer {
+		source:input,
+		tokens,
+		comments,
+		pos:0,
+		comment:0,
+		last:0,
+		indent:0,
+		block_start:true,
+		out:String::new(),
+	};
+
+	printer.interface(&iface);
+
+	Ok(printer.out)
+}
+
+/// Prints the items of the AST on their own lines, moving through the tokens
+/// of the source alongside to find the comments between them.
+struct Printer<'a> {
+	source:&'a str,
+	tokens:Vec<(Token, Span)>,
+	comments:Vec<Span>,
+	/// The next token to print.
+	pos:usize,
+	/// The next comment to print.
+	comment:usize,
+	/// Where the last printed token or comment ends in the source.
+	last:usize,
+	indent:usize,
+	/// Whether nothing was printed since the last block was opened, blank
+	/// lines are dropped there.
+	block_start:bool,
+	out:String,
+}
+
+impl<'a> Printer<'a> {
+	fn interface(&mut self, iface:&Interface) {
+		self.docs(&iface.docs);
+
+		let header = format!("interface {} {{", self.ident(&iface.ident));
+
+		self.block(&header, |printer| {
+			for item in &iface.items {
+				printer.item(item);
+			}
+		});
+
+		// the parser stops at the end of the interface, whatever follows it is
+		// kept as it is
+		if let Some((_, span)) = self.tokens.get(self.pos) {
+			let rest = self.source[span.start..].trim_end();
+
+			self.blank_line(span.start);
+
+			self.out.push_str(rest);
+
+			self.out.push('\n');
+		} else {
+			self.comments_before(self.source.len());
+		}
+	}
+
+	fn item(&mut self, item:&InterfaceItem) {
+		self.docs(&item.docs);
+
+		self.annotations(&item.annotations);
+
+		let ident = print_ident(self.source, item);
+
+		match &item.inner {
+			InterfaceItemInner::Alias(ty) => {
+				self.line(&format!("type {ident} = {}", self.ty(ty)), false);
+			},
+			InterfaceItemInner::Record(fields) => {
+				self.block(&format!("record {ident} {{"), |printer| {
+					for field in fields {
+						printer.docs(&field.docs);
+
+						printer.annotations(&field.annotations);
+
+						let mut line = format!("{}: {}", printer.ident(&field.ident), printer.ty(&field.ty));
+
+						if let Some(value) = &field.default {
+							line.push_str(" = ");
+
+							line.push_str(&printer.source[value.span().clone()]);
+						}
+
+						printer.line(&line, true);
+					}
+				});
+			},
+			InterfaceItemInner::Flags(fields) => {
+				self.block(&format!("flags {ident} {{"), |printer| {
+					for field in fields {
+						printer.docs(&field.docs);
+
+						printer.line(printer.ident(&field.ident), true);
+					}
+				});
+			},
+			InterfaceItemInner::Variant(cases) => {
+				self.block(&format!("variant {ident} {{"), |printer| {
+					for case in cases {
+						printer.docs(&case.docs);
+
+						let line = match &case.ty {
+							Some(ty) => format!("{}({})", printer.ident(&case.ident), printer.ty(ty)),
+							None => printer.ident(&case.ident).to_string(),
+						};
+
+						printer.line(&line, true);
+					}
+				});
+			},
+			InterfaceItemInner::Enum(cases) => {
+				self.block(&format!("enum {ident} {{"), |printer| {
+					for case in cases {
+						printer.docs(&case.docs);
+
+						printer.line(printer.ident(&case.ident), true);
+					}
+				});
+			},
+			InterfaceItemInner::Union(cases) => {
+				self.block(&format!("union {ident} {{"), |printer| {
+					for case in cases {
+						printer.docs(&case.docs);
+
+						printer.line(&printer.ty(&case.ty), true);
+					}
+				});
+			},
+			InterfaceItemInner::Func(func) => {
+				self.line(&format!("func {ident}{}", self.func(func)), false);
+			},
+			InterfaceItemInner::Const(konst) => {
+				let value = &self.source[konst.value.span().clone()];
+
+				self.line(&format!("const {ident}: {} = {value}", self.ty(&konst.ty)), false);
+			},
+			InterfaceItemInner::Resource(methods) => {
+				self.block(&format!("resource {ident} {{"), |printer| {
+					for method in methods {
+						printer.docs(&method.docs);
+
+						printer.annotations(&method.annotations);
+
+						let line = match method.kind {
+							MethodKind::Constructor => {
+								format!("constructor{}", printer.params(&method.inner.params))
+							},
+							MethodKind::Static => {
+								format!(
+									"static func {}{}",
+									printer.ident(&method.ident),
+									printer.func(&method.inner)
+								)
+							},
+							MethodKind::Method => {
+								format!("func {}{}", printer.ident(&method.ident), printer.func(&method.inner))
+							},
+						};
+
+						printer.line(&line, false);
+					}
+				});
+			},
+			InterfaceItemInner::Use(names) => {
+				let names = names
+					.iter()
+					.map(|name| {
+						match &name.alias {
+							Some(alias) => format!("{} as {}", self.ident(&name.ident), self.ident(alias)),
+							None => self.ident(&name.ident).to_string(),
+						}
+					})
+					.collect::<Vec<_>>()
+					.join(", ");
+
+				self.line(&format!("use {ident}.{{{names}}}"), false);
+			},
+			InterfaceItemInner::Export(funcs) => {
+				self.block("export {", |printer| {
+					for func in funcs {
+						printer.docs(&func.docs);
+
+						printer.annotations(&func.annotations);
+
+						let line = format!("func {}{}", printer.ident(&func.ident), printer.func(&func.inner));
+
+						printer.line(&line, false);
+					}
+				});
+			},
+		}
+	}
+
+	/// Prints each annotation on a line of its own, keeping empty parentheses
+	/// if they were written.
+	fn annotations(&mut self, annotations:&[Annotation]) {
+		for annotation in annotations {
+			let mut line = format!("@{}", self.ident(&annotation.name));
+
+			if self.source[annotation.span.clone()].ends_with(')') {
+				let args = annotation
+					.args
+					.iter()
+					.map(|arg| {
+						let value = &self.source[arg.value.span().clone()];
+
+						match &arg.key {
+							Some(key) => format!("{} = {value}", self.ident(key)),
+							None => value.to_string(),
+						}
+					})
+					.collect::<Vec<_>>()
+					.join(", ");
+
+				line.push_str(&format!("({args})"));
+			}
+
+			self.line(&line, false);
+		}
+	}
+
+	fn docs(&mut self, docs:&[Span]) {
+		for span in docs {
+			self.line(self.source[span.clone()].trim_end(), false);
+		}
+	}
+
+	/// Prints `header`, the lines printed by `body` one level deeper and the
+	/// closing brace. Blocks without members or comments are closed right
+	/// away.
+	fn block(&mut self, header:&str, body:impl FnOnce(&mut Self)) {
+		let close = self.advance(header);
+
+		let close_start = self.tokens[close].1.start;
+
+		if self.comments.get(self.comment).map_or(true, |comment| comment.start > close_start)
+			&& self.tokens[close].0 == Token::RightBrace
+		{
+			self.line(&format!("{header}}}"), false);
+
+			return;
+		}
+
+		self.line(header, false);
+
+		self.indent += 1;
+
+		self.block_start = true;
+
+		body(self);
+
+		self.comments_before(self.tokens[self.pos].1.start);
+
+		self.indent -= 1;
+
+		self.block_start = true;
+
+		self.line("}", false);
+	}
+
+	/// Prints a line made of the tokens in `text`, followed by a comma if
+	/// `comma` is set. Comments on the same line in the source are kept at
+	/// the end of it.
+	fn line(&mut self, text:&str, comma:bool) {
+		let start = self.tokens[self.pos].1.start;
+
+		self.comments_before(start);
+
+		self.blank_line(start);
+
+		self.push_indent();
+
+		self.out.push_str(text);
+
+		self.pos = self.advance(text);
+
+		let mut end = self.tokens[self.pos - 1].1.end;
+
+		if comma {
+			self.out.push(',');
+
+			if let Some((Token::Comma, span)) = self.tokens.get(self.pos) {
+				end = span.end;
+
+				self.pos += 1;
+			}
+		}
+
+		// comments in between the tokens of the line are moved behind them,
+		// comments after them stay if they're on the same line in the source and
+		// no other tokens come first
+		while let Some(comment) = self.comments.get(self.comment).cloned() {
+			let next_token = self.tokens.get(self.pos).map_or(usize::MAX, |(_, span)| span.start);
+
+			if comment.start > end
+				&& (self.source[end..comment.start].contains('\n') || next_token < comment.start)
+			{
+				break;
+			}
+
+			self.out.push(' ');
+
+			self.out.push_str(&self.source[comment.clone()]);
+
+			end = end.max(comment.end);
+
+			self.comment += 1;
+		}
+
+		self.out.push('\n');
+
+		self.last = end;
+
+		self.block_start = false;
+	}
+
+	/// Prints the comments starting before `pos` on lines of their own.
+	fn comments_before(&mut self, pos:usize) {
+		while let Some(comment) = self.comments.get(self.comment).cloned() {
+			if comment.start >= pos {
+				break;
+			}
+
+			self.blank_line(comment.start);
+
+			self.push_indent();
+
+			self.out.push_str(&self.source[comment.clone()]);
+
+			self.out.push('\n');
+
+			self.last = comment.end;
+
+			self.comment += 1;
+
+			self.block_start = false;
+		}
+	}
+
+	/// Keeps a single blank line where the source has one or more.
+	fn blank_line(&mut self, pos:usize) {
+		if !self.block_start && self.source[self.last..pos].matches('\n').count() > 1 {
+			self.out.push('\n');
+		}
+	}
+
+	fn push_indent(&mut self) {
+		for _ in 0..self.indent {
+			self.out.push_str(INDENT);
+		}
+	}
+
+	/// Returns the position of the token after the tokens of `text`, skipping
+	/// the trailing commas of the source.
+	fn advance(&self, text:&str) -> usize {
+		let mut pos = self.pos;
+
+		for token in Token::lexer(text).flatten() {
+			while self.tokens[pos].0 == Token::Comma && token != Token::Comma {
+				pos += 1;
+			}
+
+			debug_assert_eq!(self.tokens[pos].0, token, "formatted tokens don't match the source");
+
+			pos += 1;
+		}
+
+		pos
+	}
+
+	fn ident(&self, span:&Span) -> &'a str { &self.source[span.clone()] }
+
+	fn func(&self, func:&Func) -> String { print_func(self.source, func) }
+
+	fn params(&self, params:&NamedTypeList) -> String { print_params(self.source, params) }
+
+	fn ty(&self, ty:&Type) -> String { print_ty(self.source, ty) }
+}
+
+/// Prints the parameters and results of a function the way they're written
+/// in WIT.
+pub(crate) fn print_func(source:&str, func:&Func) -> String {
+	let params = print_params(source, &func.params);
+
+	match &func.result {
+		Some(FuncResult::Anon(ty)) => format!("{params} -> {}", print_ty(source, ty)),
+		Some(FuncResult::Named(results)) => format!("{params} -> {}", print_params(source, results)),
+		None => params,
+	}
+}
+
+pub(crate) fn print_params(source:&str, params:&NamedTypeList) -> String {
+	let params = params
+		.iter()
+		.map(|param| format!("{}: {}", &source[param.ident.clone()], print_ty(source, &param.ty)))
+		.collect::<Vec<_>>()
+		.join(", ");
+
+	format!("({params})")
+}
+
+pub(crate) fn print_ty(source:&str, ty:&Type) -> String {
+	match ty {
+		Type::Bool => "bool".to_string(),
+		Type::U8 => "u8".to_string(),
+		Type::U16 => "u16".to_string(),
+		Type::U32 => "u32".to_string(),
+		Type::U64 => "u64".to_string(),
+		Type::U128 => "u128".to_string(),
+		Type::S8 => "s8".to_string(),
+		Type::S16 => "s16".to_string(),
+		Type::S32 => "s32".to_string(),
+		Type::S64 => "s64".to_string(),
+		Type::S128 => "s128".to_string(),
+		Type::Float32 => "float32".to_string(),
+		Type::Float64 => "float64".to_string(),
+		Type::Char => "char".to_string(),
+		Type::String => "string".to_string(),
+		Type::List(ty) => format!("list<{}>", print_ty(source, ty)),
+		Type::Tuple(types) => {
+			format!("tuple<{}>", types.iter().map(|ty| print_ty(source, ty)).collect::<Vec<_>>().join(", "))
+		},
+		Type::Option(ty) => format!("option<{}>", print_ty(source, ty)),
+		Type::Result { ok:None, err:None } => "result".to_string(),
+		Type::Result { ok:Some(ok), err:None } => format!("result<{}>", print_ty(source, ok)),
+		Type::Result { ok, err:Some(err) } => {
+			let ok = ok.as_ref().map_or("_".to_string(), |ok| print_ty(source, ok));
+
+			format!("result<{ok}, {}>", print_ty(source, err))
+		},
+		Type::Stream { ty, .. } => format!("stream<{}>", print_ty(source, ty)),
+		Type::Id(span) => source[span.clone()].to_string(),
+		Type::Generic { ident, args } => {
+			let args = args.iter().map(|ty| print_ty(source, ty)).collect::<Vec<_>>();
+
+			format!("{}<{}>", &source[ident.clone()], args.join(", "))
+		},
+	}
+}
+
+/// Prints the name of an item, along with its type parameters.
+pub(crate) fn print_ident(source:&str, item:&InterfaceItem) -> String {
+	let ident = &source[item.ident.clone()];
+
+	if item.params.is_empty() {
+		return ident.to_string();
+	}
+
+	let params = item.params.iter().map(|param| &source[param.clone()]).collect::<Vec<_>>();
+
+	format!("{ident}<{}>", params.join(", "))
+}
+
+#[cfg(test)]
+mod test {
+	use pretty_assertions::assert_eq;
+
+	use super::*;
+
+	#[test]
+	fn normalizes_layout() -> Result<()> {
+		let source = "interface   demo{
+  // grouped with the record
+  record point{x:u32,
+        /// The second coordinate
+    y:u32}
+
+
+
+	enum color { red, green, }   // trailing
+  func get(p:point,c:color,)->result<_,string>
+  resource counter{constructor(start:u32)
+  static func zero()->counter
+  }
+  record empty{ }
+}
+";
+
+		let expected = "interface demo {
+  // grouped with the record
+  record point {
+    x: u32,
+    /// The second coordinate
+    y: u32,
+  }
+
+  enum color {
+    red,
+    green,
+  } // trailing
+  func get(p: point, c: color) -> result<_, string>
+  resource counter {
+    constructor(start: u32)
+    static func zero() -> counter
+  }
+  record empty {}
+}
+";
+
+		assert_eq!(format(source)?, expected);
+
+		assert_eq!(format(expected)?, expected);
+
+		Ok(())
+	}
+
+	#[test]
+	fn keeps_comments() -> Result<()> {
+		let source = "// header
+interface demo {
+    /* before */ func a(x: /* inline */ u32)
+
+    // end of block
+}
+// footer
+";
+
+		let expected = "// header
+interface demo {
+  /* before */
+  func a(x: u32) /* inline */
+
+  // end of block
+}
+// footer
+";
+
+		assert_eq!(format(source)?, expected);
+
+		Ok(())
+	}
+
+	#[test]
+	fn annotations() -> Result<()> {
+		let source = "interface demo {
+  /// Fetches a user
+  @deprecated @since(1.2)
+  func get(id:u32)
+  record user { @rename(\"userName\") name: string,
+    @serde( default , tag = \"kind\" ) @custom() age: u8 }
+}
+";
+
+		let expected = "interface demo {
+  /// Fetches a user
+  @deprecated
+  @since(1.2)
+  func get(id: u32)
+  record user {
+    @rename(\"userName\")
+    name: string,
+    @serde(default, tag = \"kind\")
+    @custom()
+    age: u8,
+  }
+}
+";
+
+		assert_eq!(format(source)?, expected);
+
+		assert_eq!(format(expected)?, expected);
+
+		Ok(())
+	}
+
+	#[test]
+	fn idempotent() -> Result<()> {
+		let formatted = format(include_str!("test.wit"))?;
+
+		assert_eq!(format(&formatted)?, formatted);
+
+		Ok(())
+	}
+}
@@ -1,4 +1,4 @@
-use logos::{FilterResult, Lexer, Logos, Source};
+use logos::{FilterResult, Lexer, Logos, Skip, Source, Span};
 use miette::Diagnostic;
 
 #[derive(Debug, Clone, thiserror::Error, Diagnostic, PartialEq, Default)]
@@ -41,15 +41,27 @@ fn block_comment(lex:&mut Lexer<Token>) -> FilterResult<(), Error> {
 	FilterResult::Emit(())
 }
 
+/// Skips a regular comment, keeping its span as trivia in the extras of the
+/// lexer.
+fn trivia(lex:&mut Lexer<Token>) -> Skip {
+	lex.extras.push(lex.span());
+
+	Skip
+}
+
 #[derive(Logos, Debug, Copy, Clone, PartialEq, Eq, Hash)]
-#[logos(error = Error)]
+#[logos(error = Error, extras = Vec<Span>)]
 pub enum Token {
 	#[regex(r"[ \t\r\n\f]+", logos::skip)]
 	Whitespace,
-	#[regex("(//[^\r\n]*)", logos::skip)]
+	#[regex("(//[^\r\n]*)", trivia)]
 	Comment,
 	#[regex(r#"/\*"#, |lex| match block_comment(lex) {
-        FilterResult::Emit(()) => FilterResult::Skip,
+        FilterResult::Emit(()) => {
+            trivia(lex);
+
+            FilterResult::Skip
+        },
         v => v
     })]
 	BlockComment,
@@ -59,6 +71,10 @@ pub enum Token {
 	BlockDocComment,
 	#[regex("%?([a-zA-Z0-9_])+")]
 	Ident,
+	#[regex(r"-?[0-9]+(\.[0-9]+)?([eE][+-]?[0-9]+)?", priority = 3)]
+	Number,
+	#[regex(r#""([^"\\\r\n]|\\.)*""#)]
+	StringLiteral,
 
 	// operators
 	#[token("=")]
@@ -67,6 +83,8 @@ pub enum Token {
 	Comma,
 	#[token(":")]
 	Colon,
+	#[token(".")]
+	Period,
 	#[token("(")]
 	LeftParen,
 	#[token(")")]
@@ -85,6 +103,8 @@ pub enum Token {
 	RArrow,
 	#[token("_")]
 	Underscore,
+	#[token("@")]
+	At,
 
 	// keywords
 	#[token("type")]
@@ -139,24 +159,32 @@ pub enum Token {
 	Result,
 	#[token("list")]
 	List,
+	#[token("stream")]
+	Stream,
 	#[token("interface")]
 	Interface,
 	#[token("tuple")]
 	Tuple,
-
-	// reserved but currently unused
 	#[token("use")]
 	Use,
 	#[token("as")]
 	As,
-	#[token("from")]
-	From,
 	#[token("static")]
 	Static,
+	#[token("constructor")]
+	Constructor,
+	#[token("export")]
+	Export,
+	#[token("const")]
+	Const,
+
+	// reserved but currently unused
+	#[token("from")]
+	From,
 }
 
 impl Token {
-	pub const IFACE_ITEM_KEYWORD:[Token; 8] = [
+	pub const IFACE_ITEM_KEYWORD:[Token; 11] = [
 		Token::Enum,
 		Token::Flags,
 		Token::Func,
@@ -165,8 +193,11 @@ impl Token {
 		Token::Union,
 		Token::Variant,
 		Token::Resource,
+		Token::Use,
+		Token::Export,
+		Token::Const,
 	];
-	pub const TYPE_KEYWORD:[Token; 20] = [
+	pub const TYPE_KEYWORD:[Token; 21] = [
 		Token::U8,
 		Token::U16,
 		Token::U32,
@@ -185,6 +216,7 @@ impl Token {
 		Token::Option,
 		Token::Result,
 		Token::List,
+		Token::Stream,
 		Token::Tuple,
 		Token::Ident,
 	];
@@ -195,9 +227,12 @@ impl Token {
 			Token::Comment | Token::BlockComment => "a comment",
 			Token::DocComment | Token::BlockDocComment => "a doc comment",
 			Token::Ident => "an identifier",
+			Token::Number => "a number",
+			Token::StringLiteral => "a string",
 			Token::Equals => "'='",
 			Token::Comma => "','",
 			Token::Colon => "':'",
+			Token::Period => "'.'",
 			Token::LeftParen => "'('",
 			Token::RightParen => "')'",
 			Token::LeftBrace => "'{{'",
@@ -207,6 +242,7 @@ impl Token {
 			Token::Star => "'*'",
 			Token::RArrow => "'->'",
 			Token::Underscore => "'_'",
+			Token::At => "'@'",
 			Token::Type => "'type'",
 			Token::Resource => "'resource'",
 			Token::Func => "'func'",
@@ -233,12 +269,16 @@ impl Token {
 			Token::Option => "'option'",
 			Token::Result => "'result'",
 			Token::List => "'list'",
+			Token::Stream => "'stream'",
 			Token::Interface => "'interface'",
 			Token::Tuple => "'tuple'",
 			Token::Use => "'use'",
 			Token::As => "'as'",
 			Token::From => "'from'",
 			Token::Static => "'static'",
+			Token::Constructor => "'constructor'",
+			Token::Export => "'export'",
+			Token::Const => "'const'",
 		}
 	}
 }
@@ -262,6 +302,17 @@ mod test {
 		assert_eq!(lex.next(), None);
 	}
 
+	#[test]
+	fn comment_trivia() {
+		let mut lex = Token::lexer("foo // first\n/* second */ bar");
+
+		assert_eq!(lex.next(), Some(Ok(Token::Ident)));
+
+		assert_eq!(lex.next(), Some(Ok(Token::Ident)));
+
+		assert_eq!(lex.extras, vec![4..12, 13..25]);
+	}
+
 	#[test]
 	fn doc_comment() {
 		let mut lex = Token::lexer("/// this is a comment");
@@ -291,4 +342,25 @@ mod test {
 
 		assert_eq!(lex.next(), Some(Ok(Token::Ident)));
 	}
+
+	#[test]
+	fn literals() {
+		for source in ["5", "-12", "1.5", "2e10", "-0.5E-3"] {
+			let mut lex = Token::lexer(source);
+
+			assert_eq!(lex.next(), Some(Ok(Token::Number)), "{source}");
+
+			assert_eq!(lex.next(), None, "{source}");
+		}
+
+		let mut lex = Token::lexer("5th");
+
+		assert_eq!(lex.next(), Some(Ok(Token::Ident)));
+
+		let mut lex = Token::lexer(r#""a \"quoted\" string""#);
+
+		assert_eq!(lex.next(), Some(Ok(Token::StringLiteral)));
+
+		assert_eq!(lex.next(), None);
+	}
 }
@@ -1,74 +1,241 @@
 #![allow(clippy::missing_panics_doc, clippy::missing_errors_doc)]
 
 mod error;
+mod format;
 mod lex;
-mod parse;
+mod package;
+pub mod parse;
+pub mod symbols;
 pub mod typecheck;
 mod util;
 
-pub use error::Error;
+pub use error::{Error, RelatedLocation};
 pub(crate) type Result<T> = std::result::Result<T, error::Error>;
 
 pub type TypeDefId = Id<TypeDef>;
 pub type TypeDefArena = Arena<TypeDef>;
 
-use std::path::Path;
+use std::{collections::HashSet, fmt, path::Path};
 
 use id_arena::{Arena, Id};
-use logos::Logos;
 use miette::{ErrReport, IntoDiagnostic, NamedSource};
-use parse::FromTokens;
+use package::Package;
 use schemars::JsonSchema;
 use serde::{Serialize, ser::SerializeSeq};
 use typecheck::Resolver;
-use util::detect_invalid_input;
+use util::{IteratorExt, detect_invalid_input, find_similar, print_list};
 
 #[inline]
 pub fn parse_and_resolve_str(
 	input:impl AsRef<str>,
 	skip:impl Fn(&str) -> bool,
 ) -> miette::Result<Interface> {
-	let iface = parse_and_resolve(input.as_ref(), skip).map_err(|error:ErrReport| {
-		error.with_source_code(NamedSource::new("virtual file", input.as_ref().to_string()))
-	})?;
+	resolve_str(input.as_ref(), skip, None)
+}
 
-	Ok(iface)
+/// Like [`parse_and_resolve_str`], but takes the names of the functions to
+/// skip as a set and reports the names that don't match any function or
+/// resource method.
+#[inline]
+pub fn parse_and_resolve_str_skipping(
+	input:impl AsRef<str>,
+	skip:&HashSet<String>,
+) -> miette::Result<Interface> {
+	resolve_str(input.as_ref(), |name| skip.contains(name), Some(skip))
 }
 
 #[inline]
 pub fn parse_and_resolve_file(
 	path:impl AsRef<Path>,
 	skip:impl Fn(&str) -> bool,
+) -> miette::Result<Interface> {
+	resolve_file(path.as_ref(), skip, None)
+}
+
+/// Like [`parse_and_resolve_file`], but takes the names of the functions to
+/// skip as a set and reports the names that don't match any function or
+/// resource method.
+#[inline]
+pub fn parse_and_resolve_file_skipping(
+	path:impl AsRef<Path>,
+	skip:&HashSet<String>,
+) -> miette::Result<Interface> {
+	resolve_file(path.as_ref(), |name| skip.contains(name), Some(skip))
+}
+
+/// Like [`parse_and_resolve_str`], but resolves the `use` items of `input`
+/// with the `.wit` files next to `path`, as if `input` were the contents of
+/// the file at `path`.
+///
+/// This is how editors check documents with unsaved changes.
+#[inline]
+pub fn parse_and_resolve_str_at(
+	input:impl AsRef<str>,
+	path:impl AsRef<Path>,
+	skip:impl Fn(&str) -> bool,
 ) -> miette::Result<Interface> {
 	let path = path.as_ref();
 
-	let input = std::fs::read_to_string(path).into_diagnostic()?;
+	Package::load_reachable_from(path, input.as_ref().to_string(), skip)?.resolve_path(path)
+}
 
-	let iface = parse_and_resolve(&input, skip).map_err(|error:ErrReport| {
-		error.with_source_code(NamedSource::new(path.to_string_lossy(), input))
+/// Parses and resolves all `.wit` files in the given directory.
+///
+/// Interfaces may import types from other interfaces of the same directory
+/// through `use` items.
+#[inline]
+pub fn parse_and_resolve_package(
+	dir:impl AsRef<Path>,
+	skip:impl Fn(&str) -> bool,
+) -> miette::Result<Vec<Interface>> {
+	Package::load(dir.as_ref(), skip)?.resolve()
+}
+
+fn resolve_str(
+	input:&str,
+	skip:impl Fn(&str) -> bool,
+	checked:Option<&HashSet<String>>,
+) -> miette::Result<Interface> {
+	let iface = parse_and_resolve(input, skip, checked).map_err(|error:ErrReport| {
+		error.with_source_code(NamedSource::new("virtual file", input.to_string()))
 	})?;
 
 	Ok(iface)
 }
 
+/// Resolves the file at `path`, checking that every name in `checked` matched
+/// a function of it or of the files it imports from.
+fn resolve_file(
+	path:&Path,
+	skip:impl Fn(&str) -> bool,
+	checked:Option<&HashSet<String>>,
+) -> miette::Result<Interface> {
+	let input = std::fs::read_to_string(path).into_diagnostic()?;
+
+	let with_source_code = |error:ErrReport| {
+		error.with_source_code(NamedSource::new(path.to_string_lossy(), input.clone()))
+	};
+
+	let (iface, names) = parse_with_names(&input, &skip).map_err(with_source_code)?;
+
+	// interfaces importing types from other files are resolved together with
+	// the files of the package they import from
+	if iface.items.iter().any(|item| matches!(item.inner, parse::InterfaceItemInner::Use(_))) {
+		let package = Package::load_reachable(path, &skip)?;
+
+		if let Some(checked) = checked {
+			check_skipped(checked, package.names(), &iface.ident)
+				.map_err(|error| with_source_code(error.into()))?;
+		}
+
+		return package.resolve_path(path);
+	}
+
+	if let Some(checked) = checked {
+		check_skipped(checked, &names, &iface.ident).map_err(|error| with_source_code(error.into()))?;
+	}
+
+	let (resolver, rest_data) = Resolver::new(&input, iface);
+
+	let iface = resolver.resolve(rest_data).map_err(|error| with_source_code(error.into()))?;
+
+	Ok(iface)
+}
+
 #[inline]
-fn parse_and_resolve(input:&str, skip:impl Fn(&str) -> bool) -> miette::Result<Interface> {
-	let iface = parse(input, skip)?;
+fn parse_and_resolve(
+	input:&str,
+	skip:impl Fn(&str) -> bool,
+	checked:Option<&HashSet<String>>,
+) -> miette::Result<Interface> {
+	let (iface, names) = parse_with_names(input, skip)?;
+
+	if let Some(checked) = checked {
+		check_skipped(checked, &names, &iface.ident)?;
+	}
 
 	let (resolver, rest_data) = Resolver::new(input, iface);
 
 	resolver.resolve(rest_data).map_err(Into::into)
 }
 
+/// Parses the given input, removing all functions and resource methods for
+/// which `skip` returns true. Resource methods are addressed as
+/// `resource.method`.
 #[inline]
-pub fn parse(input:&str, _skip:impl Fn(&str) -> bool) -> miette::Result<parse::Interface> {
-	detect_invalid_input(input)?;
+pub fn parse(input:&str, skip:impl Fn(&str) -> bool) -> miette::Result<parse::Interface> {
+	let (iface, _) = parse_with_names(input, skip)?;
 
-	let mut tokens = lex::Token::lexer(input).spanned().peekable();
+	Ok(iface)
+}
 
-	let iface = parse::Interface::parse(&mut tokens)?;
+/// Formats a WIT document.
+///
+/// Every item, member and doc comment is put on a line of its own, indented by
+/// two spaces per level, and members are followed by a comma. Runs of blank
+/// lines are collapsed into one and regular comments are kept where they are.
+#[inline]
+pub fn format(input:&str) -> miette::Result<String> { Ok(format::format(input)?) }
 
-	Ok(iface)
+/// Like [`parse`], but also returns the names of all functions that could
+/// have been skipped.
+fn parse_with_names(
+	input:&str,
+	skip:impl Fn(&str) -> bool,
+) -> miette::Result<(parse::Interface, Vec<String>)> {
+	let (iface, mut errors) = parse_partial(input);
+
+	let mut iface = match iface {
+		Some(iface) if errors.is_empty() => iface,
+		_ if errors.len() == 1 => return Err(errors.remove(0).into()),
+		_ => return Err(Error::from(errors).into()),
+	};
+
+	let names = iface.skip(input, skip);
+
+	Ok((iface, names))
+}
+
+/// Parses the given input like [`parse`], but keeps going after syntax errors
+/// to report all of them.
+///
+/// Items with syntax errors are left out of the returned interface, which is
+/// only missing if the `interface` header itself is malformed.
+#[must_use]
+pub fn parse_partial(input:&str) -> (Option<parse::Interface>, Vec<Error>) {
+	if let Err(err) = detect_invalid_input(input) {
+		return (None, vec![err]);
+	}
+
+	parse::Interface::parse_recovering(input)
+}
+
+/// Checks that every name in `skip` is one of `names`, reporting the others
+/// at the name of the interface they were skipped from.
+fn check_skipped(skip:&HashSet<String>, names:&[String], iface_ident:&logos::Span) -> Result<()> {
+	let mut unmatched = skip.iter().filter(|name| !names.contains(name)).collect::<Vec<_>>();
+
+	// sort the names so errors are reported in a deterministic order
+	unmatched.sort();
+
+	unmatched
+		.into_iter()
+		.map(|name| {
+			let suggestions = find_similar(names, name);
+
+			if suggestions.is_empty() {
+				Err(Error::unmatched_skip(iface_ident.clone(), name))
+			} else {
+				Err(Error::unmatched_skip_with_help(
+					iface_ident.clone(),
+					name,
+					format!("Did you mean \"{}\"?", print_list(suggestions)),
+				))
+			}
+		})
+		.transponse_result::<Vec<_>, ()>()?;
+
+	Ok(())
 }
 
 pub enum Int {
@@ -88,6 +255,11 @@ pub struct Interface {
 	#[schemars(with = "Vec<TypeDef>")]
 	pub typedefs:Arena<TypeDef>,
 	pub functions:Vec<Function>,
+	/// Functions implemented by the webview that the host can call.
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub exports:Vec<Function>,
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub constants:Vec<Constant>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
@@ -109,6 +281,9 @@ pub enum Type {
 	Char,
 	String,
 	List(Box<Type>),
+	/// A stream of values pushed by the host, only valid as the result of a
+	/// function.
+	Stream(Box<Type>),
 	Tuple(Vec<Type>),
 	Option(Box<Type>),
 	Result {
@@ -118,15 +293,35 @@ pub enum Type {
 	#[serde(serialize_with = "serialize_id")]
 	#[schemars(with = "u32")]
 	Id(Id<TypeDef>),
+	/// A generic typedef with its type parameters replaced by `args`.
+	Generic {
+		#[serde(serialize_with = "serialize_id")]
+		#[schemars(with = "u32")]
+		id:Id<TypeDef>,
+		args:Vec<Type>,
+	},
+	/// A type parameter of the generic typedef it's used in.
+	Param(String),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
 pub struct TypeDef {
 	#[serde(skip_serializing_if = "String::is_empty")]
 	pub docs:String,
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub annotations:Vec<Annotation>,
 	pub ident:String,
+	/// The names of the type parameters of generic records, variants, unions
+	/// and aliases.
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub params:Vec<String>,
 	#[serde(flatten)]
 	pub kind:TypeDefKind,
+	/// Where the typedef is defined in the source, for imported typedefs this is
+	/// the `use` statement importing it.
+	#[serde(skip)]
+	#[schemars(skip)]
+	pub span:logos::Span,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
@@ -138,15 +333,125 @@ pub enum TypeDefKind {
 	Variant(Vec<VariantCase>),
 	Enum(Vec<EnumCase>),
 	Union(Vec<UnionCase>),
-	Resource(Vec<Function>),
+	Resource(Resource),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct Resource {
+	/// The constructor creating a new resource, its result is the resource
+	/// itself.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub constructor:Option<Function>,
+	pub methods:Vec<Function>,
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub statics:Vec<Function>,
+}
+
+impl Resource {
+	/// Iterates over the constructor, all methods and all static functions.
+	pub fn functions(&self) -> impl Iterator<Item = &Function> {
+		self.constructor.iter().chain(&self.methods).chain(&self.statics)
+	}
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
 pub struct RecordField {
 	#[serde(skip_serializing_if = "String::is_empty")]
 	pub docs:String,
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub annotations:Vec<Annotation>,
 	pub id:String,
 	pub ty:Type,
+	/// The value used when the field is missing.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub default:Option<Value>,
+	/// Where the field is defined in the source, for imported fields this is
+	/// the `use` statement importing it.
+	#[serde(skip)]
+	#[schemars(skip)]
+	pub span:logos::Span,
+}
+
+/// Metadata attached to an item or field, like `@since(1.2)`. Apart from
+/// `@deprecated` and `@capability`, see [`deprecation`] and [`capabilities`],
+/// annotations have no meaning to the parser, generators pick the ones they
+/// know.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct Annotation {
+	pub name:String,
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub args:Vec<AnnotationArg>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct AnnotationArg {
+	/// The key of `key = value` arguments.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub key:Option<String>,
+	/// Arguments have no type to check against, so numbers are integers
+	/// unless they have a fraction or exponent and identifiers other than
+	/// `true`, `false` and `none` are kept as [`Value::Enum`].
+	pub value:Value,
+}
+
+/// Returns the message of the `@deprecated` annotation among `annotations`,
+/// which is empty if the annotation has no message.
+#[must_use]
+pub fn deprecation(annotations:&[Annotation]) -> Option<&str> {
+	let annotation = annotations.iter().find(|annotation| annotation.name == "deprecated")?;
+
+	match annotation.args.first() {
+		Some(AnnotationArg { value:Value::String(message), .. }) => Some(message),
+		_ => Some(""),
+	}
+}
+
+/// Returns the capabilities named by the `@capability` annotations among
+/// `annotations`, which the host requires to call the annotated function or
+/// resource.
+pub fn capabilities(annotations:&[Annotation]) -> impl Iterator<Item = &str> {
+	annotations.iter().filter(|annotation| annotation.name == "capability").filter_map(
+		|annotation| {
+			match annotation.args.first() {
+				Some(AnnotationArg { value:Value::String(capability), .. }) => Some(&**capability),
+				_ => None,
+			}
+		},
+	)
+}
+
+/// A literal value, checked to be valid for its type.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase", tag = "type", content = "value")]
+pub enum Value {
+	Bool(bool),
+	/// An integer, within the range of its type.
+	Int(i128),
+	Float(f64),
+	Char(char),
+	String(String),
+	/// The name of a case of an enum.
+	Enum(String),
+	/// The empty value of an `option`.
+	None,
+}
+
+// floats are parsed from number literals, which can't be NaN
+impl Eq for Value {}
+
+impl fmt::Display for Value {
+	/// Prints the value the way it is written in a wit document.
+	fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Value::Bool(value) => write!(f, "{value}"),
+			Value::Int(int) => write!(f, "{int}"),
+			Value::Float(float) => write!(f, "{float}"),
+			Value::Char(char) => write!(f, "{:?}", char.to_string()),
+			Value::String(string) => write!(f, "{string:?}"),
+			Value::Enum(case) => write!(f, "{case}"),
+			Value::None => write!(f, "none"),
+		}
+	}
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
@@ -178,15 +483,34 @@ pub struct UnionCase {
 	pub ty:Type,
 }
 
+/// A named value shared by both sides of the interface.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct Constant {
+	#[serde(skip_serializing_if = "String::is_empty")]
+	pub docs:String,
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub annotations:Vec<Annotation>,
+	pub id:String,
+	pub ty:Type,
+	pub value:Value,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
 pub struct Function {
 	#[serde(skip_serializing_if = "String::is_empty")]
 	pub docs:String,
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub annotations:Vec<Annotation>,
 	pub id:String,
 	#[serde(serialize_with = "serialize_named_type_list")]
 	#[schemars(with = "Vec<NamedType>")]
 	pub params:NamedTypeList,
 	pub result:Option<FunctionResult>,
+	/// Where the function is defined in the source, for imported functions this is
+	/// the `use` statement importing it.
+	#[serde(skip)]
+	#[schemars(skip)]
+	pub span:logos::Span,
 }
 
 impl Function {
@@ -286,3 +610,29 @@ where
 
 	s.end()
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn unmatched_skip() {
+		let source = "interface test {
+            func greet(name: string)
+          }";
+
+		let skip = HashSet::from(["gret".to_string()]);
+
+		let err = parse_and_resolve(source, |name| skip.contains(name), Some(&skip)).unwrap_err();
+
+		let err = err.downcast_ref::<Error>().unwrap();
+
+		assert!(matches!(err, Error::Multi { errors } if matches!(
+			&errors[..],
+			[Error::UnmatchedSkip { location, help: Some(_), .. }] if *location == (10..14)
+		)));
+
+		// the closure based functions skip what they are told without checking
+		assert!(parse_and_resolve_str(source, |name| name == "gret").is_ok());
+	}
+}
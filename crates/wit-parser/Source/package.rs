@@ -0,0 +1,455 @@
+use std::{
+	collections::HashMap,
+	path::{Path, PathBuf},
+};
+
+use logos::Span;
+use miette::{ErrReport, IntoDiagnostic, NamedSource};
+
+use crate::{Error, Interface, RelatedLocation, parse, typecheck::Resolver};
+
+/// A single `.wit` file that is part of a package.
+struct SourceFile {
+	path:PathBuf,
+	source:String,
+	iface:parse::Interface,
+}
+
+impl SourceFile {
+	fn ident(&self) -> &str { self.source[self.iface.ident.clone()].trim_start_matches('%') }
+
+	/// The identifiers of all interfaces this file imports from together with
+	/// the span of the corresponding `use` item.
+	fn uses(&self) -> impl Iterator<Item = (&str, &Span)> {
+		self.iface.items.iter().filter_map(|item| {
+			if let parse::InterfaceItemInner::Use(_) = item.inner {
+				Some((self.source[item.ident.clone()].trim_start_matches('%'), &item.ident))
+			} else {
+				None
+			}
+		})
+	}
+
+	fn named_source(&self) -> NamedSource {
+		NamedSource::new(self.path.to_string_lossy(), self.source.clone())
+	}
+
+	fn with_source_code(&self, error:impl Into<ErrReport>) -> ErrReport {
+		error.into().with_source_code(self.named_source())
+	}
+
+	/// The `use` item of this file importing from the interface `ident`.
+	fn use_of(&self, ident:&str) -> Option<&Span> {
+		self.uses().find(|(import, _)| *import == ident).map(|(_, span)| span)
+	}
+}
+
+/// A directory of `.wit` files whose interfaces may import types from each
+/// other through `use` items.
+pub struct Package {
+	files:Vec<SourceFile>,
+	ident2idx:HashMap<String, usize>,
+	/// The names of all functions and resource methods that could have been
+	/// skipped.
+	names:Vec<String>,
+}
+
+impl Package {
+	pub fn load(dir:&Path, skip:impl Fn(&str) -> bool) -> miette::Result<Self> {
+		let mut package = Self { files:Vec::new(), ident2idx:HashMap::new(), names:Vec::new() };
+
+		for path in wit_paths(dir)? {
+			package.add_file(path, &skip)?;
+		}
+
+		Ok(package)
+	}
+
+	/// Loads the file at `path` together with the files of its directory that
+	/// define the interfaces it transitively imports from. Other files are
+	/// only read to find out which interface they define, so errors in them
+	/// are not reported.
+	pub fn load_reachable(path:&Path, skip:impl Fn(&str) -> bool) -> miette::Result<Self> {
+		let source = std::fs::read_to_string(path).into_diagnostic()?;
+
+		Self::load_reachable_from(path, source, skip)
+	}
+
+	/// Like [`Package::load_reachable`], but takes the contents of the file
+	/// at `path` instead of reading it, such as the unsaved text of an open
+	/// document.
+	pub fn load_reachable_from(
+		path:&Path,
+		source:String,
+		skip:impl Fn(&str) -> bool,
+	) -> miette::Result<Self> {
+		let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+
+		let others = wit_paths(dir)?
+			.into_iter()
+			.filter(|other| other.file_name() != path.file_name())
+			.filter_map(|other| {
+				let source = std::fs::read_to_string(&other).ok()?;
+
+				let iface = crate::parse_partial(&source).0?;
+
+				let ident = source[iface.ident].trim_start_matches('%').to_string();
+
+				Some((other, ident))
+			})
+			.collect::<Vec<_>>();
+
+		let mut package = Self { files:Vec::new(), ident2idx:HashMap::new(), names:Vec::new() };
+
+		package.add_source(path.to_path_buf(), source, &skip)?;
+
+		let mut idx = 0;
+
+		while idx < package.files.len() {
+			let uses = package.files[idx].uses().map(|(ident, _)| ident.to_string()).collect::<Vec<_>>();
+
+			for ident in uses {
+				if package.ident2idx.contains_key(&ident) {
+					continue;
+				}
+
+				// all files defining the interface are added, so that duplicates
+				// are reported
+				for (other, _) in others.iter().filter(|(_, other)| *other == ident) {
+					package.add_file(other.clone(), &skip)?;
+				}
+			}
+
+			idx += 1;
+		}
+
+		Ok(package)
+	}
+
+	/// Parses the file at `path` and adds it to the package.
+	fn add_file(&mut self, path:PathBuf, skip:impl Fn(&str) -> bool) -> miette::Result<()> {
+		let source = std::fs::read_to_string(&path).into_diagnostic()?;
+
+		self.add_source(path, source, skip)
+	}
+
+	/// Parses the contents of the file at `path` and adds it to the package.
+	fn add_source(
+		&mut self,
+		path:PathBuf,
+		source:String,
+		skip:impl Fn(&str) -> bool,
+	) -> miette::Result<()> {
+		let (iface, names) = crate::parse_with_names(&source, skip).map_err(|error:ErrReport| {
+			error.with_source_code(NamedSource::new(path.to_string_lossy(), source.clone()))
+		})?;
+
+		self.names.extend(names);
+
+		let file = SourceFile { path, source, iface };
+
+		if let Some(previous) = self.ident2idx.get(file.ident()) {
+			let previous = &self.files[*previous];
+
+			let definition = RelatedLocation::new(
+				previous.named_source(),
+				previous.iface.ident.clone(),
+				format!("interface `{}` is first defined here.", previous.ident()),
+				"first definition",
+			);
+
+			return Err(file.with_source_code(Error::interface_already_defined(
+				file.iface.ident.clone(),
+				previous.path.display().to_string(),
+				definition,
+			)));
+		}
+
+		self.ident2idx.insert(file.ident().to_string(), self.files.len());
+
+		self.files.push(file);
+
+		Ok(())
+	}
+
+	/// The names of all functions and resource methods of the package that
+	/// could have been skipped.
+	pub fn names(&self) -> &[String] { &self.names }
+
+	/// Resolves all interfaces of this package.
+	pub fn resolve(&self) -> miette::Result<Vec<Interface>> {
+		let mut resolved = vec![None; self.files.len()];
+
+		for idx in 0..self.files.len() {
+			self.resolve_file(idx, &mut Vec::new(), &mut resolved)?;
+		}
+
+		Ok(resolved.into_iter().flatten().collect())
+	}
+
+	/// Resolves the interface defined in the file at `path` as well as all
+	/// interfaces it transitively imports from.
+	pub fn resolve_path(&self, path:&Path) -> miette::Result<Interface> {
+		let idx = self
+			.files
+			.iter()
+			.position(|file| file.path.file_name() == path.file_name())
+			.ok_or_else(|| miette::miette!("wit file `{}` is not part of the package", path.display()))?;
+
+		let mut resolved = vec![None; self.files.len()];
+
+		self.resolve_file(idx, &mut Vec::new(), &mut resolved)?;
+
+		Ok(resolved.swap_remove(idx).unwrap())
+	}
+
+	fn resolve_file(
+		&self,
+		idx:usize,
+		stack:&mut Vec<usize>,
+		resolved:&mut [Option<Interface>],
+	) -> miette::Result<()> {
+		if resolved[idx].is_some() {
+			return Ok(());
+		}
+
+		let file = &self.files[idx];
+
+		stack.push(idx);
+
+		let mut deps = Vec::new();
+
+		for (ident, span) in file.uses() {
+			// unknown interfaces are reported by the resolver, which knows how to
+			// suggest similar names
+			let Some(dep) = self.ident2idx.get(ident).copied() else {
+				continue;
+			};
+
+			if let Some(pos) = stack.iter().position(|idx| *idx == dep) {
+				let cycle = stack[pos..]
+					.iter()
+					.chain([&dep])
+					.map(|idx| self.files[*idx].ident())
+					.collect::<Vec<_>>()
+					.join(" -> ");
+
+				// the imports leading from `dep` to this file
+				let imports = stack[pos..]
+					.windows(2)
+					.filter_map(|pair| {
+						let (importer, imported) = (&self.files[pair[0]], &self.files[pair[1]]);
+
+						let (importer_ident, imported_ident) = (importer.ident(), imported.ident());
+
+						Some(RelatedLocation::new(
+							importer.named_source(),
+							importer.use_of(imported_ident)?.clone(),
+							format!("`{importer_ident}` imports from `{imported_ident}` here."),
+							"part of the cycle",
+						))
+					})
+					.collect();
+
+				return Err(file.with_source_code(Error::cyclic_use(
+					span.clone(),
+					format!("the import cycle is {cycle}"),
+					imports,
+				)));
+			}
+
+			self.resolve_file(dep, stack, resolved)?;
+
+			deps.push(dep);
+		}
+
+		stack.pop();
+
+		let imports = deps.iter().filter_map(|dep| resolved[*dep].as_ref());
+
+		let (resolver, rest_data) = Resolver::new(&file.source, file.iface.clone());
+
+		let iface = resolver
+			.with_imports(imports)
+			.resolve(rest_data)
+			.map_err(|error| file.with_source_code(error))?;
+
+		resolved[idx] = Some(iface);
+
+		Ok(())
+	}
+}
+
+/// The paths of all `.wit` files in `dir`, sorted so that resolution order
+/// and errors are deterministic.
+fn wit_paths(dir:&Path) -> miette::Result<Vec<PathBuf>> {
+	let mut paths = std::fs::read_dir(dir)
+		.into_diagnostic()?
+		.map(|entry| entry.map(|entry| entry.path()))
+		.collect::<std::io::Result<Vec<_>>>()
+		.into_diagnostic()?;
+
+	paths.retain(|path| path.extension().is_some_and(|ext| ext == "wit"));
+
+	paths.sort();
+
+	Ok(paths)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn write_package(name:&str, files:&[(&str, &str)]) -> PathBuf {
+		let dir = std::env::temp_dir().join(format!("wit-parser-{name}-{}", std::process::id()));
+
+		std::fs::create_dir_all(&dir).unwrap();
+
+		for (file, contents) in files {
+			std::fs::write(dir.join(file), contents).unwrap();
+		}
+
+		dir
+	}
+
+	/// Renders the error like the CLI does, without colors or line wrapping.
+	fn render(err:&ErrReport) -> String {
+		let mut out = String::new();
+
+		miette::GraphicalReportHandler::new_themed(miette::GraphicalTheme::unicode_nocolor())
+			.with_width(usize::MAX)
+			.render_report(&mut out, err.as_ref())
+			.unwrap();
+
+		out
+	}
+
+	#[test]
+	fn package() -> miette::Result<()> {
+		let dir = write_package(
+			"package",
+			&[
+				("a.wit", "interface a { use b.{foo} func f(x: foo) }"),
+				("b.wit", "interface b { record foo { x: u32 } func g(x: foo) }"),
+			],
+		);
+
+		let package = Package::load(&dir, |_| false);
+
+		std::fs::remove_dir_all(&dir).unwrap();
+
+		let ifaces = package?.resolve()?;
+
+		assert_eq!(ifaces.iter().map(|iface| iface.ident.as_str()).collect::<Vec<_>>(), ["a", "b"]);
+
+		assert_eq!(ifaces[0].typedefs.iter().next().unwrap().1.ident, "foo");
+
+		Ok(())
+	}
+
+	#[test]
+	fn reachable() -> miette::Result<()> {
+		let dir = write_package(
+			"reachable",
+			&[
+				("a.wit", "interface a { use types.{foo} func f(x: foo) }"),
+				("b.wit", "interface types { record foo { x: u32 } func g(x: foo) }"),
+				("broken.wit", "interface broken { func f( }"),
+				("c1.wit", "interface c { func g() }"),
+				("c2.wit", "interface c { func h() }"),
+			],
+		);
+
+		let iface = crate::parse_and_resolve_file(dir.join("a.wit"), |_| false);
+
+		let err = crate::parse_and_resolve_package(&dir, |_| false).unwrap_err();
+
+		std::fs::remove_dir_all(&dir).unwrap();
+
+		assert_eq!(iface?.typedefs.iter().next().unwrap().1.ident, "foo");
+
+		assert!(err.code().is_some());
+
+		Ok(())
+	}
+
+	#[test]
+	fn unsaved() {
+		let dir = write_package(
+			"unsaved",
+			&[
+				("a.wit", "interface a { func f() }"),
+				("b.wit", "interface b { record foo { x: u32 } func g(x: foo) }"),
+			],
+		);
+
+		let path = dir.join("a.wit");
+
+		let iface = crate::parse_and_resolve_str_at(
+			"interface a { use b.{foo} func f(x: foo) }",
+			&path,
+			|_| false,
+		);
+
+		let err = crate::parse_and_resolve_str_at(
+			"interface a { use b.{bar} func f(x: bar) }",
+			&path,
+			|_| false,
+		);
+
+		std::fs::remove_dir_all(&dir).unwrap();
+
+		assert_eq!(iface.unwrap().typedefs.iter().next().unwrap().1.ident, "foo");
+
+		assert!(err.is_err());
+	}
+
+	#[test]
+	fn cyclic_use() {
+		let dir = write_package(
+			"cyclic-use",
+			&[
+				("a.wit", "interface a { use b.{foo} record bar { x: u32 } func f(x: foo) }"),
+				("b.wit", "interface b { use a.{bar} record foo { x: bar } func g(x: foo) }"),
+			],
+		);
+
+		let package = Package::load(&dir, |_| false);
+
+		std::fs::remove_dir_all(&dir).unwrap();
+
+		let err = package.unwrap().resolve().unwrap_err();
+
+		assert_eq!(err.code().unwrap().to_string(), "wit_parser::cyclic_use");
+
+		// both imports of the cycle are shown, each in its own file
+		let out = render(&err);
+
+		assert!(out.contains(&format!("[{}:1:1]", dir.join("a.wit").display())), "{out}");
+		assert!(out.contains("use b.{foo}"), "{out}");
+		assert!(out.contains(&format!("[{}:1:1]", dir.join("b.wit").display())), "{out}");
+		assert!(out.contains("use a.{bar}"), "{out}");
+	}
+
+	#[test]
+	fn interface_already_defined() {
+		let dir = write_package(
+			"interface-already-defined",
+			&[("c1.wit", "interface c { func g() }"), ("c2.wit", "interface c { func h() }")],
+		);
+
+		let err = Package::load(&dir, |_| false).err().unwrap();
+
+		std::fs::remove_dir_all(&dir).unwrap();
+
+		assert_eq!(err.code().unwrap().to_string(), "wit_parser::interface_already_defined");
+
+		// both definitions are shown, each in its own file
+		let out = render(&err);
+
+		assert!(out.contains(&format!("[{}:1:1]", dir.join("c1.wit").display())), "{out}");
+		assert!(out.contains("func g()"), "{out}");
+		assert!(out.contains(&format!("[{}:1:1]", dir.join("c2.wit").display())), "{out}");
+		assert!(out.contains("func h()"), "{out}");
+	}
+}
@@ -1,17 +1,65 @@
+//! The syntax tree of a WIT document, before any names are resolved.
+//!
+//! Nodes keep the spans of their identifiers and doc comments, and the span of
+//! the whole node as `span`. Node spans start at the first token after the doc
+//! comments and end at the last token of the node, so they can be used to map
+//! generated code and errors back to the document.
+
 #![allow(clippy::missing_panics_doc, clippy::missing_errors_doc)]
 
 use std::iter::Peekable;
 
-use logos::{Span, SpannedIter};
+use logos::{Logos, Span, SpannedIter};
 
 use crate::{
 	Error,
 	Result,
-	lex::Token,
+	lex::{self, Token},
 	util::{find_similar, print_list},
 };
 
-pub type Tokens<'a> = Peekable<SpannedIter<'a, Token>>;
+/// The tokens of a document, remembering where the last consumed token ends
+/// so nodes can record their span.
+pub struct Tokens<'a> {
+	inner:Peekable<SpannedIter<'a, Token>>,
+	end:usize,
+}
+
+impl<'a> Tokens<'a> {
+	#[must_use]
+	pub fn new(source:&'a str) -> Self {
+		Self { inner:Token::lexer(source).spanned().peekable(), end:0 }
+	}
+
+	pub(crate) fn peek(&mut self) -> Option<&(std::result::Result<Token, lex::Error>, Span)> {
+		self.inner.peek()
+	}
+
+	/// Where the next token starts, or the end of the last one if there are
+	/// no tokens left.
+	fn start(&mut self) -> usize {
+		let end = self.end;
+
+		self.peek().map_or(end, |(_, span)| span.start)
+	}
+
+	/// The span from `start` up to the end of the last consumed token.
+	fn span_from(&self, start:usize) -> Span { start..self.end }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+	type Item = (std::result::Result<Token, lex::Error>, Span);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let next = self.inner.next();
+
+		if let Some((_, span)) = &next {
+			self.end = span.end;
+		}
+
+		next
+	}
+}
 
 trait TokensExt {
 	fn next_if_token(&mut self, token:Token) -> Result<Option<(Token, Span)>>;
@@ -21,17 +69,26 @@ trait TokensExt {
 
 impl<'a> TokensExt for Tokens<'a> {
 	fn next_if_token(&mut self, expected:Token) -> Result<Option<(Token, Span)>> {
-		self.next_if(|(found, _)| *found == Ok(expected))
-			.map(|(found, range)| found.map(|token| (token, range)))
-			.transpose()
-			.map_err(Error::Lex)
+		if !matches!(self.peek(), Some((Ok(found), _)) if *found == expected) {
+			return Ok(None);
+		}
+
+		let (_, span) = self.next().unwrap();
+
+		Ok(Some((expected, span)))
 	}
 
 	fn expect(&mut self, expected:Token) -> Result<(Token, Span)> {
-		match self.next() {
-			Some((Ok(found), span)) if found == expected => Ok((found, span)),
-			Some((Ok(found), span)) => Err(Error::unexpected_token(span, [expected], found)),
-			Some((Err(err), _)) => Err(Error::Lex(err)),
+		// unexpected tokens are left in place, so the parser can recover from
+		// them at the start of the next item
+		match self.peek() {
+			Some((Ok(found), _)) if *found == expected => {
+				let (_, span) = self.next().unwrap();
+
+				Ok((expected, span))
+			},
+			Some((Ok(found), span)) => Err(Error::unexpected_token(span.clone(), [expected], *found)),
+			Some((Err(_), _)) => Err(Error::Lex(self.next().unwrap().0.unwrap_err())),
 			None => Err(Error::UnexpectedEof),
 		}
 	}
@@ -47,13 +104,79 @@ where
 pub struct Interface {
 	pub ident:Span,
 	pub docs:Vec<Span>,
+	pub span:Span,
 	pub items:Vec<InterfaceItem>,
+	/// Functions removed through [`Interface::skip`]. They're kept around so
+	/// types only they refer to aren't reported as unused.
+	pub skipped:Vec<Func>,
+}
+
+impl Interface {
+	/// Removes all functions and resource methods for which `skip` returns
+	/// true.
+	/// Resource methods are addressed as `resource.method`.
+	///
+	/// Returns the names of all functions and resource methods that could have
+	/// been skipped.
+	pub fn skip(&mut self, source:&str, skip:impl Fn(&str) -> bool) -> Vec<String> {
+		let ident = |span:&Span| source[span.clone()].trim_start_matches('%');
+
+		let mut names = Vec::new();
+
+		let mut skipped = Vec::new();
+
+		self.items.retain_mut(|item| {
+			match &mut item.inner {
+				InterfaceItemInner::Func(func) => {
+					let name = ident(&item.ident).to_string();
+
+					let keep = !skip(&name);
+
+					if !keep {
+						skipped.push(func.clone());
+					}
+
+					names.push(name);
+
+					keep
+				},
+				InterfaceItemInner::Resource(methods) => {
+					let resource = ident(&item.ident);
+
+					methods.retain(|method| {
+						let name = format!("{resource}.{}", ident(&method.ident));
+
+						let keep = !skip(&name);
+
+						if !keep {
+							skipped.push(method.inner.clone());
+						}
+
+						names.push(name);
+
+						keep
+					});
+
+					true
+				},
+				_ => true,
+			}
+		});
+
+		self.skipped.extend(skipped);
+
+		names
+	}
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct InterfaceItem {
 	pub docs:Vec<Span>,
+	pub annotations:Vec<Annotation>,
 	pub ident:Span,
+	pub span:Span,
+	/// The type parameters of generic records, variants, unions and aliases.
+	pub params:Vec<Span>,
 	pub inner:InterfaceItemInner,
 }
 
@@ -67,6 +190,16 @@ pub enum InterfaceItemInner {
 	Union(Vec<UnionCase>),
 	Func(Func),
 	Resource(Vec<Method>),
+	Use(Vec<UseName>),
+	Export(Vec<ExportFunc>),
+	Const(Const),
+}
+
+/// A constant declared in an interface, like `const max_size: u32 = 1024`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Const {
+	pub ty:Type,
+	pub value:Value,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -75,7 +208,15 @@ pub struct Func {
 	pub result:Option<FuncResult>,
 }
 
-pub type NamedTypeList = Vec<(Span, Type)>;
+pub type NamedTypeList = Vec<NamedType>;
+
+/// A function parameter or named result.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamedType {
+	pub ident:Span,
+	pub span:Span,
+	pub ty:Type,
+}
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum FuncResult {
@@ -86,19 +227,26 @@ pub enum FuncResult {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RecordField {
 	pub ident:Span,
+	pub span:Span,
 	pub docs:Vec<Span>,
+	pub annotations:Vec<Annotation>,
 	pub ty:Type,
+	/// The value used when the field is missing, like the `5` in
+	/// `size: u32 = 5`.
+	pub default:Option<Value>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct FlagsField {
 	pub ident:Span,
+	pub span:Span,
 	pub docs:Vec<Span>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct VariantCase {
 	pub ident:Span,
+	pub span:Span,
 	pub docs:Vec<Span>,
 	pub ty:Option<Type>,
 }
@@ -106,22 +254,56 @@ pub struct VariantCase {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct EnumCase {
 	pub ident:Span,
+	pub span:Span,
 	pub docs:Vec<Span>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct UnionCase {
 	pub docs:Vec<Span>,
+	pub span:Span,
 	pub ty:Type,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UseName {
+	pub ident:Span,
+	pub span:Span,
+	pub alias:Option<Span>,
+}
+
+/// A function implemented by the webview and called by the host.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportFunc {
+	pub ident:Span,
+	pub span:Span,
+	pub docs:Vec<Span>,
+	pub annotations:Vec<Annotation>,
+	pub inner:Func,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Method {
 	pub ident:Span,
+	pub span:Span,
 	pub docs:Vec<Span>,
+	pub annotations:Vec<Annotation>,
+	pub kind:MethodKind,
 	pub inner:Func,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MethodKind {
+	/// A regular method, called on a resource handle.
+	Method,
+	/// A function associated with the resource that is called without a
+	/// handle.
+	Static,
+	/// The constructor creating a new resource, its identifier is the span of
+	/// the `constructor` keyword.
+	Constructor,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Type {
 	Bool,
@@ -143,14 +325,115 @@ pub enum Type {
 	Tuple(Vec<Type>),
 	Option(Box<Type>),
 	Result { ok:Option<Box<Type>>, err:Option<Box<Type>> },
+	/// A stream of values, `span` is the location of the `stream` keyword as
+	/// streams are only valid as the result of a function.
+	Stream { span:Span, ty:Box<Type> },
 	Id(Span),
+	/// A generic type given type arguments, such as `page<user>`.
+	Generic { ident:Span, args:Vec<Type> },
+}
+
+/// Metadata written before an item or field, like `@since(1.2)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Annotation {
+	pub name:Span,
+	pub span:Span,
+	pub args:Vec<AnnotationArg>,
+}
+
+/// An argument of an annotation, either a value or a `key = value` pair.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnnotationArg {
+	pub key:Option<Span>,
+	pub value:Value,
+}
+
+/// A literal value, checked against its type when the interface is resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+	/// An integer or floating point number.
+	Number(Span),
+	/// A quoted string, also used for `char` values.
+	String(Span),
+	/// `true`, `false`, `none` or the name of an enum case.
+	Ident(Span),
+}
+
+impl Value {
+	#[must_use]
+	pub fn span(&self) -> &Span {
+		match self {
+			Self::Number(span) | Self::String(span) | Self::Ident(span) => span,
+		}
+	}
+}
+
+impl Interface {
+	/// Parses an interface, skipping over items with syntax errors so they can
+	/// all be reported at once.
+	///
+	/// Returns the interface with the items that could be parsed, unless its
+	/// header is malformed, along with the errors.
+	pub fn parse_recovering(source:&str) -> (Option<Self>, Vec<Error>) {
+		let mut tokens = Tokens::new(source);
+
+		let header = (|| {
+			let docs = parse_docs(&mut tokens);
+
+			let (_, start) = tokens.expect(Token::Interface)?;
+
+			let (_, ident) = tokens.expect(Token::Ident)?;
+
+			tokens.expect(Token::LeftBrace)?;
+
+			Ok((docs, ident, start.start))
+		})();
+
+		let (docs, ident, start) = match header {
+			Ok(header) => header,
+			Err(err) => return (None, vec![err]),
+		};
+
+		let mut items = Vec::new();
+
+		let mut errors = Vec::new();
+
+		loop {
+			let start = match tokens.peek() {
+				Some((Ok(Token::RightBrace), _)) => {
+					tokens.next();
+
+					break;
+				},
+				Some((_, span)) => span.start,
+				None => {
+					errors.push(Error::UnexpectedEof);
+
+					break;
+				},
+			};
+
+			match InterfaceItem::parse(&mut tokens) {
+				Ok(item) => items.push(item),
+				Err(err) => {
+					errors.push(err);
+
+					skip_item(source, start, &mut tokens);
+				},
+			}
+		}
+
+		let span = tokens.span_from(start);
+
+		(Some(Interface { ident, docs, span, items, skipped:Vec::new() }), errors)
+	}
 }
 
 impl<'a> FromTokens<'a> for Interface {
 	fn parse(tokens:&mut Tokens<'a>) -> Result<Self> {
 		let docs = parse_docs(tokens);
 
-		tokens.expect(Token::Interface)?;
+		let (_, start) = tokens.expect(Token::Interface)?;
 
 		let (_, ident) = tokens.expect(Token::Ident)?;
 
@@ -160,7 +443,7 @@ impl<'a> FromTokens<'a> for Interface {
 
 		log::debug!("successfully parsed interface");
 
-		Ok(Interface { ident, docs, items })
+		Ok(Interface { ident, docs, span:tokens.span_from(start.start), items, skipped:Vec::new() })
 	}
 }
 
@@ -168,11 +451,54 @@ impl<'a> FromTokens<'a> for InterfaceItem {
 	fn parse(tokens:&mut Tokens<'a>) -> Result<Self> {
 		let docs = parse_docs(tokens);
 
+		let annotations = parse_annotations(tokens)?;
+
 		let (kind, kind_span) = tokens.next().ok_or(Error::UnexpectedEof)?;
 
+		let kind = kind?;
+
+		// export sections have no name, the keyword itself identifies the item
+		if kind == Token::Export {
+			let funcs = parse_list(tokens, Token::LeftBrace, Token::RightBrace, None)?;
+
+			return Ok(InterfaceItem {
+				docs,
+				annotations,
+				ident:kind_span.clone(),
+				span:tokens.span_from(kind_span.start),
+				params:Vec::new(),
+				inner:InterfaceItemInner::Export(funcs),
+			});
+		}
+
 		let (_, ident) = tokens.expect(Token::Ident)?;
 
-		let inner = match kind? {
+		// use items are named after the interface they import from and carry no
+		// body of their own, so they are handled before everything else
+		if kind == Token::Use {
+			tokens.expect(Token::Period)?;
+
+			let names = parse_list(tokens, Token::LeftBrace, Token::RightBrace, Some(Token::Comma))?;
+
+			return Ok(InterfaceItem {
+				docs,
+				annotations,
+				ident,
+				span:tokens.span_from(kind_span.start),
+				params:Vec::new(),
+				inner:InterfaceItemInner::Use(names),
+			});
+		}
+
+		let params = if matches!(kind, Token::Record | Token::Variant | Token::Union | Token::Type)
+			&& matches!(tokens.peek(), Some((Ok(Token::LessThan), _)))
+		{
+			parse_list(tokens, Token::LessThan, Token::GreaterThan, Some(Token::Comma))?
+		} else {
+			Vec::new()
+		};
+
+		let inner = match kind {
 			Token::Record => {
 				let inner =
 					parse_list(tokens, Token::LeftBrace, Token::RightBrace, Some(Token::Comma))?;
@@ -218,7 +544,15 @@ impl<'a> FromTokens<'a> for InterfaceItem {
 
 				InterfaceItemInner::Resource(inner)
 			},
-			Token::Use => todo!(),
+			Token::Const => {
+				tokens.expect(Token::Colon)?;
+
+				let ty = Type::parse(tokens)?;
+
+				tokens.expect(Token::Equals)?;
+
+				InterfaceItemInner::Const(Const { ty, value:Value::parse(tokens)? })
+			},
 			found => {
 				let suggestions = find_similar(
 					Token::IFACE_ITEM_KEYWORD.iter().map(ToString::to_string),
@@ -242,7 +576,14 @@ impl<'a> FromTokens<'a> for InterfaceItem {
 			},
 		};
 
-		Ok(InterfaceItem { docs, ident, inner })
+		Ok(InterfaceItem {
+			docs,
+			annotations,
+			ident,
+			span:tokens.span_from(kind_span.start),
+			params,
+			inner,
+		})
 	}
 }
 
@@ -266,7 +607,7 @@ impl<'a> FromTokens<'a> for NamedTypeList {
 	}
 }
 
-impl<'a> FromTokens<'a> for (Span, Type) {
+impl<'a> FromTokens<'a> for NamedType {
 	fn parse(tokens:&mut Tokens<'a>) -> Result<Self> {
 		let (_, ident) = tokens.expect(Token::Ident)?;
 
@@ -274,7 +615,15 @@ impl<'a> FromTokens<'a> for (Span, Type) {
 
 		let ty = Type::parse(tokens)?;
 
-		Ok((ident, ty))
+		Ok(NamedType { span:tokens.span_from(ident.start), ident, ty })
+	}
+}
+
+impl<'a> FromTokens<'a> for Span {
+	fn parse(tokens:&mut Tokens<'a>) -> Result<Self> {
+		let (_, ident) = tokens.expect(Token::Ident)?;
+
+		Ok(ident)
 	}
 }
 
@@ -292,13 +641,18 @@ impl<'a> FromTokens<'a> for RecordField {
 	fn parse(tokens:&mut Tokens<'a>) -> Result<Self> {
 		let docs = parse_docs(tokens);
 
+		let annotations = parse_annotations(tokens)?;
+
 		let (_, ident) = tokens.expect(Token::Ident)?;
 
 		tokens.expect(Token::Colon)?;
 
 		let ty = Type::parse(tokens)?;
 
-		Ok(RecordField { ident, docs, ty })
+		let default =
+			tokens.next_if_token(Token::Equals)?.map(|_| Value::parse(tokens)).transpose()?;
+
+		Ok(RecordField { span:tokens.span_from(ident.start), ident, docs, annotations, ty, default })
 	}
 }
 
@@ -308,7 +662,7 @@ impl<'a> FromTokens<'a> for FlagsField {
 
 		let (_, ident) = tokens.expect(Token::Ident)?;
 
-		Ok(FlagsField { ident, docs })
+		Ok(FlagsField { span:tokens.span_from(ident.start), ident, docs })
 	}
 }
 
@@ -328,7 +682,7 @@ impl<'a> FromTokens<'a> for VariantCase {
 			None
 		};
 
-		Ok(VariantCase { ident, docs, ty })
+		Ok(VariantCase { span:tokens.span_from(ident.start), ident, docs, ty })
 	}
 }
 
@@ -338,7 +692,7 @@ impl<'a> FromTokens<'a> for EnumCase {
 
 		let (_, ident) = tokens.expect(Token::Ident)?;
 
-		Ok(EnumCase { ident, docs })
+		Ok(EnumCase { span:tokens.span_from(ident.start), ident, docs })
 	}
 }
 
@@ -346,9 +700,27 @@ impl<'a> FromTokens<'a> for UnionCase {
 	fn parse(tokens:&mut Tokens<'a>) -> Result<Self> {
 		let docs = parse_docs(tokens);
 
+		let start = tokens.start();
+
 		let ty = Type::parse(tokens)?;
 
-		Ok(UnionCase { docs, ty })
+		Ok(UnionCase { docs, span:tokens.span_from(start), ty })
+	}
+}
+
+impl<'a> FromTokens<'a> for UseName {
+	fn parse(tokens:&mut Tokens<'a>) -> Result<Self> {
+		let (_, ident) = tokens.expect(Token::Ident)?;
+
+		let alias = if tokens.next_if_token(Token::As)?.is_some() {
+			let (_, alias) = tokens.expect(Token::Ident)?;
+
+			Some(alias)
+		} else {
+			None
+		};
+
+		Ok(UseName { span:tokens.span_from(ident.start), ident, alias })
 	}
 }
 
@@ -356,18 +728,112 @@ impl<'a> FromTokens<'a> for Method {
 	fn parse(tokens:&mut Tokens<'a>) -> Result<Self> {
 		let docs = parse_docs(tokens);
 
+		let annotations = parse_annotations(tokens)?;
+
+		let start = tokens.start();
+
+		if let Some((_, ident)) = tokens.next_if_token(Token::Constructor)? {
+			let params = NamedTypeList::parse(tokens)?;
+
+			return Ok(Method {
+				ident,
+				span:tokens.span_from(start),
+				docs,
+				annotations,
+				kind:MethodKind::Constructor,
+				inner:Func { params, result:None },
+			});
+		}
+
+		let kind = if tokens.next_if_token(Token::Static)?.is_some() {
+			MethodKind::Static
+		} else {
+			MethodKind::Method
+		};
+
 		tokens.expect(Token::Func)?;
 
 		let (_, ident) = tokens.expect(Token::Ident)?;
 
 		let inner = Func::parse(tokens)?;
 
-		Ok(Method { ident, docs, inner })
+		Ok(Method { ident, span:tokens.span_from(start), docs, annotations, kind, inner })
+	}
+}
+
+impl<'a> FromTokens<'a> for ExportFunc {
+	fn parse(tokens:&mut Tokens<'a>) -> Result<Self> {
+		let docs = parse_docs(tokens);
+
+		let annotations = parse_annotations(tokens)?;
+
+		let (_, start) = tokens.expect(Token::Func)?;
+
+		let (_, ident) = tokens.expect(Token::Ident)?;
+
+		let inner = Func::parse(tokens)?;
+
+		Ok(ExportFunc { ident, span:tokens.span_from(start.start), docs, annotations, inner })
+	}
+}
+
+impl<'a> FromTokens<'a> for Annotation {
+	fn parse(tokens:&mut Tokens<'a>) -> Result<Self> {
+		let (_, start) = tokens.expect(Token::At)?;
+
+		let (_, name) = tokens.expect(Token::Ident)?;
+
+		let args = if matches!(tokens.peek(), Some((Ok(Token::LeftParen), _))) {
+			parse_list(tokens, Token::LeftParen, Token::RightParen, Some(Token::Comma))?
+		} else {
+			Vec::new()
+		};
+
+		Ok(Annotation { name, span:tokens.span_from(start.start), args })
+	}
+}
+
+impl<'a> FromTokens<'a> for AnnotationArg {
+	fn parse(tokens:&mut Tokens<'a>) -> Result<Self> {
+		let value = Value::parse(tokens)?;
+
+		match value {
+			Value::Ident(key) if tokens.next_if_token(Token::Equals)?.is_some() => {
+				Ok(AnnotationArg { key:Some(key), value:Value::parse(tokens)? })
+			},
+			value => Ok(AnnotationArg { key:None, value }),
+		}
+	}
+}
+
+impl<'a> FromTokens<'a> for Value {
+	fn parse(tokens:&mut Tokens<'a>) -> Result<Self> {
+		const EXPECTED:[Token; 3] = [Token::Number, Token::StringLiteral, Token::Ident];
+
+		if let Some((Ok(found), span)) = tokens.peek() {
+			if !EXPECTED.contains(found) {
+				return Err(Error::unexpected_token(span.clone(), EXPECTED, *found));
+			}
+		}
+
+		let (token, span) = tokens.next().ok_or(Error::UnexpectedEof)?;
+
+		match token? {
+			Token::Number => Ok(Self::Number(span)),
+			Token::StringLiteral => Ok(Self::String(span)),
+			_ => Ok(Self::Ident(span)),
+		}
 	}
 }
 
 impl<'a> FromTokens<'a> for Type {
 	fn parse(tokens:&mut Tokens<'a>) -> Result<Self> {
+		if let Some((Ok(found), span)) = tokens.peek() {
+			if !Token::TYPE_KEYWORD.contains(found) {
+				return Err(Error::unexpected_token(span.clone(), Token::TYPE_KEYWORD, *found));
+			}
+		}
+
 		let (token, span) = tokens.next().ok_or(Error::UnexpectedEof)?;
 
 		match token? {
@@ -395,6 +861,15 @@ impl<'a> FromTokens<'a> for Type {
 
 				Ok(Self::List(Box::new(ty)))
 			},
+			Token::Stream => {
+				tokens.expect(Token::LessThan)?;
+
+				let ty = Type::parse(tokens)?;
+
+				tokens.expect(Token::GreaterThan)?;
+
+				Ok(Self::Stream { span, ty:Box::new(ty) })
+			},
 			Token::Tuple => {
 				let types =
 					parse_list(tokens, Token::LessThan, Token::GreaterThan, Some(Token::Comma))?;
@@ -433,6 +908,12 @@ impl<'a> FromTokens<'a> for Type {
 
 				Ok(Self::Result { ok, err })
 			},
+			Token::Ident if matches!(tokens.peek(), Some((Ok(Token::LessThan), _))) => {
+				let args =
+					parse_list(tokens, Token::LessThan, Token::GreaterThan, Some(Token::Comma))?;
+
+				Ok(Self::Generic { ident:span, args })
+			},
 			Token::Ident => Ok(Self::Id(span)),
 			found => Err(Error::unexpected_token(span, Token::TYPE_KEYWORD, found)),
 		}
@@ -475,6 +956,67 @@ where
 	Ok(items)
 }
 
+/// Skips the rest of an item that failed to parse, which started at `start`,
+/// up to the next item or the end of the interface.
+fn skip_item(source:&str, start:usize, tokens:&mut Tokens) {
+	let end = tokens.peek().map_or(source.len(), |(_, span)| span.start);
+
+	let mut consumed = Token::lexer(&source[start..end]).flatten();
+
+	// the item keyword, following the doc comments and annotations
+	let kind = consumed.find(|token| Token::IFACE_ITEM_KEYWORD.contains(token));
+
+	// the braces the item opened before failing
+	let mut depth = consumed.fold(0usize, |depth, token| {
+		match token {
+			Token::LeftBrace => depth + 1,
+			Token::RightBrace => depth.saturating_sub(1),
+			_ => depth,
+		}
+	});
+
+	// only resources and exports contain functions, in other items they mean
+	// the closing brace is missing
+	let nested_funcs = matches!(kind, Some(Token::Resource | Token::Export));
+
+	if end == start {
+		tokens.next();
+	}
+
+	while let Some((token, _)) = tokens.peek() {
+		match token {
+			Ok(Token::LeftBrace) => depth += 1,
+			Ok(Token::RightBrace) if depth == 0 => return,
+			Ok(Token::RightBrace) => {
+				depth -= 1;
+
+				if depth == 0 {
+					tokens.next();
+
+					return;
+				}
+			},
+			Ok(Token::DocComment | Token::BlockDocComment | Token::At) if depth == 0 => return,
+			Ok(Token::Func) if depth == 0 || !nested_funcs => return,
+			Ok(token) if *token != Token::Func && Token::IFACE_ITEM_KEYWORD.contains(token) => return,
+			_ => {},
+		}
+
+		tokens.next();
+	}
+}
+
+/// Parses the annotations following the doc comments of an item or field.
+fn parse_annotations(tokens:&mut Tokens) -> Result<Vec<Annotation>> {
+	let mut annotations = Vec::new();
+
+	while let Some((Ok(Token::At), _)) = tokens.peek() {
+		annotations.push(Annotation::parse(tokens)?);
+	}
+
+	Ok(annotations)
+}
+
 fn parse_docs(tokens:&mut Tokens) -> Vec<Span> {
 	let mut spans = Vec::new();
 
@@ -489,14 +1031,13 @@ fn parse_docs(tokens:&mut Tokens) -> Vec<Span> {
 
 #[cfg(test)]
 mod test {
-	use logos::Lexer;
 	use pretty_assertions::assert_eq;
 
 	use super::*;
 
 	#[test]
 	fn feature() -> Result<()> {
-		let mut tokens = Lexer::new("record foo {}").spanned().peekable();
+		let mut tokens = Tokens::new("record foo {}");
 
 		let ty = InterfaceItem::parse(&mut tokens)?;
 
@@ -507,7 +1048,7 @@ mod test {
 
 	#[test]
 	fn option() -> Result<()> {
-		let mut tokens = Lexer::new("option<u8>").spanned().peekable();
+		let mut tokens = Tokens::new("option<u8>");
 
 		let ty = Type::parse(&mut tokens)?;
 
@@ -518,7 +1059,7 @@ mod test {
 
 	#[test]
 	fn result() -> Result<()> {
-		let mut tokens = Lexer::new("result<u8, string>").spanned().peekable();
+		let mut tokens = Tokens::new("result<u8, string>");
 
 		let ty = Type::parse(&mut tokens)?;
 
@@ -532,7 +1073,7 @@ mod test {
 
 	#[test]
 	fn result2() -> Result<()> {
-		let mut tokens = Lexer::new("result<_, string>").spanned().peekable();
+		let mut tokens = Tokens::new("result<_, string>");
 
 		let ty = Type::parse(&mut tokens)?;
 
@@ -543,7 +1084,7 @@ mod test {
 
 	#[test]
 	fn typedef_() -> Result<()> {
-		let mut tokens = Lexer::new("type foo = result<u8, string>").spanned().peekable();
+		let mut tokens = Tokens::new("type foo = result<u8, string>");
 
 		let ty = InterfaceItem::parse(&mut tokens)?;
 
@@ -560,7 +1101,7 @@ mod test {
 
 	#[test]
 	fn enum__() -> Result<()> {
-		let mut tokens = Lexer::new(
+		let mut tokens = Tokens::new(
 			"enum color {
             red,
             green,
@@ -569,9 +1110,7 @@ mod test {
             other,
         }
         ",
-		)
-		.spanned()
-		.peekable();
+		);
 
 		let _ty = InterfaceItem::parse(&mut tokens)?;
 
@@ -589,15 +1128,13 @@ mod test {
 
 	#[test]
 	fn flags_() -> Result<()> {
-		let mut tokens = Lexer::new(
+		let mut tokens = Tokens::new(
 			"flags properties {
             lego,
             marvel_superhero,
             supervillain,
         }",
-		)
-		.spanned()
-		.peekable();
+		);
 
 		let _ty = InterfaceItem::parse(&mut tokens)?;
 
@@ -615,15 +1152,13 @@ mod test {
 
 	#[test]
 	fn union_() -> Result<()> {
-		let mut tokens = Lexer::new(
+		let mut tokens = Tokens::new(
 			"union configuration {
             string,
             list<string>,
         }
         ",
-		)
-		.spanned()
-		.peekable();
+		);
 
 		let _ty = InterfaceItem::parse(&mut tokens)?;
 
@@ -638,16 +1173,14 @@ mod test {
 
 	#[test]
 	fn variant_() -> Result<()> {
-		let mut tokens = Lexer::new(
+		let mut tokens = Tokens::new(
 			"variant filter {
             all,
             none,
             some(list<string>),
         }
         ",
-		)
-		.spanned()
-		.peekable();
+		);
 
 		let _ty = InterfaceItem::parse(&mut tokens)?;
 
@@ -664,18 +1197,65 @@ mod test {
 		Ok(())
 	}
 
+	#[test]
+	fn use_() -> Result<()> {
+		let source = "use other.{foo, bar as baz}";
+
+		let mut tokens = Tokens::new(source);
+
+		let item = InterfaceItem::parse(&mut tokens)?;
+
+		assert_eq!(&source[item.ident], "other");
+
+		let InterfaceItemInner::Use(names) = item.inner else {
+			panic!("expected a use item");
+		};
+
+		let names = names
+			.into_iter()
+			.map(|name| (&source[name.ident], name.alias.map(|alias| &source[alias])))
+			.collect::<Vec<_>>();
+
+		assert_eq!(names, vec![("foo", None), ("bar", Some("baz"))]);
+
+		Ok(())
+	}
+
+	#[test]
+	fn export() -> Result<()> {
+		let source = "export {
+            /// Asks the user to confirm an action
+            func confirm(message: string) -> bool
+            func notify(message: string)
+        }";
+
+		let mut tokens = Tokens::new(source);
+
+		let item = InterfaceItem::parse(&mut tokens)?;
+
+		let InterfaceItemInner::Export(funcs) = item.inner else {
+			panic!("expected an export item");
+		};
+
+		let idents = funcs.iter().map(|func| &source[func.ident.clone()]).collect::<Vec<_>>();
+
+		assert_eq!(idents, vec!["confirm", "notify"]);
+
+		assert_eq!(funcs[0].docs.len(), 1);
+
+		Ok(())
+	}
+
 	#[test]
 	fn interface_() -> Result<()> {
-		let mut tokens = Lexer::new(
+		let mut tokens = Tokens::new(
 			"interface chars {
             /// A function that accepts a character
             func take_char(x: char)
             /// A function that returns a character
             func return_char() -> char
           }",
-		)
-		.spanned()
-		.peekable();
+		);
 
 		let iface = Interface::parse(&mut tokens)?;
 
@@ -684,11 +1264,74 @@ mod test {
 		Ok(())
 	}
 
+	#[test]
+	fn recovery() {
+		let source = "interface demo {
+            record point { x: u32 y: u32 }
+            func get() -> point
+            enum color { red, }
+            func set(c: color
+            resource counter {
+                func inc(by u32)
+                func get() -> u32
+            }
+            type id = u32
+        }";
+
+		let (iface, errors) = Interface::parse_recovering(source);
+
+		let idents = iface
+			.unwrap()
+			.items
+			.iter()
+			.map(|item| &source[item.ident.clone()])
+			.collect::<Vec<_>>();
+
+		assert_eq!(idents, vec!["get", "color", "id"]);
+
+		assert_eq!(errors.len(), 3);
+	}
+
+	#[test]
+	fn spans() -> Result<()> {
+		let source = "interface demo {
+            /// A point
+            record point { x: u32, y: u32 }
+            func get(at: point) -> list<point>
+        }";
+
+		let iface = Interface::parse(&mut Tokens::new(source))?;
+
+		assert_eq!(&source[iface.span.clone()], &source[source.find("interface").unwrap()..]);
+
+		let record = &iface.items[0];
+
+		assert_eq!(&source[record.span.clone()], "record point { x: u32, y: u32 }");
+
+		let InterfaceItemInner::Record(fields) = &record.inner else {
+			panic!("expected a record");
+		};
+
+		assert_eq!(&source[fields[1].span.clone()], "y: u32");
+
+		let func = &iface.items[1];
+
+		assert_eq!(&source[func.span.clone()], "func get(at: point) -> list<point>");
+
+		let InterfaceItemInner::Func(inner) = &func.inner else {
+			panic!("expected a function");
+		};
+
+		assert_eq!(&source[inner.params[0].span.clone()], "at: point");
+
+		Ok(())
+	}
+
 	#[test]
 	fn full() -> Result<()> {
 		let input = include_str!("test.wit");
 
-		let mut tokens = Lexer::new(input).spanned().peekable();
+		let mut tokens = Tokens::new(input);
 
 		let iface = Interface::parse(&mut tokens)?;
 
@@ -0,0 +1,350 @@
+//! The names defined and referenced in a document, for editor tooling.
+
+use logos::{Logos, Span};
+
+use crate::{
+	Error,
+	format::{print_func, print_ident, print_params, print_ty},
+	lex::Token,
+	parse::{self, InterfaceItemInner, MethodKind, Type},
+	util::read_docs,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DefinitionKind {
+	Interface,
+	/// An alias, record, flags, variant, enum or union.
+	Type,
+	Resource,
+	/// A name imported from another interface through a `use` item.
+	Import,
+	Function,
+	/// A resource method, static function or constructor.
+	Method,
+	Constant,
+	/// A record field, flag, variant case or enum case.
+	Member,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Definition {
+	pub kind:DefinitionKind,
+	/// The name without the `%` prefix of escaped identifiers.
+	pub name:String,
+	/// The location of the name.
+	pub span:Span,
+	/// The declaration written as in WIT, such as `record point` or
+	/// `func greet(name: string) -> string`.
+	pub signature:String,
+	pub docs:String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Symbols {
+	pub definitions:Vec<Definition>,
+	/// The locations of type names used in other items, along with the index
+	/// of their definition. Undefined names are left out.
+	pub references:Vec<(Span, usize)>,
+}
+
+impl Symbols {
+	/// Collects the symbols of a document, without resolving it.
+	///
+	/// Items with syntax errors are left out, the symbols are only missing if
+	/// the `interface` header itself is malformed.
+	#[must_use]
+	pub fn parse(input:&str) -> (Option<Self>, Vec<Error>) {
+		let (iface, errors) = crate::parse_partial(input);
+
+		(iface.map(|iface| Self::collect(input, &iface)), errors)
+	}
+
+	fn collect(source:&str, iface:&parse::Interface) -> Self {
+		let mut symbols = Symbols::default();
+
+		let mut define = |kind, span:&Span, signature:String, docs:&[Span]| {
+			symbols.definitions.push(Definition {
+				kind,
+				name:source[span.clone()].trim_start_matches('%').to_string(),
+				span:span.clone(),
+				signature,
+				docs:read_docs(source, docs),
+			});
+		};
+
+		define(
+			DefinitionKind::Interface,
+			&iface.ident,
+			format!("interface {}", &source[iface.ident.clone()]),
+			&iface.docs,
+		);
+
+		let mut types = Vec::new();
+
+		for item in &iface.items {
+			let ident = print_ident(source, item);
+
+			match &item.inner {
+				InterfaceItemInner::Alias(ty) => {
+					define(
+						DefinitionKind::Type,
+						&item.ident,
+						format!("type {ident} = {}", print_ty(source, ty)),
+						&item.docs,
+					);
+
+					types.push(ty);
+				},
+				InterfaceItemInner::Record(fields) => {
+					define(DefinitionKind::Type, &item.ident, format!("record {ident}"), &item.docs);
+
+					for field in fields {
+						let signature =
+							format!("{}: {}", &source[field.ident.clone()], print_ty(source, &field.ty));
+
+						define(DefinitionKind::Member, &field.ident, signature, &field.docs);
+
+						types.push(&field.ty);
+					}
+				},
+				InterfaceItemInner::Flags(fields) => {
+					define(DefinitionKind::Type, &item.ident, format!("flags {ident}"), &item.docs);
+
+					for field in fields {
+						let signature = source[field.ident.clone()].to_string();
+
+						define(DefinitionKind::Member, &field.ident, signature, &field.docs);
+					}
+				},
+				InterfaceItemInner::Variant(cases) => {
+					define(DefinitionKind::Type, &item.ident, format!("variant {ident}"), &item.docs);
+
+					for case in cases {
+						let case_ident = &source[case.ident.clone()];
+
+						let signature = match &case.ty {
+							Some(ty) => format!("{case_ident}({})", print_ty(source, ty)),
+							None => case_ident.to_string(),
+						};
+
+						define(DefinitionKind::Member, &case.ident, signature, &case.docs);
+
+						types.extend(&case.ty);
+					}
+				},
+				InterfaceItemInner::Enum(cases) => {
+					define(DefinitionKind::Type, &item.ident, format!("enum {ident}"), &item.docs);
+
+					for case in cases {
+						let signature = source[case.ident.clone()].to_string();
+
+						define(DefinitionKind::Member, &case.ident, signature, &case.docs);
+					}
+				},
+				InterfaceItemInner::Union(cases) => {
+					define(DefinitionKind::Type, &item.ident, format!("union {ident}"), &item.docs);
+
+					types.extend(cases.iter().map(|case| &case.ty));
+				},
+				InterfaceItemInner::Func(func) => {
+					let signature = format!("func {ident}{}", print_func(source, func));
+
+					define(DefinitionKind::Function, &item.ident, signature, &item.docs);
+
+					types.extend(func_types(func));
+				},
+				InterfaceItemInner::Resource(methods) => {
+					define(DefinitionKind::Resource, &item.ident, format!("resource {ident}"), &item.docs);
+
+					for method in methods {
+						let method_ident = &source[method.ident.clone()];
+
+						let signature = match method.kind {
+							MethodKind::Constructor => {
+								format!("constructor{}", print_params(source, &method.inner.params))
+							},
+							MethodKind::Static => {
+								format!("static func {method_ident}{}", print_func(source, &method.inner))
+							},
+							MethodKind::Method => {
+								format!("func {method_ident}{}", print_func(source, &method.inner))
+							},
+						};
+
+						define(DefinitionKind::Method, &method.ident, signature, &method.docs);
+
+						types.extend(func_types(&method.inner));
+					}
+				},
+				InterfaceItemInner::Use(names) => {
+					for name in names {
+						let signature = match &name.alias {
+							Some(alias) => {
+								format!("use {ident}.{{{} as {}}}", &source[name.ident.clone()], &source[alias.clone()])
+							},
+							None => format!("use {ident}.{{{}}}", &source[name.ident.clone()]),
+						};
+
+						let span = name.alias.as_ref().unwrap_or(&name.ident);
+
+						define(DefinitionKind::Import, span, signature, &[]);
+					}
+				},
+				InterfaceItemInner::Export(funcs) => {
+					for func in funcs {
+						let signature =
+							format!("export func {}{}", &source[func.ident.clone()], print_func(source, &func.inner));
+
+						define(DefinitionKind::Function, &func.ident, signature, &func.docs);
+
+						types.extend(func_types(&func.inner));
+					}
+				},
+				InterfaceItemInner::Const(konst) => {
+					let signature = format!(
+						"const {ident}: {} = {}",
+						print_ty(source, &konst.ty),
+						&source[konst.value.span().clone()]
+					);
+
+					define(DefinitionKind::Constant, &item.ident, signature, &item.docs);
+
+					types.push(&konst.ty);
+				},
+			}
+		}
+
+		let mut spans = Vec::new();
+
+		for ty in types {
+			type_names(ty, &mut spans);
+		}
+
+		for span in spans {
+			let name = source[span.clone()].trim_start_matches('%');
+
+			let definition = symbols.definitions.iter().position(|definition| {
+				matches!(
+					definition.kind,
+					DefinitionKind::Type | DefinitionKind::Resource | DefinitionKind::Import
+				) && definition.name == name
+			});
+
+			if let Some(definition) = definition {
+				symbols.references.push((span, definition));
+			}
+		}
+
+		symbols.references.sort_by_key(|(span, _)| span.start);
+
+		symbols
+	}
+
+	/// Returns the index of the definition whose name is at `offset`, either
+	/// where it's defined or where it's referenced.
+	#[must_use]
+	pub fn find(&self, offset:usize) -> Option<usize> {
+		let contains = |span:&Span| span.start <= offset && offset <= span.end;
+
+		self.references
+			.iter()
+			.find(|(span, _)| contains(span))
+			.map(|(_, definition)| *definition)
+			.or_else(|| self.definitions.iter().position(|definition| contains(&definition.span)))
+	}
+
+	/// Returns the locations of a definition and all references to it.
+	pub fn occurrences(&self, definition:usize) -> impl Iterator<Item = &Span> {
+		std::iter::once(&self.definitions[definition].span).chain(
+			self.references
+				.iter()
+				.filter(move |(_, target)| *target == definition)
+				.map(|(span, _)| span),
+		)
+	}
+}
+
+/// The keywords that start items and types.
+pub fn keywords() -> impl Iterator<Item = &'static str> {
+	Token::IFACE_ITEM_KEYWORD
+		.iter()
+		.chain(&Token::TYPE_KEYWORD)
+		.chain(&[Token::Interface, Token::Static, Token::Constructor, Token::As])
+		.filter(|token| **token != Token::Ident)
+		.map(|token| token.as_str().trim_matches('\''))
+}
+
+/// Whether `name` can be used as an identifier.
+#[must_use]
+pub fn is_ident(name:&str) -> bool {
+	let mut lexer = Token::lexer(name);
+
+	matches!(lexer.next(), Some(Ok(Token::Ident))) && lexer.next().is_none()
+}
+
+fn func_types(func:&parse::Func) -> impl Iterator<Item = &Type> {
+	let results:Vec<_> = match &func.result {
+		Some(parse::FuncResult::Anon(ty)) => vec![ty],
+		Some(parse::FuncResult::Named(results)) => results.iter().map(|result| &result.ty).collect(),
+		None => Vec::new(),
+	};
+
+	func.params.iter().map(|param| &param.ty).chain(results)
+}
+
+/// Collects the spans of the type names used in `ty`.
+fn type_names(ty:&Type, spans:&mut Vec<Span>) {
+	match ty {
+		Type::List(ty) | Type::Option(ty) | Type::Stream { ty, .. } => type_names(ty, spans),
+		Type::Tuple(types) => {
+			for ty in types {
+				type_names(ty, spans);
+			}
+		},
+		Type::Result { ok, err } => {
+			for ty in ok.iter().chain(err) {
+				type_names(ty, spans);
+			}
+		},
+		Type::Id(span) => spans.push(span.clone()),
+		Type::Generic { ident, args } => {
+			spans.push(ident.clone());
+
+			for ty in args {
+				type_names(ty, spans);
+			}
+		},
+		_ => {},
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn references() {
+		let source = "interface demo {
+    /// A point in space
+    record point { x: u32 }
+    func get(at: option<point>) -> list<point>
+}";
+
+		let symbols = Symbols::parse(source).0.unwrap();
+
+		let point = symbols.find(source.find("point {").unwrap()).unwrap();
+
+		assert_eq!(symbols.definitions[point].docs, "A point in space");
+
+		assert_eq!(symbols.find(source.find("point>").unwrap()), Some(point));
+
+		assert_eq!(symbols.occurrences(point).count(), 3);
+
+		let get = symbols.find(source.find("get").unwrap()).unwrap();
+
+		assert_eq!(
+			symbols.definitions[get].signature,
+			"func get(at: option<point>) -> list<point>"
+		);
+	}
+}
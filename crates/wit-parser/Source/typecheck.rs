@@ -6,6 +6,9 @@ use id_arena::{Arena, Id};
 use logos::Span;
 
 use crate::{
+	Annotation,
+	AnnotationArg,
+	Constant,
 	EnumCase,
 	Error,
 	FlagsField,
@@ -13,15 +16,18 @@ use crate::{
 	FunctionResult,
 	Interface,
 	RecordField,
+	Resource,
 	Result,
 	Type,
 	TypeDef,
 	TypeDefKind,
 	UnionCase,
+	Value,
 	VariantCase,
+	format::print_ty,
 	lex,
 	parse,
-	util::{IteratorExt, find_similar, print_list},
+	util::{IteratorExt, find_similar, print_list, read_docs},
 };
 
 pub struct RestInterface {
@@ -30,11 +36,33 @@ pub struct RestInterface {
 	functions:Vec<parse::InterfaceItem>,
 }
 
+/// A single name brought into scope by a `use` item.
+#[derive(Debug, Clone)]
+struct UseItem {
+	/// The identifier of the interface the name is imported from.
+	iface:Span,
+	/// The name of the imported type in the other interface.
+	name:Span,
+	/// The name the imported type is known under in this interface.
+	ident:Span,
+}
+
 pub struct Resolver<'a> {
 	source:&'a str,
 	iface_typedefs:HashMap<&'a str, parse::InterfaceItem>,
+	iface_uses:HashMap<&'a str, UseItem>,
+	imports:HashMap<&'a str, &'a Interface>,
+	local_idents:HashMap<&'a str, Span>,
+	skipped:Vec<parse::Func>,
+	/// The type parameters of the generic typedef being resolved, along with
+	/// whether they have been used.
+	type_params:Vec<(&'a str, bool)>,
+	/// The typedefs whose definitions are being resolved, referring to one of
+	/// them again means the type contains itself.
+	resolving:Vec<&'a str>,
 
 	ident2id:HashMap<&'a str, Id<TypeDef>>,
+	imported:HashMap<(&'a str, Id<TypeDef>), Id<TypeDef>>,
 	typedefs:Arena<TypeDef>,
 }
 
@@ -44,19 +72,60 @@ impl<'a> Resolver<'a> {
 		let (iface_funcs, iface_typedefs):(Vec<_>, Vec<_>) = interface
 			.items
 			.into_iter()
-			.partition(|item| matches!(item.inner, parse::InterfaceItemInner::Func(_)));
+			.partition(|item| {
+				matches!(
+					item.inner,
+					parse::InterfaceItemInner::Func(_)
+						| parse::InterfaceItemInner::Export(_)
+						| parse::InterfaceItemInner::Const(_)
+				)
+			});
+
+		let (iface_uses, iface_typedefs):(Vec<_>, Vec<_>) = iface_typedefs
+			.into_iter()
+			.partition(|item| matches!(item.inner, parse::InterfaceItemInner::Use(_)));
 
 		let iface_typedefs:HashMap<_, _> = iface_typedefs
 			.into_iter()
 			.map(|item| (&source[item.ident.clone()], item))
 			.collect();
 
+		let iface_uses:HashMap<_, _> = iface_uses
+			.into_iter()
+			.filter_map(|item| {
+				if let parse::InterfaceItemInner::Use(names) = item.inner {
+					Some(names.into_iter().map(move |name| {
+						let ident = name.alias.unwrap_or_else(|| name.ident.clone());
+
+						let item = UseItem { iface:item.ident.clone(), name:name.ident, ident };
+
+						(source[item.ident.clone()].trim_start_matches('%'), item)
+					}))
+				} else {
+					None
+				}
+			})
+			.flatten()
+			.collect();
+
+		let local_idents = iface_typedefs
+			.iter()
+			.map(|(ident, item)| (*ident, item.ident.clone()))
+			.collect();
+
 		let this = Self {
 			ident2id:HashMap::with_capacity(iface_typedefs.len()),
+			imported:HashMap::new(),
 			typedefs:Arena::with_capacity(iface_typedefs.len()),
 
 			source,
 			iface_typedefs,
+			iface_uses,
+			imports:HashMap::new(),
+			local_idents,
+			skipped:interface.skipped,
+			type_params:Vec::new(),
+			resolving:Vec::new(),
 		};
 
 		let rest =
@@ -65,26 +134,20 @@ impl<'a> Resolver<'a> {
 		(this, rest)
 	}
 
-	fn read_span(&self, span:&Span) -> &'a str { &self.source[span.clone()] }
-
-	fn resolve_ident(&self, span:&Span) -> &'a str { self.read_span(span).trim_start_matches('%') }
-
-	fn resolve_docs(&self, docs:&[Span]) -> String {
-		docs.iter()
-			.map(|span| {
-				let str = self.read_span(span);
+	/// Makes the given, already resolved, interfaces available to `use` items.
+	#[must_use]
+	pub fn with_imports(mut self, imports:impl IntoIterator<Item = &'a Interface>) -> Self {
+		self.imports
+			.extend(imports.into_iter().map(|iface| (iface.ident.as_str(), iface)));
 
-				let str = str.strip_prefix("///").unwrap_or(str);
+		self
+	}
 
-				let str = str.strip_prefix("/**").unwrap_or(str);
+	fn read_span(&self, span:&Span) -> &'a str { &self.source[span.clone()] }
 
-				let str = str.trim();
+	fn resolve_ident(&self, span:&Span) -> &'a str { self.read_span(span).trim_start_matches('%') }
 
-				str
-			})
-			.collect::<Vec<_>>()
-			.join("\n")
-	}
+	fn resolve_docs(&self, docs:&[Span]) -> String { read_docs(self.source, docs) }
 
 	fn resolve_typedef(&mut self, typedef:&parse::InterfaceItem) -> Result<Id<TypeDef>> {
 		let ident = self.resolve_ident(&typedef.ident);
@@ -95,7 +158,69 @@ impl<'a> Resolver<'a> {
 
 		let docs = self.resolve_docs(&typedef.docs);
 
-		let kind = match &typedef.inner {
+		if let parse::InterfaceItemInner::Resource(methods) = &typedef.inner {
+			// the methods of a resource referred to by a generic typedef don't
+			// see its type parameters, and a handle doesn't contain the types
+			// its methods use
+			let outer_params = std::mem::take(&mut self.type_params);
+
+			let outer_resolving = std::mem::take(&mut self.resolving);
+
+			let id = self
+				.resolve_annotations(&typedef.annotations)
+				.and_then(|annotations| {
+					self.resolve_resource(ident, docs, annotations, methods, &typedef.span)
+				});
+
+			self.type_params = outer_params;
+
+			self.resolving = outer_resolving;
+
+			return id;
+		}
+
+		let params = self.resolve_type_params(&typedef.params)?;
+
+		let outer_params =
+			std::mem::replace(&mut self.type_params, params.iter().map(|param| (*param, false)).collect());
+
+		self.resolving.push(ident);
+
+		let kind = self.resolve_typedef_kind(&typedef.inner);
+
+		self.resolving.pop();
+
+		let type_params = std::mem::replace(&mut self.type_params, outer_params);
+
+		let kind = kind?;
+
+		// unused parameters can't be expressed in every language, Rust rejects them
+		if let Some(index) = type_params.iter().position(|(_, used)| !used) {
+			return Err(Error::unused_type(typedef.params[index].clone()));
+		}
+
+		let params = params.into_iter().map(ToString::to_string).collect();
+
+		let annotations = self.resolve_annotations(&typedef.annotations)?;
+
+		let id = self.typedefs.alloc(TypeDef {
+			docs,
+			annotations,
+			ident:ident.to_string(),
+			params,
+			kind,
+			span:typedef.span.clone(),
+		});
+
+		self.ident2id.insert(ident, id);
+
+		self.iface_typedefs.remove(ident);
+
+		Ok(id)
+	}
+
+	fn resolve_typedef_kind(&mut self, inner:&parse::InterfaceItemInner) -> Result<TypeDefKind> {
+		let kind = match inner {
 			parse::InterfaceItemInner::Alias(ty) => {
 				let ty = self.resolve_type(ty)?;
 
@@ -109,9 +234,24 @@ impl<'a> Resolver<'a> {
 
 						let ident = self.resolve_ident(&field.ident).to_string();
 
+						let annotations = self.resolve_annotations(&field.annotations)?;
+
 						let ty = self.resolve_type(&field.ty)?;
 
-						Ok(RecordField { docs, id:ident, ty })
+						let default = field
+							.default
+							.as_ref()
+							.map(|value| self.resolve_value(value, &ty, &field.ty))
+							.transpose()?;
+
+						Ok(RecordField {
+							docs,
+							annotations,
+							id:ident,
+							ty,
+							default,
+							span:field.span.clone(),
+						})
 					})
 					.transponse_result::<Vec<_>, _>()?;
 
@@ -169,23 +309,256 @@ impl<'a> Resolver<'a> {
 
 				TypeDefKind::Union(inner)
 			},
-			parse::InterfaceItemInner::Resource(methods) => {
-				let functions = methods
+			parse::InterfaceItemInner::Func(_)
+			| parse::InterfaceItemInner::Resource(_)
+			| parse::InterfaceItemInner::Use(_)
+			| parse::InterfaceItemInner::Export(_)
+			| parse::InterfaceItemInner::Const(_) => unreachable!(),
+		};
+
+		Ok(kind)
+	}
+
+	/// Checks that the literal `value` is valid for `ty`, the resolved form of
+	/// `written`.
+	fn resolve_value(&self, value:&parse::Value, ty:&Type, written:&parse::Type) -> Result<Value> {
+		let invalid = || Error::invalid_value(value.span().clone(), print_ty(self.source, written));
+
+		let text = self.read_span(value.span());
+
+		let value = match (ty, value) {
+			(Type::Id(id), _) => {
+				match &self.typedefs[*id].kind {
+					TypeDefKind::Alias(ty) => return self.resolve_value(value, ty, written),
+					TypeDefKind::Enum(cases) => {
+						let ident = self.resolve_ident(value.span());
+
+						if !matches!(value, parse::Value::Ident(_))
+							|| !cases.iter().any(|case| case.id == ident)
+						{
+							return Err(invalid());
+						}
+
+						Value::Enum(ident.to_string())
+					},
+					_ => return Err(invalid()),
+				}
+			},
+			(Type::Bool, parse::Value::Ident(_)) => {
+				match text {
+					"true" => Value::Bool(true),
+					"false" => Value::Bool(false),
+					_ => return Err(invalid()),
+				}
+			},
+			(Type::Float32 | Type::Float64, parse::Value::Number(_)) => {
+				let float = text.parse::<f64>().map_err(|_| invalid())?;
+
+				// literals too large for the type are parsed as infinity
+				let finite = if *ty == Type::Float32 {
+					text.parse::<f32>().is_ok_and(f32::is_finite)
+				} else {
+					float.is_finite()
+				};
+
+				if !finite {
+					return Err(invalid());
+				}
+
+				Value::Float(float)
+			},
+			(ty, parse::Value::Number(_)) => {
+				let (min, max) = int_range(ty).ok_or_else(invalid)?;
+
+				let int = text.parse::<i128>().map_err(|_| invalid())?;
+
+				if int < min || int > max {
+					return Err(invalid());
+				}
+
+				Value::Int(int)
+			},
+			(Type::Char, parse::Value::String(_)) => {
+				let string = unescape(text).ok_or_else(invalid)?;
+
+				let mut chars = string.chars();
+
+				match (chars.next(), chars.next()) {
+					(Some(char), None) => Value::Char(char),
+					_ => return Err(invalid()),
+				}
+			},
+			(Type::String, parse::Value::String(_)) => {
+				Value::String(unescape(text).ok_or_else(invalid)?)
+			},
+			(Type::Option(_), parse::Value::Ident(span)) if self.resolve_ident(span) == "none" => {
+				Value::None
+			},
+			_ => return Err(invalid()),
+		};
+
+		Ok(value)
+	}
+
+	fn resolve_annotations(&self, annotations:&[parse::Annotation]) -> Result<Vec<Annotation>> {
+		annotations
+			.iter()
+			.map(|annotation| {
+				let args = annotation
+					.args
 					.iter()
-					.map(|method| self.resolve_func(&method.docs, &method.ident, &method.inner))
+					.map(|arg| {
+						Ok(AnnotationArg {
+							key:arg.key.as_ref().map(|key| self.resolve_ident(key).to_string()),
+							value:self.resolve_untyped_value(&arg.value)?,
+						})
+					})
 					.transponse_result::<Vec<_>, _>()?;
 
-				TypeDefKind::Resource(functions)
+				let name = self.resolve_ident(&annotation.name).to_string();
+
+				if name == "deprecated"
+					&& !matches!(args.as_slice(), [] | [AnnotationArg { key: None, value: Value::String(_) }])
+				{
+					return Err(Error::invalid_deprecation(annotation.span.clone()));
+				}
+
+				if name == "capability"
+					&& !matches!(args.as_slice(), [AnnotationArg { key: None, value: Value::String(_) }])
+				{
+					return Err(Error::invalid_capability(annotation.span.clone()));
+				}
+
+				Ok(Annotation { name, args })
+			})
+			.transponse_result::<Vec<_>, _>()
+	}
+
+	/// Resolves a value that has no type to check it against, see
+	/// [`AnnotationArg::value`].
+	fn resolve_untyped_value(&self, value:&parse::Value) -> Result<Value> {
+		let text = self.read_span(value.span());
+
+		let value = match value {
+			parse::Value::Number(span) => {
+				match text.parse::<i128>() {
+					Ok(int) => Value::Int(int),
+					Err(_) => {
+						Value::Float(
+							text.parse().map_err(|_| Error::invalid_value(span.clone(), "float64"))?,
+						)
+					},
+				}
+			},
+			parse::Value::String(span) => {
+				Value::String(unescape(text).ok_or_else(|| Error::invalid_value(span.clone(), "string"))?)
+			},
+			parse::Value::Ident(span) => {
+				match self.resolve_ident(span) {
+					"true" => Value::Bool(true),
+					"false" => Value::Bool(false),
+					"none" => Value::None,
+					ident => Value::Enum(ident.to_string()),
+				}
 			},
-			parse::InterfaceItemInner::Func(_) => unreachable!(),
 		};
 
-		let id = self.typedefs.alloc(TypeDef { docs, ident:ident.to_string(), kind });
+		Ok(value)
+	}
+
+	/// Type parameters share the namespace with types, so the functions
+	/// generated for them in JavaScript can't clash with those of a type.
+	fn resolve_type_params(&self, params:&[Span]) -> Result<Vec<&'a str>> {
+		let mut idents:Vec<&'a str> = Vec::with_capacity(params.len());
+
+		for (idx, param) in params.iter().enumerate() {
+			let ident = self.resolve_ident(param);
+
+			let previous = idents
+				.iter()
+				.position(|previous| *previous == ident)
+				.map(|previous| params[previous].clone())
+				.or_else(|| self.local_idents.get(ident).cloned())
+				.or_else(|| self.iface_uses.get(ident).map(|item| item.ident.clone()));
+
+			if let Some(previous) = previous {
+				return Err(Error::already_defined(params[idx].clone(), previous));
+			}
+
+			idents.push(ident);
+		}
+
+		Ok(idents)
+	}
+
+	/// Resources are allocated before their functions are resolved, as these
+	/// may refer back to the resource itself.
+	fn resolve_resource(
+		&mut self,
+		ident:&'a str,
+		docs:String,
+		annotations:Vec<Annotation>,
+		methods:&[parse::Method],
+		span:&Span,
+	) -> Result<Id<TypeDef>> {
+		let id = self.typedefs.alloc(TypeDef {
+			docs,
+			annotations,
+			ident:ident.to_string(),
+			params:Vec::new(),
+			kind:TypeDefKind::Resource(Resource::default()),
+			span:span.clone(),
+		});
 
 		self.ident2id.insert(ident, id);
 
 		self.iface_typedefs.remove(ident);
 
+		let functions = methods
+			.iter()
+			.map(|method| {
+				if method.kind != parse::MethodKind::Constructor
+					&& self.resolve_ident(&method.ident) == "drop"
+				{
+					return Err(Error::reserved_method(method.ident.clone()));
+				}
+
+				let func = self.resolve_func(
+					&method.docs,
+					&method.annotations,
+					&method.ident,
+					&method.inner,
+					&method.span,
+				)?;
+
+				Ok((method, func))
+			})
+			.transponse_result::<Vec<_>, _>()?;
+
+		let mut resource = Resource::default();
+
+		let mut constructor:Option<&Span> = None;
+
+		for (method, func) in functions {
+			match method.kind {
+				parse::MethodKind::Method => resource.methods.push(func),
+				parse::MethodKind::Static => resource.statics.push(func),
+				parse::MethodKind::Constructor => {
+					if let Some(previous) = constructor {
+						return Err(Error::already_defined(method.ident.clone(), previous.clone()));
+					}
+
+					constructor = Some(&method.ident);
+
+					// the constructor returns a handle to the resource it created
+					resource.constructor =
+						Some(Function { result:Some(FunctionResult::Anon(Type::Id(id))), ..func });
+				},
+			}
+		}
+
+		self.typedefs[id].kind = TypeDefKind::Resource(resource);
+
 		Ok(id)
 	}
 
@@ -207,6 +580,7 @@ impl<'a> Resolver<'a> {
 			parse::Type::Char => Type::Char,
 			parse::Type::String => Type::String,
 			parse::Type::List(ty) => Type::List(Box::new(self.resolve_type(ty)?)),
+			parse::Type::Stream { span, .. } => return Err(Error::misplaced_stream(span.clone())),
 			parse::Type::Option(ty) => Type::Option(Box::new(self.resolve_type(ty)?)),
 			parse::Type::Tuple(types) => {
 				let types = types
@@ -226,170 +600,585 @@ impl<'a> Resolver<'a> {
 			parse::Type::Id(span) => {
 				let ident = self.resolve_ident(span);
 
-				if let Some(id) = self.ident2id.get(ident) {
-					Type::Id(*id)
-				} else {
-					let typedef = self.iface_typedefs.get(ident).ok_or_else(|| {
-						let expected = lex::Token::TYPE_KEYWORD
-							.iter()
-							.map(lex::Token::as_str)
-							.chain(self.iface_typedefs.keys().map(|str| &**str));
-
-						let suggestions = find_similar(expected, ident);
-
-						if suggestions.is_empty() {
-							Error::not_defined(span.clone())
-						} else {
-							Error::not_defined_with_help(
-								span.clone(),
-								format!("Did you mean \"{}\"?", print_list(suggestions)),
-							)
-						}
-					})?;
+				if let Some(param) = self.type_params.iter_mut().find(|(param, _)| *param == ident) {
+					param.1 = true;
+
+					return Ok(Type::Param(ident.to_string()));
+				}
 
-					let id = self.resolve_typedef(&typedef.clone())?; // TODO: avoid clone
+				let id = self.resolve_id(span)?;
 
-					Type::Id(id)
+				let expected = self.typedefs[id].params.len();
+
+				if expected != 0 {
+					return Err(Error::type_arguments(span.clone(), expected, 0));
+				}
+
+				Type::Id(id)
+			},
+			parse::Type::Generic { ident, args } => {
+				let id = self.resolve_id(ident)?;
+
+				let args =
+					args.iter().map(|ty| self.resolve_type(ty)).transponse_result::<Vec<_>, _>()?;
+
+				let expected = self.typedefs[id].params.len();
+
+				if args.len() != expected {
+					return Err(Error::type_arguments(ident.clone(), expected, args.len()));
 				}
+
+				Type::Generic { id, args }
 			},
 		};
 
 		Ok(ty)
 	}
 
-	fn resolve_named_types(
-		&mut self,
-		named_types:&[(Span, parse::Type)],
-	) -> Result<Vec<(String, Type)>> {
-		named_types
-			.iter()
-			.map(|(ident, ty)| {
-				let ident = self.resolve_ident(ident).to_string();
+	/// Looks up the typedef named at `span`, resolving it if necessary.
+	fn resolve_id(&mut self, span:&Span) -> Result<Id<TypeDef>> {
+		let ident = self.resolve_ident(span);
 
-				let ty = self.resolve_type(ty)?;
+		if let Some(id) = self.ident2id.get(ident) {
+			return Ok(*id);
+		}
 
-				Ok((ident, ty))
-			})
-			.transponse_result::<Vec<_>, _>()
-			.map_err(Into::into)
+		if let Some(item) = self.iface_uses.get(ident) {
+			return self.resolve_use(ident, &item.clone());
+		}
+
+		if self.resolving.contains(&ident) {
+			return Err(Error::recursive_type(span.clone()));
+		}
+
+		let typedef = self.iface_typedefs.get(ident).ok_or_else(|| {
+			let expected = lex::Token::TYPE_KEYWORD
+				.iter()
+				.map(lex::Token::as_str)
+				.chain(self.iface_typedefs.keys().map(|str| &**str))
+				.chain(self.iface_uses.keys().map(|str| &**str));
+
+			let suggestions = find_similar(expected, ident);
+
+			if suggestions.is_empty() {
+				Error::not_defined(span.clone())
+			} else {
+				Error::not_defined_with_help(
+					span.clone(),
+					format!("Did you mean \"{}\"?", print_list(suggestions)),
+				)
+			}
+		})?;
+
+		self.resolve_typedef(&typedef.clone()) // TODO: avoid clone
 	}
 
-	fn resolve_func(&mut self, docs:&[Span], ident:&Span, func:&parse::Func) -> Result<Function> {
-		let docs = self.resolve_docs(docs);
+	fn resolve_use(&mut self, ident:&'a str, item:&UseItem) -> Result<Id<TypeDef>> {
+		let iface_ident = self.resolve_ident(&item.iface);
 
-		let ident = self.resolve_ident(ident).to_string();
+		let iface = self.imports.get(iface_ident).copied().ok_or_else(|| {
+			let suggestions = find_similar(self.imports.keys(), iface_ident);
 
-		let params = self.resolve_named_types(&func.params)?;
+			if suggestions.is_empty() {
+				Error::not_defined(item.iface.clone())
+			} else {
+				Error::not_defined_with_help(
+					item.iface.clone(),
+					format!("Did you mean \"{}\"?", print_list(suggestions)),
+				)
+			}
+		})?;
 
-		let result = match &func.result {
-			None => None,
-			Some(parse::FuncResult::Anon(ty)) => {
-				let ty = self.resolve_type(ty)?;
+		let name = self.resolve_ident(&item.name);
 
-				Some(FunctionResult::Anon(ty))
-			},
-			Some(parse::FuncResult::Named(types)) => {
-				let types = self.resolve_named_types(types)?;
+		let (id, _) =
+			iface.typedefs.iter().find(|(_, typedef)| typedef.ident == name).ok_or_else(|| {
+				let suggestions = find_similar(
+					iface.typedefs.iter().map(|(_, typedef)| typedef.ident.as_str()),
+					name,
+				);
 
-				Some(FunctionResult::Named(types))
-			},
-		};
+				if suggestions.is_empty() {
+					Error::not_defined(item.name.clone())
+				} else {
+					Error::not_defined_with_help(
+						item.name.clone(),
+						format!("Did you mean \"{}\"?", print_list(suggestions)),
+					)
+				}
+			})?;
+
+		let id = self.import_typedef(iface, id, ident, &item.ident)?;
+
+		self.ident2id.insert(ident, id);
+
+		self.iface_uses.remove(ident);
 
-		Ok(Function { docs, id:ident, params, result })
+		Ok(id)
 	}
 
-	fn verify_not_recursive(
-		&self,
-		ident:Span,
+	/// Copies a typedef (and all typedefs it refers to) from an imported
+	/// interface into this one.
+	fn import_typedef(
+		&mut self,
+		iface:&'a Interface,
 		id:Id<TypeDef>,
-		visiting:&mut HashSet<Id<TypeDef>>,
-		valid:&mut HashSet<Id<TypeDef>>,
-	) -> Result<()> {
-		if valid.contains(&id) {
-			return Ok(());
+		ident:&'a str,
+		location:&Span,
+	) -> Result<Id<TypeDef>> {
+		if let Some(id) = self.imported.get(&(iface.ident.as_str(), id)) {
+			return Ok(*id);
 		}
 
-		if !visiting.insert(id) {
-			return Err(Error::recursive_type(ident));
+		// imported types share the namespace with local definitions
+		if let Some(previous) = self.local_idents.get(ident) {
+			return Err(Error::already_defined(location.clone(), previous.clone()));
 		}
 
-		match &self.typedefs[id].kind {
+		let typedef = &iface.typedefs[id];
+
+		if let TypeDefKind::Resource(resource) = &typedef.kind {
+			return self.import_resource(iface, id, ident, resource, location);
+		}
+
+		let kind = match &typedef.kind {
+			TypeDefKind::Alias(ty) => TypeDefKind::Alias(self.import_type(iface, ty, location)?),
 			TypeDefKind::Record(fields) => {
-				for field in fields {
-					if let Type::Id(id) = field.ty {
-						self.verify_not_recursive(ident.clone(), id, visiting, valid)?;
-					}
-				}
-			},
-			TypeDefKind::Union(cases) => {
-				for case in cases {
-					if let Type::Id(id) = case.ty {
-						self.verify_not_recursive(ident.clone(), id, visiting, valid)?;
-					}
-				}
+				let fields = fields
+					.iter()
+					.map(|field| {
+						Ok(RecordField {
+							docs:field.docs.clone(),
+							annotations:field.annotations.clone(),
+							id:field.id.clone(),
+							ty:self.import_type(iface, &field.ty, location)?,
+							default:field.default.clone(),
+							span:location.clone(),
+						})
+					})
+					.collect::<Result<_>>()?;
+
+				TypeDefKind::Record(fields)
 			},
 			TypeDefKind::Variant(cases) => {
-				for case in cases {
-					if let Some(Type::Id(id)) = case.ty {
-						self.verify_not_recursive(ident.clone(), id, visiting, valid)?;
-					}
-				}
+				let cases = cases
+					.iter()
+					.map(|case| {
+						let ty = case
+							.ty
+							.as_ref()
+							.map(|ty| self.import_type(iface, ty, location))
+							.transpose()?;
+
+						Ok(VariantCase { docs:case.docs.clone(), id:case.id.clone(), ty })
+					})
+					.collect::<Result<_>>()?;
+
+				TypeDefKind::Variant(cases)
 			},
-			TypeDefKind::Alias(ty) => {
-				match ty {
-					Type::Tuple(types) => {
-						for ty in types {
-							if let Type::Id(id) = ty {
-								self.verify_not_recursive(ident.clone(), *id, visiting, valid)?;
-							}
-						}
-					},
-					Type::List(ty) | Type::Option(ty) => {
-						if let Type::Id(id) = **ty {
-							self.verify_not_recursive(ident, id, visiting, valid)?;
-						}
-					},
-					Type::Result { ok, err } => {
-						if let Some(Type::Id(id)) = ok.as_deref() {
-							self.verify_not_recursive(ident.clone(), *id, visiting, valid)?;
-						}
+			TypeDefKind::Union(cases) => {
+				let cases = cases
+					.iter()
+					.map(|case| {
+						Ok(UnionCase {
+							docs:case.docs.clone(),
+							ty:self.import_type(iface, &case.ty, location)?,
+						})
+					})
+					.collect::<Result<_>>()?;
 
-						if let Some(Type::Id(id)) = err.as_deref() {
-							self.verify_not_recursive(ident, *id, visiting, valid)?;
-						}
-					},
-					_ => {},
-				}
+				TypeDefKind::Union(cases)
 			},
-			_ => {},
-		}
+			kind @ (TypeDefKind::Flags(_) | TypeDefKind::Enum(_)) => kind.clone(),
+			TypeDefKind::Resource(_) => unreachable!(),
+		};
 
-		valid.insert(id);
+		let new_id = self.typedefs.alloc(TypeDef {
+			docs:typedef.docs.clone(),
+			annotations:typedef.annotations.clone(),
+			ident:ident.to_string(),
+			params:typedef.params.clone(),
+			kind,
+			span:location.clone(),
+		});
 
-		visiting.remove(&id);
+		self.imported.insert((iface.ident.as_str(), id), new_id);
 
-		Ok(())
+		Ok(new_id)
 	}
 
-	pub fn resolve(mut self, rest_data:RestInterface) -> Result<Interface> {
-		let docs = self.resolve_docs(&rest_data.docs);
-
-		let ident = self.resolve_ident(&rest_data.ident).to_string();
+	/// Like [`Self::resolve_resource`], imported resources are allocated first
+	/// so their functions can refer back to them.
+	fn import_resource(
+		&mut self,
+		iface:&'a Interface,
+		id:Id<TypeDef>,
+		ident:&'a str,
+		resource:&Resource,
+		location:&Span,
+	) -> Result<Id<TypeDef>> {
+		let new_id = self.typedefs.alloc(TypeDef {
+			docs:iface.typedefs[id].docs.clone(),
+			annotations:iface.typedefs[id].annotations.clone(),
+			ident:ident.to_string(),
+			params:Vec::new(),
+			kind:TypeDefKind::Resource(Resource::default()),
+			span:location.clone(),
+		});
+
+		self.imported.insert((iface.ident.as_str(), id), new_id);
+
+		let constructor = resource
+			.constructor
+			.as_ref()
+			.map(|func| self.import_function(iface, func, location))
+			.transpose()?;
+
+		let methods = resource
+			.methods
+			.iter()
+			.map(|func| self.import_function(iface, func, location))
+			.collect::<Result<_>>()?;
 
-		let ident2span = self
-			.iface_typedefs
+		let statics = resource
+			.statics
 			.iter()
-			.map(|(ident, item)| (*ident, item.ident.clone()))
-			.collect::<HashMap<_, _>>();
+			.map(|func| self.import_function(iface, func, location))
+			.collect::<Result<_>>()?;
 
-		let mut functions = Vec::new();
+		let resource = Resource { constructor, methods, statics };
 
-		for item in rest_data.functions {
-			if let parse::InterfaceItemInner::Func(func) = &item.inner {
-				let func = self.resolve_func(&item.docs, &item.ident, func)?;
+		self.typedefs[new_id].kind = TypeDefKind::Resource(resource);
 
-				functions.push(func);
-			}
+		Ok(new_id)
+	}
+
+	fn import_type(&mut self, iface:&'a Interface, ty:&Type, location:&Span) -> Result<Type> {
+		let ty = match ty {
+			Type::List(ty) => Type::List(Box::new(self.import_type(iface, ty, location)?)),
+			Type::Stream(ty) => Type::Stream(Box::new(self.import_type(iface, ty, location)?)),
+			Type::Option(ty) => Type::Option(Box::new(self.import_type(iface, ty, location)?)),
+			Type::Tuple(types) => {
+				let types = types
+					.iter()
+					.map(|ty| self.import_type(iface, ty, location))
+					.collect::<Result<_>>()?;
+
+				Type::Tuple(types)
+			},
+			Type::Result { ok, err } => {
+				let ok = ok.as_ref().map(|ty| self.import_type(iface, ty, location)).transpose()?;
+
+				let err = err.as_ref().map(|ty| self.import_type(iface, ty, location)).transpose()?;
+
+				Type::Result { ok:ok.map(Box::new), err:err.map(Box::new) }
+			},
+			Type::Id(id) => {
+				let ident = iface.typedefs[*id].ident.as_str();
+
+				Type::Id(self.import_typedef(iface, *id, ident, location)?)
+			},
+			Type::Generic { id, args } => {
+				let ident = iface.typedefs[*id].ident.as_str();
+
+				let id = self.import_typedef(iface, *id, ident, location)?;
+
+				let args = args
+					.iter()
+					.map(|ty| self.import_type(iface, ty, location))
+					.collect::<Result<_>>()?;
+
+				Type::Generic { id, args }
+			},
+			ty => ty.clone(),
+		};
+
+		Ok(ty)
+	}
+
+	fn import_function(
+		&mut self,
+		iface:&'a Interface,
+		func:&Function,
+		location:&Span,
+	) -> Result<Function> {
+		let params = func
+			.params
+			.iter()
+			.map(|(ident, ty)| Ok((ident.clone(), self.import_type(iface, ty, location)?)))
+			.collect::<Result<_>>()?;
+
+		let result = match &func.result {
+			None => None,
+			Some(FunctionResult::Anon(ty)) => {
+				Some(FunctionResult::Anon(self.import_type(iface, ty, location)?))
+			},
+			Some(FunctionResult::Named(types)) => {
+				let types = types
+					.iter()
+					.map(|(ident, ty)| Ok((ident.clone(), self.import_type(iface, ty, location)?)))
+					.collect::<Result<_>>()?;
+
+				Some(FunctionResult::Named(types))
+			},
+		};
+
+		Ok(Function {
+			docs:func.docs.clone(),
+			annotations:func.annotations.clone(),
+			id:func.id.clone(),
+			params,
+			result,
+			span:location.clone(),
+		})
+	}
+
+	fn resolve_named_types(
+		&mut self,
+		named_types:&[parse::NamedType],
+	) -> Result<Vec<(String, Type)>> {
+		named_types
+			.iter()
+			.map(|named| {
+				let ident = self.resolve_ident(&named.ident).to_string();
+
+				let ty = self.resolve_type(&named.ty)?;
+
+				Ok((ident, ty))
+			})
+			.transponse_result::<Vec<_>, _>()
+			.map_err(Into::into)
+	}
+
+	fn resolve_func(
+		&mut self,
+		docs:&[Span],
+		annotations:&[parse::Annotation],
+		ident:&Span,
+		func:&parse::Func,
+		span:&Span,
+	) -> Result<Function> {
+		let docs = self.resolve_docs(docs);
+
+		let annotations = self.resolve_annotations(annotations)?;
+
+		let ident = self.resolve_ident(ident).to_string();
+
+		let params = self.resolve_named_types(&func.params)?;
+
+		let result = match &func.result {
+			None => None,
+			// streams are only valid as the sole result of a function
+			Some(parse::FuncResult::Anon(parse::Type::Stream { ty, .. })) => {
+				let ty = self.resolve_type(ty)?;
+
+				Some(FunctionResult::Anon(Type::Stream(Box::new(ty))))
+			},
+			Some(parse::FuncResult::Anon(ty)) => {
+				let ty = self.resolve_type(ty)?;
+
+				Some(FunctionResult::Anon(ty))
+			},
+			Some(parse::FuncResult::Named(types)) => {
+				let types = self.resolve_named_types(types)?;
+
+				Some(FunctionResult::Named(types))
+			},
+		};
+
+		Ok(Function { docs, annotations, id:ident, params, result, span:span.clone() })
+	}
+
+	/// Collects the identifiers of all not yet resolved typedefs and `use` names
+	/// that skipped functions refer to, directly or through other typedefs.
+	fn skipped_idents(&self) -> HashSet<&'a str> {
+		let mut stack = Vec::new();
+
+		for func in &self.skipped {
+			push_func_types(func, &mut stack);
+		}
+
+		let mut idents = HashSet::new();
+
+		while let Some(ty) = stack.pop() {
+			match ty {
+				parse::Type::List(ty)
+				| parse::Type::Option(ty)
+				| parse::Type::Stream { ty, .. } => stack.push(ty),
+				parse::Type::Tuple(types) => stack.extend(types),
+				parse::Type::Result { ok, err } => {
+					stack.extend(ok.as_deref());
+
+					stack.extend(err.as_deref());
+				},
+				parse::Type::Id(span) | parse::Type::Generic { ident:span, .. } => {
+					if let parse::Type::Generic { args, .. } = ty {
+						stack.extend(args);
+					}
+
+					let ident = self.resolve_ident(span);
+
+					if idents.insert(ident) {
+						if let Some(item) = self.iface_typedefs.get(ident) {
+							push_item_types(&item.inner, &mut stack);
+						}
+					}
+				},
+				_ => {},
+			}
+		}
+
+		idents
+	}
+
+	fn verify_not_recursive(
+		&self,
+		ident:Span,
+		id:Id<TypeDef>,
+		visiting:&mut HashSet<Id<TypeDef>>,
+		valid:&mut HashSet<Id<TypeDef>>,
+	) -> Result<()> {
+		if valid.contains(&id) {
+			return Ok(());
+		}
+
+		if !visiting.insert(id) {
+			return Err(Error::recursive_type(ident));
+		}
+
+		let mut referenced = Vec::new();
+
+		match &self.typedefs[id].kind {
+			TypeDefKind::Record(fields) => {
+				for field in fields {
+					referenced_ids(&field.ty, &mut referenced);
+				}
+			},
+			TypeDefKind::Union(cases) => {
+				for case in cases {
+					referenced_ids(&case.ty, &mut referenced);
+				}
+			},
+			TypeDefKind::Variant(cases) => {
+				for ty in cases.iter().filter_map(|case| case.ty.as_ref()) {
+					referenced_ids(ty, &mut referenced);
+				}
+			},
+			TypeDefKind::Alias(ty) => {
+				match ty {
+					Type::Tuple(types) => {
+						for ty in types {
+							referenced_ids(ty, &mut referenced);
+						}
+					},
+					Type::List(ty) | Type::Option(ty) => referenced_ids(ty, &mut referenced),
+					Type::Result { ok, err } => {
+						for ty in ok.iter().chain(err) {
+							referenced_ids(ty, &mut referenced);
+						}
+					},
+					Type::Generic { .. } => referenced_ids(ty, &mut referenced),
+					_ => {},
+				}
+			},
+			_ => {},
+		}
+
+		for referenced in referenced {
+			self.verify_not_recursive(ident.clone(), referenced, visiting, valid)?;
+		}
+
+		valid.insert(id);
+
+		visiting.remove(&id);
+
+		Ok(())
+	}
+
+	pub fn resolve(mut self, rest_data:RestInterface) -> Result<Interface> {
+		let docs = self.resolve_docs(&rest_data.docs);
+
+		let ident = self.resolve_ident(&rest_data.ident).to_string();
+
+		// a name brought into scope by `use` must not collide with a local definition
+		self.iface_uses
+			.iter()
+			.filter_map(|(ident, item)| {
+				self.local_idents
+					.get(ident)
+					.map(|previous| Err(Error::already_defined(item.ident.clone(), previous.clone())))
+			})
+			.transponse_result::<Vec<_>, ()>()?;
+
+		let mut functions = Vec::new();
+
+		let mut exports = Vec::new();
+
+		let mut export_idents:HashMap<&str, Span> = HashMap::new();
+
+		let mut constants = Vec::new();
+
+		let mut constant_idents:HashMap<&str, Span> = HashMap::new();
+
+		for item in rest_data.functions {
+			match &item.inner {
+				parse::InterfaceItemInner::Func(func) => {
+					let func = self.resolve_func(
+						&item.docs,
+						&item.annotations,
+						&item.ident,
+						func,
+						&item.span,
+					)?;
+
+					functions.push(func);
+				},
+				parse::InterfaceItemInner::Export(funcs) => {
+					for func in funcs {
+						// the host awaits a single reply from the webview
+						if let Some(parse::FuncResult::Anon(parse::Type::Stream { span, .. })) =
+							&func.inner.result
+						{
+							return Err(Error::misplaced_stream(span.clone()));
+						}
+
+						// all export sections of an interface share one namespace
+						let ident = self.resolve_ident(&func.ident);
+
+						if let Some(previous) = export_idents.insert(ident, func.ident.clone()) {
+							return Err(Error::already_defined(func.ident.clone(), previous));
+						}
+
+						exports.push(self.resolve_func(
+							&func.docs,
+							&func.annotations,
+							&func.ident,
+							&func.inner,
+							&func.span,
+						)?);
+					}
+				},
+				parse::InterfaceItemInner::Const(konst) => {
+					let ident = self.resolve_ident(&item.ident);
+
+					if let Some(previous) = constant_idents.insert(ident, item.ident.clone()) {
+						return Err(Error::already_defined(item.ident.clone(), previous));
+					}
+
+					let ty = self.resolve_type(&konst.ty)?;
+
+					let value = self.resolve_value(&konst.value, &ty, &konst.ty)?;
+
+					constants.push(Constant {
+						docs:self.resolve_docs(&item.docs),
+						annotations:self.resolve_annotations(&item.annotations)?,
+						id:ident.to_string(),
+						ty,
+						value,
+					});
+				},
+				_ => {},
+			}
 		}
 
 		let mut visiting = HashSet::new();
@@ -397,32 +1186,148 @@ impl<'a> Resolver<'a> {
 		let mut valid_types = HashSet::new();
 
 		for (id, typedef) in &self.typedefs {
-			let ident = ident2span[typedef.ident.as_str()].clone();
+			// imported types have already been checked in the interface defining them
+			let Some(ident) = self.local_idents.get(typedef.ident.as_str()) else {
+				continue;
+			};
 
-			self.verify_not_recursive(ident, id, &mut visiting, &mut valid_types)?;
+			self.verify_not_recursive(ident.clone(), id, &mut visiting, &mut valid_types)?;
 		}
 
-		if !self.iface_typedefs.is_empty() {
+		if !self.iface_typedefs.is_empty() || !self.iface_uses.is_empty() {
+			// types only used by skipped functions are dropped silently
+			let skipped = self.skipped_idents();
+
 			// we use `partition_result` here to aggregate all errors before
 			// throwing them, this way all errors are reported together
 			// instead of one by one.
 			self.iface_typedefs
 				.values()
-				.map(|item| Err(Error::unused_type(item.ident.clone())))
-				.transponse_result::<Vec<_>, &parse::InterfaceItem>()?;
+				.map(|item| item.ident.clone())
+				.chain(self.iface_uses.values().map(|item| item.ident.clone()))
+				.filter(|span| !skipped.contains(self.resolve_ident(span)))
+				.map(|span| Err(Error::unused_type(span)))
+				.transponse_result::<Vec<_>, ()>()?;
+		}
+
+		Ok(Interface { docs, ident, functions, exports, constants, typedefs:self.typedefs })
+	}
+}
+
+/// The smallest and largest values of integer types, `u128` values are limited
+/// to those that fit an `i128`.
+fn int_range(ty:&Type) -> Option<(i128, i128)> {
+	let range = match ty {
+		Type::U8 => (0, u8::MAX.into()),
+		Type::U16 => (0, u16::MAX.into()),
+		Type::U32 => (0, u32::MAX.into()),
+		Type::U64 => (0, u64::MAX.into()),
+		Type::U128 => (0, i128::MAX),
+		Type::S8 => (i8::MIN.into(), i8::MAX.into()),
+		Type::S16 => (i16::MIN.into(), i16::MAX.into()),
+		Type::S32 => (i32::MIN.into(), i32::MAX.into()),
+		Type::S64 => (i64::MIN.into(), i64::MAX.into()),
+		Type::S128 => (i128::MIN, i128::MAX),
+		_ => return None,
+	};
+
+	Some(range)
+}
+
+/// Removes the quotes around a string literal and resolves its escapes.
+fn unescape(literal:&str) -> Option<String> {
+	let mut chars = literal[1..literal.len() - 1].chars();
+
+	let mut out = String::new();
+
+	while let Some(char) = chars.next() {
+		if char != '\\' {
+			out.push(char);
+
+			continue;
 		}
 
-		Ok(Interface { docs, ident, functions, typedefs:self.typedefs })
+		out.push(match chars.next()? {
+			'n' => '\n',
+			'r' => '\r',
+			't' => '\t',
+			char @ ('"' | '\\') => char,
+			_ => return None,
+		});
+	}
+
+	Some(out)
+}
+
+/// Collects the typedefs `ty` refers to directly. The arguments of generic
+/// types count as well, as their parameters may be used directly.
+fn referenced_ids(ty:&Type, ids:&mut Vec<Id<TypeDef>>) {
+	match ty {
+		Type::Id(id) => ids.push(*id),
+		Type::Generic { id, args } => {
+			ids.push(*id);
+
+			for ty in args {
+				referenced_ids(ty, ids);
+			}
+		},
+		_ => {},
+	}
+}
+
+fn push_func_types<'a>(func:&'a parse::Func, stack:&mut Vec<&'a parse::Type>) {
+	stack.extend(func.params.iter().map(|param| &param.ty));
+
+	match &func.result {
+		Some(parse::FuncResult::Anon(ty)) => stack.push(ty),
+		Some(parse::FuncResult::Named(types)) => stack.extend(types.iter().map(|named| &named.ty)),
+		None => {},
+	}
+}
+
+fn push_item_types<'a>(item:&'a parse::InterfaceItemInner, stack:&mut Vec<&'a parse::Type>) {
+	match item {
+		parse::InterfaceItemInner::Alias(ty) => stack.push(ty),
+		parse::InterfaceItemInner::Record(fields) => {
+			stack.extend(fields.iter().map(|field| &field.ty));
+		},
+		parse::InterfaceItemInner::Variant(cases) => {
+			stack.extend(cases.iter().filter_map(|case| case.ty.as_ref()));
+		},
+		parse::InterfaceItemInner::Union(cases) => stack.extend(cases.iter().map(|case| &case.ty)),
+		parse::InterfaceItemInner::Resource(methods) => {
+			for method in methods {
+				push_func_types(&method.inner, stack);
+			}
+		},
+		parse::InterfaceItemInner::Func(func) => push_func_types(func, stack),
+		parse::InterfaceItemInner::Const(konst) => stack.push(&konst.ty),
+		parse::InterfaceItemInner::Export(funcs) => {
+			for func in funcs {
+				push_func_types(&func.inner, stack);
+			}
+		},
+		parse::InterfaceItemInner::Flags(_)
+		| parse::InterfaceItemInner::Enum(_)
+		| parse::InterfaceItemInner::Use(_) => {},
 	}
 }
 
 #[cfg(test)]
 mod test {
-	use logos::Lexer;
-	use parse::FromTokens;
+	use parse::{FromTokens, Tokens};
 
 	use super::*;
 
+	/// The errors aggregated into `err`, which is wrapped in [`Error::Multi`]
+	/// once for every list it was collected through.
+	fn flatten(err:&Error) -> Vec<&Error> {
+		match err {
+			Error::Multi { errors } => errors.iter().flat_map(flatten).collect(),
+			err => vec![err],
+		}
+	}
+
 	#[test]
 	fn interface_() -> Result<()> {
 		let source = "interface chars {
@@ -432,7 +1337,7 @@ mod test {
             func return_char() -> char
           }";
 
-		let mut tokens = Lexer::new(source).spanned().peekable();
+		let mut tokens = Tokens::new(source);
 
 		let iface = parse::Interface::parse(&mut tokens)?;
 
@@ -459,7 +1364,7 @@ mod test {
             func fn(x: a) -> nested
           }";
 
-		let mut tokens = Lexer::new(source).spanned().peekable();
+		let mut tokens = Tokens::new(source);
 
 		let iface = parse::Interface::parse(&mut tokens)?;
 
@@ -472,11 +1377,689 @@ mod test {
 		Ok(())
 	}
 
+	#[test]
+	fn use_() -> Result<()> {
+		let other_source = "interface other {
+            record nested {
+                bar: string
+            }
+
+            record a {
+                foo: nested
+            }
+
+            func fn(x: a)
+          }";
+
+		let mut tokens = Tokens::new(other_source);
+
+		let other = parse::Interface::parse(&mut tokens)?;
+
+		let (resolver, rest_data) = Resolver::new(other_source, other);
+
+		let other = resolver.resolve(rest_data)?;
+
+		let source = "interface test {
+            use other.{a as b}
+
+            func fn(x: b)
+          }";
+
+		let mut tokens = Tokens::new(source);
+
+		let iface = parse::Interface::parse(&mut tokens)?;
+
+		let (resolver, rest_data) = Resolver::new(source, iface);
+
+		let iface = resolver.with_imports([&other]).resolve(rest_data)?;
+
+		let idents = iface
+			.typedefs
+			.iter()
+			.map(|(_, typedef)| typedef.ident.as_str())
+			.collect::<Vec<_>>();
+
+		assert_eq!(idents, vec!["nested", "b"]);
+
+		Ok(())
+	}
+
+	#[test]
+	fn skip() -> Result<()> {
+		let source = "interface test {
+            record a {
+                foo: nested
+            }
+
+            record nested {
+                bar: string
+            }
+
+            resource res {
+                func method(x: nested)
+            }
+
+            func keep(x: res)
+            func skipped(x: a)
+          }";
+
+		let mut tokens = Tokens::new(source);
+
+		let mut iface = parse::Interface::parse(&mut tokens)?;
+
+		let skip = HashSet::from(["skipped".to_string(), "res.method".to_string()]);
+
+		let names = iface.skip(source, |name| skip.contains(name));
+
+		assert_eq!(names, vec!["res.method", "keep", "skipped"]);
+
+		let (resolver, rest_data) = Resolver::new(source, iface);
+
+		let iface = resolver.resolve(rest_data)?;
+
+		assert_eq!(iface.functions.len(), 1);
+
+		let idents = iface
+			.typedefs
+			.iter()
+			.map(|(_, typedef)| typedef.ident.as_str())
+			.collect::<Vec<_>>();
+
+		assert_eq!(idents, vec!["res"]);
+
+		Ok(())
+	}
+
+	#[test]
+	fn resource() -> Result<()> {
+		let source = "interface test {
+            resource a {
+                constructor(x: u32)
+                static func open(path: string) -> a
+                func duplicate() -> a
+            }
+
+            func fn(x: a)
+          }";
+
+		let mut tokens = Tokens::new(source);
+
+		let iface = parse::Interface::parse(&mut tokens)?;
+
+		let (resolver, rest_data) = Resolver::new(source, iface);
+
+		let iface = resolver.resolve(rest_data)?;
+
+		let (id, typedef) = iface.typedefs.iter().next().unwrap();
+
+		let TypeDefKind::Resource(resource) = &typedef.kind else {
+			panic!("expected a resource");
+		};
+
+		let constructor = resource.constructor.as_ref().unwrap();
+
+		assert_eq!(constructor.params, vec![("x".to_string(), Type::U32)]);
+
+		assert_eq!(constructor.result, Some(FunctionResult::Anon(Type::Id(id))));
+
+		assert_eq!(resource.methods[0].id, "duplicate");
+
+		assert_eq!(resource.statics[0].id, "open");
+
+		Ok(())
+	}
+
+	#[test]
+	fn resource_drop_reserved() -> Result<()> {
+		let source = "interface test {
+            resource a {
+                func drop()
+            }
+
+            func fn(x: a)
+          }";
+
+		let mut tokens = Tokens::new(source);
+
+		let iface = parse::Interface::parse(&mut tokens)?;
+
+		let (resolver, rest_data) = Resolver::new(source, iface);
+
+		let err = resolver.resolve(rest_data).unwrap_err();
+
+		assert!(matches!(flatten(&err)[..], [Error::ReservedMethod { .. }]), "{err:?}");
+
+		Ok(())
+	}
+
+	#[test]
+	fn stream() -> Result<()> {
+		let source = "interface test {
+            func watch(path: string) -> stream<u32>
+          }";
+
+		let mut tokens = Tokens::new(source);
+
+		let iface = parse::Interface::parse(&mut tokens)?;
+
+		let (resolver, rest_data) = Resolver::new(source, iface);
+
+		let iface = resolver.resolve(rest_data)?;
+
+		assert_eq!(
+			iface.functions[0].result,
+			Some(FunctionResult::Anon(Type::Stream(Box::new(Type::U32))))
+		);
+
+		for source in [
+			"interface test { func f(x: stream<u32>) }",
+			"interface test { func f() -> list<stream<u32>> }",
+			"interface test { func f() -> stream<stream<u32>> }",
+		] {
+			let mut tokens = Tokens::new(source);
+
+			let iface = parse::Interface::parse(&mut tokens)?;
+
+			let (resolver, rest_data) = Resolver::new(source, iface);
+
+			let err = resolver.resolve(rest_data).unwrap_err();
+
+			assert!(format!("{err:?}").contains("MisplacedStream"), "{source}");
+		}
+
+		Ok(())
+	}
+
+	#[test]
+	fn export() -> Result<()> {
+		let source = "interface test {
+            record choice {
+                label: string
+            }
+
+            func fn()
+
+            export {
+                func pick(choices: list<choice>) -> option<u32>
+            }
+
+            export {
+                func notify(message: string)
+            }
+          }";
+
+		let mut tokens = Tokens::new(source);
+
+		let iface = parse::Interface::parse(&mut tokens)?;
+
+		let (resolver, rest_data) = Resolver::new(source, iface);
+
+		let iface = resolver.resolve(rest_data)?;
+
+		assert_eq!(iface.functions.len(), 1);
+
+		let idents = iface.exports.iter().map(|func| func.id.as_str()).collect::<Vec<_>>();
+
+		assert_eq!(idents, vec!["pick", "notify"]);
+
+		for (source, error) in [
+			(
+				"interface test { export { func f() } export { func f() } }",
+				"AlreadyDefined",
+			),
+			("interface test { export { func f() -> stream<u32> } }", "MisplacedStream"),
+		] {
+			let mut tokens = Tokens::new(source);
+
+			let iface = parse::Interface::parse(&mut tokens)?;
+
+			let (resolver, rest_data) = Resolver::new(source, iface);
+
+			let err = resolver.resolve(rest_data).unwrap_err();
+
+			assert!(format!("{err:?}").contains(error), "{source}");
+		}
+
+		Ok(())
+	}
+
+	#[test]
+	fn generic() -> Result<()> {
+		let source = "interface test {
+            record user {
+                name: string
+            }
+
+            record page<t> {
+                items: list<t>,
+                next: option<u32>
+            }
+
+            func users() -> page<user>
+          }";
+
+		let mut tokens = Tokens::new(source);
+
+		let iface = parse::Interface::parse(&mut tokens)?;
+
+		let (resolver, rest_data) = Resolver::new(source, iface);
+
+		let iface = resolver.resolve(rest_data)?;
+
+		let (page, typedef) = iface.typedefs.iter().find(|(_, typedef)| typedef.ident == "page").unwrap();
+
+		assert_eq!(typedef.params, vec!["t".to_string()]);
+
+		let TypeDefKind::Record(fields) = &typedef.kind else { panic!("expected a record") };
+
+		assert_eq!(fields[0].ty, Type::List(Box::new(Type::Param("t".to_string()))));
+
+		let Some(FunctionResult::Anon(Type::Generic { id, args })) = &iface.functions[0].result else {
+			panic!("expected a generic result")
+		};
+
+		assert_eq!(*id, page);
+
+		assert!(matches!(&args[..], [Type::Id(id)] if iface.typedefs[*id].ident == "user"));
+
+		for (source, error) in [
+			("interface test { record a<t> { x: t } func f() -> a }", "TypeArguments"),
+			("interface test { record a<t> { x: t } func f() -> a<u8, u8> }", "TypeArguments"),
+			("interface test { record a { x: u8 } func f() -> a<u8> }", "TypeArguments"),
+			("interface test { record a<t> { x: u8 } func f() -> a<u8> }", "UnusedType"),
+			("interface test { record a<t, t> { x: t } func f() -> a<u8, u8> }", "AlreadyDefined"),
+			("interface test { record a<t> { x: option<a<t>> } func f() -> a<u8> }", "RecursiveType"),
+		] {
+			let mut tokens = Tokens::new(source);
+
+			let iface = parse::Interface::parse(&mut tokens)?;
+
+			let (resolver, rest_data) = Resolver::new(source, iface);
+
+			let err = resolver.resolve(rest_data).unwrap_err();
+
+			assert!(format!("{err:?}").contains(error), "{source}");
+		}
+
+		Ok(())
+	}
+
+	#[test]
+	fn default() -> Result<()> {
+		let source = r#"interface test {
+            enum color { red, green }
+
+            type size = u32
+
+            record options {
+                size: size = 5,
+                scale: float32 = 1.5,
+                offset: s8 = -3,
+                visible: bool = true,
+                title: string = "untitled \"page\"",
+                separator: char = "/",
+                color: color = green,
+                limit: option<u32> = none,
+                label: string
+            }
+
+            func render(options: options)
+          }"#;
+
+		let mut tokens = Tokens::new(source);
+
+		let iface = parse::Interface::parse(&mut tokens)?;
+
+		let (resolver, rest_data) = Resolver::new(source, iface);
+
+		let iface = resolver.resolve(rest_data)?;
+
+		let TypeDefKind::Record(fields) =
+			&iface.typedefs.iter().find(|(_, typedef)| typedef.ident == "options").unwrap().1.kind
+		else {
+			panic!("expected a record")
+		};
+
+		let defaults = fields.iter().map(|field| field.default.clone()).collect::<Vec<_>>();
+
+		assert_eq!(
+			defaults,
+			vec![
+				Some(Value::Int(5)),
+				Some(Value::Float(1.5)),
+				Some(Value::Int(-3)),
+				Some(Value::Bool(true)),
+				Some(Value::String("untitled \"page\"".to_string())),
+				Some(Value::Char('/')),
+				Some(Value::Enum("green".to_string())),
+				Some(Value::None),
+				None,
+			]
+		);
+
+		for source in [
+			"interface test { record a { x: u8 = 256 } func f(a: a) }",
+			"interface test { record a { x: u32 = -1 } func f(a: a) }",
+			"interface test { record a { x: u32 = 1.5 } func f(a: a) }",
+			"interface test { record a { x: string = 5 } func f(a: a) }",
+			"interface test { record a { x: char = \"ab\" } func f(a: a) }",
+			"interface test { enum b { c } record a { x: b = d } func f(a: a) }",
+			"interface test { record a { x: list<u8> = none } func f(a: a) }",
+		] {
+			let mut tokens = Tokens::new(source);
+
+			let iface = parse::Interface::parse(&mut tokens)?;
+
+			let (resolver, rest_data) = Resolver::new(source, iface);
+
+			let err = resolver.resolve(rest_data).unwrap_err();
+
+			assert!(format!("{err:?}").contains("InvalidValue"), "{source}");
+		}
+
+		Ok(())
+	}
+
+	#[test]
+	fn constant() -> Result<()> {
+		let source = r#"interface test {
+            enum level { low, high }
+
+            /// The largest upload accepted, in bytes
+            const max_size: u32 = 1024
+            const version: string = "1.2"
+            const default_level: level = high
+          }"#;
+
+		let mut tokens = Tokens::new(source);
+
+		let iface = parse::Interface::parse(&mut tokens)?;
+
+		let (resolver, rest_data) = Resolver::new(source, iface);
+
+		let iface = resolver.resolve(rest_data)?;
+
+		let level = iface.typedefs.iter().find(|(_, typedef)| typedef.ident == "level").unwrap().0;
+
+		assert_eq!(
+			iface.constants,
+			vec![
+				Constant {
+					docs:"The largest upload accepted, in bytes".to_string(),
+					annotations:Vec::new(),
+					id:"max_size".to_string(),
+					ty:Type::U32,
+					value:Value::Int(1024),
+				},
+				Constant {
+					docs:String::new(),
+					annotations:Vec::new(),
+					id:"version".to_string(),
+					ty:Type::String,
+					value:Value::String("1.2".to_string()),
+				},
+				Constant {
+					docs:String::new(),
+					annotations:Vec::new(),
+					id:"default_level".to_string(),
+					ty:Type::Id(level),
+					value:Value::Enum("high".to_string()),
+				},
+			]
+		);
+
+		for source in [
+			"interface test { const a: u8 = 256 }",
+			"interface test { const a: list<u8> = none }",
+			"interface test { const a: u8 = 1 const a: u8 = 2 }",
+		] {
+			let mut tokens = Tokens::new(source);
+
+			let iface = parse::Interface::parse(&mut tokens)?;
+
+			let (resolver, rest_data) = Resolver::new(source, iface);
+
+			assert!(resolver.resolve(rest_data).is_err(), "{source}");
+		}
+
+		Ok(())
+	}
+
+	#[test]
+	fn float_range() -> Result<()> {
+		let source = "interface test {
+            const a: float32 = 3.4e38
+            const b: float32 = -1.5
+            const c: float64 = 1e40
+          }";
+
+		let mut tokens = Tokens::new(source);
+
+		let iface = parse::Interface::parse(&mut tokens)?;
+
+		let (resolver, rest_data) = Resolver::new(source, iface);
+
+		let iface = resolver.resolve(rest_data)?;
+
+		let values = iface.constants.iter().map(|constant| constant.value.clone()).collect::<Vec<_>>();
+
+		assert_eq!(values, vec![Value::Float(3.4e38), Value::Float(-1.5), Value::Float(1e40)]);
+
+		for source in [
+			"interface test { const a: float32 = 1e40 }",
+			"interface test { const a: float32 = -3.5e38 }",
+			"interface test { const a: float64 = 1e400 }",
+			"interface test { record a { x: float32 = 1e39 } func f(a: a) }",
+		] {
+			let mut tokens = Tokens::new(source);
+
+			let iface = parse::Interface::parse(&mut tokens)?;
+
+			let (resolver, rest_data) = Resolver::new(source, iface);
+
+			let err = resolver.resolve(rest_data).unwrap_err();
+
+			assert!(format!("{err:?}").contains("InvalidValue"), "{source}");
+		}
+
+		Ok(())
+	}
+
+	#[test]
+	fn annotations() -> Result<()> {
+		let source = r#"interface test {
+            @deprecated("use fetch")
+            @since(1.2)
+            func get(e: entry)
+
+            @serde(rename_all = "camelCase", default)
+            record entry {
+                @custom(-3, true, none)
+                name: string
+            }
+          }"#;
+
+		let mut tokens = Tokens::new(source);
+
+		let iface = parse::Interface::parse(&mut tokens)?;
+
+		let (resolver, rest_data) = Resolver::new(source, iface);
+
+		let iface = resolver.resolve(rest_data)?;
+
+		let annotation = |name:&str, args:Vec<(Option<&str>, Value)>| {
+			Annotation {
+				name:name.to_string(),
+				args:args
+					.into_iter()
+					.map(|(key, value)| AnnotationArg { key:key.map(ToString::to_string), value })
+					.collect(),
+			}
+		};
+
+		assert_eq!(
+			iface.functions[0].annotations,
+			vec![
+				annotation("deprecated", vec![(None, Value::String("use fetch".to_string()))]),
+				annotation("since", vec![(None, Value::Float(1.2))]),
+			]
+		);
+
+		let typedef = &iface.typedefs.iter().next().unwrap().1;
+
+		assert_eq!(
+			typedef.annotations,
+			vec![annotation(
+				"serde",
+				vec![
+					(Some("rename_all"), Value::String("camelCase".to_string())),
+					(None, Value::Enum("default".to_string())),
+				]
+			)]
+		);
+
+		let TypeDefKind::Record(fields) = &typedef.kind else { panic!("expected a record") };
+
+		assert_eq!(
+			fields[0].annotations,
+			vec![annotation(
+				"custom",
+				vec![(None, Value::Int(-3)), (None, Value::Bool(true)), (None, Value::None)]
+			)]
+		);
+
+		assert_eq!(crate::deprecation(&iface.functions[0].annotations), Some("use fetch"));
+
+		assert_eq!(crate::deprecation(&typedef.annotations), None);
+
+		for source in [
+			"interface test { @deprecated(1) func f() }",
+			"interface test { @deprecated(note = \"x\") func f() }",
+			"interface test { @deprecated(\"x\", \"y\") func f() }",
+		] {
+			let mut tokens = Tokens::new(source);
+
+			let iface = parse::Interface::parse(&mut tokens)?;
+
+			let (resolver, rest_data) = Resolver::new(source, iface);
+
+			let err = resolver.resolve(rest_data).unwrap_err();
+
+			assert!(format!("{err:?}").contains("InvalidDeprecation"), "{source}");
+		}
+
+		Ok(())
+	}
+
+	#[test]
+	fn capabilities() -> Result<()> {
+		let source = r#"interface test {
+            @capability("fs:read")
+            @capability("fs:write")
+            func f()
+            func g()
+          }"#;
+
+		let mut tokens = Tokens::new(source);
+
+		let iface = parse::Interface::parse(&mut tokens)?;
+
+		let (resolver, rest_data) = Resolver::new(source, iface);
+
+		let iface = resolver.resolve(rest_data)?;
+
+		assert_eq!(
+			crate::capabilities(&iface.functions[0].annotations).collect::<Vec<_>>(),
+			["fs:read", "fs:write"]
+		);
+
+		assert_eq!(crate::capabilities(&iface.functions[1].annotations).count(), 0);
+
+		for source in [
+			"interface test { @capability func f() }",
+			"interface test { @capability(fs) func f() }",
+			"interface test { @capability(name = \"fs\") func f() }",
+			"interface test { @capability(\"fs\", \"net\") func f() }",
+		] {
+			let mut tokens = Tokens::new(source);
+
+			let iface = parse::Interface::parse(&mut tokens)?;
+
+			let (resolver, rest_data) = Resolver::new(source, iface);
+
+			let err = resolver.resolve(rest_data).unwrap_err();
+
+			assert!(matches!(flatten(&err)[..], [Error::InvalidCapability { .. }]), "{source}");
+		}
+
+		Ok(())
+	}
+
+	#[test]
+	fn spans() -> Result<()> {
+		let source = "interface test {
+	record point {
+		x: u32,
+		/// The height.
+		y: u32,
+	}
+
+	resource counter {
+		func get() -> point
+	}
+
+	@since(1.2)
+	func make() -> counter
+}";
+
+		let mut tokens = Tokens::new(source);
+
+		let iface = parse::Interface::parse(&mut tokens)?;
+
+		let (resolver, rest_data) = Resolver::new(source, iface);
+
+		let iface = resolver.resolve(rest_data)?;
+
+		let text = |span:&Span| &source[span.clone()];
+
+		let (point, counter) = {
+			let mut typedefs = iface.typedefs.iter().map(|(_, typedef)| typedef);
+
+			let (a, b) = (typedefs.next().unwrap(), typedefs.next().unwrap());
+
+			if a.ident == "point" { (a, b) } else { (b, a) }
+		};
+
+		assert_eq!(
+			text(&point.span),
+			"record point {\n\t\tx: u32,\n\t\t/// The height.\n\t\ty: u32,\n\t}"
+		);
+
+		let TypeDefKind::Record(fields) = &point.kind else { panic!("expected a record") };
+
+		assert_eq!(text(&fields[0].span), "x: u32");
+
+		assert_eq!(text(&fields[1].span), "y: u32");
+
+		let TypeDefKind::Resource(resource) = &counter.kind else { panic!("expected a resource") };
+
+		assert_eq!(
+			text(&counter.span),
+			"resource counter {\n\t\tfunc get() -> point\n\t}"
+		);
+
+		assert_eq!(text(&resource.methods[0].span), "func get() -> point");
+
+		assert_eq!(text(&iface.functions[0].span), "func make() -> counter");
+
+		Ok(())
+	}
+
 	#[test]
 	fn full() -> Result<()> {
 		let source = include_str!("test.wit");
 
-		let mut tokens = Lexer::new(source).spanned().peekable();
+		let mut tokens = Tokens::new(source);
 
 		let iface = parse::Interface::parse(&mut tokens)?;
 
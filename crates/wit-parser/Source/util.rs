@@ -1,5 +1,7 @@
 use std::fmt::{Display, Write};
 
+use logos::Span;
+
 use crate::Error;
 
 pub trait IteratorExt {
@@ -116,3 +118,21 @@ pub fn detect_invalid_input(input:&str) -> crate::Result<()> {
 
 	Ok(())
 }
+
+/// Joins doc comments into a single string, without their comment markers.
+pub fn read_docs(source:&str, docs:&[Span]) -> String {
+	docs.iter()
+		.map(|span| {
+			let str = &source[span.clone()];
+
+			let str = str.strip_prefix("///").unwrap_or(str);
+
+			let str = str.strip_prefix("/**").unwrap_or(str);
+
+			let str = str.trim();
+
+			str
+		})
+		.collect::<Vec<_>>()
+		.join("\n")
+}
@@ -11,7 +11,7 @@ pub fn App<G:Html>(cx:Scope) -> View<G> {
 	let greet_msg = create_signal(cx, String::new());
 
 	let do_greet = move |_| {
-		spawn_local_scoped(cx, async move {
+			let new_msg = greet::greet(&name.get()).await.unwrap_or_else(|err| err.to_string());
 			let new_msg = greet::greet(&name.get()).await;
 
 			greet_msg.set(new_msg);
@@ -51,7 +51,7 @@ fn main() {
 				update_output(
 					"guest-js",
 					&gen_interface(
-						tauri_bindgen_gen_guest_js::Builder { prettier:false, romefmt:false },
+						tauri_bindgen_gen_guest_js::Builder { prettier:false, romefmt:false, json:false },
 						iface.clone(),
 					),
 				);
@@ -59,7 +59,7 @@ fn main() {
 				update_output(
 					"guest-ts",
 					&gen_interface(
-						tauri_bindgen_gen_guest_ts::Builder { prettier:false, romefmt:false },
+						tauri_bindgen_gen_guest_ts::Builder { prettier:false, romefmt:false, json:false },
 						iface.clone(),
 					),
 				);
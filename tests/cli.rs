@@ -0,0 +1,110 @@
+use std::{
+	fs,
+	path::PathBuf,
+	process::{Command, Output},
+};
+
+/// Writes the documents to a fresh directory and returns their paths.
+fn write_docs(test:&str, docs:&[(&str, &str)]) -> Vec<PathBuf> {
+	let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(test);
+
+	let _ = fs::remove_dir_all(&dir);
+
+	fs::create_dir_all(&dir).unwrap();
+
+	docs.iter()
+		.map(|(name, contents)| {
+			let path = dir.join(name);
+
+			fs::write(&path, contents).unwrap();
+
+			path
+		})
+		.collect()
+}
+
+fn run(args:&[&str], paths:&[PathBuf]) -> Output {
+	Command::new(env!("CARGO_BIN_EXE_tauri-bindgen")).args(args).args(paths).output().unwrap()
+}
+
+const GREET:&str = "interface greet { func greet(name: string) -> string }";
+
+const BROKEN:&str = "interface greet { func greet(name: string -> string }";
+
+#[test]
+fn diff_compatible() {
+	let paths = write_docs("diff_compatible", &[
+		("old.wit", GREET),
+		("new.wit", "interface greet { func greet(name: string) -> string func farewell() }"),
+	]);
+
+	let out = run(&["diff"], &paths);
+
+	assert_eq!(out.status.code(), Some(0), "{out:?}");
+}
+
+#[test]
+fn diff_breaking() {
+	let paths = write_docs("diff_breaking", &[
+		("old.wit", GREET),
+		("new.wit", "interface greet { func greet(name: string, loud: bool) -> string }"),
+	]);
+
+	let out = run(&["diff"], &paths);
+
+	assert_eq!(out.status.code(), Some(1), "{out:?}");
+	assert!(String::from_utf8_lossy(&out.stdout).contains("func greet"), "{out:?}");
+}
+
+#[test]
+fn diff_errors() {
+	let paths = write_docs("diff_errors", &[("old.wit", BROKEN), ("new.wit", GREET)]);
+
+	let out = run(&["diff"], &paths);
+
+	assert_eq!(out.status.code(), Some(1), "{out:?}");
+	assert!(out.stdout.is_empty(), "{out:?}");
+
+	// documents that fail to typecheck are errors as well
+	let paths = write_docs("diff_errors_typecheck", &[
+		("old.wit", GREET),
+		("new.wit", "interface greet { func greet(name: strin) -> string }"),
+	]);
+
+	assert_eq!(run(&["diff"], &paths).status.code(), Some(1));
+
+	let missing = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("missing.wit");
+
+	assert_eq!(run(&["diff"], &[paths[0].clone(), missing]).status.code(), Some(1));
+}
+
+#[test]
+fn fmt_check_errors() {
+	let paths = write_docs("fmt_check_errors", &[("broken.wit", BROKEN)]);
+
+	let out = run(&["fmt", "--check"], &paths);
+
+	assert_eq!(out.status.code(), Some(1), "{out:?}");
+
+	// the file is left as is
+	assert_eq!(fs::read_to_string(&paths[0]).unwrap(), BROKEN);
+}
+
+#[test]
+fn lint_errors() {
+	let paths = write_docs("lint_errors", &[("broken.wit", BROKEN)]);
+
+	let out = run(&["lint"], &paths);
+
+	assert_eq!(out.status.code(), Some(1), "{out:?}");
+}
+
+#[test]
+fn host_unknown_item_capability() {
+	let paths = write_docs("host_unknown_item_capability", &[("greet.wit", GREET)]);
+
+	let out = run(&["host", "--item-capability", "gret=greet:call"], &paths);
+
+	assert_eq!(out.status.code(), Some(1), "{out:?}");
+	assert!(String::from_utf8_lossy(&out.stderr).contains("`gret`"), "{out:?}");
+}